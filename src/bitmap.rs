@@ -0,0 +1,852 @@
+use crate::command_parser::{parse_number, to_keyword, Command};
+use crate::data::{Data, Keyspace, StringData};
+use crate::string::get_string;
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+
+/// Bits a string can hold, strings being at most 512MB.
+const MAX_BITS: u64 = 512 * 1024 * 1024 * 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitmapCommand {
+    SetBit {
+        key: Bytes,
+        offset: u64,
+        bit: bool,
+    },
+    GetBit {
+        key: Bytes,
+        offset: u64,
+    },
+    /// BITCOUNT, over the whole string when `range` is `None`
+    Count {
+        key: Bytes,
+        range: Option<BitRange>,
+    },
+    /// BITPOS; `range` holds as much of the range as was given
+    Pos {
+        key: Bytes,
+        bit: bool,
+        range: BitRange,
+        end_given: bool,
+    },
+    Op {
+        op: BitOp,
+        destination: Bytes,
+        keys: Vec<Bytes>,
+    },
+    /// BITFIELD and BITFIELD_RO
+    Field {
+        key: Bytes,
+        ops: Vec<FieldOp>,
+    },
+}
+
+/// Range of BITCOUNT and BITPOS, negative indexes counting from the end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitRange {
+    pub start: i64,
+    pub end: i64,
+    /// BIT, indexes are bits rather than bytes
+    pub bit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitOp {
+    And,
+    Or,
+    Xor,
+    Not,
+    /// Bits of the first key set in none of the others
+    Diff,
+}
+
+/// Integer type of a BITFIELD operation, `i1` to `i64` or `u1` to `u63`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldType {
+    pub signed: bool,
+    pub bits: u32,
+}
+
+/// What BITFIELD does when SET or INCRBY goes past the range of the type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    Wrap,
+    Sat,
+    /// The operation is skipped and replies with a null
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldOp {
+    Get {
+        ty: FieldType,
+        offset: u64,
+    },
+    Set {
+        ty: FieldType,
+        offset: u64,
+        value: i64,
+        overflow: Overflow,
+    },
+    IncrBy {
+        ty: FieldType,
+        offset: u64,
+        increment: i64,
+        overflow: Overflow,
+    },
+}
+
+fn generic_error(msg: &str) -> RusdisError {
+    RusdisError::Generic {
+        msg: msg.to_string(),
+    }
+}
+
+fn invalid_offset() -> RusdisError {
+    generic_error("bit offset is not an integer or out of range")
+}
+
+fn parse_offset(bytes: &Bytes) -> Result<u64, RusdisError> {
+    parse_number::<u64>(bytes)
+        .ok()
+        .filter(|offset| *offset < MAX_BITS)
+        .ok_or_else(invalid_offset)
+}
+
+pub(crate) fn parse_setbit_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let offset = parse_offset(&argv[2])?;
+    let bit = match &argv[3][..] {
+        b"0" => false,
+        b"1" => true,
+        _ => return Err(generic_error("bit is not an integer or out of range")),
+    };
+
+    Ok(Command::Bitmap(BitmapCommand::SetBit {
+        key: argv[1].clone(),
+        offset,
+        bit,
+    }))
+}
+
+pub(crate) fn parse_getbit_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Bitmap(BitmapCommand::GetBit {
+        key: argv[1].clone(),
+        offset: parse_offset(&argv[2])?,
+    }))
+}
+
+/// Parses the BYTE or BIT unit closing a range.
+fn parse_unit(bytes: &Bytes) -> Result<bool, RusdisError> {
+    match to_keyword(bytes).as_str() {
+        "BYTE" => Ok(false),
+        "BIT" => Ok(true),
+        _ => Err(RusdisError::SyntaxError),
+    }
+}
+
+pub(crate) fn parse_bitcount_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let range = match &argv[2..] {
+        [] => None,
+        [start, end, unit @ ..] if unit.len() <= 1 => Some(BitRange {
+            start: parse_number(start)?,
+            end: parse_number(end)?,
+            bit: unit.first().map_or(Ok(false), parse_unit)?,
+        }),
+        _ => return Err(RusdisError::SyntaxError),
+    };
+
+    Ok(Command::Bitmap(BitmapCommand::Count {
+        key: argv[1].clone(),
+        range,
+    }))
+}
+
+pub(crate) fn parse_bitpos_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let bit = match parse_number::<i64>(&argv[2])? {
+        0 => false,
+        1 => true,
+        _ => return Err(generic_error("The bit argument must be 1 or 0.")),
+    };
+    let mut range = BitRange {
+        start: 0,
+        end: -1,
+        bit: false,
+    };
+    let end_given = match &argv[3..] {
+        [] => false,
+        [start] => {
+            range.start = parse_number(start)?;
+            false
+        }
+        [start, end, unit @ ..] if unit.len() <= 1 => {
+            range.start = parse_number(start)?;
+            range.end = parse_number(end)?;
+            range.bit = unit.first().map_or(Ok(false), parse_unit)?;
+            true
+        }
+        _ => return Err(RusdisError::SyntaxError),
+    };
+
+    Ok(Command::Bitmap(BitmapCommand::Pos {
+        key: argv[1].clone(),
+        bit,
+        range,
+        end_given,
+    }))
+}
+
+pub(crate) fn parse_bitop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let op = match to_keyword(&argv[1]).as_str() {
+        "AND" => BitOp::And,
+        "OR" => BitOp::Or,
+        "XOR" => BitOp::Xor,
+        "NOT" => BitOp::Not,
+        "DIFF" => BitOp::Diff,
+        _ => return Err(RusdisError::SyntaxError),
+    };
+    let keys = argv[3..].to_vec();
+    match op {
+        BitOp::Not if keys.len() != 1 => {
+            return Err(generic_error(
+                "BITOP NOT must be called with a single source key.",
+            ))
+        }
+        BitOp::Diff if keys.len() < 2 => {
+            return Err(generic_error(
+                "BITOP DIFF must be called with at least two source keys.",
+            ))
+        }
+        _ => {}
+    }
+
+    Ok(Command::Bitmap(BitmapCommand::Op {
+        op,
+        destination: argv[2].clone(),
+        keys,
+    }))
+}
+
+fn parse_field_type(bytes: &Bytes) -> Result<FieldType, RusdisError> {
+    let err = || {
+        generic_error(
+            "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.",
+        )
+    };
+    let (signed, bits) = match bytes.split_first() {
+        Some((b'i' | b'I', bits)) => (true, bits),
+        Some((b'u' | b'U', bits)) => (false, bits),
+        _ => return Err(err()),
+    };
+    let bits = std::str::from_utf8(bits)
+        .ok()
+        .and_then(|bits| bits.parse::<u32>().ok())
+        .filter(|bits| (1..=if signed { 64 } else { 63 }).contains(bits))
+        .ok_or_else(err)?;
+
+    Ok(FieldType { signed, bits })
+}
+
+/// Parses a BITFIELD offset, in bits or, after `#`, in multiples of the type width.
+fn parse_field_offset(bytes: &Bytes, ty: FieldType) -> Result<u64, RusdisError> {
+    let (offset, multiplier) = match bytes.strip_prefix(b"#") {
+        Some(offset) => (offset, ty.bits as u64),
+        None => (&bytes[..], 1),
+    };
+    std::str::from_utf8(offset)
+        .ok()
+        .and_then(|offset| offset.parse::<u64>().ok())
+        .and_then(|offset| offset.checked_mul(multiplier))
+        .filter(|offset| offset + ty.bits as u64 <= MAX_BITS)
+        .ok_or_else(invalid_offset)
+}
+
+fn parse_bitfield(argv: &[Bytes], readonly: bool) -> Result<Command, RusdisError> {
+    let mut ops = vec![];
+    let mut overflow = Overflow::Wrap;
+
+    let mut idx = 2;
+    while idx < argv.len() {
+        let subcommand = to_keyword(&argv[idx]);
+        if readonly && subcommand != "GET" {
+            return Err(generic_error(
+                "BITFIELD_RO only supports the GET subcommand",
+            ));
+        }
+        let arg = |n: usize| argv.get(idx + n).ok_or(RusdisError::SyntaxError);
+
+        match subcommand.as_str() {
+            "OVERFLOW" => {
+                overflow = match to_keyword(arg(1)?).as_str() {
+                    "WRAP" => Overflow::Wrap,
+                    "SAT" => Overflow::Sat,
+                    "FAIL" => Overflow::Fail,
+                    _ => return Err(generic_error("Invalid OVERFLOW type specified")),
+                };
+                idx += 2;
+            }
+            "GET" => {
+                let ty = parse_field_type(arg(1)?)?;
+                let offset = parse_field_offset(arg(2)?, ty)?;
+                ops.push(FieldOp::Get { ty, offset });
+                idx += 3;
+            }
+            "SET" | "INCRBY" => {
+                let ty = parse_field_type(arg(1)?)?;
+                let offset = parse_field_offset(arg(2)?, ty)?;
+                let value = parse_number::<i64>(arg(3)?)?;
+                ops.push(if subcommand == "SET" {
+                    FieldOp::Set {
+                        ty,
+                        offset,
+                        value,
+                        overflow,
+                    }
+                } else {
+                    FieldOp::IncrBy {
+                        ty,
+                        offset,
+                        increment: value,
+                        overflow,
+                    }
+                });
+                idx += 4;
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    Ok(Command::Bitmap(BitmapCommand::Field {
+        key: argv[1].clone(),
+        ops,
+    }))
+}
+
+pub(crate) fn parse_bitfield_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_bitfield(argv, false)
+}
+
+pub(crate) fn parse_bitfield_ro_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_bitfield(argv, true)
+}
+
+/// The bit at `offset`, bit 0 being the most significant bit of the first byte.
+fn get_bit(buf: &[u8], offset: u64) -> bool {
+    buf.get((offset / 8) as usize)
+        .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0)
+}
+
+/// Sets the bit at `offset`, which must be within `buf`.
+fn set_bit(buf: &mut [u8], offset: u64, bit: bool) {
+    let mask = 0x80 >> (offset % 8);
+    let byte = &mut buf[(offset / 8) as usize];
+    if bit {
+        *byte |= mask;
+    } else {
+        *byte &= !mask;
+    }
+}
+
+/// Resolves `range` against a string of `len` bytes into an inclusive range of
+/// bits, `None` when it is empty.
+fn bit_range(range: BitRange, len: usize) -> Option<(u64, u64)> {
+    let total = if range.bit { len * 8 } else { len } as i64;
+    let resolve = |index: i64| {
+        if index < 0 {
+            (total + index).max(0)
+        } else {
+            index
+        }
+    };
+    let (start, end) = (resolve(range.start), resolve(range.end).min(total - 1));
+    if total == 0 || start > end {
+        return None;
+    }
+
+    Some(if range.bit {
+        (start as u64, end as u64)
+    } else {
+        (start as u64 * 8, end as u64 * 8 + 7)
+    })
+}
+
+/// Number of set bits from `start` to `end`, both included.
+fn count_bits(buf: &[u8], start: u64, end: u64) -> u64 {
+    let (first, last) = ((start / 8) as usize, (end / 8) as usize);
+    let mut count: u64 = buf[first..=last]
+        .iter()
+        .map(|byte| byte.count_ones() as u64)
+        .sum();
+    // leave out the bits of the edge bytes outside the range
+    count -= (buf[first] as u32 >> (8 - start % 8)).count_ones() as u64;
+    count -= (buf[last] as u32 & (0xff >> (end % 8 + 1))).count_ones() as u64;
+
+    count
+}
+
+/// Reads an unsigned integer of `bits` bits at `offset`, zero past the end.
+fn get_bits(buf: &[u8], offset: u64, bits: u32) -> u64 {
+    (0..bits as u64).fold(0, |value, i| (value << 1) | get_bit(buf, offset + i) as u64)
+}
+
+/// Writes the low `bits` bits of `value` at `offset`, which must be within `buf`.
+fn set_bits(buf: &mut [u8], offset: u64, bits: u32, value: u64) {
+    for i in 0..bits as u64 {
+        set_bit(buf, offset + i, value >> (bits as u64 - 1 - i) & 1 != 0);
+    }
+}
+
+impl FieldType {
+    /// The integer stored as `raw`, sign extended for signed types.
+    fn decode(&self, raw: u64) -> i64 {
+        if self.signed && self.bits < 64 {
+            let shift = 64 - self.bits;
+            ((raw << shift) as i64) >> shift
+        } else {
+            raw as i64
+        }
+    }
+
+    fn range(&self) -> (i128, i128) {
+        if self.signed {
+            (-(1 << (self.bits - 1)), (1 << (self.bits - 1)) - 1)
+        } else {
+            (0, (1 << self.bits) - 1)
+        }
+    }
+
+    /// Fits `value` in the type as `overflow` says, `None` when it fails.
+    fn fit(&self, value: i128, overflow: Overflow) -> Option<i64> {
+        let (min, max) = self.range();
+        if (min..=max).contains(&value) {
+            return Some(value as i64);
+        }
+
+        match overflow {
+            Overflow::Wrap => {
+                let wrapped = value.rem_euclid(1 << self.bits);
+                Some(if wrapped > max {
+                    wrapped - (1 << self.bits)
+                } else {
+                    wrapped
+                } as i64)
+            }
+            Overflow::Sat => Some(value.clamp(min, max) as i64),
+            Overflow::Fail => None,
+        }
+    }
+}
+
+/// Runs the BITFIELD operations on `buf`, already long enough for the writes.
+fn run_field_ops(buf: &mut [u8], ops: &[FieldOp]) -> Vec<Value> {
+    ops.iter()
+        .map(|op| {
+            let (ty, offset, value) = match *op {
+                FieldOp::Get { ty, offset } => {
+                    return Value::Integer(ty.decode(get_bits(buf, offset, ty.bits)))
+                }
+                FieldOp::Set {
+                    ty,
+                    offset,
+                    value,
+                    overflow,
+                } => (
+                    ty,
+                    offset,
+                    ty.fit(value as i128, overflow).map(|new| (new, true)),
+                ),
+                FieldOp::IncrBy {
+                    ty,
+                    offset,
+                    increment,
+                    overflow,
+                } => {
+                    let old = ty.decode(get_bits(buf, offset, ty.bits)) as i128;
+                    (
+                        ty,
+                        offset,
+                        ty.fit(old + increment as i128, overflow)
+                            .map(|new| (new, false)),
+                    )
+                }
+            };
+            let (new, reply_old) = match value {
+                Some(value) => value,
+                None => return Value::Null,
+            };
+
+            let old = ty.decode(get_bits(buf, offset, ty.bits));
+            set_bits(buf, offset, ty.bits, new as u64);
+            Value::Integer(if reply_old { old } else { new })
+        })
+        .collect()
+}
+
+pub fn execute_bitmap_command(
+    keyspace: &mut Keyspace,
+    cmd: BitmapCommand,
+) -> Result<Value, RusdisError> {
+    match cmd {
+        BitmapCommand::SetBit { key, offset, bit } => {
+            let mut buf = match get_string(keyspace, &key)? {
+                Some(data) => data.get_data().to_vec(),
+                None => vec![],
+            };
+            let len = (offset / 8) as usize + 1;
+            if buf.len() < len {
+                buf.resize(len, 0);
+            }
+
+            let old = get_bit(&buf, offset);
+            set_bit(&mut buf, offset, bit);
+            match get_string(keyspace, &key)? {
+                Some(data) => data.set_data(Bytes::from(buf)),
+                None => {
+                    keyspace.insert(key, Data::String(StringData::new(Bytes::from(buf))));
+                }
+            }
+
+            Ok(Value::Integer(old as i64))
+        }
+        BitmapCommand::GetBit { key, offset } => Ok(Value::Integer(
+            get_string(keyspace, &key)?.is_some_and(|data| get_bit(data.get_data(), offset)) as i64,
+        )),
+        BitmapCommand::Count { key, range } => {
+            let buf = match get_string(keyspace, &key)? {
+                Some(data) => data.get_data(),
+                None => return Ok(Value::Integer(0)),
+            };
+            let range = range.unwrap_or(BitRange {
+                start: 0,
+                end: -1,
+                bit: false,
+            });
+
+            Ok(Value::Integer(
+                bit_range(range, buf.len()).map_or(0, |(start, end)| count_bits(buf, start, end))
+                    as i64,
+            ))
+        }
+        BitmapCommand::Pos {
+            key,
+            bit,
+            range,
+            end_given,
+        } => {
+            // a missing key is all clear bits
+            let buf = match get_string(keyspace, &key)? {
+                Some(data) => data.get_data(),
+                None => return Ok(Value::Integer(if bit { -1 } else { 0 })),
+            };
+            let (start, end) = match bit_range(range, buf.len()) {
+                Some(range) => range,
+                None => return Ok(Value::Integer(-1)),
+            };
+
+            let pos = (start..=end).find(|offset| get_bit(buf, *offset) == bit);
+            Ok(Value::Integer(match pos {
+                Some(pos) => pos as i64,
+                // past the end of the string the bits are clear, unless the
+                // range ends there
+                None if !bit && !end_given => end as i64 + 1,
+                None => -1,
+            }))
+        }
+        BitmapCommand::Op {
+            op,
+            destination,
+            keys,
+        } => {
+            let mut sources = Vec::with_capacity(keys.len());
+            for key in keys.iter() {
+                sources.push(match get_string(keyspace, key)? {
+                    Some(data) => data.get_data().clone(),
+                    None => Bytes::new(),
+                });
+            }
+
+            let len = sources.iter().map(|source| source.len()).max().unwrap_or(0);
+            let byte = |source: &Bytes, i: usize| source.get(i).copied().unwrap_or(0);
+            let result: Vec<u8> = (0..len)
+                .map(|i| {
+                    let mut bytes = sources.iter().map(|source| byte(source, i));
+                    let first = bytes.next().unwrap_or(0);
+                    match op {
+                        BitOp::And => bytes.fold(first, |acc, byte| acc & byte),
+                        BitOp::Or => bytes.fold(first, |acc, byte| acc | byte),
+                        BitOp::Xor => bytes.fold(first, |acc, byte| acc ^ byte),
+                        BitOp::Not => !first,
+                        BitOp::Diff => first & !bytes.fold(0, |acc, byte| acc | byte),
+                    }
+                })
+                .collect();
+
+            if result.is_empty() {
+                keyspace.remove(&destination);
+            } else {
+                keyspace.insert(
+                    destination,
+                    Data::String(StringData::new(Bytes::from(result))),
+                );
+            }
+
+            Ok(Value::Integer(len as i64))
+        }
+        BitmapCommand::Field { key, ops } => {
+            // writes grow the string to the furthest bit they touch, even
+            // when they fail
+            let end = ops
+                .iter()
+                .filter_map(|op| match *op {
+                    FieldOp::Get { .. } => None,
+                    FieldOp::Set { ty, offset, .. } | FieldOp::IncrBy { ty, offset, .. } => {
+                        Some(offset + ty.bits as u64)
+                    }
+                })
+                .max();
+
+            let mut buf = match get_string(keyspace, &key)? {
+                Some(data) => data.get_data().to_vec(),
+                None if end.is_none() => vec![],
+                None => {
+                    keyspace.insert(key.clone(), Data::String(StringData::new(Bytes::new())));
+                    vec![]
+                }
+            };
+            let end = match end {
+                Some(end) => end,
+                None => return Ok(Value::Array(run_field_ops(&mut buf, &ops))),
+            };
+            let len = end.div_ceil(8) as usize;
+            if buf.len() < len {
+                buf.resize(len, 0);
+            }
+
+            let replies = run_field_ops(&mut buf, &ops);
+            if let Some(data) = get_string(keyspace, &key)? {
+                data.set_data(Bytes::from(buf));
+            }
+
+            Ok(Value::Array(replies))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{error, run, string};
+
+    #[test]
+    fn test_bitmap_setbit_and_getbit() {
+        let mut keyspace = Keyspace::new();
+
+        assert_eq!(
+            run(&mut keyspace, &["SETBIT", "k", "7", "1"]),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SETBIT", "k", "7", "0"]),
+            Value::Integer(1)
+        );
+        run(&mut keyspace, &["SETBIT", "k", "17", "1"]);
+        assert_eq!(string(&keyspace, "k").as_ref(), b"\x00\x00\x40");
+        assert_eq!(
+            run(&mut keyspace, &["GETBIT", "k", "17"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETBIT", "k", "100"]),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETBIT", "none", "0"]),
+            Value::Integer(0)
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["SETBIT", "k", "4294967296", "1"]),
+            error("ERR bit offset is not an integer or out of range")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SETBIT", "k", "0", "2"]),
+            error("ERR bit is not an integer or out of range")
+        );
+    }
+
+    #[test]
+    fn test_bitmap_count_and_pos() {
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(
+            Bytes::from("k"),
+            Data::String(StringData::new(Bytes::from_static(b"\xff\xf0\x00"))),
+        );
+
+        assert_eq!(run(&mut keyspace, &["BITCOUNT", "k"]), Value::Integer(12));
+        assert_eq!(
+            run(&mut keyspace, &["BITCOUNT", "k", "1", "-1"]),
+            Value::Integer(4)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITCOUNT", "k", "5", "10", "BIT"]),
+            Value::Integer(6)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITCOUNT", "k", "2", "1"]),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITCOUNT", "k", "1"]),
+            error("ERR syntax error")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITCOUNT", "none", "0", "-1"]),
+            Value::Integer(0)
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["BITPOS", "k", "0"]),
+            Value::Integer(12)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITPOS", "k", "1", "1"]),
+            Value::Integer(8)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITPOS", "k", "1", "10", "-1", "BIT"]),
+            Value::Integer(10)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITPOS", "k", "1", "2"]),
+            Value::Integer(-1)
+        );
+        // clear bits past the end of the string count unless the range ends
+        keyspace.insert(
+            Bytes::from("ones"),
+            Data::String(StringData::new(Bytes::from_static(b"\xff"))),
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITPOS", "ones", "0"]),
+            Value::Integer(8)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITPOS", "ones", "0", "0", "-1"]),
+            Value::Integer(-1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITPOS", "none", "0"]),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITPOS", "k", "2"]),
+            error("ERR The bit argument must be 1 or 0.")
+        );
+    }
+
+    #[test]
+    fn test_bitmap_bitop() {
+        let mut keyspace = Keyspace::new();
+        for (key, value) in [("a", b"\xf0\x0f"), ("b", b"\x3c\xff")] {
+            keyspace.insert(
+                Bytes::from(key),
+                Data::String(StringData::new(Bytes::from_static(value))),
+            );
+        }
+
+        assert_eq!(
+            run(&mut keyspace, &["BITOP", "AND", "d", "a", "b", "none"]),
+            Value::Integer(2)
+        );
+        assert_eq!(string(&keyspace, "d").as_ref(), b"\x00\x00");
+        run(&mut keyspace, &["BITOP", "OR", "d", "a", "b"]);
+        assert_eq!(string(&keyspace, "d").as_ref(), b"\xfc\xff");
+        run(&mut keyspace, &["BITOP", "XOR", "d", "a", "b"]);
+        assert_eq!(string(&keyspace, "d").as_ref(), b"\xcc\xf0");
+        run(&mut keyspace, &["BITOP", "NOT", "d", "a"]);
+        assert_eq!(string(&keyspace, "d").as_ref(), b"\x0f\xf0");
+        run(&mut keyspace, &["BITOP", "DIFF", "d", "a", "b"]);
+        assert_eq!(string(&keyspace, "d").as_ref(), b"\xc0\x00");
+
+        assert_eq!(
+            run(&mut keyspace, &["BITOP", "OR", "d", "none"]),
+            Value::Integer(0)
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("d")));
+        assert_eq!(
+            run(&mut keyspace, &["BITOP", "NOT", "d", "a", "b"]),
+            error("ERR BITOP NOT must be called with a single source key.")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITOP", "DIFF", "d", "a"]),
+            error("ERR BITOP DIFF must be called with at least two source keys.")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITOP", "NAND", "d", "a"]),
+            error("ERR syntax error")
+        );
+    }
+
+    #[test]
+    fn test_bitmap_bitfield() {
+        let mut keyspace = Keyspace::new();
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["BITFIELD", "k", "SET", "u8", "0", "255", "GET", "i8", "0", "GET", "u4", "#1"]
+            ),
+            Value::Array(vec![
+                Value::Integer(0),
+                Value::Integer(-1),
+                Value::Integer(15)
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "BITFIELD", "k", "INCRBY", "u8", "0", "10", "OVERFLOW", "SAT", "INCRBY", "u8",
+                    "0", "300", "OVERFLOW", "FAIL", "INCRBY", "u8", "0", "1"
+                ]
+            ),
+            Value::Array(vec![Value::Integer(9), Value::Integer(255), Value::Null])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["BITFIELD", "k", "INCRBY", "i4", "8", "9", "GET", "i64", "0"]
+            ),
+            Value::Array(vec![
+                Value::Integer(-7),
+                Value::Integer(i64::from_be_bytes([0xff, 0x90, 0, 0, 0, 0, 0, 0]))
+            ])
+        );
+        assert_eq!(string(&keyspace, "k").as_ref(), b"\xff\x90");
+
+        assert_eq!(
+            run(&mut keyspace, &["BITFIELD", "none", "GET", "u8", "0"]),
+            Value::Array(vec![Value::Integer(0)])
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("none")));
+        assert_eq!(
+            run(&mut keyspace, &["BITFIELD_RO", "k", "GET", "u8", "8"]),
+            Value::Array(vec![Value::Integer(0x90)])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITFIELD_RO", "k", "SET", "u8", "0", "1"]),
+            error("ERR BITFIELD_RO only supports the GET subcommand")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITFIELD", "k", "GET", "u64", "0"]),
+            error("ERR Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITFIELD", "k", "OVERFLOW", "NOPE"]),
+            error("ERR Invalid OVERFLOW type specified")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["BITFIELD", "k", "GET", "u8"]),
+            error("ERR syntax error")
+        );
+    }
+}
@@ -0,0 +1,642 @@
+use crate::data::{get_live, Data, Keyspace};
+use crate::list::{move_element, pop, End};
+use crate::stream::{
+    has_entries, has_group_entries, read, read_group, read_group_argv, resolve_new, GroupReadFrom,
+    ReadFrom,
+};
+use crate::zset::{mpop_reply, pop_key};
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// What a blocked client does with the first of its keys that has data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockingOp {
+    /// BLPOP and BRPOP
+    Pop(End),
+    /// BLMOVE from the key to `destination`
+    Move {
+        destination: Bytes,
+        from: End,
+        to: End,
+    },
+    /// BLMPOP
+    MPop { end: End, count: usize },
+    /// BZPOPMIN and BZPOPMAX
+    ZPop { max: bool },
+    /// BZMPOP
+    ZMPop { max: bool, count: usize },
+    /// XREAD with BLOCK, reading every stream that has entries
+    XRead {
+        streams: Vec<(Bytes, ReadFrom)>,
+        count: usize,
+    },
+    /// XREADGROUP with BLOCK, reading new entries of every stream that has some
+    XReadGroup {
+        group: Bytes,
+        consumer: Bytes,
+        streams: Vec<(Bytes, GroupReadFrom)>,
+        count: usize,
+        noack: bool,
+    },
+}
+
+/// Reply to a served client, and the command to propagate if it wrote.
+type Served = (Value, Option<Vec<Bytes>>);
+
+fn end_name(end: End, pop: bool) -> Bytes {
+    Bytes::from(match (end, pop) {
+        (End::Left, true) => "LPOP",
+        (End::Right, true) => "RPOP",
+        (End::Left, false) => "LEFT",
+        (End::Right, false) => "RIGHT",
+    })
+}
+
+fn zpop_name(max: bool) -> Bytes {
+    Bytes::from(if max { "ZPOPMAX" } else { "ZPOPMIN" })
+}
+
+impl BlockingOp {
+    /// Reply when the timeout passes before any key has data.
+    pub fn timeout_reply(&self) -> Value {
+        match self {
+            BlockingOp::Move { .. } => Value::Null,
+            _ => Value::NullArray,
+        }
+    }
+
+    /// Pins what the operation waits for to the keyspace as it is when the
+    /// command runs: XREAD's `$` becomes the last ID of each stream.
+    pub fn prepare(&mut self, keyspace: &mut Keyspace) {
+        if let BlockingOp::XRead { streams, .. } = self {
+            resolve_new(keyspace, streams);
+        }
+    }
+
+    fn is_ready(&self, key: &Bytes, data: &Data) -> bool {
+        match self {
+            BlockingOp::XRead { streams, .. } => match data {
+                Data::Stream(data) => streams
+                    .iter()
+                    .any(|(k, from)| k == key && has_entries(data.get_stream(), *from)),
+                _ => false,
+            },
+            BlockingOp::XReadGroup { group, .. } => match data {
+                Data::Stream(data) => has_group_entries(data.get_stream(), group),
+                _ => false,
+            },
+            BlockingOp::ZPop { .. } | BlockingOp::ZMPop { .. } => matches!(data, Data::ZSet(_)),
+            _ => matches!(data, Data::List(_)),
+        }
+    }
+
+    /// Runs the operation on `key`, returning the reply and the non-blocking
+    /// command to propagate if it wrote, or `None` when the key has no data.
+    fn serve_key(
+        &self,
+        keyspace: &mut Keyspace,
+        key: &Bytes,
+    ) -> Result<Option<Served>, RusdisError> {
+        match self {
+            BlockingOp::Pop(end) => Ok(pop(keyspace, key, *end, 1)?.map(|mut elements| {
+                let element = elements.pop().unwrap_or_default();
+                (
+                    Value::Array(vec![
+                        Value::BulkString(key.clone()),
+                        Value::BulkString(element),
+                    ]),
+                    Some(vec![end_name(*end, true), key.clone()]),
+                )
+            })),
+            BlockingOp::Move {
+                destination,
+                from,
+                to,
+            } => Ok(
+                move_element(keyspace, key, destination, *from, *to)?.map(|element| {
+                    (
+                        Value::BulkString(element),
+                        Some(vec![
+                            Bytes::from("LMOVE"),
+                            key.clone(),
+                            destination.clone(),
+                            end_name(*from, false),
+                            end_name(*to, false),
+                        ]),
+                    )
+                }),
+            ),
+            BlockingOp::MPop { end, count } => {
+                Ok(pop(keyspace, key, *end, *count)?.map(|elements| {
+                    (
+                        Value::Array(vec![
+                            Value::BulkString(key.clone()),
+                            Value::Array(elements.into_iter().map(Value::BulkString).collect()),
+                        ]),
+                        Some(vec![
+                            end_name(*end, true),
+                            key.clone(),
+                            Bytes::from(count.to_string()),
+                        ]),
+                    )
+                }))
+            }
+            BlockingOp::ZPop { max } => Ok(pop_key(keyspace, key, *max, 1)?.and_then(|popped| {
+                let (member, score) = popped.into_iter().next()?;
+                Some((
+                    Value::Array(vec![
+                        Value::BulkString(key.clone()),
+                        Value::BulkString(member),
+                        Value::Double(score),
+                    ]),
+                    Some(vec![zpop_name(*max), key.clone()]),
+                ))
+            })),
+            BlockingOp::ZMPop { max, count } => {
+                Ok(pop_key(keyspace, key, *max, *count)?.map(|popped| {
+                    (
+                        mpop_reply(key, popped),
+                        Some(vec![
+                            zpop_name(*max),
+                            key.clone(),
+                            Bytes::from(count.to_string()),
+                        ]),
+                    )
+                }))
+            }
+            BlockingOp::XRead { streams, count } => {
+                Ok(read(keyspace, streams, *count)?.map(|reply| (reply, None)))
+            }
+            BlockingOp::XReadGroup {
+                group,
+                consumer,
+                streams,
+                count,
+                noack,
+            } => Ok(
+                read_group(keyspace, group, consumer, streams, *count, *noack)?.map(|reply| {
+                    (
+                        reply,
+                        Some(read_group_argv(group, consumer, streams, *count, *noack)),
+                    )
+                }),
+            ),
+        }
+    }
+}
+
+/// Runs `op` on the first of `keys` that has data, without blocking.
+pub fn try_serve(
+    keyspace: &mut Keyspace,
+    keys: &[Bytes],
+    op: &BlockingOp,
+) -> Result<Option<Served>, RusdisError> {
+    for key in keys.iter() {
+        if let Some(served) = op.serve_key(keyspace, key)? {
+            return Ok(Some(served));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Parses the timeout of a blocking command, in seconds; zero blocks forever.
+pub fn parse_timeout(bytes: &Bytes) -> Result<Option<Duration>, RusdisError> {
+    let timeout = std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|timeout| timeout.parse::<f64>().ok())
+        .filter(|timeout| timeout.is_finite())
+        .ok_or_else(|| RusdisError::Generic {
+            msg: "timeout is not a float or out of range".to_string(),
+        })?;
+
+    if timeout < 0.0 {
+        return Err(RusdisError::Generic {
+            msg: "timeout is negative".to_string(),
+        });
+    }
+
+    if timeout == 0.0 {
+        return Ok(None);
+    }
+    Duration::try_from_secs_f64(timeout)
+        .map(Some)
+        .map_err(|_| RusdisError::Generic {
+            msg: "timeout is out of range".to_string(),
+        })
+}
+
+#[derive(Debug)]
+struct Waiter {
+    keys: Vec<Bytes>,
+    op: BlockingOp,
+    tx: oneshot::Sender<Value>,
+}
+
+/// Clients blocked on keys, each key serving its clients in the order they blocked.
+#[derive(Debug, Default)]
+pub struct BlockedClients {
+    next_id: u64,
+    by_key: HashMap<Bytes, VecDeque<u64>>,
+    waiters: HashMap<u64, Waiter>,
+}
+
+impl BlockedClients {
+    /// Blocks a client on `keys`; the receiver gets the reply once it is served.
+    pub fn block(&mut self, keys: Vec<Bytes>, op: BlockingOp) -> (u64, oneshot::Receiver<Value>) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        for key in keys.iter() {
+            let queue = self.by_key.entry(key.clone()).or_default();
+            if !queue.contains(&id) {
+                queue.push_back(id);
+            }
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.waiters.insert(id, Waiter { keys, op, tx });
+
+        (id, rx)
+    }
+
+    /// Removes a client, returning false if it was already served.
+    pub fn unblock(&mut self, id: u64) -> bool {
+        self.remove(id).is_some()
+    }
+
+    fn remove(&mut self, id: u64) -> Option<Waiter> {
+        let waiter = self.waiters.remove(&id)?;
+        for key in waiter.keys.iter() {
+            if let Some(queue) = self.by_key.get_mut(key) {
+                queue.retain(|other| *other != id);
+                if queue.is_empty() {
+                    self.by_key.remove(key);
+                }
+            }
+        }
+
+        Some(waiter)
+    }
+
+    /// Serves the clients blocked on the keys a command wrote to, oldest
+    /// first, and returns the commands run for them. Keys a served client
+    /// pushed to are served in turn.
+    pub fn serve(&mut self, keyspace: &mut Keyspace, keys: Vec<Bytes>) -> Vec<Vec<Bytes>> {
+        let mut served = vec![];
+        let mut ready: VecDeque<Bytes> = keys.into();
+
+        while let Some(key) = ready.pop_front() {
+            // the oldest client waiting for the type the key holds; list and
+            // sorted set waiters may share a key
+            while self.by_key.contains_key(&key) {
+                let data = match get_live(keyspace, &key) {
+                    Some(data) => data,
+                    None => break,
+                };
+                let id = match self.by_key.get(&key).and_then(|queue| {
+                    queue.iter().copied().find(|id| {
+                        let waiter = &self.waiters[id];
+                        waiter.tx.is_closed() || waiter.op.is_ready(&key, data)
+                    })
+                }) {
+                    Some(id) => id,
+                    None => break,
+                };
+                let waiter = &self.waiters[&id];
+                if waiter.tx.is_closed() {
+                    self.remove(id);
+                    continue;
+                }
+
+                let reply = match waiter.op.serve_key(keyspace, &key) {
+                    Ok(Some((reply, argv))) => {
+                        served.extend(argv);
+                        reply
+                    }
+                    Ok(None) => break,
+                    Err(e) => e.to_reply(),
+                };
+                if let Some(waiter) = self.remove(id) {
+                    if let BlockingOp::Move { destination, .. } = &waiter.op {
+                        ready.push_back(destination.clone());
+                    }
+                    let _ = waiter.tx.send(reply);
+                }
+            }
+        }
+
+        served
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::list::push;
+
+    fn key(key: &str) -> Bytes {
+        Bytes::from(key.to_string())
+    }
+
+    #[test]
+    fn test_blocking_parse_timeout() {
+        assert_eq!(parse_timeout(&key("0")).unwrap(), None);
+        assert_eq!(
+            parse_timeout(&key("0.5")).unwrap(),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            parse_timeout(&key("-1")).unwrap_err().to_string(),
+            "ERR timeout is negative"
+        );
+        assert_eq!(
+            parse_timeout(&key("abc")).unwrap_err().to_string(),
+            "ERR timeout is not a float or out of range"
+        );
+        assert_eq!(
+            parse_timeout(&key("1e20")).unwrap_err().to_string(),
+            "ERR timeout is out of range"
+        );
+    }
+
+    #[test]
+    fn test_blocking_serves_in_fifo_order() {
+        let mut keyspace = Keyspace::new();
+        let mut blocked = BlockedClients::default();
+
+        let (_, mut first) = blocked.block(vec![key("a"), key("b")], BlockingOp::Pop(End::Left));
+        let (second_id, mut second) = blocked.block(vec![key("b")], BlockingOp::Pop(End::Right));
+
+        push(&mut keyspace, &key("b"), End::Right, vec![key("x")]);
+        let served = blocked.serve(&mut keyspace, vec![key("b")]);
+
+        assert_eq!(
+            first.try_recv().unwrap(),
+            Value::Array(vec![
+                Value::BulkString(key("b")),
+                Value::BulkString(key("x"))
+            ])
+        );
+        assert!(second.try_recv().is_err());
+        assert!(keyspace.is_empty());
+        assert_eq!(served, vec![vec![key("LPOP"), key("b")]]);
+
+        push(&mut keyspace, &key("a"), End::Right, vec![key("y")]);
+        blocked.serve(&mut keyspace, vec![key("a")]);
+        assert!(second.try_recv().is_err());
+        assert_eq!(keyspace.len(), 1);
+
+        assert!(blocked.unblock(second_id));
+        assert!(blocked.by_key.is_empty());
+    }
+
+    #[test]
+    fn test_blocking_serves_only_ready_keys() {
+        let mut keyspace = Keyspace::new();
+        let mut blocked = BlockedClients::default();
+
+        let (_, mut waiter) = blocked.block(vec![key("a")], BlockingOp::Pop(End::Left));
+        push(&mut keyspace, &key("a"), End::Right, vec![key("x")]);
+
+        assert!(blocked.serve(&mut keyspace, vec![key("b")]).is_empty());
+        assert!(waiter.try_recv().is_err());
+
+        blocked.serve(&mut keyspace, vec![key("a")]);
+        assert!(waiter.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_blocking_move_wakes_destination_waiters() {
+        let mut keyspace = Keyspace::new();
+        let mut blocked = BlockedClients::default();
+
+        let (_, mut mover) = blocked.block(
+            vec![key("src")],
+            BlockingOp::Move {
+                destination: key("dst"),
+                from: End::Left,
+                to: End::Left,
+            },
+        );
+        let (_, mut popper) = blocked.block(
+            vec![key("dst")],
+            BlockingOp::MPop {
+                end: End::Left,
+                count: 2,
+            },
+        );
+
+        push(&mut keyspace, &key("src"), End::Right, vec![key("x")]);
+        blocked.serve(&mut keyspace, vec![key("src")]);
+
+        assert_eq!(mover.try_recv().unwrap(), Value::BulkString(key("x")));
+        assert_eq!(
+            popper.try_recv().unwrap(),
+            Value::Array(vec![
+                Value::BulkString(key("dst")),
+                Value::Array(vec![Value::BulkString(key("x"))])
+            ])
+        );
+        assert!(keyspace.is_empty());
+    }
+
+    #[test]
+    fn test_blocking_sorted_set_pops() {
+        let mut keyspace = Keyspace::new();
+        let mut blocked = BlockedClients::default();
+
+        let (_, mut list_waiter) = blocked.block(vec![key("a")], BlockingOp::Pop(End::Left));
+        let (_, mut min) = blocked.block(vec![key("a")], BlockingOp::ZPop { max: false });
+        let (_, mut mpop) = blocked.block(
+            vec![key("b"), key("a")],
+            BlockingOp::ZMPop {
+                max: true,
+                count: 5,
+            },
+        );
+
+        let zset = keyspace
+            .entry(key("a"))
+            .or_insert(Data::ZSet(crate::data::ZSetData::new(
+                crate::sorted_set::SortedSet::new(),
+            )));
+        if let Data::ZSet(data) = zset {
+            for (member, score) in [("x", 1.0), ("y", 2.0), ("z", 3.0)] {
+                data.get_zset_mut().insert(key(member), score);
+            }
+        }
+        let served = blocked.serve(&mut keyspace, vec![key("a")]);
+
+        assert!(list_waiter.try_recv().is_err());
+        assert_eq!(
+            min.try_recv().unwrap(),
+            Value::Array(vec![
+                Value::BulkString(key("a")),
+                Value::BulkString(key("x")),
+                Value::Double(1.0)
+            ])
+        );
+        assert_eq!(
+            mpop.try_recv().unwrap(),
+            mpop_reply(&key("a"), vec![(key("z"), 3.0), (key("y"), 2.0)])
+        );
+        assert!(keyspace.is_empty());
+        assert_eq!(
+            served,
+            vec![
+                vec![key("ZPOPMIN"), key("a")],
+                vec![key("ZPOPMAX"), key("a"), key("5")]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blocking_xread_waits_for_new_entries() {
+        use crate::data::StreamData;
+        use crate::stream::{Stream, StreamId};
+
+        let mut keyspace = Keyspace::new();
+        let mut blocked = BlockedClients::default();
+        let mut stream = Stream::new();
+        stream.append(StreamId::new(1, 0), vec![(key("f"), key("old"))]);
+        keyspace.insert(key("s"), Data::Stream(StreamData::new(stream)));
+
+        let mut op = BlockingOp::XRead {
+            streams: vec![(key("s"), ReadFrom::New), (key("t"), ReadFrom::New)],
+            count: 0,
+        };
+        op.prepare(&mut keyspace);
+        assert!(try_serve(&mut keyspace, &[key("s"), key("t")], &op)
+            .unwrap()
+            .is_none());
+        let (_, mut reader) = blocked.block(vec![key("s"), key("t")], op);
+
+        blocked.serve(&mut keyspace, vec![key("s")]);
+        assert!(reader.try_recv().is_err());
+
+        if let Some(Data::Stream(data)) = keyspace.get_mut(&key("s")) {
+            data.get_stream_mut()
+                .append(StreamId::new(2, 0), vec![(key("f"), key("new"))]);
+        }
+        let served = blocked.serve(&mut keyspace, vec![key("s")]);
+
+        assert_eq!(
+            reader.try_recv().unwrap(),
+            Value::ArrayMap(vec![(
+                Value::BulkString(key("s")),
+                Value::Array(vec![Value::Array(vec![
+                    Value::BulkString(key("2-0")),
+                    Value::Array(vec![
+                        Value::BulkString(key("f")),
+                        Value::BulkString(key("new"))
+                    ])
+                ])])
+            )])
+        );
+        // reads write nothing to propagate
+        assert!(served.is_empty());
+    }
+
+    #[test]
+    fn test_blocking_xreadgroup_delivers_to_one_consumer() {
+        use crate::data::StreamData;
+        use crate::stream::{
+            execute_stream_command, ConsumerGroup, Stream, StreamCommand, StreamId,
+        };
+
+        let mut keyspace = Keyspace::new();
+        let mut blocked = BlockedClients::default();
+        let mut stream = Stream::new();
+        stream.append(StreamId::new(1, 0), vec![(key("f"), key("old"))]);
+        stream.insert_group(key("g"), ConsumerGroup::new(StreamId::new(1, 0), Some(1)));
+        keyspace.insert(key("s"), Data::Stream(StreamData::new(stream)));
+
+        let read_op = |consumer: &str| BlockingOp::XReadGroup {
+            group: key("g"),
+            consumer: key(consumer),
+            streams: vec![(key("s"), GroupReadFrom::New)],
+            count: 1,
+            noack: false,
+        };
+        assert!(try_serve(&mut keyspace, &[key("s")], &read_op("alice"))
+            .unwrap()
+            .is_none());
+        let (_, mut alice) = blocked.block(vec![key("s")], read_op("alice"));
+        let (_, mut bob) = blocked.block(vec![key("s")], read_op("bob"));
+
+        if let Some(Data::Stream(data)) = keyspace.get_mut(&key("s")) {
+            data.get_stream_mut()
+                .append(StreamId::new(2, 0), vec![(key("f"), key("new"))]);
+        }
+        let served = blocked.serve(&mut keyspace, vec![key("s")]);
+
+        assert_eq!(
+            alice.try_recv().unwrap(),
+            Value::ArrayMap(vec![(
+                Value::BulkString(key("s")),
+                Value::Array(vec![Value::Array(vec![
+                    Value::BulkString(key("2-0")),
+                    Value::Array(vec![
+                        Value::BulkString(key("f")),
+                        Value::BulkString(key("new"))
+                    ])
+                ])])
+            )])
+        );
+        assert!(bob.try_recv().is_err());
+        assert_eq!(
+            served,
+            vec![[
+                "XREADGROUP",
+                "GROUP",
+                "g",
+                "alice",
+                "COUNT",
+                "1",
+                "STREAMS",
+                "s",
+                ">"
+            ]
+            .map(key)
+            .to_vec()]
+        );
+
+        // destroying the group errors the clients waiting on it
+        execute_stream_command(
+            &mut keyspace,
+            StreamCommand::GroupDestroy {
+                key: key("s"),
+                group: key("g"),
+            },
+        )
+        .unwrap();
+        blocked.serve(&mut keyspace, vec![key("s")]);
+        assert_eq!(
+            bob.try_recv().unwrap(),
+            Value::SimpleError(
+                "NOGROUP No such key 's' or consumer group 'g' in XREADGROUP with GROUP option"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_blocking_skips_gone_clients() {
+        let mut keyspace = Keyspace::new();
+        let mut blocked = BlockedClients::default();
+
+        let (_, gone) = blocked.block(vec![key("a")], BlockingOp::Pop(End::Left));
+        let (_, mut waiting) = blocked.block(vec![key("a")], BlockingOp::Pop(End::Left));
+        drop(gone);
+
+        push(&mut keyspace, &key("a"), End::Right, vec![key("x")]);
+        blocked.serve(&mut keyspace, vec![key("a")]);
+
+        assert!(waiting.try_recv().is_ok());
+        assert!(blocked.waiters.is_empty());
+    }
+}
@@ -1,3 +1,6 @@
+use crate::parser::{
+    DecoderLimits, DEFAULT_MAX_BUFFER_LEN, DEFAULT_MAX_BULK_LEN, DEFAULT_MAX_MULTIBULK_LEN,
+};
 use clap::Parser;
 
 /// Rusdis
@@ -18,6 +21,18 @@ pub struct Args {
     /// Master to listen to
     #[arg(long)]
     pub replicaof: Option<String>,
+
+    /// Maximum length of a single bulk string sent by a client
+    #[arg(long, default_value_t = DEFAULT_MAX_BULK_LEN)]
+    pub proto_max_bulk_len: usize,
+
+    /// Maximum number of arguments in a single client command
+    #[arg(long, default_value_t = DEFAULT_MAX_MULTIBULK_LEN)]
+    pub proto_max_multibulk_len: usize,
+
+    /// Maximum number of bytes buffered for a client's incomplete command
+    #[arg(long, default_value_t = DEFAULT_MAX_BUFFER_LEN)]
+    pub client_query_buffer_limit: usize,
 }
 
 impl Args {
@@ -27,6 +42,18 @@ impl Args {
             dbfilename: None,
             port: None,
             replicaof: None,
+            proto_max_bulk_len: DEFAULT_MAX_BULK_LEN,
+            proto_max_multibulk_len: DEFAULT_MAX_MULTIBULK_LEN,
+            client_query_buffer_limit: DEFAULT_MAX_BUFFER_LEN,
+        }
+    }
+
+    /// Limits applied to the commands clients send.
+    pub fn decoder_limits(&self) -> DecoderLimits {
+        DecoderLimits {
+            max_bulk_len: self.proto_max_bulk_len,
+            max_multibulk_len: self.proto_max_multibulk_len,
+            max_buffer_len: self.client_query_buffer_limit,
         }
     }
 }
@@ -1,21 +1,37 @@
+use crate::bitmap::BitmapCommand;
+use crate::blocking::BlockingOp;
+use crate::command_table::lookup_command;
+use crate::geo::GeoCommand;
+use crate::hash::{FieldExpiry, HashCommand};
+use crate::hyperloglog::HllCommand;
+use crate::list::ListCommand;
+use crate::set::SetCommand;
+use crate::stream::StreamCommand;
+use crate::string::StringCommand;
+use crate::zset::ZSetCommand;
 use crate::{RusdisError, Value};
-use regex::Regex;
+use bytes::Bytes;
+use std::time::Duration;
 
-const NOT_BULK_STRING: &'static str = "Not Bulk String in command";
+const NOT_BULK_STRING: &str = "Not Bulk String in command";
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Command {
+    /// SET, with PX or PXAT as `expiry`
     Set {
-        key: String,
-        value: String,
-        px: Option<u64>,
+        key: Bytes,
+        value: Bytes,
+        expiry: Option<FieldExpiry>,
+        keep_ttl: bool,
     },
-    Get(String),
+    Get(Bytes),
+    /// The other commands on string values
+    String(StringCommand),
     Ping,
-    Echo(String),
+    Echo(Bytes),
     Config(ConfigSubcommand),
     Keys(String),
-    Incr(String),
+    Incr(Bytes),
     Multi,
     Exec,
     Discard,
@@ -25,6 +41,44 @@ pub enum Command {
         id: Option<String>,
         offset: Option<u64>,
     },
+    Hello {
+        protover: Option<i64>,
+        auth: Option<(Bytes, Bytes)>,
+        setname: Option<Bytes>,
+    },
+    /// The `COMMAND` family
+    Introspect(CommandSubcommand),
+    /// `<container> HELP`, holding the container's name
+    Help(Bytes),
+    List(ListCommand),
+    Hash(HashCommand),
+    /// Commands on set values, `Set` being the SET string command
+    SetType(SetCommand),
+    Type(Bytes),
+    ZSet(ZSetCommand),
+    Stream(StreamCommand),
+    /// Bit-level commands on string values
+    Bitmap(BitmapCommand),
+    HyperLogLog(HllCommand),
+    /// Geospatial commands on sorted set values, GEOADD being a ZADD
+    Geo(GeoCommand),
+    /// Blocking commands, waiting for one of `keys` to have data for up to
+    /// `timeout`, or forever when it is `None`
+    Block {
+        keys: Vec<Bytes>,
+        op: BlockingOp,
+        timeout: Option<Duration>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandSubcommand {
+    /// Bare `COMMAND`
+    All,
+    Count,
+    Info(Vec<Bytes>),
+    Docs(Vec<Bytes>),
+    GetKeys(Vec<Bytes>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -43,7 +97,7 @@ pub enum CapaOption {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConfigSubcommand {
-    Get(ConfigGetOption),
+    Get(Vec<ConfigGetOption>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -55,404 +109,334 @@ pub enum InfoSection {
 pub enum ConfigGetOption {
     Dir,
     DbFilename,
+    ProtoMaxBulkLen,
+    ProtoMaxMultibulkLen,
+    ClientQueryBufferLimit,
 }
 
-pub fn parse_command(value_vec: Vec<Value>) -> Result<Command, RusdisError> {
-    let mut value_iter = value_vec.into_iter();
+/// Upper-cased text of a keyword argument such as a command name, subcommand or flag.
+pub(crate) fn to_keyword(bytes: &Bytes) -> String {
+    String::from_utf8_lossy(bytes).to_uppercase()
+}
 
-    let command = value_iter.next();
-    if command.is_none() {
-        return Err(RusdisError::CommandParserError {
-            msg: "No leading command".to_string(),
-        });
-    }
-    let command = command.unwrap();
-
-    if let Value::BulkString(cmd) = command {
-        let cmd = cmd.to_uppercase();
-        match cmd.as_str() {
-            "SET" => parse_set_command(value_iter),
-            "GET" => parse_get_command(value_iter),
-            "PING" => Ok(Command::Ping),
-            "ECHO" => parse_echo_command(value_iter),
-            "CONFIG" => parse_config_command(value_iter),
-            "KEYS" => parse_keys_command(value_iter),
-            "INCR" => parse_incr_command(value_iter),
-            "MULTI" => Ok(Command::Multi),
-            "EXEC" => Ok(Command::Exec),
-            "DISCARD" => Ok(Command::Discard),
-            "INFO" => parse_info_command(value_iter),
-            "REPLCONF" => parse_replconf_command(value_iter),
-            "PSYNC" => parse_psync_command(value_iter),
+fn to_text(bytes: &Bytes) -> String {
+    String::from_utf8_lossy(bytes).to_string()
+}
+
+pub(crate) fn parse_number<T>(bytes: &Bytes) -> Result<T, RusdisError>
+where
+    T: std::str::FromStr<Err = std::num::ParseIntError>,
+{
+    to_text(bytes)
+        .parse::<T>()
+        .map_err(|_| RusdisError::NotInteger)
+}
+
+/// Arguments of a command as sent, command name included.
+pub fn to_argv(value_vec: Vec<Value>) -> Result<Vec<Bytes>, RusdisError> {
+    value_vec
+        .into_iter()
+        .map(|value| match value {
+            Value::BulkString(arg) => Ok(arg),
             _ => Err(RusdisError::CommandParserError {
-                msg: "Unrecognized command".to_string(),
+                msg: NOT_BULK_STRING.to_string(),
             }),
-        }
-    } else {
-        Err(RusdisError::CommandParserError {
-            msg: "Invalid command format".to_string(),
         })
-    }
+        .collect()
+}
+
+pub fn parse_command(value_vec: Vec<Value>) -> Result<Command, RusdisError> {
+    let argv = to_argv(value_vec)?;
+    let spec = lookup_command(&argv)?;
+
+    spec.parse(&argv)
+}
+
+pub(crate) fn parse_command_command(_argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Introspect(CommandSubcommand::All))
+}
+
+pub(crate) fn parse_command_count_command(_argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Introspect(CommandSubcommand::Count))
 }
 
-fn parse_psync_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
-    match (iter.next(), iter.next()) {
-        (Some(id_value), Some(offset_value)) => match (id_value, offset_value) {
-            (Value::BulkString(id_string), Value::BulkString(offset_string)) => {
-                Ok(Command::Psync {
-                    id: if id_string.len() != 40 {
-                        None
-                    } else {
-                        Some(id_string)
-                    },
-                    offset: match offset_string.parse::<u64>() {
-                        Ok(offset) => Some(offset),
-                        Err(_) => None,
-                    },
+pub(crate) fn parse_command_info_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Introspect(CommandSubcommand::Info(
+        argv[2..].to_vec(),
+    )))
+}
+
+pub(crate) fn parse_command_docs_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Introspect(CommandSubcommand::Docs(
+        argv[2..].to_vec(),
+    )))
+}
+
+pub(crate) fn parse_command_getkeys_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Introspect(CommandSubcommand::GetKeys(
+        argv[2..].to_vec(),
+    )))
+}
+
+pub(crate) fn parse_help_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Help(argv[0].clone()))
+}
+
+pub(crate) fn parse_hello_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let mut protover = None;
+    let mut auth = None;
+    let mut setname = None;
+
+    let mut iter = argv[1..].iter();
+    if let Some(v) = iter.next() {
+        protover = Some(
+            parse_number::<i64>(v).map_err(|_| RusdisError::CommandParserError {
+                msg: "Protocol version is not an integer or out of range".to_string(),
+            })?,
+        );
+    }
+
+    while let Some(option) = iter.next() {
+        match (to_keyword(option).as_str(), iter.next()) {
+            ("AUTH", Some(username)) => match iter.next() {
+                Some(password) => auth = Some((username.clone(), password.clone())),
+                None => {
+                    return Err(RusdisError::CommandParserError {
+                        msg: format!("Syntax error in HELLO option '{}'", to_text(option)),
+                    })
+                }
+            },
+            ("SETNAME", Some(name)) => setname = Some(name.clone()),
+            _ => {
+                return Err(RusdisError::CommandParserError {
+                    msg: format!("Syntax error in HELLO option '{}'", to_text(option)),
                 })
             }
-            _ => Err(RusdisError::CommandParserError {
-                msg: NOT_BULK_STRING.to_string(),
-            }),
-        },
-        _ => Err(RusdisError::CommandParserError {
-            msg: "Missing parameters in PSYNC command".to_string(),
-        }),
+        }
     }
+
+    Ok(Command::Hello {
+        protover,
+        auth,
+        setname,
+    })
 }
 
-fn parse_replconf_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
-    let subcommand_bulk_string = iter.next();
-    if subcommand_bulk_string.is_none() {
-        return Err(RusdisError::CommandParserError {
-            msg: "No subcommand after REPLCONF".to_string(),
-        });
-    }
-    let subcommand_bulk_string = subcommand_bulk_string.unwrap();
-
-    match subcommand_bulk_string {
-        Value::BulkString(subcommand) => {
-            let subcommand = subcommand.to_uppercase();
-            match subcommand.as_str() {
-                "LISTENING-PORT" => Ok(Command::Replconf(parse_replconf_listening_port_command(
-                    iter,
-                )?)),
-                "CAPA" => Ok(Command::Replconf(parse_replconf_capa_command(iter)?)),
-                "GETACK" => Ok(Command::Replconf(parse_replconf_getack_command(iter)?)),
-                "ACK" => Ok(Command::Replconf(parse_replconf_ack_command(iter)?)),
-                _ => Err(RusdisError::CommandParserError {
-                    msg: "Invalid REPLCONF subcommand".to_string(),
-                }),
-            }
+pub(crate) fn parse_psync_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (id_string, offset_string) = (&argv[1], &argv[2]);
+
+    Ok(Command::Psync {
+        id: if id_string.len() != 40 {
+            None
+        } else {
+            Some(to_text(id_string))
+        },
+        offset: parse_number::<u64>(offset_string).ok(),
+    })
+}
+
+pub(crate) fn parse_replconf_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let subcommand = match argv.get(1) {
+        Some(subcommand) => subcommand,
+        None => {
+            return Err(RusdisError::WrongArity {
+                command: "replconf".to_string(),
+            })
         }
+    };
+
+    let args = &argv[2..];
+    match to_keyword(subcommand).as_str() {
+        "LISTENING-PORT" => Ok(Command::Replconf(parse_replconf_listening_port_command(
+            args,
+        )?)),
+        "CAPA" => Ok(Command::Replconf(parse_replconf_capa_command(args)?)),
+        "GETACK" => Ok(Command::Replconf(parse_replconf_getack_command(args)?)),
+        "ACK" => Ok(Command::Replconf(parse_replconf_ack_command(args)?)),
         _ => Err(RusdisError::CommandParserError {
-            msg: NOT_BULK_STRING.to_string(),
+            msg: format!("Unrecognized REPLCONF option: {}", to_text(subcommand)),
         }),
     }
 }
 
-fn parse_replconf_ack_command(
-    mut iter: impl Iterator<Item = Value>,
-) -> Result<ReplconfSubcommand, RusdisError> {
-    match iter.next() {
-        Some(bulk_string) => {
-            if let Value::BulkString(s) = bulk_string {
-                let offset = s.parse::<u64>()?;
-                Ok(ReplconfSubcommand::Ack(offset))
-            } else {
-                Err(RusdisError::CommandParserError {
-                    msg: NOT_BULK_STRING.to_string(),
-                })
-            }
+fn parse_replconf_ack_command(args: &[Bytes]) -> Result<ReplconfSubcommand, RusdisError> {
+    match args.first() {
+        Some(s) => {
+            let offset = parse_number::<u64>(s)?;
+            Ok(ReplconfSubcommand::Ack(offset))
         }
-        None => Err(RusdisError::CommandParserError {
-            msg: "No parameter in ACK subcommand".to_string(),
-        }),
+        None => Err(RusdisError::SyntaxError),
     }
 }
 
-fn parse_replconf_getack_command(
-    mut iter: impl Iterator<Item = Value>,
-) -> Result<ReplconfSubcommand, RusdisError> {
-    match iter.next() {
-        Some(bulk_string) => {
-            if let Value::BulkString(s) = bulk_string {
-                Ok(ReplconfSubcommand::Getack(s))
-            } else {
-                Err(RusdisError::CommandParserError {
-                    msg: NOT_BULK_STRING.to_string(),
-                })
-            }
-        }
-        None => Err(RusdisError::CommandParserError {
-            msg: "No parameter in GETACK subcommand".to_string(),
-        }),
+fn parse_replconf_getack_command(args: &[Bytes]) -> Result<ReplconfSubcommand, RusdisError> {
+    match args.first() {
+        Some(s) => Ok(ReplconfSubcommand::Getack(to_text(s))),
+        None => Err(RusdisError::SyntaxError),
     }
 }
 
-fn parse_replconf_capa_command(
-    mut iter: impl Iterator<Item = Value>,
-) -> Result<ReplconfSubcommand, RusdisError> {
+fn parse_replconf_capa_command(args: &[Bytes]) -> Result<ReplconfSubcommand, RusdisError> {
     let mut options = vec![];
-    while let Some(value) = iter.next() {
-        if let Value::BulkString(s) = value {
-            let s = s.to_uppercase();
-            match s.as_str() {
-                "EOF" => options.push(CapaOption::Eof),
-                "PSYNC2" => options.push(CapaOption::Psync2),
-                _ => {}
-            }
-        } else {
-            return Err(RusdisError::CommandParserError {
-                msg: NOT_BULK_STRING.to_string(),
-            });
+    for s in args.iter() {
+        match to_keyword(s).as_str() {
+            "EOF" => options.push(CapaOption::Eof),
+            "PSYNC2" => options.push(CapaOption::Psync2),
+            _ => {}
         }
     }
 
-    if options.len() == 0 {
-        return Err(RusdisError::CommandParserError {
-            msg: "No options after capa".to_string(),
-        });
+    if options.is_empty() {
+        return Err(RusdisError::SyntaxError);
     }
 
     Ok(ReplconfSubcommand::Capa(options))
 }
 
 fn parse_replconf_listening_port_command(
-    mut iter: impl Iterator<Item = Value>,
+    args: &[Bytes],
 ) -> Result<ReplconfSubcommand, RusdisError> {
-    match iter.next() {
-        Some(p_bulk_string) => {
-            if let Value::BulkString(p) = p_bulk_string {
-                let port = p.parse::<u16>()?;
-                Ok(ReplconfSubcommand::ListeningPort(port))
-            } else {
-                Err(RusdisError::CommandParserError {
-                    msg: NOT_BULK_STRING.to_string(),
-                })
-            }
+    match args.first() {
+        Some(p) => {
+            let port = parse_number::<u16>(p)?;
+            Ok(ReplconfSubcommand::ListeningPort(port))
         }
-        None => Err(RusdisError::CommandParserError {
-            msg: "REPLCONF listening-port without port parameter".to_string(),
-        }),
+        None => Err(RusdisError::SyntaxError),
     }
 }
 
-fn parse_info_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
+pub(crate) fn parse_info_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
     let mut sections = vec![];
 
-    while let Some(value) = iter.next() {
-        if let Value::BulkString(s) = value {
-            let s = s.to_uppercase();
-            match s.as_str() {
-                "REPLICATION" => {
-                    sections.push(InfoSection::Replication);
-                }
-                _ => {}
-            }
-        } else {
-            return Err(RusdisError::CommandParserError {
-                msg: NOT_BULK_STRING.to_string(),
-            });
+    for s in argv[1..].iter() {
+        if to_keyword(s).as_str() == "REPLICATION" {
+            sections.push(InfoSection::Replication);
         }
     }
 
     Ok(Command::Info(sections))
 }
 
-fn parse_incr_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
-    let key = iter.next();
-    if key.is_none() {
-        return Err(RusdisError::CommandParserError {
-            msg: "No key after incr command".to_string(),
-        });
-    }
-    let key = key.unwrap();
+pub(crate) fn parse_incr_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Incr(argv[1].clone()))
+}
 
-    if let Value::BulkString(key) = key {
-        Ok(Command::Incr(key))
-    } else {
-        Err(RusdisError::CommandParserError {
-            msg: NOT_BULK_STRING.to_string(),
-        })
+pub(crate) fn parse_keys_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    // The glob becomes a byte regex: every literal byte is hex-escaped so
+    // binary keys and regex metacharacters match themselves.
+    let mut new_pattern = String::from("(?s-u)^");
+
+    for b in argv[1].iter() {
+        match b {
+            b'*' => new_pattern += ".*",
+            b'?' => new_pattern += ".",
+            _ => new_pattern += format!("\\x{:02x}", b).as_str(),
+        }
     }
+    new_pattern += "$";
+
+    Ok(Command::Keys(new_pattern))
 }
 
-fn parse_keys_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
-    let pattern = iter.next();
-    if pattern.is_none() {
-        return Err(RusdisError::CommandParserError {
-            msg: "No pattern after keys command".to_string(),
-        });
+/// Parameters Redis does not have, or we do not expose, are left out of the
+/// reply rather than reported as errors.
+pub(crate) fn parse_config_get_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let mut options = vec![];
+    for parameter in argv[2..].iter() {
+        let option = match to_keyword(parameter).as_str() {
+            "DIR" => ConfigGetOption::Dir,
+            "DBFILENAME" => ConfigGetOption::DbFilename,
+            "PROTO-MAX-BULK-LEN" => ConfigGetOption::ProtoMaxBulkLen,
+            "PROTO-MAX-MULTIBULK-LEN" => ConfigGetOption::ProtoMaxMultibulkLen,
+            "CLIENT-QUERY-BUFFER-LIMIT" => ConfigGetOption::ClientQueryBufferLimit,
+            _ => continue,
+        };
+        if !options.contains(&option) {
+            options.push(option);
+        }
     }
-    let pattern = pattern.unwrap();
 
-    if let Value::BulkString(pattern) = pattern {
-        let mut new_pattern = String::new();
+    Ok(Command::Config(ConfigSubcommand::Get(options)))
+}
 
-        for b in pattern.into_bytes() {
-            if b == '*' as u8 {
-                new_pattern += ".*";
-            } else {
-                new_pattern.push(b as char);
-            }
-        }
+pub(crate) fn parse_get_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Get(argv[1].clone()))
+}
 
-        Ok(Command::Keys(new_pattern))
-    } else {
-        Err(RusdisError::CommandParserError {
-            msg: NOT_BULK_STRING.to_string(),
-        })
-    }
+pub(crate) fn parse_type_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Type(argv[1].clone()))
 }
 
-fn parse_config_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
-    match iter.next() {
-        Some(subcommand_bulk_string) => {
-            if let Value::BulkString(subcommand) = subcommand_bulk_string {
-                let subcommand = subcommand.to_uppercase();
-                match subcommand.as_str() {
-                    "GET" => Ok(Command::Config(parse_config_get_command(iter)?)),
-                    _ => Err(RusdisError::CommandParserError {
-                        msg: "Unrecognizable subcommand in config command".to_string(),
-                    }),
+pub(crate) fn parse_set_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (key, value) = (argv[1].clone(), argv[2].clone());
+    let mut expiry: Option<FieldExpiry> = None;
+    let mut keep_ttl = false;
+
+    let mut iter = argv[3..].iter();
+    while let Some(s) = iter.next() {
+        let keyword = to_keyword(s);
+        match keyword.as_str() {
+            "PX" | "PXAT" => {
+                let millis = match iter.next() {
+                    Some(mil_sec_str) => parse_number::<u64>(mil_sec_str)?,
+                    None => return Err(RusdisError::SyntaxError),
+                };
+                if keep_ttl || expiry.is_some() {
+                    return Err(RusdisError::SyntaxError);
                 }
-            } else {
-                Err(RusdisError::CommandParserError {
-                    msg: NOT_BULK_STRING.to_string(),
-                })
+                expiry = Some(match keyword.as_str() {
+                    "PX" => FieldExpiry::In(millis),
+                    _ => FieldExpiry::At(millis),
+                });
             }
-        }
-        None => Err(RusdisError::CommandParserError {
-            msg: "No subcommand in config command".to_string(),
-        }),
-    }
-}
-
-fn parse_config_get_command(
-    mut iter: impl Iterator<Item = Value>,
-) -> Result<ConfigSubcommand, RusdisError> {
-    match iter.next() {
-        Some(value) => {
-            if let Value::BulkString(parameter) = value {
-                let parameter = parameter.to_uppercase();
-                match parameter.as_str() {
-                    "DIR" => Ok(ConfigSubcommand::Get(ConfigGetOption::Dir)),
-                    "DBFILENAME" => Ok(ConfigSubcommand::Get(ConfigGetOption::DbFilename)),
-                    _ => Err(RusdisError::CommandParserError {
-                        msg: "Unrecognizable config get option".to_string(),
-                    }),
+            "KEEPTTL" => {
+                if expiry.is_some() {
+                    return Err(RusdisError::SyntaxError);
                 }
-            } else {
-                Err(RusdisError::CommandParserError {
-                    msg: NOT_BULK_STRING.to_string(),
-                })
+                keep_ttl = true;
             }
+            _ => {}
         }
-        None => Err(RusdisError::CommandParserError {
-            msg: "No parameter in config get command".to_string(),
-        }),
     }
+
+    Ok(Command::Set {
+        key,
+        value,
+        expiry,
+        keep_ttl,
+    })
 }
 
-fn parse_get_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
-    match iter.next() {
-        Some(key_v) => {
-            if let Value::BulkString(key) = key_v {
-                Ok(Command::Get(key))
-            } else {
-                Err(RusdisError::CommandParserError {
-                    msg: NOT_BULK_STRING.to_string(),
-                })
-            }
-        }
-        None => Err(RusdisError::CommandParserError {
-            msg: "No key in get command".to_string(),
-        }),
-    }
+pub(crate) fn parse_echo_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Echo(argv[1].clone()))
 }
 
-fn parse_set_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
-    match (iter.next(), iter.next()) {
-        (Some(key_v), Some(value_v)) => match (key_v, value_v) {
-            (Value::BulkString(key), Value::BulkString(value)) => {
-                let mut px: Option<u64> = None;
-
-                while let Some(v) = iter.next() {
-                    if let Value::BulkString(s) = v {
-                        let s = s.to_uppercase();
-
-                        match s.as_str() {
-                            "PX" => {
-                                let value_px = iter.next();
-
-                                match value_px {
-                                    Some(mil_sec_bulk_str) => {
-                                        if let Value::BulkString(mil_sec_str) = mil_sec_bulk_str {
-                                            let mil_sec = mil_sec_str.parse::<u64>()?;
-                                            px = Some(mil_sec);
-                                        } else {
-                                            return Err(RusdisError::CommandParserError {
-                                                msg: NOT_BULK_STRING.to_string(),
-                                            });
-                                        }
-                                    }
-                                    None => {
-                                        return Err(RusdisError::CommandParserError {
-                                            msg: "No millisecond value after PX".to_string(),
-                                        })
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else {
-                        return Err(RusdisError::CommandParserError {
-                            msg: NOT_BULK_STRING.to_string(),
-                        });
-                    }
-                }
+pub(crate) fn parse_ping_command(_argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Ping)
+}
 
-                Ok(Command::Set { key, value, px })
-            }
-            _ => Err(RusdisError::CommandParserError {
-                msg: NOT_BULK_STRING.to_string(),
-            }),
-        },
-        _ => Err(RusdisError::CommandParserError {
-            msg: "No key or value in set command".to_string(),
-        }),
-    }
+pub(crate) fn parse_multi_command(_argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Multi)
 }
 
-fn parse_echo_command(mut iter: impl Iterator<Item = Value>) -> Result<Command, RusdisError> {
-    match iter.next() {
-        Some(value) => {
-            if let Value::BulkString(words) = value {
-                Ok(Command::Echo(words))
-            } else {
-                Err(RusdisError::CommandParserError {
-                    msg: NOT_BULK_STRING.to_string(),
-                })
-            }
-        }
-        None => Err(RusdisError::CommandParserError {
-            msg: "Echo without words".to_string(),
-        }),
-    }
+pub(crate) fn parse_exec_command(_argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Exec)
 }
 
-#[cfg(test)]
+pub(crate) fn parse_discard_command(_argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Discard)
+}
 
+#[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
     fn test_command_parser_set_command_without_px() {
         let test_vec = vec![
-            Value::BulkString("seT".to_string()),
-            Value::BulkString("a".to_string()),
-            Value::BulkString("30".to_string()),
+            Value::BulkString(Bytes::from("seT")),
+            Value::BulkString(Bytes::from("a")),
+            Value::BulkString(Bytes::from("30")),
         ];
 
         let res = parse_command(test_vec);
@@ -462,9 +446,10 @@ mod test {
         assert_eq!(
             res,
             Command::Set {
-                key: "a".to_string(),
-                value: "30".to_string(),
-                px: None
+                key: Bytes::from("a"),
+                value: Bytes::from("30"),
+                expiry: None,
+                keep_ttl: false
             }
         );
     }
@@ -472,11 +457,11 @@ mod test {
     #[test]
     fn test_command_parser_set_command_with_px() {
         let test_vec = vec![
-            Value::BulkString("seT".to_string()),
-            Value::BulkString("a".to_string()),
-            Value::BulkString("30".to_string()),
-            Value::BulkString("pX".to_string()),
-            Value::BulkString("5000".to_string()),
+            Value::BulkString(Bytes::from("seT")),
+            Value::BulkString(Bytes::from("a")),
+            Value::BulkString(Bytes::from("30")),
+            Value::BulkString(Bytes::from("pX")),
+            Value::BulkString(Bytes::from("5000")),
         ];
 
         let res = parse_command(test_vec);
@@ -486,9 +471,10 @@ mod test {
         assert_eq!(
             res,
             Command::Set {
-                key: "a".to_string(),
-                value: "30".to_string(),
-                px: Some(5000)
+                key: Bytes::from("a"),
+                value: Bytes::from("30"),
+                expiry: Some(FieldExpiry::In(5000)),
+                keep_ttl: false
             }
         );
     }
@@ -496,17 +482,16 @@ mod test {
     #[test]
     fn test_command_parser_set_command_with_not_bulk_string() {
         let test_vec = vec![
-            Value::BulkString("seT".to_string()),
-            Value::BulkString("a".to_string()),
-            Value::BulkString("30".to_string()),
+            Value::BulkString(Bytes::from("seT")),
+            Value::BulkString(Bytes::from("a")),
+            Value::BulkString(Bytes::from("30")),
             Value::SimpleString("pX".to_string()),
-            Value::BulkString("5000".to_string()),
+            Value::BulkString(Bytes::from("5000")),
         ];
 
         let res = parse_command(test_vec);
-        let is_err_correct = res.is_err_and(|e| {
-            e.to_string() == "Command Parser Error: Not Bulk String in command".to_string()
-        });
+        let is_err_correct =
+            res.is_err_and(|e| e.to_string() == "Command Parser Error: Not Bulk String in command");
 
         assert!(is_err_correct);
     }
@@ -514,16 +499,14 @@ mod test {
     #[test]
     fn test_command_parser_set_command_px_without_value() {
         let test_vec = vec![
-            Value::BulkString("seT".to_string()),
-            Value::BulkString("a".to_string()),
-            Value::BulkString("30".to_string()),
-            Value::BulkString("Px".to_string()),
+            Value::BulkString(Bytes::from("seT")),
+            Value::BulkString(Bytes::from("a")),
+            Value::BulkString(Bytes::from("30")),
+            Value::BulkString(Bytes::from("Px")),
         ];
 
         let res = parse_command(test_vec);
-        let is_err_correct = res.is_err_and(|e| {
-            e.to_string() == "Command Parser Error: No millisecond value after PX".to_string()
-        });
+        let is_err_correct = res.is_err_and(|e| e.to_string() == "ERR syntax error");
 
         assert!(is_err_correct);
     }
@@ -531,15 +514,16 @@ mod test {
     #[test]
     fn test_command_parser_set_command_px_with_invalid_value() {
         let test_vec = vec![
-            Value::BulkString("seT".to_string()),
-            Value::BulkString("a".to_string()),
-            Value::BulkString("30".to_string()),
-            Value::BulkString("Px".to_string()),
-            Value::BulkString("-1000".to_string()),
+            Value::BulkString(Bytes::from("seT")),
+            Value::BulkString(Bytes::from("a")),
+            Value::BulkString(Bytes::from("30")),
+            Value::BulkString(Bytes::from("Px")),
+            Value::BulkString(Bytes::from("-1000")),
         ];
 
         let res = parse_command(test_vec);
-        let is_err_correct = res.is_err_and(|e| e.to_string() == "Parse int errors".to_string());
+        let is_err_correct =
+            res.is_err_and(|e| e.to_string() == "ERR value is not an integer or out of range");
 
         assert!(is_err_correct);
     }
@@ -547,14 +531,14 @@ mod test {
     #[test]
     fn test_command_parser_set_command_with_useless_flag() {
         let test_vec = vec![
-            Value::BulkString("seT".to_string()),
-            Value::BulkString("a".to_string()),
-            Value::BulkString("30".to_string()),
-            Value::BulkString("i love rust".to_string()),
-            Value::BulkString("pX".to_string()),
-            Value::BulkString("5000".to_string()),
-            Value::BulkString("13452".to_string()),
-            Value::BulkString("ppp".to_string()),
+            Value::BulkString(Bytes::from("seT")),
+            Value::BulkString(Bytes::from("a")),
+            Value::BulkString(Bytes::from("30")),
+            Value::BulkString(Bytes::from("i love rust")),
+            Value::BulkString(Bytes::from("pX")),
+            Value::BulkString(Bytes::from("5000")),
+            Value::BulkString(Bytes::from("13452")),
+            Value::BulkString(Bytes::from("ppp")),
         ];
 
         let res = parse_command(test_vec);
@@ -564,24 +548,60 @@ mod test {
         assert_eq!(
             res,
             Command::Set {
-                key: "a".to_string(),
-                value: "30".to_string(),
-                px: Some(5000)
+                key: Bytes::from("a"),
+                value: Bytes::from("30"),
+                expiry: Some(FieldExpiry::In(5000)),
+                keep_ttl: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_command_parser_set_command_with_pxat_or_keepttl() {
+        let argv = |args: &[&str]| {
+            args.iter()
+                .map(|arg| Value::BulkString(Bytes::from(arg.to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            parse_command(argv(&["SET", "a", "30", "PXAT", "1700000000000"])).unwrap(),
+            Command::Set {
+                key: Bytes::from("a"),
+                value: Bytes::from("30"),
+                expiry: Some(FieldExpiry::At(1700000000000)),
+                keep_ttl: false
             }
         );
+        assert_eq!(
+            parse_command(argv(&["SET", "a", "30", "keepttl"])).unwrap(),
+            Command::Set {
+                key: Bytes::from("a"),
+                value: Bytes::from("30"),
+                expiry: None,
+                keep_ttl: true
+            }
+        );
+        assert!(
+            parse_command(argv(&["SET", "a", "30", "KEEPTTL", "PX", "5"]))
+                .is_err_and(|e| e.to_string() == "ERR syntax error")
+        );
+        assert!(
+            parse_command(argv(&["SET", "a", "30", "PX", "5", "PXAT", "5"]))
+                .is_err_and(|e| e.to_string() == "ERR syntax error")
+        );
     }
 
     #[test]
     fn test_command_parser_set_command_missing_key_or_value() {
         let test_vec = vec![
-            Value::BulkString("seT".to_string()),
-            Value::BulkString("a".to_string()),
+            Value::BulkString(Bytes::from("seT")),
+            Value::BulkString(Bytes::from("a")),
         ];
 
         let res = parse_command(test_vec);
-        let is_err_correct = res.is_err_and(|e| {
-            e.to_string() == "Command Parser Error: No key or value in set command".to_string()
-        });
+        let is_err_correct =
+            res.is_err_and(|e| e.to_string() == "ERR wrong number of arguments for 'set' command");
 
         assert!(is_err_correct);
     }
@@ -589,8 +609,8 @@ mod test {
     #[test]
     fn test_command_parser_get_command() {
         let test_vec = vec![
-            Value::BulkString("gEt".to_string()),
-            Value::BulkString("mypassword".to_string()),
+            Value::BulkString(Bytes::from("gEt")),
+            Value::BulkString(Bytes::from("mypassword")),
         ];
 
         let res = parse_command(test_vec);
@@ -600,12 +620,117 @@ mod test {
 
     #[test]
     fn test_command_parser_get_command_without_key() {
-        let test_vec = vec![Value::BulkString("gEt".to_string())];
+        let test_vec = vec![Value::BulkString(Bytes::from("gEt"))];
 
         let res = parse_command(test_vec);
 
         assert!(res.is_err_and(|e| {
-            e.to_string() == "Command Parser Error: No key in get command".to_string()
+            e.to_string() == "ERR wrong number of arguments for 'get' command"
+        }));
+    }
+
+    #[test]
+    fn test_command_parser_set_command_binary_value() {
+        let test_vec = vec![
+            Value::BulkString(Bytes::from("SET")),
+            Value::BulkString(Bytes::from_static(b"\xffkey")),
+            Value::BulkString(Bytes::from_static(b"\x00\x9f\r\n")),
+        ];
+
+        let res = parse_command(test_vec);
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            Command::Set {
+                key: Bytes::from_static(b"\xffkey"),
+                value: Bytes::from_static(b"\x00\x9f\r\n"),
+                expiry: None,
+                keep_ttl: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_command_parser_keys_pattern_matches_bytes() {
+        let test_vec = vec![
+            Value::BulkString(Bytes::from("KEYS")),
+            Value::BulkString(Bytes::from_static(b"a.\xff*")),
+        ];
+
+        let res = parse_command(test_vec);
+        let pattern = match res {
+            Ok(Command::Keys(pattern)) => regex::bytes::Regex::new(&pattern).unwrap(),
+            _ => panic!("expected KEYS command"),
+        };
+
+        assert!(pattern.is_match(b"a.\xff"));
+        assert!(pattern.is_match(b"a.\xff\x00tail"));
+        assert!(!pattern.is_match(b"ab\xff"));
+        assert!(!pattern.is_match(b"xa.\xff"));
+    }
+
+    #[test]
+    fn test_command_parser_hello_command() {
+        let test_vec = vec![
+            Value::BulkString(Bytes::from("hello")),
+            Value::BulkString(Bytes::from("3")),
+            Value::BulkString(Bytes::from("setname")),
+            Value::BulkString(Bytes::from("worker-1")),
+            Value::BulkString(Bytes::from("AUTH")),
+            Value::BulkString(Bytes::from("default")),
+            Value::BulkString(Bytes::from("secret")),
+        ];
+
+        let res = parse_command(test_vec);
+        assert!(res.is_ok());
+        assert_eq!(
+            res.unwrap(),
+            Command::Hello {
+                protover: Some(3),
+                auth: Some((Bytes::from("default"), Bytes::from("secret"))),
+                setname: Some(Bytes::from("worker-1")),
+            }
+        );
+
+        let res = parse_command(vec![Value::BulkString(Bytes::from("HELLO"))]);
+        assert_eq!(
+            res.unwrap(),
+            Command::Hello {
+                protover: None,
+                auth: None,
+                setname: None,
+            }
+        );
+
+        let test_vec = vec![
+            Value::BulkString(Bytes::from("HELLO")),
+            Value::BulkString(Bytes::from("3")),
+            Value::BulkString(Bytes::from("AUTH")),
+            Value::BulkString(Bytes::from("default")),
+        ];
+        assert!(parse_command(test_vec).is_err_and(|e| {
+            e.to_string() == "Command Parser Error: Syntax error in HELLO option 'AUTH'"
+        }));
+    }
+
+    #[test]
+    fn test_command_parser_unknown_command() {
+        let test_vec = vec![
+            Value::BulkString(Bytes::from("Foo")),
+            Value::BulkString(Bytes::from("a")),
+            Value::BulkString(Bytes::from("b c")),
+        ];
+
+        assert!(parse_command(test_vec).is_err_and(|e| {
+            e.to_string() == "ERR unknown command 'Foo', with args beginning with: 'a' 'b c' "
+        }));
+
+        let test_vec = vec![
+            Value::BulkString(Bytes::from("CONFIG")),
+            Value::BulkString(Bytes::from("rewrite")),
+        ];
+        assert!(parse_command(test_vec).is_err_and(|e| {
+            e.to_string() == "ERR unknown subcommand 'rewrite'. Try CONFIG HELP."
         }));
     }
 }
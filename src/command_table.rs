@@ -0,0 +1,3489 @@
+use crate::bitmap::*;
+use crate::command_parser::*;
+use crate::geo::*;
+use crate::hash::*;
+use crate::hyperloglog::*;
+use crate::list::*;
+use crate::set::*;
+use crate::stream::*;
+use crate::string::*;
+use crate::zset::*;
+use crate::{Command, RusdisError, Value};
+use bytes::Bytes;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+/// Builds a `Command` out of the arguments of a call, command name included.
+///
+/// Handlers can rely on the argument count matching the command's arity.
+pub type ParseFn = fn(&[Bytes]) -> Result<Command, RusdisError>;
+
+/// Properties of a command that tell the server how it may run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommandFlag {
+    /// May modify the dataset
+    Write,
+    /// Only reads the dataset
+    Readonly,
+    /// May grow memory usage
+    Denyoom,
+    /// Administrative command
+    Admin,
+    /// Not allowed from scripts
+    Noscript,
+    /// Allowed while the dataset is loading
+    Loading,
+    /// Allowed on a replica with stale data
+    Stale,
+    /// Runs in constant or logarithmic time
+    Fast,
+    /// Not allowed inside MULTI
+    NoMulti,
+    /// Allowed before authenticating
+    NoAuth,
+    /// May block the client
+    Blocking,
+}
+
+impl CommandFlag {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandFlag::Write => "write",
+            CommandFlag::Readonly => "readonly",
+            CommandFlag::Denyoom => "denyoom",
+            CommandFlag::Admin => "admin",
+            CommandFlag::Noscript => "noscript",
+            CommandFlag::Loading => "loading",
+            CommandFlag::Stale => "stale",
+            CommandFlag::Fast => "fast",
+            CommandFlag::NoMulti => "no_multi",
+            CommandFlag::NoAuth => "no_auth",
+            CommandFlag::Blocking => "blocking",
+        }
+    }
+}
+
+/// Where the search for keys starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BeginSearch {
+    /// At a fixed argument index
+    Index(usize),
+    /// After the first `keyword` found, searching from `start_from`; a negative
+    /// index searches backwards from the end
+    Keyword {
+        keyword: &'static str,
+        start_from: i64,
+    },
+}
+
+/// Which arguments are keys once the search has begun.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FindKeys {
+    /// Every `step`th argument up to `last_key`, relative to the start. A
+    /// negative `last_key` counts from the end, with `limit` splitting the
+    /// remaining arguments when it is -1.
+    Range {
+        last_key: i64,
+        step: usize,
+        limit: usize,
+    },
+    /// The argument at `keynum_idx` holds the number of keys, which start at
+    /// `first_key`; both are relative to the start.
+    Keynum {
+        keynum_idx: usize,
+        first_key: usize,
+        step: usize,
+    },
+}
+
+/// Describes the positions of one group of key arguments, as Redis key specs do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeySpec {
+    /// Access flags such as `RW`, `ACCESS` or `UPDATE`
+    pub flags: &'static [&'static str],
+    pub begin_search: BeginSearch,
+    pub find_keys: FindKeys,
+}
+
+impl KeySpec {
+    /// Positions in `argv` of the keys this spec describes.
+    pub fn key_positions(&self, argv: &[Bytes]) -> Vec<usize> {
+        let start = match self.begin_search {
+            BeginSearch::Index(idx) => idx,
+            BeginSearch::Keyword {
+                keyword,
+                start_from,
+            } => {
+                let found = if start_from >= 0 {
+                    (start_from as usize..argv.len())
+                        .find(|i| argv[*i].eq_ignore_ascii_case(keyword.as_bytes()))
+                } else {
+                    let from = argv
+                        .len()
+                        .saturating_sub(start_from.unsigned_abs() as usize);
+                    (1..=from)
+                        .rev()
+                        .find(|i| argv[*i].eq_ignore_ascii_case(keyword.as_bytes()))
+                };
+                match found {
+                    Some(idx) => idx + 1,
+                    None => return vec![],
+                }
+            }
+        };
+        if start >= argv.len() {
+            return vec![];
+        }
+
+        match self.find_keys {
+            FindKeys::Range {
+                last_key,
+                step,
+                limit,
+            } => {
+                let last = if last_key >= 0 {
+                    start + last_key as usize
+                } else if limit <= 1 {
+                    (argv.len() as i64 + last_key) as usize
+                } else {
+                    start + (argv.len() - start) / limit - 1
+                };
+                (start..=last.min(argv.len() - 1)).step_by(step).collect()
+            }
+            FindKeys::Keynum {
+                keynum_idx,
+                first_key,
+                step,
+            } => {
+                let numkeys = argv
+                    .get(start + keynum_idx)
+                    .and_then(|n| std::str::from_utf8(n).ok())
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .unwrap_or(0);
+                (0..numkeys)
+                    .map(|i| start + first_key + i * step)
+                    .take_while(|i| *i < argv.len())
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Human readable documentation of a command, as returned by `COMMAND DOCS`.
+#[derive(Debug)]
+pub struct CommandDocs {
+    pub summary: &'static str,
+    /// Redis version the command first appeared in
+    pub since: &'static str,
+    pub group: &'static str,
+    pub complexity: &'static str,
+}
+
+/// Static description of a command, the single source for dispatch, argument
+/// checks, replication and introspection.
+#[derive(Debug)]
+pub struct CommandSpec {
+    /// Lowercase name, `container|subcommand` for subcommands
+    pub name: &'static str,
+    /// Exact number of arguments including the name, or the negated minimum
+    pub arity: i64,
+    pub docs: CommandDocs,
+    pub flags: &'static [CommandFlag],
+    /// Categories beyond the ones implied by the flags
+    pub acl_categories: &'static [&'static str],
+    pub key_specs: &'static [KeySpec],
+    /// `None` for container commands that only dispatch to `subcommands`
+    pub parse: Option<ParseFn>,
+    pub subcommands: &'static [CommandSpec],
+}
+
+impl CommandSpec {
+    pub fn has_flag(&self, flag: CommandFlag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    /// All ACL categories, including the ones Redis derives from the flags.
+    pub fn acl_categories(&self) -> Vec<&'static str> {
+        let mut categories = self.acl_categories.to_vec();
+        if self.has_flag(CommandFlag::Write) {
+            categories.push("@write");
+        }
+        if self.has_flag(CommandFlag::Readonly) {
+            categories.push("@read");
+        }
+        if self.has_flag(CommandFlag::Admin) {
+            categories.push("@admin");
+            if !categories.contains(&"@dangerous") {
+                categories.push("@dangerous");
+            }
+        }
+        if self.has_flag(CommandFlag::Fast) {
+            categories.push("@fast");
+        }
+        if self.has_flag(CommandFlag::Blocking) {
+            categories.push("@blocking");
+        }
+        if !self.has_flag(CommandFlag::Fast) {
+            categories.push("@slow");
+        }
+        categories
+    }
+
+    fn arity_matches(&self, argc: usize) -> bool {
+        if self.arity >= 0 {
+            argc as i64 == self.arity
+        } else {
+            argc as i64 >= -self.arity
+        }
+    }
+
+    /// Positions in `argv` of all the keys the call accesses.
+    pub fn key_positions(&self, argv: &[Bytes]) -> Vec<usize> {
+        self.key_specs
+            .iter()
+            .flat_map(|spec| spec.key_positions(argv))
+            .collect()
+    }
+
+    /// All the keys the call accesses.
+    pub fn keys(&self, argv: &[Bytes]) -> Vec<Bytes> {
+        self.key_positions(argv)
+            .into_iter()
+            .map(|pos| argv[pos].clone())
+            .collect()
+    }
+
+    /// First key, last key and step of the keys, as in the legacy `COMMAND`
+    /// reply, plus whether some keys can only be found by parsing the arguments.
+    fn legacy_key_range(&self) -> (i64, i64, i64, bool) {
+        let mut range: Option<(i64, i64, i64)> = None;
+        let mut movable = false;
+        for spec in self.key_specs.iter() {
+            match (spec.begin_search, spec.find_keys) {
+                (
+                    BeginSearch::Index(idx),
+                    FindKeys::Range {
+                        last_key,
+                        step,
+                        limit: 0,
+                    },
+                ) => {
+                    let idx = idx as i64;
+                    let last = if last_key >= 0 {
+                        idx + last_key
+                    } else {
+                        last_key
+                    };
+                    range = Some(match range {
+                        None => (idx, last, step as i64),
+                        Some((first, prev_last, step)) => {
+                            let last = if prev_last < 0 || last < 0 {
+                                prev_last.min(last)
+                            } else {
+                                prev_last.max(last)
+                            };
+                            (first.min(idx), last, step)
+                        }
+                    });
+                }
+                _ => movable = true,
+            }
+        }
+
+        let (first, last, step) = range.unwrap_or((0, 0, 0));
+        (first, last, step, movable)
+    }
+
+    pub fn parse(&self, argv: &[Bytes]) -> Result<Command, RusdisError> {
+        match self.parse {
+            Some(parse) => parse(argv),
+            None => Err(RusdisError::CommandParserError {
+                msg: format!("'{}' needs a subcommand", self.name),
+            }),
+        }
+    }
+}
+
+const NO_FLAGS: &[CommandFlag] = &[];
+const NO_KEYS: &[KeySpec] = &[];
+const NO_SUBCOMMANDS: &[CommandSpec] = &[];
+
+/// Key spec of commands whose only key is the first argument.
+const fn first_key(flags: &'static [&'static str]) -> KeySpec {
+    KeySpec {
+        flags,
+        begin_search: BeginSearch::Index(1),
+        find_keys: FindKeys::Range {
+            last_key: 0,
+            step: 1,
+            limit: 0,
+        },
+    }
+}
+
+/// Key spec of subcommands whose only key follows the subcommand name.
+const fn subcommand_key(flags: &'static [&'static str]) -> KeySpec {
+    KeySpec {
+        flags,
+        begin_search: BeginSearch::Index(2),
+        find_keys: FindKeys::Range {
+            last_key: 0,
+            step: 1,
+            limit: 0,
+        },
+    }
+}
+
+/// Key spec of commands whose keys are all the arguments from `index` on.
+const fn keys_from(index: usize, flags: &'static [&'static str]) -> KeySpec {
+    KeySpec {
+        flags,
+        begin_search: BeginSearch::Index(index),
+        find_keys: FindKeys::Range {
+            last_key: -1,
+            step: 1,
+            limit: 0,
+        },
+    }
+}
+
+/// Every command the server supports.
+pub static COMMAND_TABLE: &[CommandSpec] = &[
+    CommandSpec {
+        name: "get",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns the string value of a key.",
+            since: "1.0.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_get_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "set",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Sets the string value of a key, ignoring its type. The key is created if it doesn't exist.",
+            since: "1.0.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_set_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "incr",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Increments the integer value of a key by one. Uses 0 as initial value if the key doesn't exist.",
+            since: "1.0.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_incr_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "keys",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns all key names that match a pattern.",
+            since: "1.0.0",
+            group: "generic",
+            complexity: "O(N) with N being the number of keys in the database, under the assumption that the key names in the database and the given pattern have limited length.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@keyspace", "@dangerous"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_keys_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "ping",
+        arity: -1,
+        docs: CommandDocs {
+            summary: "Returns the server's liveliness response.",
+            since: "1.0.0",
+            group: "connection",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Fast],
+        acl_categories: &["@connection"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_ping_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "echo",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns the given string.",
+            since: "1.0.0",
+            group: "connection",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Fast],
+        acl_categories: &["@connection"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_echo_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hello",
+        arity: -1,
+        docs: CommandDocs {
+            summary: "Handshakes with the Redis server.",
+            since: "6.0.0",
+            group: "connection",
+            complexity: "O(1)",
+        },
+        flags: &[
+            CommandFlag::Noscript,
+            CommandFlag::Loading,
+            CommandFlag::Stale,
+            CommandFlag::Fast,
+            CommandFlag::NoAuth,
+        ],
+        acl_categories: &["@connection"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_hello_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "config",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "A container for server configuration commands.",
+            since: "2.0.0",
+            group: "server",
+            complexity: "Depends on subcommand.",
+        },
+        flags: NO_FLAGS,
+        acl_categories: &[],
+        key_specs: NO_KEYS,
+        parse: None,
+        subcommands: &[CommandSpec {
+            name: "config|get",
+            arity: -3,
+            docs: CommandDocs {
+                summary: "Returns the effective values of configuration parameters.",
+                since: "2.0.0",
+                group: "server",
+                complexity: "O(N) when N is the number of configuration parameters provided",
+            },
+            flags: &[
+                CommandFlag::Admin,
+                CommandFlag::Noscript,
+                CommandFlag::Loading,
+                CommandFlag::Stale,
+            ],
+            acl_categories: &[],
+            key_specs: NO_KEYS,
+            parse: Some(parse_config_get_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "config|help",
+            arity: 2,
+            docs: CommandDocs {
+                summary: "Returns helpful text about the different subcommands.",
+                since: "5.0.0",
+                group: "server",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Loading, CommandFlag::Stale],
+            acl_categories: &[],
+            key_specs: NO_KEYS,
+            parse: Some(parse_help_command),
+            subcommands: NO_SUBCOMMANDS,
+        }],
+    },
+    CommandSpec {
+        name: "info",
+        arity: -1,
+        docs: CommandDocs {
+            summary: "Returns information and statistics about the server.",
+            since: "1.0.0",
+            group: "server",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Loading, CommandFlag::Stale],
+        acl_categories: &["@dangerous"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_info_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "multi",
+        arity: 1,
+        docs: CommandDocs {
+            summary: "Starts a transaction.",
+            since: "1.2.0",
+            group: "transactions",
+            complexity: "O(1)",
+        },
+        flags: &[
+            CommandFlag::Noscript,
+            CommandFlag::Loading,
+            CommandFlag::Stale,
+            CommandFlag::Fast,
+        ],
+        acl_categories: &["@transaction"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_multi_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "exec",
+        arity: 1,
+        docs: CommandDocs {
+            summary: "Executes all commands in a transaction.",
+            since: "1.2.0",
+            group: "transactions",
+            complexity: "Depends on commands in the transaction",
+        },
+        flags: &[
+            CommandFlag::Noscript,
+            CommandFlag::Loading,
+            CommandFlag::Stale,
+        ],
+        acl_categories: &["@transaction"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_exec_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "discard",
+        arity: 1,
+        docs: CommandDocs {
+            summary: "Discards a transaction.",
+            since: "2.0.0",
+            group: "transactions",
+            complexity: "O(N), when N is the number of queued commands",
+        },
+        flags: &[
+            CommandFlag::Noscript,
+            CommandFlag::Loading,
+            CommandFlag::Stale,
+            CommandFlag::Fast,
+        ],
+        acl_categories: &["@transaction"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_discard_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "replconf",
+        arity: -1,
+        docs: CommandDocs {
+            summary: "An internal command for configuring the replication stream.",
+            since: "3.0.0",
+            group: "server",
+            complexity: "O(1)",
+        },
+        flags: &[
+            CommandFlag::Admin,
+            CommandFlag::Noscript,
+            CommandFlag::Loading,
+            CommandFlag::Stale,
+        ],
+        acl_categories: &[],
+        key_specs: NO_KEYS,
+        parse: Some(parse_replconf_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "psync",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "An internal command used in replication.",
+            since: "2.8.0",
+            group: "server",
+            complexity: "",
+        },
+        flags: &[
+            CommandFlag::Admin,
+            CommandFlag::Noscript,
+            CommandFlag::NoMulti,
+        ],
+        acl_categories: &[],
+        key_specs: NO_KEYS,
+        parse: Some(parse_psync_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "command",
+        arity: -1,
+        docs: CommandDocs {
+            summary: "Returns detailed information about all commands.",
+            since: "2.8.13",
+            group: "server",
+            complexity: "O(N) where N is the total number of Redis commands",
+        },
+        flags: &[CommandFlag::Loading, CommandFlag::Stale],
+        acl_categories: &["@connection"],
+        key_specs: NO_KEYS,
+        parse: Some(parse_command_command),
+        subcommands: &[
+            CommandSpec {
+                name: "command|count",
+                arity: 2,
+                docs: CommandDocs {
+                    summary: "Returns a count of commands.",
+                    since: "2.8.13",
+                    group: "server",
+                    complexity: "O(1)",
+                },
+                flags: &[CommandFlag::Loading, CommandFlag::Stale],
+                acl_categories: &["@connection"],
+                key_specs: NO_KEYS,
+                parse: Some(parse_command_count_command),
+                subcommands: NO_SUBCOMMANDS,
+            },
+            CommandSpec {
+                name: "command|docs",
+                arity: -2,
+                docs: CommandDocs {
+                    summary: "Returns documentary information about one, multiple or all commands.",
+                    since: "7.0.0",
+                    group: "server",
+                    complexity: "O(N) where N is the number of commands to look up",
+                },
+                flags: &[CommandFlag::Loading, CommandFlag::Stale],
+                acl_categories: &["@connection"],
+                key_specs: NO_KEYS,
+                parse: Some(parse_command_docs_command),
+                subcommands: NO_SUBCOMMANDS,
+            },
+            CommandSpec {
+                name: "command|getkeys",
+                arity: -3,
+                docs: CommandDocs {
+                    summary: "Extracts the key names from an arbitrary command.",
+                    since: "2.8.13",
+                    group: "server",
+                    complexity: "O(N) where N is the number of arguments to the command",
+                },
+                flags: &[CommandFlag::Loading, CommandFlag::Stale],
+                acl_categories: &["@connection"],
+                key_specs: NO_KEYS,
+                parse: Some(parse_command_getkeys_command),
+                subcommands: NO_SUBCOMMANDS,
+            },
+            CommandSpec {
+                name: "command|help",
+                arity: 2,
+                docs: CommandDocs {
+                    summary: "Returns helpful text about the different subcommands.",
+                    since: "5.0.0",
+                    group: "server",
+                    complexity: "O(1)",
+                },
+                flags: &[CommandFlag::Loading, CommandFlag::Stale],
+                acl_categories: &["@connection"],
+                key_specs: NO_KEYS,
+                parse: Some(parse_help_command),
+                subcommands: NO_SUBCOMMANDS,
+            },
+            CommandSpec {
+                name: "command|info",
+                arity: -2,
+                docs: CommandDocs {
+                    summary: "Returns information about one, multiple or all commands.",
+                    since: "2.8.13",
+                    group: "server",
+                    complexity: "O(N) where N is the number of commands to look up",
+                },
+                flags: &[CommandFlag::Loading, CommandFlag::Stale],
+                acl_categories: &["@connection"],
+                key_specs: NO_KEYS,
+                parse: Some(parse_command_info_command),
+                subcommands: NO_SUBCOMMANDS,
+            },
+        ],
+    },
+    CommandSpec {
+        name: "lpush",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Prepends one or more elements to a list. Creates the key if it doesn't exist.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_lpush_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "rpush",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Appends one or more elements to a list. Creates the key if it doesn't exist.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_rpush_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lpushx",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Prepends one or more elements to a list only when the list exists.",
+            since: "2.2.0",
+            group: "list",
+            complexity: "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_lpushx_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "rpushx",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Appends an element to a list only when the list exists.",
+            since: "2.2.0",
+            group: "list",
+            complexity: "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_rpushx_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lpop",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the first elements in a list after removing it. Deletes the list if the last element was popped.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(N) where N is the number of elements returned",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "ACCESS", "DELETE"])],
+        parse: Some(parse_lpop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "rpop",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns and removes the last elements of the list. Deletes the list if the last element was popped.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(N) where N is the number of elements returned",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "ACCESS", "DELETE"])],
+        parse: Some(parse_rpop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lrange",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Returns a range of elements from a list.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(S+N) where S is the distance of start offset from HEAD for small lists, from nearest end (HEAD or TAIL) for large lists; and N is the number of elements in the specified range.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_lrange_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "llen",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns the length of a list.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_llen_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lindex",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Returns an element from a list by its index.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(N) where N is the number of elements to traverse to get to the element at index. This makes asking for the first or the last element of the list O(1).",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_lindex_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lset",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Sets the value of an element in a list by its index.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(N) where N is the length of the list. Setting either the first or the last element of the list is O(1).",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_lset_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "linsert",
+        arity: 5,
+        docs: CommandDocs {
+            summary: "Inserts an element before or after another element in a list.",
+            since: "2.2.0",
+            group: "list",
+            complexity: "O(N) where N is the number of elements to traverse before seeing the value pivot. This means that inserting somewhere on the left end on the list (head) can be considered O(1) and inserting somewhere on the right end (tail) is O(N).",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_linsert_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lrem",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Removes elements from a list. Deletes the list if the last element was removed.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(N+M) where N is the length of the list and M is the number of elements removed.",
+        },
+        flags: &[CommandFlag::Write],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "DELETE"])],
+        parse: Some(parse_lrem_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "ltrim",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Removes elements from both ends a list. Deletes the list if all elements were trimmed.",
+            since: "1.0.0",
+            group: "list",
+            complexity: "O(N) where N is the number of elements to be removed by the operation.",
+        },
+        flags: &[CommandFlag::Write],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RW", "DELETE"])],
+        parse: Some(parse_ltrim_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lpos",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the index of matching elements in a list.",
+            since: "6.0.6",
+            group: "list",
+            complexity: "O(N) where N is the number of elements in the list, for the average case. When searching for elements near the head or the tail of the list, or when the MAXLEN option is provided, the command may run in constant time.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@list"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_lpos_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lmove",
+        arity: 5,
+        docs: CommandDocs {
+            summary: "Returns an element after popping it from one list and pushing it to another. Deletes the list if the last element was moved.",
+            since: "6.2.0",
+            group: "list",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@list"],
+        key_specs: &[
+            first_key(&["RW", "ACCESS", "DELETE"]),
+            KeySpec {
+                flags: &["RW", "INSERT"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Range {
+                    last_key: 0,
+                    step: 1,
+                    limit: 0,
+                },
+            },
+        ],
+        parse: Some(parse_lmove_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lmpop",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Returns multiple elements from a list after removing them. Deletes the list if the last element was popped.",
+            since: "7.0.0",
+            group: "list",
+            complexity: "O(N+M) where N is the number of provided keys and M is the number of elements returned.",
+        },
+        flags: &[CommandFlag::Write],
+        acl_categories: &["@list"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS", "DELETE"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_lmpop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "blpop",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Removes and returns the first element in a list. Blocks until an element is available otherwise. Deletes the list if the last element was popped.",
+            since: "2.0.0",
+            group: "list",
+            complexity: "O(N) where N is the number of provided keys.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Blocking],
+        acl_categories: &["@list"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS", "DELETE"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: -2,
+                step: 1,
+                limit: 0,
+            },
+        }],
+        parse: Some(parse_blpop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "brpop",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Removes and returns the last element in a list. Blocks until an element is available otherwise. Deletes the list if the last element was popped.",
+            since: "2.0.0",
+            group: "list",
+            complexity: "O(N) where N is the number of provided keys.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Blocking],
+        acl_categories: &["@list"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS", "DELETE"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: -2,
+                step: 1,
+                limit: 0,
+            },
+        }],
+        parse: Some(parse_brpop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "blmove",
+        arity: 6,
+        docs: CommandDocs {
+            summary: "Pops an element from a list, pushes it to another list and returns it. Blocks until an element is available otherwise. Deletes the list if the last element was moved.",
+            since: "6.2.0",
+            group: "list",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Blocking],
+        acl_categories: &["@list"],
+        key_specs: &[
+            first_key(&["RW", "ACCESS", "DELETE"]),
+            KeySpec {
+                flags: &["RW", "INSERT"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Range {
+                    last_key: 0,
+                    step: 1,
+                    limit: 0,
+                },
+            },
+        ],
+        parse: Some(parse_blmove_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "blmpop",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Pops the first element from one of multiple lists. Blocks until an element is available otherwise. Deletes the list if the last element was popped.",
+            since: "7.0.0",
+            group: "list",
+            complexity: "O(N+M) where N is the number of provided keys and M is the number of elements returned.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Blocking],
+        acl_categories: &["@list"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS", "DELETE"],
+            begin_search: BeginSearch::Index(2),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_blmpop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hset",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Creates or modifies the value of a field in a hash.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(1) for each field/value pair added, so O(N) to add N field/value pairs when the command is called with multiple field/value pairs.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_hset_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hsetnx",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Sets the value of a field in a hash only when the field doesn't exist.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_hsetnx_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hget",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Returns the value of a field in a hash.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_hget_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hmget",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the values of all fields in a hash.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of fields being requested.",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_hmget_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hdel",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Deletes one or more fields and their values from a hash. Deletes the hash if no fields remain.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of fields to be removed.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "DELETE"])],
+        parse: Some(parse_hdel_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hexists",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Determines whether a field exists in a hash.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_hexists_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hlen",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns the number of fields in a hash.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_hlen_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hkeys",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns all fields in a hash.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(N) where N is the size of the hash.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_hkeys_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hvals",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns all values in a hash.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(N) where N is the size of the hash.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_hvals_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hgetall",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns all fields and values in a hash.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(N) where N is the size of the hash.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_hgetall_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hincrby",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Increments the integer value of a field in a hash by a number. Uses 0 as initial value if the field doesn't exist.",
+            since: "2.0.0",
+            group: "hash",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_hincrby_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hincrbyfloat",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Increments the floating point value of a field by a number. Uses 0 as initial value if the field doesn't exist.",
+            since: "2.6.0",
+            group: "hash",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_hincrbyfloat_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hstrlen",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Returns the length of the value of a field.",
+            since: "3.2.0",
+            group: "hash",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_hstrlen_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hrandfield",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns one or more random fields from a hash.",
+            since: "6.2.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of fields returned",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_hrandfield_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hexpire",
+        arity: -6,
+        docs: CommandDocs {
+            summary: "Set expiry for hash field using relative time to expire (seconds)",
+            since: "7.4.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of specified fields",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_hexpire_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hpexpire",
+        arity: -6,
+        docs: CommandDocs {
+            summary: "Set expiry for hash field using relative time to expire (milliseconds)",
+            since: "7.4.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of specified fields",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_hpexpire_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hexpireat",
+        arity: -6,
+        docs: CommandDocs {
+            summary: "Set expiry for hash field using an absolute Unix timestamp (seconds)",
+            since: "7.4.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of specified fields",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_hexpireat_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hpexpireat",
+        arity: -6,
+        docs: CommandDocs {
+            summary: "Set expiry for hash field using an absolute Unix timestamp (milliseconds)",
+            since: "7.4.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of specified fields",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_hpexpireat_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "httl",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Returns the TTL in seconds of a hash field.",
+            since: "7.4.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of specified fields",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_httl_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hpttl",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Returns the TTL in milliseconds of a hash field.",
+            since: "7.4.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of specified fields",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_hpttl_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hpersist",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Removes the expiration time for each specified field",
+            since: "7.4.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of specified fields",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_hpersist_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hgetex",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Get the value of one or more fields of a given hash key, and optionally set their expiration.",
+            since: "8.0.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of specified fields",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_hgetex_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "hsetex",
+        arity: -6,
+        docs: CommandDocs {
+            summary: "Set the value of one or more fields of a given hash key, and optionally set their expiration.",
+            since: "8.0.0",
+            group: "hash",
+            complexity: "O(N) where N is the number of fields being set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hash"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_hsetex_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sadd",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Adds one or more members to a set. Creates the key if it doesn't exist.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(1) for each element added, so O(N) to add N elements when the command is called with multiple arguments.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@set"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_sadd_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "srem",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Removes one or more members from a set. Deletes the set if the last member was removed.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(N) where N is the number of members to be removed.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@set"],
+        key_specs: &[first_key(&["RW", "DELETE"])],
+        parse: Some(parse_srem_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "smembers",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns all members of a set.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(N) where N is the set cardinality.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@set"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_smembers_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sismember",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Determines whether a member belongs to a set.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@set"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_sismember_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "smismember",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Determines whether multiple members belong to a set.",
+            since: "6.2.0",
+            group: "set",
+            complexity: "O(N) where N is the number of elements being checked for membership",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@set"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_smismember_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "scard",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns the number of members in a set.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@set"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_scard_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "spop",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns one or more random members from a set after removing them. Deletes the set if the last member was popped.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "Without the count argument O(1), otherwise O(N) where N is the value of the passed count.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@set"],
+        key_specs: &[first_key(&["RW", "ACCESS", "DELETE"])],
+        parse: Some(parse_spop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "srandmember",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Get one or multiple random members from a set",
+            since: "1.0.0",
+            group: "set",
+            complexity: "Without the count argument O(1), otherwise O(N) where N is the absolute value of the passed count.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@set"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_srandmember_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "smove",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Moves a member from one set to another.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@set"],
+        key_specs: &[
+            first_key(&["RW", "ACCESS", "DELETE"]),
+            KeySpec {
+                flags: &["RW", "INSERT"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Range {
+                    last_key: 0,
+                    step: 1,
+                    limit: 0,
+                },
+            },
+        ],
+        parse: Some(parse_smove_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sinter",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the intersect of multiple sets.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(N*M) worst case where N is the cardinality of the smallest set and M is the number of sets.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@set"],
+        key_specs: &[keys_from(1, &["RO", "ACCESS"])],
+        parse: Some(parse_sinter_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sinterstore",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Stores the intersect of multiple sets in a key.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(N*M) worst case where N is the cardinality of the smallest set and M is the number of sets.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@set"],
+        key_specs: &[
+            first_key(&["OW", "UPDATE"]),
+            keys_from(2, &["RO", "ACCESS"]),
+        ],
+        parse: Some(parse_sinterstore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sunion",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the union of multiple sets.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(N) where N is the total number of elements in all given sets.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@set"],
+        key_specs: &[keys_from(1, &["RO", "ACCESS"])],
+        parse: Some(parse_sunion_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sunionstore",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Stores the union of multiple sets in a key.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(N) where N is the total number of elements in all given sets.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@set"],
+        key_specs: &[
+            first_key(&["OW", "UPDATE"]),
+            keys_from(2, &["RO", "ACCESS"]),
+        ],
+        parse: Some(parse_sunionstore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sdiff",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the difference of multiple sets.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(N) where N is the total number of elements in all given sets.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@set"],
+        key_specs: &[keys_from(1, &["RO", "ACCESS"])],
+        parse: Some(parse_sdiff_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sdiffstore",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Stores the difference of multiple sets in a key.",
+            since: "1.0.0",
+            group: "set",
+            complexity: "O(N) where N is the total number of elements in all given sets.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@set"],
+        key_specs: &[
+            first_key(&["OW", "UPDATE"]),
+            keys_from(2, &["RO", "ACCESS"]),
+        ],
+        parse: Some(parse_sdiffstore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "sintercard",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the number of members of the intersect of multiple sets.",
+            since: "7.0.0",
+            group: "set",
+            complexity: "O(N*M) worst case where N is the cardinality of the smallest set and M is the number of sets.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@set"],
+        key_specs: &[KeySpec {
+            flags: &["RO", "ACCESS"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_sintercard_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "type",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Determines the type of value stored at a key.",
+            since: "1.0.0",
+            group: "generic",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@keyspace"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_type_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zadd",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Adds one or more members to a sorted set, or updates their scores. Creates the key if it doesn't exist.",
+            since: "1.2.0",
+            group: "sorted-set",
+            complexity: "O(log(N)) for each item added, where N is the number of elements in the sorted set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_zadd_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zrem",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Removes one or more members from a sorted set. Deletes the sorted set if all members were removed.",
+            since: "1.2.0",
+            group: "sorted-set",
+            complexity: "O(M*log(N)) with N being the number of elements in the sorted set and M the number of elements to be removed.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RW", "DELETE"])],
+        parse: Some(parse_zrem_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zscore",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Returns the score of a member in a sorted set.",
+            since: "1.2.0",
+            group: "sorted-set",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_zscore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zmscore",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the score of one or more members in a sorted set.",
+            since: "6.2.0",
+            group: "sorted-set",
+            complexity: "O(N) where N is the number of members being requested.",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_zmscore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zincrby",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Increments the score of a member in a sorted set.",
+            since: "1.2.0",
+            group: "sorted-set",
+            complexity: "O(log(N)) where N is the number of elements in the sorted set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_zincrby_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zcard",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns the number of members in a sorted set.",
+            since: "1.2.0",
+            group: "sorted-set",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_zcard_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zcount",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Returns the count of members in a sorted set that have scores within a range.",
+            since: "2.0.0",
+            group: "sorted-set",
+            complexity: "O(log(N)) with N being the number of elements in the sorted set.",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_zcount_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zrank",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the index of a member in a sorted set ordered by ascending scores.",
+            since: "2.0.0",
+            group: "sorted-set",
+            complexity: "O(log(N))",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_zrank_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zrevrank",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the index of a member in a sorted set ordered by descending scores.",
+            since: "2.0.0",
+            group: "sorted-set",
+            complexity: "O(log(N))",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_zrevrank_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zrange",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Returns members in a sorted set within a range of indexes.",
+            since: "1.2.0",
+            group: "sorted-set",
+            complexity: "O(log(N)+M) with N being the number of elements in the sorted set and M the number of elements returned.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_zrange_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zpopmin",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the lowest-scoring members from a sorted set after removing them. Deletes the sorted set if the last member was popped.",
+            since: "5.0.0",
+            group: "sorted-set",
+            complexity: "O(log(N)*M) with N being the number of elements in the sorted set, and M being the number of elements popped.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RW", "ACCESS", "DELETE"])],
+        parse: Some(parse_zpopmin_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zpopmax",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the highest-scoring members from a sorted set after removing them. Deletes the sorted set if the last member was popped.",
+            since: "5.0.0",
+            group: "sorted-set",
+            complexity: "O(log(N)*M) with N being the number of elements in the sorted set, and M being the number of elements popped.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RW", "ACCESS", "DELETE"])],
+        parse: Some(parse_zpopmax_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zrandmember",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns one or more random members from a sorted set.",
+            since: "6.2.0",
+            group: "sorted-set",
+            complexity: "O(N) where N is the number of members returned",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@sortedset"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_zrandmember_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zunion",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the union of multiple sorted sets.",
+            since: "6.2.0",
+            group: "sorted-set",
+            complexity: "O(N)+O(M*log(M)) with N being the sum of the sizes of the input sorted sets, and M being the number of elements in the resulting sorted set.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@sortedset"],
+        key_specs: &[KeySpec {
+            flags: &["RO", "ACCESS"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_zunion_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zunionstore",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Stores the union of multiple sorted sets in a key.",
+            since: "2.0.0",
+            group: "sorted-set",
+            complexity: "O(N)+O(M log(M)) with N being the sum of the sizes of the input sorted sets, and M being the number of elements in the resulting sorted set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@sortedset"],
+        key_specs: &[
+            first_key(&["OW", "UPDATE"]),
+            KeySpec {
+                flags: &["RO", "ACCESS"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Keynum {
+                    keynum_idx: 0,
+                    first_key: 1,
+                    step: 1,
+                },
+            },
+        ],
+        parse: Some(parse_zunionstore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zinter",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the intersect of multiple sorted sets.",
+            since: "6.2.0",
+            group: "sorted-set",
+            complexity: "O(N*K)+O(M*log(M)) worst case with N being the smallest input sorted set, K being the number of input sorted sets and M being the number of elements in the resulting sorted set.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@sortedset"],
+        key_specs: &[KeySpec {
+            flags: &["RO", "ACCESS"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_zinter_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zinterstore",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Stores the intersect of multiple sorted sets in a key.",
+            since: "2.0.0",
+            group: "sorted-set",
+            complexity: "O(N*K)+O(M*log(M)) worst case with N being the smallest input sorted set, K being the number of input sorted sets and M being the number of elements in the resulting sorted set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@sortedset"],
+        key_specs: &[
+            first_key(&["OW", "UPDATE"]),
+            KeySpec {
+                flags: &["RO", "ACCESS"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Keynum {
+                    keynum_idx: 0,
+                    first_key: 1,
+                    step: 1,
+                },
+            },
+        ],
+        parse: Some(parse_zinterstore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zintercard",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the number of members of the intersect of multiple sorted sets.",
+            since: "7.0.0",
+            group: "sorted-set",
+            complexity: "O(N*K) worst case with N being the smallest input sorted set, K being the number of input sorted sets.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@sortedset"],
+        key_specs: &[KeySpec {
+            flags: &["RO", "ACCESS"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_zintercard_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zdiff",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the difference between multiple sorted sets.",
+            since: "6.2.0",
+            group: "sorted-set",
+            complexity: "O(L + (N-K)log(N)) worst case where L is the total number of elements in all the sets, N is the size of the first set, and K is the size of the result set.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@sortedset"],
+        key_specs: &[KeySpec {
+            flags: &["RO", "ACCESS"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_zdiff_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zdiffstore",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Stores the difference of multiple sorted sets in a key.",
+            since: "6.2.0",
+            group: "sorted-set",
+            complexity: "O(L + (N-K)log(N)) worst case where L is the total number of elements in all the sets, N is the size of the first set, and K is the size of the result set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@sortedset"],
+        key_specs: &[
+            first_key(&["OW", "UPDATE"]),
+            KeySpec {
+                flags: &["RO", "ACCESS"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Keynum {
+                    keynum_idx: 0,
+                    first_key: 1,
+                    step: 1,
+                },
+            },
+        ],
+        parse: Some(parse_zdiffstore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zrangestore",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Stores a range of members from sorted set in a key.",
+            since: "6.2.0",
+            group: "sorted-set",
+            complexity: "O(log(N)+M) with N being the number of elements in the sorted set and M the number of elements stored into the destination key.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@sortedset"],
+        key_specs: &[
+            first_key(&["OW", "UPDATE"]),
+            KeySpec {
+                flags: &["RO", "ACCESS"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Range {
+                    last_key: 0,
+                    step: 1,
+                    limit: 0,
+                },
+            },
+        ],
+        parse: Some(parse_zrangestore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "zmpop",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Returns the highest- or lowest-scoring members from one or more sorted sets after removing them. Deletes the sorted set if the last member was popped.",
+            since: "7.0.0",
+            group: "sorted-set",
+            complexity: "O(K) + O(M*log(N)) where K is the number of provided keys, N being the number of elements in the sorted set, and M being the number of elements popped.",
+        },
+        flags: &[CommandFlag::Write],
+        acl_categories: &["@sortedset"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS", "DELETE"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_zmpop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "bzpopmin",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Removes and returns the member with the lowest score from one or more sorted sets. Blocks until a member is available otherwise. Deletes the sorted set if the last element was popped.",
+            since: "5.0.0",
+            group: "sorted-set",
+            complexity: "O(log(N)) with N being the number of elements in the sorted set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast, CommandFlag::Blocking],
+        acl_categories: &["@sortedset"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS", "DELETE"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: -2,
+                step: 1,
+                limit: 0,
+            },
+        }],
+        parse: Some(parse_bzpopmin_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "bzpopmax",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Removes and returns the member with the highest score from one or more sorted sets. Blocks until a member available otherwise.  Deletes the sorted set if the last element was popped.",
+            since: "5.0.0",
+            group: "sorted-set",
+            complexity: "O(log(N)) with N being the number of elements in the sorted set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast, CommandFlag::Blocking],
+        acl_categories: &["@sortedset"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS", "DELETE"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: -2,
+                step: 1,
+                limit: 0,
+            },
+        }],
+        parse: Some(parse_bzpopmax_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "bzmpop",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Removes and returns a member by score from one or more sorted sets. Blocks until a member is available otherwise. Deletes the sorted set if the last element was popped.",
+            since: "7.0.0",
+            group: "sorted-set",
+            complexity: "O(K) + O(M*log(N)) where K is the number of provided keys, N being the number of elements in the sorted set, and M being the number of elements popped.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Blocking],
+        acl_categories: &["@sortedset"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS", "DELETE"],
+            begin_search: BeginSearch::Index(2),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        }],
+        parse: Some(parse_bzmpop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xadd",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Appends a new message to a stream. Creates the key if it doesn't exist.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(1) when adding a new entry, O(N) when trimming where N being the number of entries evicted.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_xadd_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xrange",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Returns the messages from a stream within a range of IDs.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(N) with N being the number of elements being returned. If N is constant (e.g. always asking for the first 10 elements with COUNT), you can consider it O(1).",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_xrange_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xrevrange",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Returns the messages from a stream within a range of IDs in reverse order.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(N) with N being the number of elements returned. If N is constant (e.g. always asking for the first 10 elements with COUNT), you can consider it O(1).",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_xrevrange_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xlen",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Return the number of messages in a stream.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_xlen_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xdel",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the number of messages after removing them from a stream.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(1) for each single item to delete in the stream, regardless of the stream size.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RW", "DELETE"])],
+        parse: Some(parse_xdel_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xtrim",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Deletes messages from the beginning of a stream.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(N), with N being the number of evicted entries. Constant times are very small however, since entries are organized in macro nodes containing multiple entries that can be released with a single deallocation.",
+        },
+        flags: &[CommandFlag::Write],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RW", "DELETE"])],
+        parse: Some(parse_xtrim_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xread",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Returns messages from multiple streams with IDs greater than the ones requested. Blocks until a message is available otherwise.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "For each stream mentioned: O(M) with M being the number of elements returned. If M is constant (e.g. always asking for the first 10 elements with COUNT), you can consider it O(1). On the other side when XREAD blocks, XADD will pay O(N) time in order to serve the N clients blocked on the stream getting new data.",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Blocking],
+        acl_categories: &["@stream"],
+        key_specs: &[KeySpec {
+            flags: &["RO", "ACCESS"],
+            begin_search: BeginSearch::Keyword {
+                keyword: "STREAMS",
+                start_from: 1,
+            },
+            find_keys: FindKeys::Range {
+                last_key: -1,
+                step: 1,
+                limit: 2,
+            },
+        }],
+        parse: Some(parse_xread_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xgroup",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "A container for consumer groups commands.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "Depends on subcommand.",
+        },
+        flags: NO_FLAGS,
+        acl_categories: &[],
+        key_specs: NO_KEYS,
+        parse: None,
+        subcommands: &[
+            CommandSpec {
+            name: "xgroup|create",
+            arity: -5,
+            docs: CommandDocs {
+                summary: "Creates a consumer group.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+            acl_categories: &["@stream"],
+            key_specs: &[subcommand_key(&["RW", "INSERT"])],
+            parse: Some(parse_xgroup_create_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "xgroup|setid",
+            arity: -5,
+            docs: CommandDocs {
+                summary: "Sets the last-delivered ID of a consumer group.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Write],
+            acl_categories: &["@stream"],
+            key_specs: &[subcommand_key(&["RW", "UPDATE"])],
+            parse: Some(parse_xgroup_setid_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "xgroup|destroy",
+            arity: 4,
+            docs: CommandDocs {
+                summary: "Destroys a consumer group.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(N) where N is the number of entries in the group's pending entries list (PEL).",
+            },
+            flags: &[CommandFlag::Write],
+            acl_categories: &["@stream"],
+            key_specs: &[subcommand_key(&["RW", "DELETE"])],
+            parse: Some(parse_xgroup_destroy_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "xgroup|createconsumer",
+            arity: 5,
+            docs: CommandDocs {
+                summary: "Creates a consumer in a consumer group.",
+                since: "6.2.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+            acl_categories: &["@stream"],
+            key_specs: &[subcommand_key(&["RW", "INSERT"])],
+            parse: Some(parse_xgroup_createconsumer_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "xgroup|delconsumer",
+            arity: 5,
+            docs: CommandDocs {
+                summary: "Deletes a consumer from a consumer group.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Write],
+            acl_categories: &["@stream"],
+            key_specs: &[subcommand_key(&["RW", "DELETE"])],
+            parse: Some(parse_xgroup_delconsumer_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "xgroup|help",
+            arity: 2,
+            docs: CommandDocs {
+                summary: "Returns helpful text about the different subcommands.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Loading, CommandFlag::Stale],
+            acl_categories: &["@stream"],
+            key_specs: NO_KEYS,
+            parse: Some(parse_help_command),
+            subcommands: NO_SUBCOMMANDS,
+        },
+        ],
+    },
+    CommandSpec {
+        name: "xreadgroup",
+        arity: -7,
+        docs: CommandDocs {
+            summary: "Returns new or historical messages from a stream for a consumer in a group. Blocks until a message is available otherwise.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "For each stream mentioned: O(M) with M being the number of elements returned. If M is constant (e.g. always asking for the first 10 elements with COUNT), you can consider it O(1). On the other side when XREADGROUP blocks, XADD will pay the O(N) time in order to serve the N clients blocked on the stream getting new data.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Blocking],
+        acl_categories: &["@stream"],
+        key_specs: &[KeySpec {
+            flags: &["RW", "ACCESS"],
+            begin_search: BeginSearch::Keyword {
+                keyword: "STREAMS",
+                start_from: 4,
+            },
+            find_keys: FindKeys::Range {
+                last_key: -1,
+                step: 1,
+                limit: 2,
+            },
+        }],
+        parse: Some(parse_xreadgroup_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xack",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Returns the number of messages that were successfully acknowledged by the consumer group member of a stream.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(1) for each message ID processed.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_xack_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xpending",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Returns the information and entries from a stream consumer group's pending entries list.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(N) with N being the number of elements returned, so asking for a small fixed number of entries per call is O(1). O(M), where M is the total number of entries scanned when used with the IDLE filter. When the command returns just the summary and the list of consumers is small, it runs in O(1) time; otherwise, an additional O(N) time for iterating every consumer.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_xpending_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xclaim",
+        arity: -6,
+        docs: CommandDocs {
+            summary: "Changes, or acquires, ownership of a message in a consumer group, as if the message was delivered a consumer group member.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "O(log N) with N being the number of messages in the PEL of the consumer group.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_xclaim_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xautoclaim",
+        arity: -6,
+        docs: CommandDocs {
+            summary: "Changes, or acquires, ownership of messages in a consumer group, as if the messages were delivered to as consumer group member.",
+            since: "6.2.0",
+            group: "stream",
+            complexity: "O(1) if COUNT is small.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@stream"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_xautoclaim_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "xinfo",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "A container for stream introspection commands.",
+            since: "5.0.0",
+            group: "stream",
+            complexity: "Depends on subcommand.",
+        },
+        flags: NO_FLAGS,
+        acl_categories: &[],
+        key_specs: NO_KEYS,
+        parse: None,
+        subcommands: &[
+            CommandSpec {
+            name: "xinfo|stream",
+            arity: -3,
+            docs: CommandDocs {
+                summary: "Returns information about a stream.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Readonly],
+            acl_categories: &["@stream"],
+            key_specs: &[subcommand_key(&["RO", "ACCESS"])],
+            parse: Some(parse_xinfo_stream_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "xinfo|groups",
+            arity: 3,
+            docs: CommandDocs {
+                summary: "Returns a list of the consumer groups of a stream.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Readonly],
+            acl_categories: &["@stream"],
+            key_specs: &[subcommand_key(&["RO", "ACCESS"])],
+            parse: Some(parse_xinfo_groups_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "xinfo|consumers",
+            arity: 4,
+            docs: CommandDocs {
+                summary: "Returns a list of the consumers in a consumer group.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Readonly],
+            acl_categories: &["@stream"],
+            key_specs: &[subcommand_key(&["RO", "ACCESS"])],
+            parse: Some(parse_xinfo_consumers_command),
+            subcommands: NO_SUBCOMMANDS,
+        }, CommandSpec {
+            name: "xinfo|help",
+            arity: 2,
+            docs: CommandDocs {
+                summary: "Returns helpful text about the different subcommands.",
+                since: "5.0.0",
+                group: "stream",
+                complexity: "O(1)",
+            },
+            flags: &[CommandFlag::Loading, CommandFlag::Stale],
+            acl_categories: &["@stream"],
+            key_specs: NO_KEYS,
+            parse: Some(parse_help_command),
+            subcommands: NO_SUBCOMMANDS,
+        },
+        ],
+    },
+    CommandSpec {
+        name: "setbit",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Sets or clears the bit at offset of the string value. Creates the key if it doesn't exist.",
+            since: "2.2.0",
+            group: "bitmap",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@bitmap"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_setbit_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "getbit",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Returns a bit value by offset.",
+            since: "2.2.0",
+            group: "bitmap",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@bitmap"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_getbit_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "bitcount",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Counts the number of set bits (population counting) in a string.",
+            since: "2.6.0",
+            group: "bitmap",
+            complexity: "O(N)",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@bitmap"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_bitcount_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "bitpos",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Finds the first set (1) or clear (0) bit in a string.",
+            since: "2.8.7",
+            group: "bitmap",
+            complexity: "O(N)",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@bitmap"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_bitpos_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "bitop",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Performs bitwise operations on multiple strings, and stores the result.",
+            since: "2.6.0",
+            group: "bitmap",
+            complexity: "O(N)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@bitmap"],
+        key_specs: &[
+            KeySpec {
+                flags: &["OW", "UPDATE"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Range {
+                    last_key: 0,
+                    step: 1,
+                    limit: 0,
+                },
+            },
+            keys_from(3, &["RO", "ACCESS"]),
+        ],
+        parse: Some(parse_bitop_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "bitfield",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Performs arbitrary bitfield integer operations on strings.",
+            since: "3.2.0",
+            group: "bitmap",
+            complexity: "O(1) for each subcommand specified",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@bitmap"],
+        key_specs: &[first_key(&["RW", "UPDATE", "ACCESS"])],
+        parse: Some(parse_bitfield_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "bitfield_ro",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Performs arbitrary read-only bitfield integer operations on strings.",
+            since: "6.0.0",
+            group: "bitmap",
+            complexity: "O(1) for each subcommand specified",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@bitmap"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_bitfield_ro_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "pfadd",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Adds elements to a HyperLogLog key. Creates the key if it doesn't exist.",
+            since: "2.8.9",
+            group: "hyperloglog",
+            complexity: "O(1) to add every element.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@hyperloglog"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_pfadd_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "pfcount",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the approximated cardinality of the set(s) observed by the HyperLogLog key(s).",
+            since: "2.8.9",
+            group: "hyperloglog",
+            complexity: "O(1) with a very small average constant time when called with a single key. O(N) with N being the number of keys, and much bigger constant times, when called with multiple keys.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@hyperloglog"],
+        key_specs: &[keys_from(1, &["RW", "ACCESS"])],
+        parse: Some(parse_pfcount_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "pfmerge",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Merges one or more HyperLogLog values into a single key.",
+            since: "2.8.9",
+            group: "hyperloglog",
+            complexity: "O(N) to merge N HyperLogLogs, but with high constant times.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@hyperloglog"],
+        key_specs: &[
+            first_key(&["RW", "ACCESS", "INSERT"]),
+            keys_from(2, &["RO", "ACCESS"]),
+        ],
+        parse: Some(parse_pfmerge_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "geoadd",
+        arity: -5,
+        docs: CommandDocs {
+            summary: "Adds one or more members to a geospatial index. The key is created if it doesn't exist.",
+            since: "3.2.0",
+            group: "geo",
+            complexity: "O(log(N)) for each item added, where N is the number of elements in the sorted set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@geo"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_geoadd_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "geodist",
+        arity: -4,
+        docs: CommandDocs {
+            summary: "Returns the distance between two members of a geospatial index.",
+            since: "3.2.0",
+            group: "geo",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@geo"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_geodist_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "geohash",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns members from a geospatial index as geohash strings.",
+            since: "3.2.0",
+            group: "geo",
+            complexity: "O(1) for each member requested.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@geo"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_geohash_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "geopos",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the longitude and latitude of members from a geospatial index.",
+            since: "3.2.0",
+            group: "geo",
+            complexity: "O(1) for each member requested.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@geo"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_geopos_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "geosearch",
+        arity: -7,
+        docs: CommandDocs {
+            summary: "Queries a geospatial index for members inside an area of a box or a circle.",
+            since: "6.2.0",
+            group: "geo",
+            complexity: "O(N+log(M)) where N is the number of elements in the grid-aligned bounding box area around the shape provided as the filter and M is the number of items inside the shape",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@geo"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_geosearch_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "geosearchstore",
+        arity: -8,
+        docs: CommandDocs {
+            summary: "Queries a geospatial index for members inside an area of a box or a circle, optionally stores the result.",
+            since: "6.2.0",
+            group: "geo",
+            complexity: "O(N+log(M)) where N is the number of elements in the grid-aligned bounding box area around the shape provided as the filter and M is the number of items inside the shape",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@geo"],
+        key_specs: &[
+            first_key(&["OW", "UPDATE"]),
+            KeySpec {
+                flags: &["RO", "ACCESS"],
+                begin_search: BeginSearch::Index(2),
+                find_keys: FindKeys::Range {
+                    last_key: 0,
+                    step: 1,
+                    limit: 0,
+                },
+            },
+        ],
+        parse: Some(parse_geosearchstore_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "append",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Appends a string to the value of a key. Creates the key if it doesn't exist.",
+            since: "2.0.0",
+            group: "string",
+            complexity: "O(1). The amortized time complexity is O(1) assuming the appended value is small and the already present value is of any size, since the dynamic string library used by Redis will double the free space available on every reallocation.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RW", "INSERT"])],
+        parse: Some(parse_append_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "strlen",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns the length of a string value.",
+            since: "2.2.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RO"])],
+        parse: Some(parse_strlen_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "getrange",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Returns a substring of the string stored at a key.",
+            since: "2.4.0",
+            group: "string",
+            complexity: "O(N) where N is the length of the returned string. The complexity is ultimately determined by the returned length, but because creating a substring from an existing string is very cheap, it can be considered O(1) for small strings.",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RO", "ACCESS"])],
+        parse: Some(parse_getrange_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "setrange",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Overwrites a part of a string value with another by an offset. Creates the key if it doesn't exist.",
+            since: "2.2.0",
+            group: "string",
+            complexity: "O(1), not counting the time taken to copy the new string in place. Usually, this string is very small so the amortized complexity is O(1). Otherwise, complexity is O(M) with M being the length of the value argument.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RW", "UPDATE"])],
+        parse: Some(parse_setrange_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "mget",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Atomically returns the string values of one or more keys.",
+            since: "1.0.0",
+            group: "string",
+            complexity: "O(N) where N is the number of keys to retrieve.",
+        },
+        flags: &[CommandFlag::Readonly, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[keys_from(1, &["RO", "ACCESS"])],
+        parse: Some(parse_mget_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "mset",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Atomically creates or modifies the string values of one or more keys.",
+            since: "1.0.1",
+            group: "string",
+            complexity: "O(N) where N is the number of keys to set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@string"],
+        key_specs: &[KeySpec {
+            flags: &["OW", "UPDATE"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: -1,
+                step: 2,
+                limit: 0,
+            },
+        }],
+        parse: Some(parse_mset_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "msetnx",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Atomically modifies the string values of one or more keys only when all keys don't exist.",
+            since: "1.0.1",
+            group: "string",
+            complexity: "O(N) where N is the number of keys to set.",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@string"],
+        key_specs: &[KeySpec {
+            flags: &["OW", "INSERT"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: -1,
+                step: 2,
+                limit: 0,
+            },
+        }],
+        parse: Some(parse_msetnx_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "getset",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Returns the previous string value of a key after setting it to a new value.",
+            since: "1.0.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_getset_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "getdel",
+        arity: 2,
+        docs: CommandDocs {
+            summary: "Returns the string value of a key after deleting the key.",
+            since: "6.2.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RW", "ACCESS", "DELETE"])],
+        parse: Some(parse_getdel_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "getex",
+        arity: -2,
+        docs: CommandDocs {
+            summary: "Returns the string value of a key after setting its expiration time.",
+            since: "6.2.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["RW", "ACCESS", "UPDATE"])],
+        parse: Some(parse_getex_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "setnx",
+        arity: 3,
+        docs: CommandDocs {
+            summary: "Set the string value of a key only when the key doesn't exist.",
+            since: "1.0.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom, CommandFlag::Fast],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["OW", "INSERT"])],
+        parse: Some(parse_setnx_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "setex",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Sets the string value and expiration time of a key. Creates the key if it doesn't exist.",
+            since: "2.0.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["OW", "UPDATE"])],
+        parse: Some(parse_setex_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "psetex",
+        arity: 4,
+        docs: CommandDocs {
+            summary: "Sets both string value and expiration time in milliseconds of a key. The key is created if it doesn't exist.",
+            since: "2.6.0",
+            group: "string",
+            complexity: "O(1)",
+        },
+        flags: &[CommandFlag::Write, CommandFlag::Denyoom],
+        acl_categories: &["@string"],
+        key_specs: &[first_key(&["OW", "UPDATE"])],
+        parse: Some(parse_psetex_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+    CommandSpec {
+        name: "lcs",
+        arity: -3,
+        docs: CommandDocs {
+            summary: "Finds the longest common substring.",
+            since: "7.0.0",
+            group: "string",
+            complexity: "O(N*M) where N and M are the lengths of s1 and s2, respectively",
+        },
+        flags: &[CommandFlag::Readonly],
+        acl_categories: &["@string"],
+        key_specs: &[KeySpec {
+            flags: &["RO", "ACCESS"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: 1,
+                step: 1,
+                limit: 0,
+            },
+        }],
+        parse: Some(parse_lcs_command),
+        subcommands: NO_SUBCOMMANDS,
+    },
+];
+lazy_static! {
+    static ref COMMANDS_BY_NAME: HashMap<&'static str, &'static CommandSpec> =
+        COMMAND_TABLE.iter().map(|spec| (spec.name, spec)).collect();
+}
+
+/// Finds the spec `argv` runs, resolving subcommands and checking the argument count.
+pub fn lookup_command(argv: &[Bytes]) -> Result<&'static CommandSpec, RusdisError> {
+    let name = match argv.first() {
+        Some(name) => name,
+        None => {
+            return Err(RusdisError::CommandParserError {
+                msg: "No leading command".to_string(),
+            })
+        }
+    };
+
+    let spec = match COMMANDS_BY_NAME.get(String::from_utf8_lossy(name).to_lowercase().as_str()) {
+        Some(spec) => *spec,
+        None => return Err(unknown_command(argv)),
+    };
+
+    if !spec.arity_matches(argv.len()) {
+        return Err(RusdisError::WrongArity {
+            command: spec.name.to_string(),
+        });
+    }
+
+    if spec.subcommands.is_empty() || argv.len() == 1 {
+        return Ok(spec);
+    }
+
+    let subcommand = String::from_utf8_lossy(&argv[1]).to_string();
+    let full_name = format!("{}|{}", spec.name, subcommand.to_lowercase());
+    match spec.subcommands.iter().find(|sub| sub.name == full_name) {
+        Some(sub) if sub.arity_matches(argv.len()) => Ok(sub),
+        Some(sub) => Err(RusdisError::WrongArity {
+            command: sub.name.to_string(),
+        }),
+        None => Err(RusdisError::UnknownSubcommand {
+            command: spec.name.to_uppercase(),
+            subcommand,
+        }),
+    }
+}
+
+/// Finds a command by name, `container|subcommand` for subcommands.
+pub fn find_command(name: &[u8]) -> Option<&'static CommandSpec> {
+    let name = String::from_utf8_lossy(name).to_lowercase();
+    match name.split_once('|') {
+        Some((container, _)) => COMMANDS_BY_NAME
+            .get(container)?
+            .subcommands
+            .iter()
+            .find(|sub| sub.name == name),
+        None => COMMANDS_BY_NAME.get(name.as_str()).copied(),
+    }
+}
+
+/// Keys a call would access, for `COMMAND GETKEYS`.
+pub fn get_keys(argv: &[Bytes]) -> Result<Vec<Bytes>, RusdisError> {
+    let spec = match lookup_command(argv) {
+        Ok(spec) => spec,
+        Err(RusdisError::WrongArity { .. }) => {
+            return Err(RusdisError::Generic {
+                msg: "Invalid number of arguments specified for command".to_string(),
+            })
+        }
+        Err(_) => {
+            return Err(RusdisError::Generic {
+                msg: "Invalid command specified".to_string(),
+            })
+        }
+    };
+
+    let keys = spec.keys(argv);
+    if keys.is_empty() {
+        return Err(RusdisError::Generic {
+            msg: "The command has no key arguments".to_string(),
+        });
+    }
+
+    Ok(keys)
+}
+
+fn bulk(text: &str) -> Value {
+    Value::BulkString(Bytes::copy_from_slice(text.as_bytes()))
+}
+
+fn status_set(names: impl Iterator<Item = &'static str>) -> Value {
+    Value::Set(
+        names
+            .map(|name| Value::SimpleString(name.to_string()))
+            .collect(),
+    )
+}
+
+fn key_spec_info(spec: &KeySpec) -> Value {
+    let begin_search = match spec.begin_search {
+        BeginSearch::Index(idx) => ("index", vec![(bulk("index"), Value::Integer(idx as i64))]),
+        BeginSearch::Keyword {
+            keyword,
+            start_from,
+        } => (
+            "keyword",
+            vec![
+                (bulk("keyword"), bulk(keyword)),
+                (bulk("startfrom"), Value::Integer(start_from)),
+            ],
+        ),
+    };
+    let find_keys = match spec.find_keys {
+        FindKeys::Range {
+            last_key,
+            step,
+            limit,
+        } => (
+            "range",
+            vec![
+                (bulk("lastkey"), Value::Integer(last_key)),
+                (bulk("keystep"), Value::Integer(step as i64)),
+                (bulk("limit"), Value::Integer(limit as i64)),
+            ],
+        ),
+        FindKeys::Keynum {
+            keynum_idx,
+            first_key,
+            step,
+        } => (
+            "keynum",
+            vec![
+                (bulk("keynumidx"), Value::Integer(keynum_idx as i64)),
+                (bulk("firstkey"), Value::Integer(first_key as i64)),
+                (bulk("keystep"), Value::Integer(step as i64)),
+            ],
+        ),
+    };
+
+    Value::Map(vec![
+        (bulk("flags"), status_set(spec.flags.iter().copied())),
+        (
+            bulk("begin_search"),
+            Value::Map(vec![
+                (bulk("type"), bulk(begin_search.0)),
+                (bulk("spec"), Value::Map(begin_search.1)),
+            ]),
+        ),
+        (
+            bulk("find_keys"),
+            Value::Map(vec![
+                (bulk("type"), bulk(find_keys.0)),
+                (bulk("spec"), Value::Map(find_keys.1)),
+            ]),
+        ),
+    ])
+}
+
+/// Entry of a command in the `COMMAND` and `COMMAND INFO` replies.
+pub fn command_info(spec: &CommandSpec) -> Value {
+    let (first_key, last_key, step, movable) = spec.legacy_key_range();
+    let mut flags: Vec<&'static str> = spec.flags.iter().map(|flag| flag.name()).collect();
+    if movable {
+        flags.push("movablekeys");
+    }
+
+    Value::Array(vec![
+        bulk(spec.name),
+        Value::Integer(spec.arity),
+        status_set(flags.into_iter()),
+        Value::Integer(first_key),
+        Value::Integer(last_key),
+        Value::Integer(step),
+        status_set(spec.acl_categories().into_iter()),
+        Value::Set(vec![]),
+        Value::Array(spec.key_specs.iter().map(key_spec_info).collect()),
+        Value::Array(spec.subcommands.iter().map(command_info).collect()),
+    ])
+}
+
+/// Documentation of a command in the `COMMAND DOCS` reply.
+pub fn command_docs(spec: &CommandSpec) -> Value {
+    let mut docs = vec![
+        (bulk("summary"), bulk(spec.docs.summary)),
+        (bulk("since"), bulk(spec.docs.since)),
+        (bulk("group"), bulk(spec.docs.group)),
+        (bulk("complexity"), bulk(spec.docs.complexity)),
+    ];
+    if !spec.subcommands.is_empty() {
+        docs.push((
+            bulk("subcommands"),
+            Value::Map(
+                spec.subcommands
+                    .iter()
+                    .map(|sub| (bulk(sub.name), command_docs(sub)))
+                    .collect(),
+            ),
+        ));
+    }
+
+    Value::Map(docs)
+}
+
+/// Reply to `<container> HELP`: each subcommand followed by its summary.
+pub fn command_help(spec: &CommandSpec) -> Value {
+    let mut lines = vec![format!(
+        "{} <subcommand> [<arg> [value] [opt] ...]. Subcommands are:",
+        spec.name.to_uppercase()
+    )];
+    for sub in spec.subcommands.iter() {
+        let name = sub.name.split_once('|').map_or(sub.name, |(_, name)| name);
+        lines.push(name.to_uppercase());
+        lines.push(format!("    {}", sub.docs.summary));
+    }
+
+    Value::Array(lines.into_iter().map(Value::SimpleString).collect())
+}
+
+/// Error for a command name nothing is registered under, quoting the first
+/// arguments the way Redis does.
+fn unknown_command(argv: &[Bytes]) -> RusdisError {
+    let mut args_text = String::new();
+    for arg in argv[1..].iter() {
+        if args_text.len() >= 128 {
+            break;
+        }
+        let text: String = String::from_utf8_lossy(arg)
+            .chars()
+            .take(128 - args_text.len())
+            .collect();
+        args_text += &format!("'{}' ", text);
+    }
+
+    RusdisError::UnknownCommand {
+        name: String::from_utf8_lossy(&argv[0])
+            .chars()
+            .take(128)
+            .collect(),
+        args: args_text,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<Bytes> {
+        args.iter()
+            .map(|arg| Bytes::from(arg.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_command_table_lookup() {
+        assert_eq!(lookup_command(&argv(&["GeT", "a"])).unwrap().name, "get");
+        assert_eq!(
+            lookup_command(&argv(&["config", "GET", "dir"]))
+                .unwrap()
+                .name,
+            "config|get"
+        );
+
+        assert!(lookup_command(&argv(&["get", "a", "b"]))
+            .is_err_and(|e| e.to_string() == "ERR wrong number of arguments for 'get' command"));
+        assert!(lookup_command(&argv(&["config"]))
+            .is_err_and(|e| e.to_string() == "ERR wrong number of arguments for 'config' command"));
+        assert!(lookup_command(&argv(&["config", "get"])).is_err_and(
+            |e| e.to_string() == "ERR wrong number of arguments for 'config|get' command"
+        ));
+        assert!(lookup_command(&argv(&["config", "nope"]))
+            .is_err_and(|e| e.to_string() == "ERR unknown subcommand 'nope'. Try CONFIG HELP."));
+        assert_eq!(
+            lookup_command(&argv(&["xinfo", "help"])).unwrap().name,
+            "xinfo|help"
+        );
+        assert!(lookup_command(&argv(&["nope"])).is_err_and(
+            |e| e.to_string() == "ERR unknown command 'nope', with args beginning with: "
+        ));
+    }
+
+    #[test]
+    fn test_command_table_flags_and_categories() {
+        let set = lookup_command(&argv(&["set", "a", "b"])).unwrap();
+        assert!(set.has_flag(CommandFlag::Write));
+        assert_eq!(set.acl_categories(), vec!["@string", "@write", "@slow"]);
+
+        let psync = lookup_command(&argv(&["psync", "?", "-1"])).unwrap();
+        assert!(psync.has_flag(CommandFlag::NoMulti));
+        assert_eq!(
+            psync.acl_categories(),
+            vec!["@admin", "@dangerous", "@slow"]
+        );
+    }
+
+    #[test]
+    fn test_key_spec_positions() {
+        let get = lookup_command(&argv(&["get", "a"])).unwrap();
+        assert_eq!(get.key_positions(&argv(&["get", "a"])), vec![1]);
+
+        let all_keys = KeySpec {
+            flags: &["RO"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: -1,
+                step: 1,
+                limit: 0,
+            },
+        };
+        assert_eq!(
+            all_keys.key_positions(&argv(&["mget", "a", "b", "c"])),
+            vec![1, 2, 3]
+        );
+
+        let pairs = KeySpec {
+            flags: &["OW"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Range {
+                last_key: -1,
+                step: 2,
+                limit: 0,
+            },
+        };
+        assert_eq!(
+            pairs.key_positions(&argv(&["mset", "a", "1", "b", "2"])),
+            vec![1, 3]
+        );
+
+        let numkeys = KeySpec {
+            flags: &["RO"],
+            begin_search: BeginSearch::Index(1),
+            find_keys: FindKeys::Keynum {
+                keynum_idx: 0,
+                first_key: 1,
+                step: 1,
+            },
+        };
+        assert_eq!(
+            numkeys.key_positions(&argv(&["zunion", "2", "a", "b", "WITHSCORES"])),
+            vec![2, 3]
+        );
+
+        let streams = KeySpec {
+            flags: &["RO"],
+            begin_search: BeginSearch::Keyword {
+                keyword: "STREAMS",
+                start_from: 1,
+            },
+            find_keys: FindKeys::Range {
+                last_key: -1,
+                step: 1,
+                limit: 2,
+            },
+        };
+        assert_eq!(
+            streams.key_positions(&argv(&[
+                "xread", "COUNT", "2", "STREAMS", "a", "b", "0", "0"
+            ])),
+            vec![4, 5]
+        );
+    }
+
+    #[test]
+    fn test_command_info_and_docs() {
+        let get = find_command(b"GET").unwrap();
+        assert_eq!(
+            command_info(get),
+            Value::Array(vec![
+                bulk("get"),
+                Value::Integer(2),
+                Value::Set(vec![
+                    Value::SimpleString("readonly".to_string()),
+                    Value::SimpleString("fast".to_string()),
+                ]),
+                Value::Integer(1),
+                Value::Integer(1),
+                Value::Integer(1),
+                Value::Set(vec![
+                    Value::SimpleString("@string".to_string()),
+                    Value::SimpleString("@read".to_string()),
+                    Value::SimpleString("@fast".to_string()),
+                ]),
+                Value::Set(vec![]),
+                Value::Array(vec![key_spec_info(&first_key(&["RO", "ACCESS"]))]),
+                Value::Array(vec![]),
+            ])
+        );
+
+        let config_get = find_command(b"config|get").unwrap();
+        assert_eq!(config_get.name, "config|get");
+        assert!(find_command(b"config|nope").is_none());
+
+        let Value::Map(docs) = command_docs(find_command(b"config").unwrap()) else {
+            panic!("docs should be a map");
+        };
+        assert_eq!(
+            docs[0],
+            (
+                bulk("summary"),
+                bulk("A container for server configuration commands.")
+            )
+        );
+        assert_eq!(docs[4].0, bulk("subcommands"));
+
+        let Value::Array(help) = command_help(find_command(b"config").unwrap()) else {
+            panic!("help should be an array");
+        };
+        assert_eq!(
+            help,
+            vec![
+                Value::SimpleString(
+                    "CONFIG <subcommand> [<arg> [value] [opt] ...]. Subcommands are:".to_string()
+                ),
+                Value::SimpleString("GET".to_string()),
+                Value::SimpleString(
+                    "    Returns the effective values of configuration parameters.".to_string()
+                ),
+                Value::SimpleString("HELP".to_string()),
+                Value::SimpleString(
+                    "    Returns helpful text about the different subcommands.".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_command_getkeys() {
+        assert_eq!(get_keys(&argv(&["set", "a", "b"])).unwrap(), argv(&["a"]));
+        assert!(get_keys(&argv(&["set", "a"])).is_err_and(|e| {
+            e.to_string() == "ERR Invalid number of arguments specified for command"
+        }));
+        assert!(get_keys(&argv(&["nope", "a"]))
+            .is_err_and(|e| e.to_string() == "ERR Invalid command specified"));
+        assert!(get_keys(&argv(&["ping", "a"]))
+            .is_err_and(|e| e.to_string() == "ERR The command has no key arguments"));
+    }
+}
@@ -1,8 +1,12 @@
 use crate::rdb_file_reader::{Dataset, ValueType};
+use crate::sorted_set::SortedSet;
+use crate::stream::Stream;
 use crate::utils::generate_random_string;
-use std::collections::HashMap;
+use bytes::Bytes;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::Mutex;
 
 #[derive(Debug, Clone)]
@@ -55,35 +59,364 @@ impl ReplicationInfo {
 
 #[derive(Clone, Debug)]
 pub struct StringData {
-    data: String,
-    expiration: Option<SystemTime>,
+    data: Bytes,
 }
 
 impl StringData {
-    pub fn new(data: String, expiration: Option<SystemTime>) -> Self {
-        StringData { data, expiration }
+    pub fn new(data: Bytes) -> Self {
+        StringData { data }
     }
 
-    pub fn is_expired(&self) -> bool {
-        if let Some(expiration) = self.expiration {
-            let now = SystemTime::now();
+    pub fn get_data(&self) -> &Bytes {
+        &self.data
+    }
 
-            if now >= expiration {
-                true
-            } else {
-                false
+    pub fn set_data(&mut self, s: Bytes) {
+        self.data = s;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ListData {
+    list: VecDeque<Bytes>,
+}
+
+impl ListData {
+    pub fn new(list: VecDeque<Bytes>) -> Self {
+        ListData { list }
+    }
+
+    pub fn get_list(&self) -> &VecDeque<Bytes> {
+        &self.list
+    }
+
+    pub fn get_list_mut(&mut self) -> &mut VecDeque<Bytes> {
+        &mut self.list
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HashData {
+    hash: HashMap<Bytes, Bytes>,
+    /// Expiration of the fields that have one
+    field_expirations: HashMap<Bytes, SystemTime>,
+    /// The same expirations, soonest first
+    field_expiration_order: BTreeSet<(SystemTime, Bytes)>,
+}
+
+impl HashData {
+    pub fn new(hash: HashMap<Bytes, Bytes>) -> Self {
+        HashData {
+            hash,
+            field_expirations: HashMap::new(),
+            field_expiration_order: BTreeSet::new(),
+        }
+    }
+
+    pub fn get_hash(&self) -> &HashMap<Bytes, Bytes> {
+        &self.hash
+    }
+
+    /// Sets a field, clearing its expiration unless `keep_ttl`. Returns
+    /// whether the field is new.
+    pub fn set(&mut self, field: Bytes, value: Bytes, keep_ttl: bool) -> bool {
+        if !keep_ttl {
+            self.set_field_expiration(&field, None);
+        }
+
+        self.hash.insert(field, value).is_none()
+    }
+
+    pub fn remove(&mut self, field: &Bytes) -> bool {
+        self.set_field_expiration(field, None);
+
+        self.hash.remove(field).is_some()
+    }
+
+    pub fn get_field_expiration(&self, field: &Bytes) -> Option<SystemTime> {
+        self.field_expirations.get(field).copied()
+    }
+
+    pub fn set_field_expiration(&mut self, field: &Bytes, expiration: Option<SystemTime>) {
+        if let Some(old) = self.field_expirations.remove(field) {
+            self.field_expiration_order.remove(&(old, field.clone()));
+        }
+        if let Some(expiration) = expiration {
+            self.field_expirations.insert(field.clone(), expiration);
+            self.field_expiration_order
+                .insert((expiration, field.clone()));
+        }
+    }
+
+    pub fn has_field_expirations(&self) -> bool {
+        !self.field_expirations.is_empty()
+    }
+
+    /// Removes the fields whose expiration has passed. Returns whether there were any.
+    pub fn remove_expired_fields(&mut self) -> bool {
+        let now = SystemTime::now();
+        let mut removed = false;
+        while let Some((expiration, field)) = self.field_expiration_order.first().cloned() {
+            if expiration > now {
+                break;
             }
-        } else {
-            return false;
+            self.remove(&field);
+            removed = true;
         }
+        removed
     }
+}
 
-    pub fn get_data(&self) -> &String {
-        &self.data
+#[derive(Clone, Debug)]
+pub struct SetData {
+    set: HashSet<Bytes>,
+}
+
+impl SetData {
+    pub fn new(set: HashSet<Bytes>) -> Self {
+        SetData { set }
     }
 
-    pub fn set_data(&mut self, s: String) {
-        self.data = s;
+    pub fn get_set(&self) -> &HashSet<Bytes> {
+        &self.set
+    }
+
+    pub fn get_set_mut(&mut self) -> &mut HashSet<Bytes> {
+        &mut self.set
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ZSetData {
+    zset: SortedSet,
+}
+
+impl ZSetData {
+    pub fn new(zset: SortedSet) -> Self {
+        ZSetData { zset }
+    }
+
+    pub fn get_zset(&self) -> &SortedSet {
+        &self.zset
+    }
+
+    pub fn get_zset_mut(&mut self) -> &mut SortedSet {
+        &mut self.zset
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StreamData {
+    stream: Stream,
+}
+
+impl StreamData {
+    pub fn new(stream: Stream) -> Self {
+        StreamData { stream }
+    }
+
+    pub fn get_stream(&self) -> &Stream {
+        &self.stream
+    }
+
+    pub fn get_stream_mut(&mut self) -> &mut Stream {
+        &mut self.stream
+    }
+}
+
+/// Value stored under a key, one variant per data type.
+#[derive(Clone, Debug)]
+pub enum Data {
+    String(StringData),
+    List(ListData),
+    Hash(HashData),
+    Set(SetData),
+    ZSet(ZSetData),
+    Stream(StreamData),
+}
+
+impl Data {
+    /// Name of the type, as TYPE replies it.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Data::String(_) => "string",
+            Data::List(_) => "list",
+            Data::Hash(_) => "hash",
+            Data::Set(_) => "set",
+            Data::ZSet(_) => "zset",
+            Data::Stream(_) => "stream",
+        }
+    }
+}
+
+/// Keys and their values. Expirations are kept apart, for the keys that
+/// have one, like Redis' `expires` dict.
+#[derive(Clone, Debug, Default)]
+pub struct Keyspace {
+    data: HashMap<Bytes, Data>,
+    expires: HashMap<Bytes, SystemTime>,
+    /// Keys of hashes that may have fields with an expiration, for active
+    /// expiry. Keys deleted since are dropped when it visits them.
+    expiring_hashes: HashSet<Bytes>,
+    /// The same keys, in the order active expiry visits them
+    expiring_hash_queue: VecDeque<Bytes>,
+    /// What the running command propagates to replicas instead of the
+    /// arguments it was called with
+    propagate_as: Option<Vec<Vec<Bytes>>>,
+}
+
+impl Keyspace {
+    pub fn new() -> Self {
+        Keyspace::default()
+    }
+
+    pub fn get(&self, key: &Bytes) -> Option<&Data> {
+        self.data.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &Bytes) -> Option<&mut Data> {
+        self.data.get_mut(key)
+    }
+
+    #[cfg(test)]
+    pub fn contains_key(&self, key: &Bytes) -> bool {
+        self.data.contains_key(key)
+    }
+
+    /// Stores `data` at `key`, clearing the expiration of a value it replaces.
+    pub fn insert(&mut self, key: Bytes, data: Data) -> Option<Data> {
+        self.expires.remove(&key);
+        self.data.insert(key, data)
+    }
+
+    /// Entry of `key`. Values inserted through it have no expiration.
+    pub fn entry(&mut self, key: Bytes) -> Entry<'_, Bytes, Data> {
+        self.data.entry(key)
+    }
+
+    pub fn remove(&mut self, key: &Bytes) -> Option<Data> {
+        self.expires.remove(key);
+        self.data.remove(key)
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Bytes, &Data)> {
+        self.data.iter()
+    }
+
+    pub fn get_expiration(&self, key: &Bytes) -> Option<SystemTime> {
+        self.expires.get(key).copied()
+    }
+
+    /// Sets or, with `None`, clears the expiration of an existing key.
+    pub fn set_expiration(&mut self, key: &Bytes, expiration: Option<SystemTime>) {
+        match expiration {
+            Some(expiration) if self.data.contains_key(key) => {
+                self.expires.insert(key.clone(), expiration);
+            }
+            _ => {
+                self.expires.remove(key);
+            }
+        }
+    }
+
+    pub fn is_expired(&self, key: &Bytes) -> bool {
+        self.expires
+            .get(key)
+            .is_some_and(|expiration| SystemTime::now() >= *expiration)
+    }
+
+    /// Has the running command propagate `commands` instead of its own
+    /// arguments, for replicas to get its effect rather than run it again.
+    /// No commands propagates nothing.
+    pub fn propagate_as(&mut self, commands: Vec<Vec<Bytes>>) {
+        self.propagate_as = Some(commands);
+    }
+
+    /// Takes what the command that ran set to propagate, if anything.
+    pub fn take_propagate_as(&mut self) -> Option<Vec<Vec<Bytes>>> {
+        self.propagate_as.take()
+    }
+
+    /// Has active expiry visit the hash at `key` if any of its fields expire.
+    pub fn track_field_expirations(&mut self, key: &Bytes) {
+        if let Some(Data::Hash(hash)) = self.data.get(key) {
+            if hash.has_field_expirations() && self.expiring_hashes.insert(key.clone()) {
+                self.expiring_hash_queue.push_back(key.clone());
+            }
+        }
+    }
+}
+
+/// Looks `key` up, deleting it first if it has expired. Expired hash fields
+/// are dropped too, along with the hash if no field is left.
+pub fn get_live<'a>(keyspace: &'a mut Keyspace, key: &Bytes) -> Option<&'a mut Data> {
+    let gone = keyspace.is_expired(key)
+        || match keyspace.get_mut(key) {
+            Some(Data::Hash(data)) => {
+                data.remove_expired_fields();
+                data.get_hash().is_empty()
+            }
+            _ => false,
+        };
+    if gone {
+        keyspace.remove(key);
+    }
+
+    keyspace.get_mut(key)
+}
+
+/// Hashes active expiry visits per round, Redis' `ACTIVE_EXPIRE_CYCLE_KEYS_PER_LOOP`.
+const ACTIVE_EXPIRE_KEYS_PER_ROUND: usize = 20;
+/// Longest an active expiry cycle keeps the keyspace locked.
+const ACTIVE_EXPIRE_CYCLE_TIME: Duration = Duration::from_millis(25);
+
+/// Active expiry of hash fields, modeled on Redis' `activeExpireCycle`.
+///
+/// Only hashes with expiring fields are visited, a round of a few at a time.
+/// Rounds go on while more than a quarter of the hashes visited had expired
+/// fields, within a time budget. Hashes left empty are deleted.
+pub fn remove_expired_fields(keyspace: &mut Keyspace) {
+    let start = Instant::now();
+    loop {
+        let round = ACTIVE_EXPIRE_KEYS_PER_ROUND.min(keyspace.expiring_hash_queue.len());
+        let mut expired = 0;
+        for _ in 0..round {
+            let key = keyspace.expiring_hash_queue.pop_front().unwrap();
+            let still_expiring = match keyspace.data.get_mut(&key) {
+                Some(Data::Hash(hash)) => {
+                    if hash.remove_expired_fields() {
+                        expired += 1;
+                    }
+                    if hash.get_hash().is_empty() {
+                        keyspace.remove(&key);
+                        false
+                    } else {
+                        hash.has_field_expirations()
+                    }
+                }
+                _ => false,
+            };
+
+            if still_expiring {
+                keyspace.expiring_hash_queue.push_back(key);
+            } else {
+                keyspace.expiring_hashes.remove(&key);
+            }
+        }
+
+        if round == 0 || expired * 4 <= round || start.elapsed() > ACTIVE_EXPIRE_CYCLE_TIME {
+            break;
+        }
     }
 }
 
@@ -99,24 +432,27 @@ impl Admin {
         let mut databases = vec![Database::default(); length];
 
         for (idx, dataset) in preload_datasets.into_iter().enumerate() {
-            let mut string_data_vec = vec![];
+            let mut keyspace = Keyspace::new();
             for (key, value, expiration) in dataset.get_pairs().into_iter() {
-                match value {
-                    ValueType::String(string) => string_data_vec.push((
-                        key,
-                        StringData {
-                            data: string,
-                            expiration,
-                        },
-                    )),
-                }
+                let data = match value {
+                    ValueType::String(string) => Data::String(StringData::new(string)),
+                    ValueType::List(list) => Data::List(ListData::new(list)),
+                    ValueType::Hash(hash) => Data::Hash(HashData::new(hash)),
+                    ValueType::Set(set) => Data::Set(SetData::new(set)),
+                    ValueType::ZSet(members) => {
+                        let mut zset = SortedSet::new();
+                        for (member, score) in members.into_iter() {
+                            zset.insert(member, score);
+                        }
+                        Data::ZSet(ZSetData::new(zset))
+                    }
+                    ValueType::Stream(stream) => Data::Stream(StreamData::new(stream)),
+                };
+                keyspace.insert(key.clone(), data);
+                keyspace.set_expiration(&key, expiration);
             }
 
-            let string_data = string_data_vec
-                .into_iter()
-                .collect::<HashMap<String, StringData>>();
-
-            databases[idx].string_data = Arc::new(Mutex::new(string_data));
+            databases[idx].keyspace = Arc::new(Mutex::new(keyspace));
         }
         Admin {
             databases,
@@ -124,66 +460,80 @@ impl Admin {
         }
     }
 
+    #[allow(dead_code)]
     pub fn select_database(&mut self, idx: usize) {
         if idx < self.databases.len() {
             self.cur_idx = idx;
         }
     }
 
-    pub fn get_string_data_map(&self) -> Arc<Mutex<HashMap<String, StringData>>> {
-        let arc = self.databases[self.cur_idx].string_data.clone();
-        arc
+    pub fn get_keyspace(&self) -> Arc<Mutex<Keyspace>> {
+        self.databases[self.cur_idx].keyspace.clone()
     }
 }
 
 #[derive(Default, Debug, Clone)]
 pub struct Database {
-    string_data: Arc<Mutex<HashMap<String, StringData>>>,
+    keyspace: Arc<Mutex<Keyspace>>,
 }
 
 #[cfg(test)]
-
 mod test {
     use super::*;
 
     #[test]
     fn test_data_expired() {
+        let (key, key2) = (Bytes::from("key"), Bytes::from("key2"));
         let now = SystemTime::now();
         let fu = now.checked_add(Duration::from_millis(20)).unwrap();
-        let data = StringData::new("Data stores here".to_string(), Some(fu));
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(
+            key.clone(),
+            Data::String(StringData::new(Bytes::from("Data stores here"))),
+        );
+        keyspace.set_expiration(&key, Some(fu));
 
-        assert!(!data.is_expired());
+        assert!(!keyspace.is_expired(&key));
 
         std::thread::sleep(Duration::from_millis(30));
 
-        assert!(data.is_expired());
+        assert!(keyspace.is_expired(&key));
 
-        let data2 = StringData::new("Never expire".to_string(), None);
+        keyspace.insert(
+            key2.clone(),
+            Data::String(StringData::new(Bytes::from("Never expire"))),
+        );
         std::thread::sleep(Duration::from_millis(30));
-        assert!(!data2.is_expired());
-        std::thread::sleep(Duration::from_millis(100));
-        assert!(!data2.is_expired());
+        assert!(!keyspace.is_expired(&key2));
+
+        // overwriting a key clears its expiration
+        keyspace.insert(
+            key.clone(),
+            Data::String(StringData::new(Bytes::from("new"))),
+        );
+        assert!(!keyspace.is_expired(&key));
+        assert_eq!(keyspace.get_expiration(&key), None);
     }
 
     #[test]
     fn test_database() {
         let d1 = Dataset {
             pairs: vec![(
-                "key1".to_string(),
-                ValueType::String("value1".to_string()),
+                Bytes::from("key1"),
+                ValueType::String(Bytes::from("value1")),
                 Some(SystemTime::now()),
             )],
         };
         let d2 = Dataset {
             pairs: vec![
                 (
-                    "key1".to_string(),
-                    ValueType::String("value1".to_string()),
+                    Bytes::from("key1"),
+                    ValueType::String(Bytes::from("value1")),
                     Some(SystemTime::now()),
                 ),
                 (
-                    "car".to_string(),
-                    ValueType::String("BMW".to_string()),
+                    Bytes::from("car"),
+                    ValueType::String(Bytes::from("BMW")),
                     None,
                 ),
             ],
@@ -193,4 +543,71 @@ mod test {
         let mut admin = Admin::new(datasets);
         admin.select_database(1);
     }
+
+    #[test]
+    fn test_get_live_removes_expired_keys() {
+        let past = SystemTime::now() - Duration::from_secs(1);
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(
+            Bytes::from("old"),
+            Data::List(ListData::new(VecDeque::from([Bytes::from("a")]))),
+        );
+        keyspace.set_expiration(&Bytes::from("old"), Some(past));
+        keyspace.insert(
+            Bytes::from("new"),
+            Data::String(StringData::new(Bytes::from("b"))),
+        );
+
+        assert!(get_live(&mut keyspace, &Bytes::from("old")).is_none());
+        assert!(!keyspace.contains_key(&Bytes::from("old")));
+        assert!(get_live(&mut keyspace, &Bytes::from("new")).is_some());
+    }
+
+    #[test]
+    fn test_hash_field_expiration() {
+        let past = SystemTime::now() - Duration::from_secs(1);
+        let future = SystemTime::now() + Duration::from_secs(60);
+        let (a, b) = (Bytes::from("a"), Bytes::from("b"));
+
+        let mut hash = HashData::new(HashMap::new());
+        hash.set(a.clone(), Bytes::from("1"), false);
+        hash.set(b.clone(), Bytes::from("2"), false);
+        hash.set_field_expiration(&a, Some(future));
+        hash.set_field_expiration(&b, Some(future));
+
+        // HSET clears the expiration, HINCRBY keeps it
+        hash.set(a.clone(), Bytes::from("3"), false);
+        hash.set(b.clone(), Bytes::from("4"), true);
+        assert_eq!(hash.get_field_expiration(&a), None);
+        assert_eq!(hash.get_field_expiration(&b), Some(future));
+
+        hash.set_field_expiration(&b, Some(past));
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(Bytes::from("h"), Data::Hash(hash.clone()));
+        match get_live(&mut keyspace, &Bytes::from("h")) {
+            Some(Data::Hash(data)) => assert_eq!(data.get_hash().len(), 1),
+            _ => panic!("expected a hash"),
+        }
+
+        hash.set_field_expiration(&a, Some(past));
+        keyspace.insert(Bytes::from("h"), Data::Hash(hash));
+        // only tracked hashes are visited by active expiry
+        remove_expired_fields(&mut keyspace);
+        assert_eq!(keyspace.len(), 1);
+        keyspace.track_field_expirations(&Bytes::from("h"));
+        remove_expired_fields(&mut keyspace);
+        assert!(keyspace.is_empty());
+        assert!(keyspace.expiring_hashes.is_empty());
+
+        // hashes whose fields have not expired yet stay tracked
+        let mut hash = HashData::new(HashMap::new());
+        hash.set(a.clone(), Bytes::from("1"), false);
+        hash.set_field_expiration(&a, Some(future));
+        keyspace.insert(Bytes::from("h"), Data::Hash(hash));
+        keyspace.track_field_expirations(&Bytes::from("h"));
+        keyspace.track_field_expirations(&Bytes::from("h"));
+        remove_expired_fields(&mut keyspace);
+        assert_eq!(keyspace.len(), 1);
+        assert_eq!(keyspace.expiring_hash_queue.len(), 1);
+    }
 }
@@ -0,0 +1,308 @@
+use crate::parser::Value;
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// Serializes `value` into `buf` in the wire format of `protocol`.
+///
+/// RESP3-only types are downgraded for RESP2 clients the same way Redis does:
+/// maps become flat arrays, doubles and big numbers become bulk strings,
+/// booleans become integers and attributes are dropped.
+pub fn encode(value: &Value, buf: &mut BytesMut, protocol: u8) {
+    let resp3 = protocol >= 3;
+
+    match value {
+        Value::SimpleString(s) => put_simple(buf, b'+', s),
+        Value::SimpleError(s) => put_simple(buf, b'-', s),
+        Value::Integer(num) => put_line(buf, b':', num.to_string().as_bytes()),
+        Value::BulkString(data) => put_blob(buf, b'$', data),
+        Value::Array(values) => put_aggregate(buf, b'*', values, protocol),
+        Value::Null => {
+            if resp3 {
+                buf.put_slice(b"_\r\n");
+            } else {
+                buf.put_slice(b"$-1\r\n");
+            }
+        }
+        Value::NullArray => {
+            if resp3 {
+                buf.put_slice(b"_\r\n");
+            } else {
+                buf.put_slice(b"*-1\r\n");
+            }
+        }
+        Value::Map(pairs) => {
+            if resp3 {
+                put_line(buf, b'%', pairs.len().to_string().as_bytes());
+            } else {
+                put_line(buf, b'*', (pairs.len() * 2).to_string().as_bytes());
+            }
+            for (key, value) in pairs.iter() {
+                encode(key, buf, protocol);
+                encode(value, buf, protocol);
+            }
+        }
+        Value::Pairs(pairs) => {
+            if resp3 {
+                put_line(buf, b'*', pairs.len().to_string().as_bytes());
+            } else {
+                put_line(buf, b'*', (pairs.len() * 2).to_string().as_bytes());
+            }
+            for (first, second) in pairs.iter() {
+                if resp3 {
+                    buf.put_slice(b"*2\r\n");
+                }
+                encode(first, buf, protocol);
+                encode(second, buf, protocol);
+            }
+        }
+        Value::ArrayMap(pairs) => {
+            if resp3 {
+                put_line(buf, b'%', pairs.len().to_string().as_bytes());
+            } else {
+                put_line(buf, b'*', pairs.len().to_string().as_bytes());
+            }
+            for (key, value) in pairs.iter() {
+                if !resp3 {
+                    buf.put_slice(b"*2\r\n");
+                }
+                encode(key, buf, protocol);
+                encode(value, buf, protocol);
+            }
+        }
+        Value::Set(values) => put_aggregate(buf, if resp3 { b'~' } else { b'*' }, values, protocol),
+        Value::Double(num) => {
+            let num_str = format_double(*num);
+            if resp3 {
+                put_line(buf, b',', num_str.as_bytes());
+            } else {
+                put_blob(buf, b'$', num_str.as_bytes());
+            }
+        }
+        Value::Boolean(b) => {
+            if resp3 {
+                put_line(buf, b'#', if *b { b"t" } else { b"f" });
+            } else {
+                put_line(buf, b':', if *b { b"1" } else { b"0" });
+            }
+        }
+        Value::BigNumber(digits) => {
+            if resp3 {
+                put_line(buf, b'(', digits.as_bytes());
+            } else {
+                put_blob(buf, b'$', digits.as_bytes());
+            }
+        }
+        Value::VerbatimString(format, text) => {
+            if resp3 {
+                let mut data = Vec::with_capacity(format.len() + 1 + text.len());
+                data.extend_from_slice(format.as_bytes());
+                data.push(b':');
+                data.extend_from_slice(text);
+                put_blob(buf, b'=', &data);
+            } else {
+                put_blob(buf, b'$', text);
+            }
+        }
+        Value::Attribute(attributes, value) => {
+            if resp3 {
+                put_line(buf, b'|', attributes.len().to_string().as_bytes());
+                for (key, value) in attributes.iter() {
+                    encode(key, buf, protocol);
+                    encode(value, buf, protocol);
+                }
+            }
+            encode(value, buf, protocol);
+        }
+        Value::Push(values) => {
+            put_aggregate(buf, if resp3 { b'>' } else { b'*' }, values, protocol)
+        }
+    }
+}
+
+/// Convenience wrapper around `encode` for a single value.
+pub fn encode_to_bytes(value: &Value, protocol: u8) -> Bytes {
+    let mut buf = BytesMut::new();
+    encode(value, &mut buf, protocol);
+    buf.freeze()
+}
+
+/// Formats a double the way Redis prints scores: the shortest representation
+/// that round-trips, switching to an exponent like `%.17g` does (`1.5e+308`,
+/// `1e-05`), with `inf`, `-inf` and `nan` spelled out.
+pub fn format_double(num: f64) -> String {
+    if num.is_nan() {
+        return "nan".to_string();
+    } else if num == f64::INFINITY {
+        return "inf".to_string();
+    } else if num == f64::NEG_INFINITY {
+        return "-inf".to_string();
+    }
+
+    let scientific = format!("{:e}", num);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent = exponent.parse::<i32>().unwrap();
+    if (-4..17).contains(&exponent) {
+        num.to_string()
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+    }
+}
+
+fn put_line(buf: &mut BytesMut, symbol: u8, line: &[u8]) {
+    buf.put_u8(symbol);
+    buf.put_slice(line);
+    buf.put_slice(b"\r\n");
+}
+
+/// Writes a status or error line. Any CR or LF in the payload, which may
+/// echo user input, is replaced by a space so it cannot end the line early.
+fn put_simple(buf: &mut BytesMut, symbol: u8, line: &str) {
+    buf.put_u8(symbol);
+    buf.extend(line.bytes().map(|b| match b {
+        b'\r' | b'\n' => b' ',
+        b => b,
+    }));
+    buf.put_slice(b"\r\n");
+}
+
+fn put_blob(buf: &mut BytesMut, symbol: u8, data: &[u8]) {
+    put_line(buf, symbol, data.len().to_string().as_bytes());
+    buf.put_slice(data);
+    buf.put_slice(b"\r\n");
+}
+
+fn put_aggregate(buf: &mut BytesMut, symbol: u8, values: &[Value], protocol: u8) {
+    put_line(buf, symbol, values.len().to_string().as_bytes());
+    for value in values.iter() {
+        encode(value, buf, protocol);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::Decoder;
+
+    #[test]
+    fn test_encoder_resp2_types() {
+        let value = Value::Array(vec![
+            Value::SimpleString("OK".to_string()),
+            Value::SimpleError("ERR unknown".to_string()),
+            Value::Integer(-42),
+            Value::BulkString(Bytes::from_static(b"a\r\nb")),
+            Value::Null,
+            Value::NullArray,
+            Value::Array(vec![]),
+        ]);
+
+        assert_eq!(
+            &encode_to_bytes(&value, 2)[..],
+            b"*7\r\n+OK\r\n-ERR unknown\r\n:-42\r\n$4\r\na\r\nb\r\n$-1\r\n*-1\r\n*0\r\n"
+        );
+    }
+
+    #[test]
+    fn test_encoder_strips_newlines_from_simple_strings() {
+        let value = Value::Array(vec![
+            Value::SimpleError("ERR unknown command 'FOO\r\n+INJECT'".to_string()),
+            Value::SimpleString("a\nb".to_string()),
+        ]);
+
+        assert_eq!(
+            &encode_to_bytes(&value, 2)[..],
+            &b"*2\r\n-ERR unknown command 'FOO  +INJECT'\r\n+a b\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn test_encoder_resp3_types_round_trip() {
+        let value = Value::Push(vec![
+            Value::Map(vec![(
+                Value::BulkString(Bytes::from("dir")),
+                Value::Set(vec![Value::Boolean(true), Value::Null]),
+            )]),
+            Value::Double(1.5),
+            Value::Double(f64::INFINITY),
+            Value::BigNumber("12345678901234567890".to_string()),
+            Value::VerbatimString("txt".to_string(), Bytes::from("role:master")),
+            Value::Attribute(
+                vec![(Value::SimpleString("ttl".to_string()), Value::Integer(10))],
+                Box::new(Value::Integer(1)),
+            ),
+        ]);
+
+        let mut decoder = Decoder::new();
+        encode(&value, decoder.buffer_mut(), 3);
+
+        assert_eq!(decoder.decode().unwrap(), Some(value));
+    }
+
+    #[test]
+    fn test_encoder_downgrades_resp3_types_for_resp2() {
+        let value = Value::Array(vec![
+            Value::Map(vec![(
+                Value::BulkString(Bytes::from("k")),
+                Value::Integer(1),
+            )]),
+            Value::Set(vec![Value::Integer(2)]),
+            Value::Double(2.0),
+            Value::Boolean(false),
+            Value::BigNumber("7".to_string()),
+            Value::VerbatimString("txt".to_string(), Bytes::from("hi")),
+            Value::Attribute(vec![], Box::new(Value::Integer(3))),
+        ]);
+
+        assert_eq!(
+            &encode_to_bytes(&value, 2)[..],
+            &b"*7\r\n*2\r\n$1\r\nk\r\n:1\r\n*1\r\n:2\r\n$1\r\n2\r\n:0\r\n$1\r\n7\r\n$2\r\nhi\r\n:3\r\n"[..]
+        );
+    }
+
+    #[test]
+    fn test_format_double() {
+        assert_eq!(format_double(1.5), "1.5");
+        assert_eq!(format_double(-0.0001), "-0.0001");
+        assert_eq!(format_double(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(format_double(1e16), "10000000000000000");
+        assert_eq!(format_double(1e17), "1e+17");
+        assert_eq!(format_double(1.5e308), "1.5e+308");
+        assert_eq!(format_double(-2.5e-5), "-2.5e-05");
+        assert_eq!(format_double(5e-324), "5e-324");
+        assert_eq!(format_double(f64::INFINITY), "inf");
+        assert_eq!(format_double(f64::NEG_INFINITY), "-inf");
+    }
+
+    #[test]
+    fn test_encoder_pairs() {
+        let value = Value::Pairs(vec![
+            (Value::BulkString(Bytes::from("a")), Value::Integer(1)),
+            (Value::BulkString(Bytes::from("b")), Value::Integer(2)),
+        ]);
+
+        assert_eq!(
+            &encode_to_bytes(&value, 2)[..],
+            b"*4\r\n$1\r\na\r\n:1\r\n$1\r\nb\r\n:2\r\n"
+        );
+        assert_eq!(
+            &encode_to_bytes(&value, 3)[..],
+            b"*2\r\n*2\r\n$1\r\na\r\n:1\r\n*2\r\n$1\r\nb\r\n:2\r\n"
+        );
+    }
+
+    #[test]
+    fn test_encoder_array_map() {
+        let value = Value::ArrayMap(vec![(
+            Value::BulkString(Bytes::from("a")),
+            Value::Array(vec![Value::Integer(1)]),
+        )]);
+
+        assert_eq!(
+            &encode_to_bytes(&value, 2)[..],
+            b"*1\r\n*2\r\n$1\r\na\r\n*1\r\n:1\r\n"
+        );
+        assert_eq!(
+            &encode_to_bytes(&value, 3)[..],
+            b"%1\r\n$1\r\na\r\n*1\r\n:1\r\n"
+        );
+    }
+}
@@ -1,12 +1,15 @@
-use regex;
+use crate::parser::Value;
 use thiserror::Error;
 
+/// Errors of the server.
+///
+/// Variants a client can trigger with a command are displayed as the exact
+/// error reply Redis sends, code prefix included. The others are internal and
+/// reach clients as a generic `ERR` reply.
 #[derive(Error, Debug)]
 pub enum RusdisError {
-    #[error("Parser Error")]
+    #[error("Parser Error: {0}")]
     ParserError(#[from] crate::ParserError),
-    #[error("Invalid Command")]
-    InvalidCommand,
     #[error("IO error")]
     IO(#[from] std::io::Error),
     #[error("Command Parser Error: {msg}")]
@@ -21,4 +24,91 @@ pub enum RusdisError {
     RegexError(#[from] regex::Error),
     #[error("Master Connection Error: {msg}")]
     MasterConnectionError { msg: String },
+
+    // Error replies
+    #[error("ERR {msg}")]
+    Generic { msg: String },
+    #[error("ERR unknown command '{name}', with args beginning with: {args}")]
+    UnknownCommand { name: String, args: String },
+    #[error("ERR unknown subcommand '{subcommand}'. Try {command} HELP.")]
+    UnknownSubcommand { command: String, subcommand: String },
+    #[error("ERR wrong number of arguments for '{command}' command")]
+    WrongArity { command: String },
+    #[error("ERR syntax error")]
+    SyntaxError,
+    #[error("ERR value is not an integer or out of range")]
+    NotInteger,
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+    WrongType,
+    #[error("NOGROUP {msg}")]
+    NoGroup { msg: String },
+    #[error("BUSYGROUP Consumer Group name already exists")]
+    BusyGroup,
+    #[error("WRONGTYPE Key is not a valid HyperLogLog string value.")]
+    InvalidHll,
+    #[error("INVALIDOBJ Corrupted HLL object detected")]
+    CorruptedHll,
+    #[error("EXECABORT Transaction discarded because of previous errors.")]
+    ExecAbort,
+    #[error("READONLY You can't write against a read only replica.")]
+    ReadOnly,
+    #[error("NOPROTO sorry, this protocol version is not supported.")]
+    NoProto,
+    #[error("WRONGPASS invalid username-password pair or user is disabled.")]
+    WrongPass,
+}
+
+impl RusdisError {
+    /// The single error reply sent to a client for this error.
+    pub fn to_reply(&self) -> Value {
+        let msg = match self {
+            RusdisError::ParserError(e) => format!("ERR {}", e),
+            RusdisError::CommandParserError { msg } => format!("ERR {}", msg),
+            RusdisError::ParseIntError(_) => RusdisError::NotInteger.to_string(),
+            RusdisError::IO(_)
+            | RusdisError::InstantAdditionError
+            | RusdisError::RDBFileParserError { .. }
+            | RusdisError::RegexError(_)
+            | RusdisError::MasterConnectionError { .. } => format!("ERR {}", self),
+            _ => self.to_string(),
+        };
+
+        Value::SimpleError(msg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_replies() {
+        assert_eq!(
+            RusdisError::UnknownCommand {
+                name: "FOO".to_string(),
+                args: "'a' 'b' ".to_string()
+            }
+            .to_reply(),
+            Value::SimpleError(
+                "ERR unknown command 'FOO', with args beginning with: 'a' 'b' ".to_string()
+            )
+        );
+        assert_eq!(
+            RusdisError::WrongType.to_reply(),
+            Value::SimpleError(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+            )
+        );
+        assert_eq!(
+            RusdisError::CommandParserError {
+                msg: "Unrecognized REPLCONF option: foo".to_string()
+            }
+            .to_reply(),
+            Value::SimpleError("ERR Unrecognized REPLCONF option: foo".to_string())
+        );
+        assert_eq!(
+            RusdisError::InstantAdditionError.to_reply(),
+            Value::SimpleError("ERR Instant addition error".to_string())
+        );
+    }
 }
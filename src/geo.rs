@@ -0,0 +1,1126 @@
+use crate::command_parser::{parse_number, to_keyword, Command};
+use crate::data::Keyspace;
+use crate::hash::parse_float;
+use crate::sorted_set::SortedSet;
+use crate::zset::{get_zset, store, AddOptions, ZSetCommand};
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+
+/// Bits per coordinate of the geohashes stored as scores.
+const GEO_STEP_MAX: u8 = 26;
+const GEO_LONG_MIN: f64 = -180.0;
+const GEO_LONG_MAX: f64 = 180.0;
+/// Latitudes the Web Mercator projection covers.
+const GEO_LAT_MIN: f64 = -85.05112878;
+const GEO_LAT_MAX: f64 = 85.05112878;
+const EARTH_RADIUS_IN_METERS: f64 = 6372797.560856;
+const MERCATOR_MAX: f64 = 20037726.37;
+const GEO_ALPHABET: &[u8] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoCommand {
+    Pos {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    /// GEODIST, `unit` being the meters in one unit of the reply
+    Dist {
+        key: Bytes,
+        first: Bytes,
+        second: Bytes,
+        unit: f64,
+    },
+    Hash {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    Search {
+        key: Bytes,
+        search: GeoSearch,
+    },
+    /// GEOSEARCHSTORE; STOREDIST stores distances in place of geohashes
+    SearchStore {
+        destination: Bytes,
+        source: Bytes,
+        search: GeoSearch,
+        store_dist: bool,
+    },
+}
+
+/// Where a search is centered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoCenter {
+    Member(Bytes),
+    LonLat(f64, f64),
+}
+
+/// The area searched, in the unit of the search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoShape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoSort {
+    Unsorted,
+    Asc,
+    Desc,
+}
+
+/// Options of GEOSEARCH and GEOSEARCHSTORE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoSearch {
+    center: GeoCenter,
+    shape: GeoShape,
+    /// Meters in one unit of the shape and of the distances replied
+    unit: f64,
+    sort: GeoSort,
+    /// COUNT, 0 meaning no limit
+    count: usize,
+    /// ANY, stopping at the first `count` matches found
+    any: bool,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+}
+
+fn generic_error(msg: &str) -> RusdisError {
+    RusdisError::Generic {
+        msg: msg.to_string(),
+    }
+}
+
+fn parse_double(bytes: &Bytes, msg: &str) -> Result<f64, RusdisError> {
+    parse_float(bytes).ok_or_else(|| generic_error(msg))
+}
+
+fn parse_lon_lat(lon: &Bytes, lat: &Bytes) -> Result<(f64, f64), RusdisError> {
+    let lon = parse_double(lon, "value is not a valid float")?;
+    let lat = parse_double(lat, "value is not a valid float")?;
+    if !(GEO_LONG_MIN..=GEO_LONG_MAX).contains(&lon) || !(GEO_LAT_MIN..=GEO_LAT_MAX).contains(&lat)
+    {
+        return Err(generic_error(&format!(
+            "invalid longitude,latitude pair {:.6},{:.6}",
+            lon, lat
+        )));
+    }
+
+    Ok((lon, lat))
+}
+
+fn parse_unit(bytes: &Bytes) -> Result<f64, RusdisError> {
+    match to_keyword(bytes).as_str() {
+        "M" => Ok(1.0),
+        "KM" => Ok(1000.0),
+        "FT" => Ok(0.3048),
+        "MI" => Ok(1609.34),
+        _ => Err(generic_error(
+            "unsupported unit provided. please use M, KM, FT, MI",
+        )),
+    }
+}
+
+/// GEOADD stores the geohash of each position as its score, as ZADD would.
+pub(crate) fn parse_geoadd_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (mut only_new, mut only_existing, mut changed) = (false, false, false);
+    let mut idx = 2;
+    while let Some(option) = argv.get(idx) {
+        match to_keyword(option).as_str() {
+            "NX" => only_new = true,
+            "XX" => only_existing = true,
+            "CH" => changed = true,
+            _ => break,
+        }
+        idx += 1;
+    }
+
+    let triples = &argv[idx..];
+    if triples.is_empty() || !triples.len().is_multiple_of(3) || (only_new && only_existing) {
+        return Err(RusdisError::SyntaxError);
+    }
+
+    Ok(Command::ZSet(ZSetCommand::Add {
+        key: argv[1].clone(),
+        members: triples
+            .chunks(3)
+            .map(|triple| {
+                let (lon, lat) = parse_lon_lat(&triple[0], &triple[1])?;
+                Ok((geohash_score(lon, lat), triple[2].clone()))
+            })
+            .collect::<Result<_, RusdisError>>()?,
+        options: AddOptions::geo(only_new, only_existing, changed),
+    }))
+}
+
+pub(crate) fn parse_geopos_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Geo(GeoCommand::Pos {
+        key: argv[1].clone(),
+        members: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_geodist_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let unit = match argv.len() {
+        4 => 1.0,
+        5 => parse_unit(&argv[4])?,
+        _ => return Err(RusdisError::SyntaxError),
+    };
+
+    Ok(Command::Geo(GeoCommand::Dist {
+        key: argv[1].clone(),
+        first: argv[2].clone(),
+        second: argv[3].clone(),
+        unit,
+    }))
+}
+
+pub(crate) fn parse_geohash_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Geo(GeoCommand::Hash {
+        key: argv[1].clone(),
+        members: argv[2..].to_vec(),
+    }))
+}
+
+/// Parses the options of GEOSEARCH and GEOSEARCHSTORE from `argv[start..]`.
+/// Returns them with whether STOREDIST was given.
+fn parse_search(
+    argv: &[Bytes],
+    start: usize,
+    store: bool,
+) -> Result<(GeoSearch, bool), RusdisError> {
+    let mut center = None;
+    let mut shape = None;
+    let mut unit = 1.0;
+    let mut search_sort = GeoSort::Unsorted;
+    let mut count = 0;
+    let (mut any, mut store_dist) = (false, false);
+    let (mut with_coord, mut with_dist, mut with_hash) = (false, false, false);
+
+    let mut idx = start;
+    while idx < argv.len() {
+        let left = argv.len() - idx - 1;
+        match to_keyword(&argv[idx]).as_str() {
+            "WITHDIST" => with_dist = true,
+            "WITHHASH" => with_hash = true,
+            "WITHCOORD" => with_coord = true,
+            "ANY" => any = true,
+            "ASC" => search_sort = GeoSort::Asc,
+            "DESC" => search_sort = GeoSort::Desc,
+            "COUNT" if left >= 1 => {
+                let value: i64 = parse_number(&argv[idx + 1])?;
+                if value <= 0 {
+                    return Err(generic_error("COUNT must be > 0"));
+                }
+                count = value as usize;
+                idx += 1;
+            }
+            "FROMMEMBER" if left >= 1 && !matches!(center, Some(GeoCenter::LonLat(..))) => {
+                center = Some(GeoCenter::Member(argv[idx + 1].clone()));
+                idx += 1;
+            }
+            "FROMLONLAT" if left >= 2 && !matches!(center, Some(GeoCenter::Member(_))) => {
+                let (lon, lat) = parse_lon_lat(&argv[idx + 1], &argv[idx + 2])?;
+                center = Some(GeoCenter::LonLat(lon, lat));
+                idx += 2;
+            }
+            "BYRADIUS" if left >= 2 && !matches!(shape, Some(GeoShape::Box { .. })) => {
+                let radius = parse_double(&argv[idx + 1], "need numeric radius")?;
+                if radius < 0.0 {
+                    return Err(generic_error("radius cannot be negative"));
+                }
+                unit = parse_unit(&argv[idx + 2])?;
+                shape = Some(GeoShape::Radius(radius));
+                idx += 2;
+            }
+            "BYBOX" if left >= 3 && !matches!(shape, Some(GeoShape::Radius(_))) => {
+                let width = parse_double(&argv[idx + 1], "need numeric width")?;
+                let height = parse_double(&argv[idx + 2], "need numeric height")?;
+                if width < 0.0 || height < 0.0 {
+                    return Err(generic_error("height or width cannot be negative"));
+                }
+                unit = parse_unit(&argv[idx + 3])?;
+                shape = Some(GeoShape::Box { width, height });
+                idx += 3;
+            }
+            "STOREDIST" if store => store_dist = true,
+            _ => return Err(RusdisError::SyntaxError),
+        }
+        idx += 1;
+    }
+
+    let name = to_keyword(&argv[0]);
+    if store && (with_dist || with_hash || with_coord) {
+        return Err(generic_error(
+            "GEOSEARCHSTORE is not compatible with WITHDIST, WITHHASH and WITHCOORD options",
+        ));
+    }
+    let center = center.ok_or_else(|| {
+        generic_error(&format!(
+            "exactly one of FROMMEMBER or FROMLONLAT can be specified for {}",
+            name.to_lowercase()
+        ))
+    })?;
+    let shape = shape.ok_or_else(|| {
+        generic_error(&format!(
+            "exactly one of BYRADIUS and BYBOX can be specified for {}",
+            name.to_lowercase()
+        ))
+    })?;
+    if any && count == 0 {
+        return Err(generic_error("the ANY argument requires COUNT argument"));
+    }
+    // the closest members are the ones kept by COUNT
+    if count != 0 && search_sort == GeoSort::Unsorted && !any {
+        search_sort = GeoSort::Asc;
+    }
+
+    Ok((
+        GeoSearch {
+            center,
+            shape,
+            unit,
+            sort: search_sort,
+            count,
+            any,
+            with_coord,
+            with_dist,
+            with_hash,
+        },
+        store_dist,
+    ))
+}
+
+pub(crate) fn parse_geosearch_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (search, _) = parse_search(argv, 2, false)?;
+
+    Ok(Command::Geo(GeoCommand::Search {
+        key: argv[1].clone(),
+        search,
+    }))
+}
+
+pub(crate) fn parse_geosearchstore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (search, store_dist) = parse_search(argv, 3, true)?;
+
+    Ok(Command::Geo(GeoCommand::SearchStore {
+        destination: argv[1].clone(),
+        source: argv[2].clone(),
+        search,
+        store_dist,
+    }))
+}
+
+/// A geohash of `step` bits per coordinate, longitude bits at odd positions
+/// and latitude bits at even ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HashBits {
+    bits: u64,
+    step: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CoordRange {
+    min: f64,
+    max: f64,
+}
+
+const LONG_RANGE: CoordRange = CoordRange {
+    min: GEO_LONG_MIN,
+    max: GEO_LONG_MAX,
+};
+const LAT_RANGE: CoordRange = CoordRange {
+    min: GEO_LAT_MIN,
+    max: GEO_LAT_MAX,
+};
+
+/// The cell a geohash stands for.
+struct Area {
+    longitude: CoordRange,
+    latitude: CoordRange,
+}
+
+/// Spreads the bits of `value` to the even positions.
+fn spread(value: u32) -> u64 {
+    let mut x = value as u64;
+    x = (x | (x << 16)) & 0x0000_ffff_0000_ffff;
+    x = (x | (x << 8)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x << 2)) & 0x3333_3333_3333_3333;
+    (x | (x << 1)) & 0x5555_5555_5555_5555
+}
+
+/// Gathers the bits at even positions, undoing `spread`.
+fn squash(value: u64) -> u32 {
+    let mut x = value & 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x >> 4)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x >> 8)) & 0x0000_ffff_0000_ffff;
+    ((x | (x >> 16)) & 0x0000_0000_ffff_ffff) as u32
+}
+
+fn encode(
+    long_range: CoordRange,
+    lat_range: CoordRange,
+    longitude: f64,
+    latitude: f64,
+    step: u8,
+) -> Option<HashBits> {
+    if !(GEO_LONG_MIN..=GEO_LONG_MAX).contains(&longitude)
+        || !(GEO_LAT_MIN..=GEO_LAT_MAX).contains(&latitude)
+        || !(lat_range.min..=lat_range.max).contains(&latitude)
+        || !(long_range.min..=long_range.max).contains(&longitude)
+    {
+        return None;
+    }
+
+    let cells = (1_u64 << step) as f64;
+    let lat_offset = (latitude - lat_range.min) / (lat_range.max - lat_range.min) * cells;
+    let long_offset = (longitude - long_range.min) / (long_range.max - long_range.min) * cells;
+
+    Some(HashBits {
+        bits: spread(lat_offset as u32) | (spread(long_offset as u32) << 1),
+        step,
+    })
+}
+
+fn decode(long_range: CoordRange, lat_range: CoordRange, hash: HashBits) -> Area {
+    let cells = (1_u64 << hash.step) as f64;
+    let lat_cell = squash(hash.bits) as f64;
+    let long_cell = squash(hash.bits >> 1) as f64;
+    let lat_scale = lat_range.max - lat_range.min;
+    let long_scale = long_range.max - long_range.min;
+
+    Area {
+        latitude: CoordRange {
+            min: lat_range.min + (lat_cell / cells) * lat_scale,
+            max: lat_range.min + ((lat_cell + 1.0) / cells) * lat_scale,
+        },
+        longitude: CoordRange {
+            min: long_range.min + (long_cell / cells) * long_scale,
+            max: long_range.min + ((long_cell + 1.0) / cells) * long_scale,
+        },
+    }
+}
+
+/// The score a position is stored with, its geohash aligned to 52 bits.
+fn geohash_score(longitude: f64, latitude: f64) -> f64 {
+    encode(LONG_RANGE, LAT_RANGE, longitude, latitude, GEO_STEP_MAX)
+        .map_or(0.0, |hash| hash.bits as f64)
+}
+
+/// The longitude and latitude at the center of the cell of a score.
+fn decode_score(score: f64) -> (f64, f64) {
+    let hash = HashBits {
+        bits: score as u64,
+        step: GEO_STEP_MAX,
+    };
+    let area = decode(LONG_RANGE, LAT_RANGE, hash);
+    let longitude = (area.longitude.min + area.longitude.max) / 2.0;
+    let latitude = (area.latitude.min + area.latitude.max) / 2.0;
+
+    (
+        longitude.clamp(GEO_LONG_MIN, GEO_LONG_MAX),
+        latitude.clamp(GEO_LAT_MIN, GEO_LAT_MAX),
+    )
+}
+
+/// The standard 11 character geohash of a score, re-encoded with the full
+/// -90..90 latitude range.
+fn geohash_string(score: f64) -> Bytes {
+    let (longitude, latitude) = decode_score(score);
+    let range = CoordRange {
+        min: -90.0,
+        max: 90.0,
+    };
+    let bits =
+        encode(LONG_RANGE, range, longitude, latitude, GEO_STEP_MAX).map_or(0, |hash| hash.bits);
+
+    // 52 bits make 10 characters, the 11th is always 0
+    let mut hash: Vec<u8> = (0..10)
+        .map(|i| GEO_ALPHABET[((bits >> (52 - (i + 1) * 5)) & 0x1f) as usize])
+        .collect();
+    hash.push(GEO_ALPHABET[0]);
+
+    Bytes::from(hash)
+}
+
+/// Moves `hash` one cell east, or west if `d` is negative.
+fn move_x(hash: HashBits, d: i8) -> HashBits {
+    let x = hash.bits & 0xaaaa_aaaa_aaaa_aaaa;
+    let y = hash.bits & 0x5555_5555_5555_5555;
+    let zz = 0x5555_5555_5555_5555_u64 >> (64 - hash.step as u32 * 2);
+    let x = if d > 0 {
+        x.wrapping_add(zz + 1)
+    } else {
+        (x | zz).wrapping_sub(zz + 1)
+    };
+
+    HashBits {
+        bits: (x & (0xaaaa_aaaa_aaaa_aaaa_u64 >> (64 - hash.step as u32 * 2))) | y,
+        step: hash.step,
+    }
+}
+
+/// Moves `hash` one cell north, or south if `d` is negative.
+fn move_y(hash: HashBits, d: i8) -> HashBits {
+    let x = hash.bits & 0xaaaa_aaaa_aaaa_aaaa;
+    let y = hash.bits & 0x5555_5555_5555_5555;
+    let zz = 0xaaaa_aaaa_aaaa_aaaa_u64 >> (64 - hash.step as u32 * 2);
+    let y = if d > 0 {
+        y.wrapping_add(zz + 1)
+    } else {
+        (y | zz).wrapping_sub(zz + 1)
+    };
+
+    HashBits {
+        bits: x | (y & (0x5555_5555_5555_5555_u64 >> (64 - hash.step as u32 * 2))),
+        step: hash.step,
+    }
+}
+
+fn deg_rad(angle: f64) -> f64 {
+    angle * (std::f64::consts::PI / 180.0)
+}
+
+fn rad_deg(angle: f64) -> f64 {
+    angle / (std::f64::consts::PI / 180.0)
+}
+
+fn lat_distance(lat1: f64, lat2: f64) -> f64 {
+    EARTH_RADIUS_IN_METERS * (deg_rad(lat2) - deg_rad(lat1)).abs()
+}
+
+/// Haversine distance in meters.
+fn distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let v = ((deg_rad(lon2) - deg_rad(lon1)) / 2.0).sin();
+    // on the same meridian only latitudes differ
+    if v == 0.0 {
+        return lat_distance(lat1, lat2);
+    }
+    let (lat1, lat2) = (deg_rad(lat1), deg_rad(lat2));
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let a = u * u + lat1.cos() * lat2.cos() * v * v;
+
+    2.0 * EARTH_RADIUS_IN_METERS * a.sqrt().asin()
+}
+
+/// Geohash bits per coordinate giving cells about as large as `range_meters`.
+fn estimate_steps(range_meters: f64, latitude: f64) -> u8 {
+    if range_meters == 0.0 {
+        return GEO_STEP_MAX;
+    }
+    let mut range_meters = range_meters;
+    let mut step: i32 = 1;
+    while range_meters < MERCATOR_MAX {
+        range_meters *= 2.0;
+        step += 1;
+    }
+    step -= 2;
+
+    // meridians get closer towards the poles
+    if !(-66.0..=66.0).contains(&latitude) {
+        step -= 1;
+        if !(-80.0..=80.0).contains(&latitude) {
+            step -= 1;
+        }
+    }
+
+    step.clamp(1, GEO_STEP_MAX as i32) as u8
+}
+
+/// A member found by a search.
+struct GeoPoint {
+    member: Bytes,
+    score: f64,
+    longitude: f64,
+    latitude: f64,
+    /// Meters from the center of the search
+    dist: f64,
+}
+
+impl GeoSearch {
+    fn radius_meters(&self) -> f64 {
+        match self.shape {
+            GeoShape::Radius(radius) => radius * self.unit,
+            GeoShape::Box { width, height } => {
+                ((width / 2.0).powi(2) + (height / 2.0).powi(2)).sqrt() * self.unit
+            }
+        }
+    }
+
+    /// Min longitude, min latitude, max longitude and max latitude of the shape.
+    fn bounding_box(&self, longitude: f64, latitude: f64) -> [f64; 4] {
+        let (height, width) = match self.shape {
+            GeoShape::Radius(radius) => (radius * self.unit, radius * self.unit),
+            GeoShape::Box { width, height } => (height / 2.0 * self.unit, width / 2.0 * self.unit),
+        };
+        let lat_delta = rad_deg(height / EARTH_RADIUS_IN_METERS);
+        let long_delta_top =
+            rad_deg(width / EARTH_RADIUS_IN_METERS / deg_rad(latitude + lat_delta).cos());
+        let long_delta_bottom =
+            rad_deg(width / EARTH_RADIUS_IN_METERS / deg_rad(latitude - lat_delta).cos());
+        // the shape is widest on the side closest to the equator
+        let long_delta = if latitude < 0.0 {
+            long_delta_bottom
+        } else {
+            long_delta_top
+        };
+
+        [
+            longitude - long_delta,
+            latitude - lat_delta,
+            longitude + long_delta,
+            latitude + lat_delta,
+        ]
+    }
+
+    /// Meters from the center to a position inside the shape, `None` if it is
+    /// outside.
+    fn distance_within(&self, center: (f64, f64), longitude: f64, latitude: f64) -> Option<f64> {
+        match self.shape {
+            GeoShape::Radius(radius) => {
+                let dist = distance(center.0, center.1, longitude, latitude);
+                (dist <= radius * self.unit).then_some(dist)
+            }
+            GeoShape::Box { width, height } => {
+                if lat_distance(latitude, center.1) > height * self.unit / 2.0
+                    || distance(longitude, latitude, center.0, latitude) > width * self.unit / 2.0
+                {
+                    return None;
+                }
+                Some(distance(center.0, center.1, longitude, latitude))
+            }
+        }
+    }
+
+    /// The cell of the center and its neighbours, none smaller than the shape,
+    /// leaving out the neighbours the shape does not reach.
+    fn areas(&self, center: (f64, f64)) -> Vec<HashBits> {
+        let (longitude, latitude) = center;
+        let [min_lon, min_lat, max_lon, max_lat] = self.bounding_box(longitude, latitude);
+        let mut steps = estimate_steps(self.radius_meters(), latitude);
+
+        let cell = |steps| {
+            let hash =
+                encode(LONG_RANGE, LAT_RANGE, longitude, latitude, steps).unwrap_or(HashBits {
+                    bits: 0,
+                    step: steps,
+                });
+            (hash, decode(LONG_RANGE, LAT_RANGE, hash))
+        };
+        let (mut hash, mut area) = cell(steps);
+
+        // cells next to the shape's edge may be too small to cover it
+        let north = decode(LONG_RANGE, LAT_RANGE, move_y(hash, 1));
+        let south = decode(LONG_RANGE, LAT_RANGE, move_y(hash, -1));
+        let east = decode(LONG_RANGE, LAT_RANGE, move_x(hash, 1));
+        let west = decode(LONG_RANGE, LAT_RANGE, move_x(hash, -1));
+        if steps > 1
+            && (north.latitude.max < max_lat
+                || south.latitude.min > min_lat
+                || east.longitude.max < max_lon
+                || west.longitude.min > min_lon)
+        {
+            steps -= 1;
+            (hash, area) = cell(steps);
+        }
+
+        let (mut go_north, mut go_south, mut go_east, mut go_west) = (true, true, true, true);
+        if steps >= 2 {
+            go_south = area.latitude.min >= min_lat;
+            go_north = area.latitude.max <= max_lat;
+            go_west = area.longitude.min >= min_lon;
+            go_east = area.longitude.max <= max_lon;
+        }
+
+        // the cell itself, then north, south, east, west, north east, north
+        // west, south east and south west, as Redis walks them
+        let neighbours = [
+            (true, 0, 0),
+            (go_north, 0, 1),
+            (go_south, 0, -1),
+            (go_east, 1, 0),
+            (go_west, -1, 0),
+            (go_north && go_east, 1, 1),
+            (go_north && go_west, -1, 1),
+            (go_south && go_east, 1, -1),
+            (go_south && go_west, -1, -1),
+        ];
+        let mut areas: Vec<HashBits> = vec![];
+        for (wanted, dx, dy) in neighbours {
+            if !wanted {
+                continue;
+            }
+            let mut neighbour = hash;
+            if dx != 0 {
+                neighbour = move_x(neighbour, dx);
+            }
+            if dy != 0 {
+                neighbour = move_y(neighbour, dy);
+            }
+            // huge shapes can make neighbours the same cell; like Redis, the
+            // center cell is not compared against
+            if areas.len() < 2 || areas.last() != Some(&neighbour) {
+                areas.push(neighbour);
+            }
+        }
+
+        areas
+    }
+
+    /// Members inside the shape centered at `center`, at most `limit` of them
+    /// unless it is 0.
+    fn find(&self, zset: &SortedSet, center: (f64, f64), limit: usize) -> Vec<GeoPoint> {
+        let mut points = vec![];
+        for area in self.areas(center) {
+            if limit != 0 && points.len() >= limit {
+                break;
+            }
+            let shift = 52 - area.step as u32 * 2;
+            let min = (area.bits << shift) as f64;
+            let max = ((area.bits + 1) << shift) as f64;
+
+            let start = zset.count_while(|score, _| score < min);
+            for (member, score) in zset.iter_from(start, false) {
+                if score >= max || (limit != 0 && points.len() >= limit) {
+                    break;
+                }
+                let (longitude, latitude) = decode_score(score);
+                if let Some(dist) = self.distance_within(center, longitude, latitude) {
+                    points.push(GeoPoint {
+                        member: member.clone(),
+                        score,
+                        longitude,
+                        latitude,
+                        dist,
+                    });
+                }
+            }
+        }
+
+        points
+    }
+
+    /// Members the search finds in `zset`, ordered and limited as asked.
+    fn run(&self, zset: &SortedSet) -> Result<Vec<GeoPoint>, RusdisError> {
+        let center = match &self.center {
+            GeoCenter::LonLat(longitude, latitude) => (*longitude, *latitude),
+            GeoCenter::Member(member) => match zset.score(member) {
+                Some(score) => decode_score(score),
+                None => return Err(generic_error("could not decode requested zset member")),
+            },
+        };
+
+        let mut points = self.find(zset, center, if self.any { self.count } else { 0 });
+        match self.sort {
+            GeoSort::Unsorted => {}
+            GeoSort::Asc => points.sort_by(|a, b| a.dist.total_cmp(&b.dist)),
+            GeoSort::Desc => points.sort_by(|a, b| b.dist.total_cmp(&a.dist)),
+        }
+        if self.count != 0 {
+            points.truncate(self.count);
+        }
+
+        Ok(points)
+    }
+
+    fn reply(&self, point: GeoPoint) -> Value {
+        if !(self.with_dist || self.with_hash || self.with_coord) {
+            return Value::BulkString(point.member);
+        }
+
+        let mut reply = vec![Value::BulkString(point.member)];
+        if self.with_dist {
+            reply.push(distance_reply(point.dist / self.unit));
+        }
+        if self.with_hash {
+            reply.push(Value::Integer(point.score as i64));
+        }
+        if self.with_coord {
+            reply.push(position_reply(point.longitude, point.latitude));
+        }
+
+        Value::Array(reply)
+    }
+}
+
+/// Distances are replied with 4 decimals, enough for kilometers.
+fn distance_reply(dist: f64) -> Value {
+    Value::BulkString(Bytes::from(format!("{:.4}", dist)))
+}
+
+fn position_reply(longitude: f64, latitude: f64) -> Value {
+    Value::Array(vec![Value::Double(longitude), Value::Double(latitude)])
+}
+
+pub fn execute_geo_command(keyspace: &mut Keyspace, cmd: GeoCommand) -> Result<Value, RusdisError> {
+    match cmd {
+        GeoCommand::Pos { key, members } => {
+            let zset = get_zset(keyspace, &key)?;
+            Ok(Value::Array(
+                members
+                    .iter()
+                    .map(
+                        |member| match zset.as_ref().and_then(|zset| zset.score(member)) {
+                            Some(score) => {
+                                let (longitude, latitude) = decode_score(score);
+                                position_reply(longitude, latitude)
+                            }
+                            None => Value::NullArray,
+                        },
+                    )
+                    .collect(),
+            ))
+        }
+        GeoCommand::Dist {
+            key,
+            first,
+            second,
+            unit,
+        } => {
+            let zset = match get_zset(keyspace, &key)? {
+                Some(zset) => zset,
+                None => return Ok(Value::Null),
+            };
+            match (zset.score(&first), zset.score(&second)) {
+                (Some(first), Some(second)) => {
+                    let (lon1, lat1) = decode_score(first);
+                    let (lon2, lat2) = decode_score(second);
+                    Ok(distance_reply(distance(lon1, lat1, lon2, lat2) / unit))
+                }
+                _ => Ok(Value::Null),
+            }
+        }
+        GeoCommand::Hash { key, members } => {
+            let zset = get_zset(keyspace, &key)?;
+            Ok(Value::Array(
+                members
+                    .iter()
+                    .map(
+                        |member| match zset.as_ref().and_then(|zset| zset.score(member)) {
+                            Some(score) => Value::BulkString(geohash_string(score)),
+                            None => Value::Null,
+                        },
+                    )
+                    .collect(),
+            ))
+        }
+        GeoCommand::Search { key, search } => {
+            let points = match get_zset(keyspace, &key)? {
+                Some(zset) => search.run(zset)?,
+                None => vec![],
+            };
+
+            Ok(Value::Array(
+                points
+                    .into_iter()
+                    .map(|point| search.reply(point))
+                    .collect(),
+            ))
+        }
+        GeoCommand::SearchStore {
+            destination,
+            source,
+            search,
+            store_dist,
+        } => {
+            let points = match get_zset(keyspace, &source)? {
+                Some(zset) => search.run(zset)?,
+                None => vec![],
+            };
+
+            let mut zset = SortedSet::new();
+            for point in points.into_iter() {
+                let score = if store_dist {
+                    point.dist / search.unit
+                } else {
+                    point.score
+                };
+                zset.insert(point.member, score);
+            }
+
+            Ok(Value::Integer(store(keyspace, destination, zset) as i64))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{bulk, bulks, error, run};
+
+    fn sicily() -> Keyspace {
+        let mut keyspace = Keyspace::new();
+        run(
+            &mut keyspace,
+            &[
+                "GEOADD",
+                "Sicily",
+                "13.361389",
+                "38.115556",
+                "Palermo",
+                "15.087269",
+                "37.502669",
+                "Catania",
+            ],
+        );
+        keyspace
+    }
+
+    #[test]
+    fn test_geo_positions() {
+        let mut keyspace = sicily();
+
+        assert_eq!(
+            run(&mut keyspace, &["ZSCORE", "Sicily", "Palermo"]),
+            Value::Double(3479099956230698.0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZSCORE", "Sicily", "Catania"]),
+            Value::Double(3479447370796909.0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GEODIST", "Sicily", "Palermo", "Catania"]),
+            bulk("166274.1516")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["GEODIST", "Sicily", "Palermo", "Catania", "km"]
+            ),
+            bulk("166.2742")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["GEODIST", "Sicily", "Palermo", "Catania", "MI"]
+            ),
+            bulk("103.3182")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GEODIST", "Sicily", "Palermo", "Nowhere"]),
+            Value::Null
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["GEOHASH", "Sicily", "Palermo", "Catania", "Nowhere"]
+            ),
+            Value::Array(vec![bulk("sqc8b49rny0"), bulk("sqdtr74hyu0"), Value::Null])
+        );
+
+        let reply = run(&mut keyspace, &["GEOPOS", "Sicily", "Palermo", "Nowhere"]);
+        let Value::Array(positions) = reply else {
+            panic!("unexpected reply {:?}", reply);
+        };
+        let Value::Array(palermo) = &positions[0] else {
+            panic!("unexpected position {:?}", positions[0]);
+        };
+        let (Value::Double(longitude), Value::Double(latitude)) = (&palermo[0], &palermo[1]) else {
+            panic!("unexpected position {:?}", palermo);
+        };
+        assert!((longitude - 13.361389).abs() < 1e-5);
+        assert!((latitude - 38.115556).abs() < 1e-5);
+        assert_eq!(positions[1], Value::NullArray);
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["GEOADD", "Sicily", "NX", "CH", "0", "0", "Palermo"]
+            ),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GEOADD", "Sicily", "200", "10", "x"]),
+            error("ERR invalid longitude,latitude pair 200.000000,10.000000")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["GEOADD", "Sicily", "NX", "XX", "1", "1", "x"]
+            ),
+            error("ERR syntax error")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["GEODIST", "Sicily", "Palermo", "Catania", "yd"]
+            ),
+            error("ERR unsupported unit provided. please use M, KM, FT, MI")
+        );
+    }
+
+    #[test]
+    fn test_geo_search() {
+        let mut keyspace = sicily();
+        run(
+            &mut keyspace,
+            &[
+                "GEOADD",
+                "Sicily",
+                "12.758489",
+                "38.788135",
+                "edge1",
+                "17.241510",
+                "38.788135",
+                "edge2",
+            ],
+        );
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "200",
+                    "km",
+                    "ASC"
+                ]
+            ),
+            bulks(&["Catania", "Palermo"])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYBOX",
+                    "400",
+                    "400",
+                    "km",
+                    "DESC",
+                    "WITHDIST",
+                ]
+            ),
+            Value::Array(vec![
+                Value::Array(vec![bulk("edge1"), bulk("279.7405")]),
+                Value::Array(vec![bulk("edge2"), bulk("279.7403")]),
+                Value::Array(vec![bulk("Palermo"), bulk("190.4424")]),
+                Value::Array(vec![bulk("Catania"), bulk("56.4413")]),
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMMEMBER",
+                    "Palermo",
+                    "BYRADIUS",
+                    "200",
+                    "km",
+                    "COUNT",
+                    "1",
+                    "WITHHASH",
+                ]
+            ),
+            Value::Array(vec![Value::Array(vec![
+                bulk("Palermo"),
+                Value::Integer(3479099956230698)
+            ])])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMMEMBER",
+                    "Rome",
+                    "BYRADIUS",
+                    "1",
+                    "km"
+                ]
+            ),
+            error("ERR could not decode requested zset member")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "BYRADIUS",
+                    "1",
+                    "km",
+                    "ASC",
+                    "COUNT",
+                    "1"
+                ]
+            ),
+            error("ERR exactly one of FROMMEMBER or FROMLONLAT can be specified for geosearch")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "GEOSEARCH",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "1",
+                    "km",
+                    "ANY"
+                ]
+            ),
+            error("ERR the ANY argument requires COUNT argument")
+        );
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "GEOSEARCHSTORE",
+                    "near",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "15",
+                    "37",
+                    "BYRADIUS",
+                    "100",
+                    "km",
+                    "STOREDIST",
+                ]
+            ),
+            Value::Integer(1)
+        );
+        let reply = run(&mut keyspace, &["ZRANGE", "near", "0", "-1", "WITHSCORES"]);
+        let Value::Pairs(pairs) = reply else {
+            panic!("unexpected reply {:?}", reply);
+        };
+        assert_eq!(pairs[0].0, bulk("Catania"));
+        assert!(matches!(pairs[0].1, Value::Double(dist) if (dist - 56.4413).abs() < 1e-4));
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "GEOSEARCHSTORE",
+                    "near",
+                    "Sicily",
+                    "FROMLONLAT",
+                    "0",
+                    "0",
+                    "BYRADIUS",
+                    "1",
+                    "km",
+                ]
+            ),
+            Value::Integer(0)
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("near")));
+    }
+}
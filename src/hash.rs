@@ -0,0 +1,1426 @@
+use crate::command_parser::{parse_number, to_keyword, Command};
+use crate::data::{get_live, Data, HashData, Keyspace};
+use crate::utils::unix_millis;
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HashCommand {
+    Set {
+        key: Bytes,
+        pairs: Vec<(Bytes, Bytes)>,
+    },
+    SetNx {
+        key: Bytes,
+        field: Bytes,
+        value: Bytes,
+    },
+    Get {
+        key: Bytes,
+        field: Bytes,
+    },
+    MGet {
+        key: Bytes,
+        fields: Vec<Bytes>,
+    },
+    Del {
+        key: Bytes,
+        fields: Vec<Bytes>,
+    },
+    Exists {
+        key: Bytes,
+        field: Bytes,
+    },
+    Len(Bytes),
+    Keys(Bytes),
+    Vals(Bytes),
+    GetAll(Bytes),
+    IncrBy {
+        key: Bytes,
+        field: Bytes,
+        increment: i64,
+    },
+    IncrByFloat {
+        key: Bytes,
+        field: Bytes,
+        increment: f64,
+    },
+    StrLen {
+        key: Bytes,
+        field: Bytes,
+    },
+    /// HRANDFIELD; a negative `count` allows the same field more than once
+    RandField {
+        key: Bytes,
+        count: Option<i64>,
+        with_values: bool,
+    },
+    /// HEXPIRE, HPEXPIRE, HEXPIREAT and HPEXPIREAT
+    Expire {
+        key: Bytes,
+        expiry: FieldExpiry,
+        condition: Option<ExpireCondition>,
+        fields: Vec<Bytes>,
+    },
+    /// HTTL and HPTTL
+    Ttl {
+        key: Bytes,
+        fields: Vec<Bytes>,
+        millis: bool,
+    },
+    Persist {
+        key: Bytes,
+        fields: Vec<Bytes>,
+    },
+    GetEx {
+        key: Bytes,
+        update: Option<TtlUpdate>,
+        fields: Vec<Bytes>,
+    },
+    SetEx {
+        key: Bytes,
+        condition: Option<SetCondition>,
+        update: Option<TtlUpdate>,
+        pairs: Vec<(Bytes, Bytes)>,
+    },
+}
+
+/// Expiration time of hash fields or keys, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldExpiry {
+    /// From the time the command runs
+    In(u64),
+    /// Unix time
+    At(u64),
+}
+
+impl FieldExpiry {
+    pub(crate) fn resolve(&self) -> SystemTime {
+        match self {
+            FieldExpiry::In(millis) => SystemTime::now() + Duration::from_millis(*millis),
+            FieldExpiry::At(millis) => UNIX_EPOCH + Duration::from_millis(*millis),
+        }
+    }
+
+    /// Unix time of the expiration, counting a relative one from now.
+    pub(crate) fn unix_millis(&self) -> u64 {
+        match self {
+            FieldExpiry::In(millis) => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64;
+                now.saturating_add(*millis)
+            }
+            FieldExpiry::At(millis) => *millis,
+        }
+    }
+
+    /// The expiration as unix time, so that it means the same when the
+    /// command is propagated.
+    pub(crate) fn absolute(&self) -> FieldExpiry {
+        FieldExpiry::At(self.unix_millis())
+    }
+}
+
+/// NX, XX, GT and LT of HEXPIRE and friends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExpireCondition {
+    /// The field has no expiration
+    Nx,
+    /// The field has an expiration
+    Xx,
+    /// The new expiration is later than the current one
+    Gt,
+    /// The new expiration is earlier than the current one, or there is none
+    Lt,
+}
+
+/// FNX and FXX of HSETEX.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetCondition {
+    /// None of the fields exist
+    Fnx,
+    /// All the fields exist
+    Fxx,
+}
+
+/// How HGETEX and HSETEX change the expiration of the fields they touch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TtlUpdate {
+    Expire(FieldExpiry),
+    Persist,
+    KeepTtl,
+}
+
+impl TtlUpdate {
+    fn absolute(self) -> TtlUpdate {
+        match self {
+            TtlUpdate::Expire(expiry) => TtlUpdate::Expire(expiry.absolute()),
+            update => update,
+        }
+    }
+}
+
+/// Largest expiration a hash field can have, in unix milliseconds.
+const MAX_FIELD_EXPIRATION: u64 = (1 << 48) - 1;
+
+fn generic_error(msg: &str) -> RusdisError {
+    RusdisError::Generic {
+        msg: msg.to_string(),
+    }
+}
+
+/// Parses a float argument the way Redis does, rejecting NaN.
+pub(crate) fn parse_float(bytes: &Bytes) -> Option<f64> {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|num| num.parse::<f64>().ok())
+        .filter(|num| !num.is_nan())
+}
+
+pub(crate) fn parse_hset_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    if !argv.len().is_multiple_of(2) {
+        return Err(RusdisError::WrongArity {
+            command: "hset".to_string(),
+        });
+    }
+
+    Ok(Command::Hash(HashCommand::Set {
+        key: argv[1].clone(),
+        pairs: argv[2..]
+            .chunks(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect(),
+    }))
+}
+
+pub(crate) fn parse_hsetnx_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::SetNx {
+        key: argv[1].clone(),
+        field: argv[2].clone(),
+        value: argv[3].clone(),
+    }))
+}
+
+pub(crate) fn parse_hget_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Get {
+        key: argv[1].clone(),
+        field: argv[2].clone(),
+    }))
+}
+
+pub(crate) fn parse_hmget_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::MGet {
+        key: argv[1].clone(),
+        fields: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_hdel_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Del {
+        key: argv[1].clone(),
+        fields: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_hexists_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Exists {
+        key: argv[1].clone(),
+        field: argv[2].clone(),
+    }))
+}
+
+pub(crate) fn parse_hlen_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Len(argv[1].clone())))
+}
+
+pub(crate) fn parse_hkeys_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Keys(argv[1].clone())))
+}
+
+pub(crate) fn parse_hvals_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Vals(argv[1].clone())))
+}
+
+pub(crate) fn parse_hgetall_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::GetAll(argv[1].clone())))
+}
+
+pub(crate) fn parse_hincrby_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::IncrBy {
+        key: argv[1].clone(),
+        field: argv[2].clone(),
+        increment: parse_number(&argv[3])?,
+    }))
+}
+
+pub(crate) fn parse_hincrbyfloat_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::IncrByFloat {
+        key: argv[1].clone(),
+        field: argv[2].clone(),
+        increment: parse_float(&argv[3])
+            .ok_or_else(|| generic_error("value is not a valid float"))?,
+    }))
+}
+
+pub(crate) fn parse_hstrlen_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::StrLen {
+        key: argv[1].clone(),
+        field: argv[2].clone(),
+    }))
+}
+
+pub(crate) fn parse_hrandfield_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let count = match argv.get(2) {
+        Some(count) => Some(parse_number::<i64>(count)?),
+        None => None,
+    };
+    // as in Redis, so that the reply's length can't overflow
+    if count.is_some_and(|count| count.unsigned_abs() > (i64::MAX / 2) as u64) {
+        return Err(generic_error("value is out of range"));
+    }
+    let with_values = match argv.get(3) {
+        Some(option) if to_keyword(option) == "WITHVALUES" => true,
+        Some(_) => return Err(RusdisError::SyntaxError),
+        None => false,
+    };
+    if argv.len() > 4 {
+        return Err(RusdisError::SyntaxError);
+    }
+
+    Ok(Command::Hash(HashCommand::RandField {
+        key: argv[1].clone(),
+        count,
+        with_values,
+    }))
+}
+
+fn command_name(argv: &[Bytes]) -> String {
+    String::from_utf8_lossy(&argv[0]).to_lowercase()
+}
+
+/// Parses an expiration given in `unit` milliseconds, as relative time unless
+/// `absolute`.
+fn parse_field_expiry(
+    argv: &[Bytes],
+    bytes: &Bytes,
+    unit: u64,
+    absolute: bool,
+) -> Result<FieldExpiry, RusdisError> {
+    let time = parse_number::<i64>(bytes)?;
+    if time < 0 {
+        return Err(generic_error("invalid expire time, must be >= 0"));
+    }
+
+    let invalid = || RusdisError::Generic {
+        msg: format!("invalid expire time in '{}' command", command_name(argv)),
+    };
+    let millis = (time as u64).checked_mul(unit).ok_or_else(invalid)?;
+    let at = if absolute {
+        millis
+    } else {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        now.saturating_add(millis)
+    };
+    if at > MAX_FIELD_EXPIRATION {
+        return Err(invalid());
+    }
+
+    Ok(if absolute {
+        FieldExpiry::At(millis)
+    } else {
+        FieldExpiry::In(millis)
+    })
+}
+
+/// Parses the EX, PX, EXAT and PXAT options of HGETEX and HSETEX, which
+/// reject expirations that are not positive.
+fn parse_ttl_option(
+    argv: &[Bytes],
+    keyword: &str,
+    bytes: Option<&Bytes>,
+) -> Result<TtlUpdate, RusdisError> {
+    let bytes = bytes.ok_or(RusdisError::SyntaxError)?;
+    if parse_number::<i64>(bytes)? <= 0 {
+        return Err(RusdisError::Generic {
+            msg: format!("invalid expire time in '{}' command", command_name(argv)),
+        });
+    }
+
+    let expiry = match keyword {
+        "EX" => parse_field_expiry(argv, bytes, 1000, false),
+        "PX" => parse_field_expiry(argv, bytes, 1, false),
+        "EXAT" => parse_field_expiry(argv, bytes, 1000, true),
+        _ => parse_field_expiry(argv, bytes, 1, true),
+    }?;
+
+    Ok(TtlUpdate::Expire(expiry))
+}
+
+/// Parses `FIELDS numfields field...` starting at `idx`, with `width`
+/// arguments per field.
+fn parse_fields(argv: &[Bytes], idx: usize, width: usize) -> Result<&[Bytes], RusdisError> {
+    if argv.get(idx).map(to_keyword).as_deref() != Some("FIELDS") {
+        return Err(generic_error(
+            "Mandatory argument FIELDS is missing or not at the right position",
+        ));
+    }
+    let count = match argv.get(idx + 1) {
+        Some(count) => parse_number::<i64>(count)?,
+        None => return Err(RusdisError::SyntaxError),
+    };
+    if count <= 0 {
+        return Err(generic_error(
+            "Parameter `numFields` should be greater than 0",
+        ));
+    }
+    let fields = &argv[idx + 2..];
+    if fields.len() as u64 != count as u64 * width as u64 {
+        return Err(generic_error(
+            "The `numfields` parameter must match the number of arguments",
+        ));
+    }
+
+    Ok(fields)
+}
+
+fn parse_expire_command(argv: &[Bytes], unit: u64, absolute: bool) -> Result<Command, RusdisError> {
+    let expiry = parse_field_expiry(argv, &argv[2], unit, absolute)?;
+    let condition = match to_keyword(&argv[3]).as_str() {
+        "NX" => Some(ExpireCondition::Nx),
+        "XX" => Some(ExpireCondition::Xx),
+        "GT" => Some(ExpireCondition::Gt),
+        "LT" => Some(ExpireCondition::Lt),
+        _ => None,
+    };
+    let idx = if condition.is_some() { 4 } else { 3 };
+
+    Ok(Command::Hash(HashCommand::Expire {
+        key: argv[1].clone(),
+        expiry,
+        condition,
+        fields: parse_fields(argv, idx, 1)?.to_vec(),
+    }))
+}
+
+pub(crate) fn parse_hexpire_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_expire_command(argv, 1000, false)
+}
+
+pub(crate) fn parse_hpexpire_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_expire_command(argv, 1, false)
+}
+
+pub(crate) fn parse_hexpireat_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_expire_command(argv, 1000, true)
+}
+
+pub(crate) fn parse_hpexpireat_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_expire_command(argv, 1, true)
+}
+
+pub(crate) fn parse_httl_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Ttl {
+        key: argv[1].clone(),
+        fields: parse_fields(argv, 2, 1)?.to_vec(),
+        millis: false,
+    }))
+}
+
+pub(crate) fn parse_hpttl_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Ttl {
+        key: argv[1].clone(),
+        fields: parse_fields(argv, 2, 1)?.to_vec(),
+        millis: true,
+    }))
+}
+
+pub(crate) fn parse_hpersist_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Hash(HashCommand::Persist {
+        key: argv[1].clone(),
+        fields: parse_fields(argv, 2, 1)?.to_vec(),
+    }))
+}
+
+pub(crate) fn parse_hgetex_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let mut update = None;
+    let mut idx = 2;
+    while idx < argv.len() {
+        let keyword = to_keyword(&argv[idx]);
+        let option = match keyword.as_str() {
+            "FIELDS" => break,
+            "EX" | "PX" | "EXAT" | "PXAT" => {
+                idx += 1;
+                parse_ttl_option(argv, &keyword, argv.get(idx))?
+            }
+            "PERSIST" => TtlUpdate::Persist,
+            _ => return Err(RusdisError::SyntaxError),
+        };
+        if update.replace(option).is_some() {
+            return Err(RusdisError::SyntaxError);
+        }
+        idx += 1;
+    }
+
+    Ok(Command::Hash(HashCommand::GetEx {
+        key: argv[1].clone(),
+        update,
+        fields: parse_fields(argv, idx, 1)?.to_vec(),
+    }))
+}
+
+pub(crate) fn parse_hsetex_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let mut condition = None;
+    let mut update = None;
+    let mut idx = 2;
+    while idx < argv.len() {
+        let keyword = to_keyword(&argv[idx]);
+        match keyword.as_str() {
+            "FIELDS" => break,
+            "FNX" | "FXX" => {
+                let option = if keyword == "FNX" {
+                    SetCondition::Fnx
+                } else {
+                    SetCondition::Fxx
+                };
+                if condition.replace(option).is_some() {
+                    return Err(RusdisError::SyntaxError);
+                }
+            }
+            "EX" | "PX" | "EXAT" | "PXAT" | "KEEPTTL" => {
+                let option = if keyword == "KEEPTTL" {
+                    TtlUpdate::KeepTtl
+                } else {
+                    idx += 1;
+                    parse_ttl_option(argv, &keyword, argv.get(idx))?
+                };
+                if update.replace(option).is_some() {
+                    return Err(RusdisError::SyntaxError);
+                }
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+        idx += 1;
+    }
+
+    Ok(Command::Hash(HashCommand::SetEx {
+        key: argv[1].clone(),
+        condition,
+        update,
+        pairs: parse_fields(argv, idx, 2)?
+            .chunks(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect(),
+    }))
+}
+
+/// The hash stored at `key`, if any.
+fn get_hash<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<Option<&'a mut HashData>, RusdisError> {
+    match get_live(keyspace, key) {
+        Some(Data::Hash(data)) => Ok(Some(data)),
+        Some(_) => Err(RusdisError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// The hash stored at `key`, created empty if the key does not exist.
+fn get_or_create_hash<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<&'a mut HashData, RusdisError> {
+    get_live(keyspace, key);
+    match keyspace
+        .entry(key.clone())
+        .or_insert_with(|| Data::Hash(HashData::new(HashMap::new())))
+    {
+        Data::Hash(data) => Ok(data),
+        _ => Err(RusdisError::WrongType),
+    }
+}
+
+fn bulk(bytes: &Bytes) -> Value {
+    Value::BulkString(bytes.clone())
+}
+
+/// Deletes the hash at `key` once its last field is gone.
+fn remove_if_empty(keyspace: &mut Keyspace, key: &Bytes) {
+    if let Some(Data::Hash(data)) = keyspace.get(key) {
+        if data.get_hash().is_empty() {
+            keyspace.remove(key);
+        }
+    }
+}
+
+/// HPEXPIREAT setting the expiration of `fields` to `expiration`.
+fn hpexpireat_argv(
+    key: &Bytes,
+    expiration: SystemTime,
+    condition: Option<ExpireCondition>,
+    fields: &[Bytes],
+) -> Vec<Bytes> {
+    let mut argv = vec![
+        Bytes::from("HPEXPIREAT"),
+        key.clone(),
+        unix_millis(expiration),
+    ];
+    argv.extend(condition.map(|condition| {
+        Bytes::from(match condition {
+            ExpireCondition::Nx => "NX",
+            ExpireCondition::Xx => "XX",
+            ExpireCondition::Gt => "GT",
+            ExpireCondition::Lt => "LT",
+        })
+    }));
+    argv.extend([Bytes::from("FIELDS"), Bytes::from(fields.len().to_string())]);
+    argv.extend(fields.iter().cloned());
+
+    argv
+}
+
+/// Applies `update` to the existing `fields`; an expiration in the past
+/// deletes them.
+fn update_field_ttls(hash: &mut HashData, fields: &[Bytes], update: TtlUpdate) {
+    let expiration = match update {
+        TtlUpdate::Expire(expiry) => Some(expiry.resolve()),
+        TtlUpdate::Persist => None,
+        TtlUpdate::KeepTtl => return,
+    };
+    for field in fields.iter() {
+        if !hash.get_hash().contains_key(field) {
+            continue;
+        }
+        match expiration {
+            Some(expiration) if expiration <= SystemTime::now() => {
+                hash.remove(field);
+            }
+            expiration => hash.set_field_expiration(field, expiration),
+        }
+    }
+}
+
+pub fn execute_hash_command(
+    keyspace: &mut Keyspace,
+    cmd: HashCommand,
+) -> Result<Value, RusdisError> {
+    match cmd {
+        HashCommand::Set { key, pairs } => {
+            let hash = get_or_create_hash(keyspace, &key)?;
+            let mut added = 0;
+            for (field, value) in pairs.into_iter() {
+                if hash.set(field, value, false) {
+                    added += 1;
+                }
+            }
+
+            Ok(Value::Integer(added))
+        }
+        HashCommand::SetNx { key, field, value } => {
+            let hash = get_or_create_hash(keyspace, &key)?;
+            if hash.get_hash().contains_key(&field) {
+                return Ok(Value::Integer(0));
+            }
+            hash.set(field, value, false);
+
+            Ok(Value::Integer(1))
+        }
+        HashCommand::Get { key, field } => Ok(get_hash(keyspace, &key)?
+            .and_then(|hash| hash.get_hash().get(&field))
+            .map_or(Value::Null, bulk)),
+        HashCommand::MGet { key, fields } => {
+            let hash = get_hash(keyspace, &key)?;
+
+            Ok(Value::Array(
+                fields
+                    .iter()
+                    .map(|field| {
+                        hash.as_ref()
+                            .and_then(|hash| hash.get_hash().get(field))
+                            .map_or(Value::Null, bulk)
+                    })
+                    .collect(),
+            ))
+        }
+        HashCommand::Del { key, fields } => {
+            let hash = match get_hash(keyspace, &key)? {
+                Some(hash) => hash,
+                None => return Ok(Value::Integer(0)),
+            };
+            let removed = fields.iter().filter(|field| hash.remove(field)).count();
+            remove_if_empty(keyspace, &key);
+
+            Ok(Value::Integer(removed as i64))
+        }
+        HashCommand::Exists { key, field } => Ok(Value::Integer(
+            get_hash(keyspace, &key)?.is_some_and(|hash| hash.get_hash().contains_key(&field))
+                as i64,
+        )),
+        HashCommand::Len(key) => Ok(Value::Integer(
+            get_hash(keyspace, &key)?.map_or(0, |hash| hash.get_hash().len()) as i64,
+        )),
+        HashCommand::Keys(key) => Ok(Value::Array(
+            get_hash(keyspace, &key)?
+                .map(|hash| hash.get_hash().keys().map(bulk).collect())
+                .unwrap_or_default(),
+        )),
+        HashCommand::Vals(key) => Ok(Value::Array(
+            get_hash(keyspace, &key)?
+                .map(|hash| hash.get_hash().values().map(bulk).collect())
+                .unwrap_or_default(),
+        )),
+        HashCommand::GetAll(key) => Ok(Value::Map(
+            get_hash(keyspace, &key)?
+                .map(|hash| {
+                    hash.get_hash()
+                        .iter()
+                        .map(|(field, value)| (bulk(field), bulk(value)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        )),
+        HashCommand::IncrBy {
+            key,
+            field,
+            increment,
+        } => {
+            let hash = get_or_create_hash(keyspace, &key)?;
+            let current = match hash.get_hash().get(&field) {
+                Some(value) => std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|value| value.parse::<i64>().ok())
+                    .ok_or_else(|| generic_error("hash value is not an integer"))?,
+                None => 0,
+            };
+            let num = current
+                .checked_add(increment)
+                .ok_or_else(|| generic_error("increment or decrement would overflow"))?;
+            hash.set(field, Bytes::from(num.to_string()), true);
+
+            Ok(Value::Integer(num))
+        }
+        HashCommand::IncrByFloat {
+            key,
+            field,
+            increment,
+        } => {
+            let hash = get_or_create_hash(keyspace, &key)?;
+            let current = match hash.get_hash().get(&field) {
+                Some(value) => {
+                    parse_float(value).ok_or_else(|| generic_error("hash value is not a float"))?
+                }
+                None => 0.0,
+            };
+            let num = current + increment;
+            if !num.is_finite() {
+                return Err(generic_error("increment would produce NaN or Infinity"));
+            }
+            let value = Bytes::from(num.to_string());
+            hash.set(field.clone(), value.clone(), true);
+
+            // replicas get the result, which the float formatting could change
+            let mut propagated = vec![vec![
+                Bytes::from("HSET"),
+                key.clone(),
+                field.clone(),
+                value.clone(),
+            ]];
+            if let Some(expiration) = hash.get_field_expiration(&field) {
+                propagated.push(hpexpireat_argv(
+                    &key,
+                    expiration,
+                    None,
+                    std::slice::from_ref(&field),
+                ));
+            }
+            keyspace.propagate_as(propagated);
+
+            Ok(Value::BulkString(value))
+        }
+        HashCommand::StrLen { key, field } => Ok(Value::Integer(
+            get_hash(keyspace, &key)?
+                .and_then(|hash| hash.get_hash().get(&field))
+                .map_or(0, |value| value.len()) as i64,
+        )),
+        HashCommand::RandField {
+            key,
+            count,
+            with_values,
+        } => {
+            let hash = get_hash(keyspace, &key)?.map(|hash| hash.get_hash());
+            let count = match count {
+                Some(count) => count,
+                None => {
+                    return Ok(hash
+                        .and_then(|hash| hash.keys().choose(&mut rand::thread_rng()))
+                        .map_or(Value::Null, bulk))
+                }
+            };
+            let hash = match hash {
+                Some(hash) => hash,
+                None => return Ok(Value::Array(vec![])),
+            };
+
+            let mut rng = rand::thread_rng();
+            let picked: Vec<(&Bytes, &Bytes)> = if count >= 0 {
+                let count = (count as usize).min(hash.len());
+                hash.iter().choose_multiple(&mut rng, count)
+            } else {
+                let entries: Vec<(&Bytes, &Bytes)> = hash.iter().collect();
+                (0..count.unsigned_abs())
+                    .map(|_| entries[rng.gen_range(0..entries.len())])
+                    .collect()
+            };
+
+            Ok(if with_values {
+                Value::Pairs(
+                    picked
+                        .into_iter()
+                        .map(|(field, value)| (bulk(field), bulk(value)))
+                        .collect(),
+                )
+            } else {
+                Value::Array(picked.into_iter().map(|(field, _)| bulk(field)).collect())
+            })
+        }
+        HashCommand::Expire {
+            key,
+            expiry,
+            condition,
+            fields,
+        } => {
+            let hash = match get_hash(keyspace, &key)? {
+                Some(hash) => hash,
+                None => return Ok(Value::Array(vec![Value::Integer(-2); fields.len()])),
+            };
+            let expiry = expiry.absolute();
+            let expiration = expiry.resolve();
+            let replies = fields
+                .iter()
+                .map(|field| {
+                    if !hash.get_hash().contains_key(field) {
+                        return Value::Integer(-2);
+                    }
+                    let current = hash.get_field_expiration(field);
+                    let allowed = match condition {
+                        None => true,
+                        Some(ExpireCondition::Nx) => current.is_none(),
+                        Some(ExpireCondition::Xx) => current.is_some(),
+                        Some(ExpireCondition::Gt) => current.is_some_and(|cur| expiration > cur),
+                        Some(ExpireCondition::Lt) => current.is_none_or(|cur| expiration < cur),
+                    };
+                    if !allowed {
+                        return Value::Integer(0);
+                    }
+
+                    if expiration <= SystemTime::now() {
+                        hash.remove(field);
+                        Value::Integer(2)
+                    } else {
+                        hash.set_field_expiration(field, Some(expiration));
+                        Value::Integer(1)
+                    }
+                })
+                .collect();
+            remove_if_empty(keyspace, &key);
+            keyspace.track_field_expirations(&key);
+            keyspace.propagate_as(vec![hpexpireat_argv(&key, expiration, condition, &fields)]);
+
+            Ok(Value::Array(replies))
+        }
+        HashCommand::Ttl {
+            key,
+            fields,
+            millis,
+        } => {
+            let hash = get_hash(keyspace, &key)?;
+            let now = SystemTime::now();
+
+            Ok(Value::Array(
+                fields
+                    .iter()
+                    .map(|field| match hash.as_ref() {
+                        Some(hash) if hash.get_hash().contains_key(field) => {
+                            match hash.get_field_expiration(field) {
+                                Some(expiration) => {
+                                    let left = expiration
+                                        .duration_since(now)
+                                        .unwrap_or_default()
+                                        .as_millis()
+                                        as i64;
+                                    Value::Integer(if millis { left } else { (left + 999) / 1000 })
+                                }
+                                None => Value::Integer(-1),
+                            }
+                        }
+                        _ => Value::Integer(-2),
+                    })
+                    .collect(),
+            ))
+        }
+        HashCommand::Persist { key, fields } => {
+            let mut hash = get_hash(keyspace, &key)?;
+
+            Ok(Value::Array(
+                fields
+                    .iter()
+                    .map(|field| match hash.as_mut() {
+                        Some(hash) if hash.get_hash().contains_key(field) => {
+                            if hash.get_field_expiration(field).is_none() {
+                                return Value::Integer(-1);
+                            }
+                            hash.set_field_expiration(field, None);
+                            Value::Integer(1)
+                        }
+                        _ => Value::Integer(-2),
+                    })
+                    .collect(),
+            ))
+        }
+        HashCommand::GetEx {
+            key,
+            update,
+            fields,
+        } => {
+            let hash = match get_hash(keyspace, &key)? {
+                Some(hash) => hash,
+                None => return Ok(Value::Array(vec![Value::Null; fields.len()])),
+            };
+            let values = fields
+                .iter()
+                .map(|field| hash.get_hash().get(field).map_or(Value::Null, bulk))
+                .collect();
+            let update = update.map(TtlUpdate::absolute);
+            if let Some(update) = update {
+                update_field_ttls(hash, &fields, update);
+            }
+            remove_if_empty(keyspace, &key);
+            keyspace.track_field_expirations(&key);
+            if let Some(TtlUpdate::Expire(expiry)) = update {
+                keyspace.propagate_as(vec![hpexpireat_argv(&key, expiry.resolve(), None, &fields)]);
+            }
+
+            Ok(Value::Array(values))
+        }
+        HashCommand::SetEx {
+            key,
+            condition,
+            update,
+            pairs,
+        } => {
+            let allowed = match (condition, get_hash(keyspace, &key)?) {
+                (None, _) => true,
+                (Some(SetCondition::Fnx), hash) => hash.is_none_or(|hash| {
+                    pairs
+                        .iter()
+                        .all(|(field, _)| !hash.get_hash().contains_key(field))
+                }),
+                (Some(SetCondition::Fxx), hash) => hash.is_some_and(|hash| {
+                    pairs
+                        .iter()
+                        .all(|(field, _)| hash.get_hash().contains_key(field))
+                }),
+            };
+            if !allowed {
+                return Ok(Value::Integer(0));
+            }
+
+            let update = update.map(TtlUpdate::absolute);
+            if let Some(TtlUpdate::Expire(expiry)) = update {
+                let mut argv = vec![Bytes::from("HSETEX"), key.clone()];
+                argv.extend(condition.map(|condition| {
+                    Bytes::from(match condition {
+                        SetCondition::Fnx => "FNX",
+                        SetCondition::Fxx => "FXX",
+                    })
+                }));
+                argv.extend([
+                    Bytes::from("PXAT"),
+                    unix_millis(expiry.resolve()),
+                    Bytes::from("FIELDS"),
+                    Bytes::from(pairs.len().to_string()),
+                ]);
+                argv.extend(pairs.iter().flat_map(|(f, v)| [f.clone(), v.clone()]));
+                keyspace.propagate_as(vec![argv]);
+            }
+
+            let hash = get_or_create_hash(keyspace, &key)?;
+            let keep_ttl = update == Some(TtlUpdate::KeepTtl);
+            let fields: Vec<Bytes> = pairs.iter().map(|(field, _)| field.clone()).collect();
+            for (field, value) in pairs.into_iter() {
+                hash.set(field, value, keep_ttl);
+            }
+            if let Some(update) = update {
+                update_field_ttls(hash, &fields, update);
+            }
+            remove_if_empty(keyspace, &key);
+            keyspace.track_field_expirations(&key);
+
+            Ok(Value::Integer(1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::StringData;
+    use crate::test_util::{bulk, error, now_millis, propagated, run};
+
+    #[test]
+    fn test_hash_set_get_del() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(
+            run(&mut keyspace, &["HSET", "h", "a", "1", "b", "2"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HSET", "h", "a", "3", "c", "4"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HSET", "h", "a"]),
+            error("ERR wrong number of arguments for 'hset' command")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HSET", "h", "a", "1", "b"]),
+            error("ERR wrong number of arguments for 'hset' command")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HSETNX", "h", "a", "9"]),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HSETNX", "h", "d", "9"]),
+            Value::Integer(1)
+        );
+
+        assert_eq!(run(&mut keyspace, &["HGET", "h", "a"]), bulk("3"));
+        assert_eq!(run(&mut keyspace, &["HGET", "h", "x"]), Value::Null);
+        assert_eq!(run(&mut keyspace, &["HGET", "missing", "x"]), Value::Null);
+        assert_eq!(
+            run(&mut keyspace, &["HMGET", "h", "a", "x", "b"]),
+            Value::Array(vec![bulk("3"), Value::Null, bulk("2")])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HEXISTS", "h", "c"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HEXISTS", "h", "x"]),
+            Value::Integer(0)
+        );
+        assert_eq!(run(&mut keyspace, &["HLEN", "h"]), Value::Integer(4));
+        assert_eq!(
+            run(&mut keyspace, &["HSTRLEN", "h", "d"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HSTRLEN", "h", "x"]),
+            Value::Integer(0)
+        );
+
+        match run(&mut keyspace, &["HGETALL", "h"]) {
+            Value::Map(mut pairs) => {
+                pairs.sort_by_key(|(field, _)| format!("{:?}", field));
+                assert_eq!(
+                    pairs,
+                    vec![
+                        (bulk("a"), bulk("3")),
+                        (bulk("b"), bulk("2")),
+                        (bulk("c"), bulk("4")),
+                        (bulk("d"), bulk("9")),
+                    ]
+                );
+            }
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        assert_eq!(
+            run(&mut keyspace, &["HGETALL", "missing"]),
+            Value::Map(vec![])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HKEYS", "missing"]),
+            Value::Array(vec![])
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["HDEL", "h", "a", "b", "x"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HDEL", "h", "c", "d"]),
+            Value::Integer(2)
+        );
+        assert!(keyspace.is_empty());
+    }
+
+    #[test]
+    fn test_hash_incr() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBY", "h", "n", "5"]),
+            Value::Integer(5)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBY", "h", "n", "-7"]),
+            Value::Integer(-2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBY", "h", "n", "x"]),
+            error("ERR value is not an integer or out of range")
+        );
+        run(
+            &mut keyspace,
+            &["HSET", "h", "s", "abc", "big", "9223372036854775807"],
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBY", "h", "s", "1"]),
+            error("ERR hash value is not an integer")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBY", "h", "big", "1"]),
+            error("ERR increment or decrement would overflow")
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBYFLOAT", "h", "f", "10.5"]),
+            bulk("10.5")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBYFLOAT", "h", "f", "0.1"]),
+            bulk("10.6")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBYFLOAT", "h", "n", "2"]),
+            bulk("0")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBYFLOAT", "h", "f", "abc"]),
+            error("ERR value is not a valid float")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBYFLOAT", "h", "s", "1"]),
+            error("ERR hash value is not a float")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HINCRBYFLOAT", "h", "f", "inf"]),
+            error("ERR increment would produce NaN or Infinity")
+        );
+    }
+
+    #[test]
+    fn test_hash_randfield() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(run(&mut keyspace, &["HRANDFIELD", "h"]), Value::Null);
+        assert_eq!(
+            run(&mut keyspace, &["HRANDFIELD", "h", "2"]),
+            Value::Array(vec![])
+        );
+
+        run(&mut keyspace, &["HSET", "h", "a", "1", "b", "2", "c", "3"]);
+        match run(&mut keyspace, &["HRANDFIELD", "h"]) {
+            Value::BulkString(field) => {
+                assert!(["a", "b", "c"].iter().any(|f| field == f.as_bytes()))
+            }
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        match run(&mut keyspace, &["HRANDFIELD", "h", "5"]) {
+            Value::Array(fields) => assert_eq!(fields.len(), 3),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        match run(&mut keyspace, &["HRANDFIELD", "h", "-5", "WITHVALUES"]) {
+            Value::Pairs(pairs) => assert_eq!(pairs.len(), 5),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        assert_eq!(
+            run(&mut keyspace, &["HRANDFIELD", "h", "1", "WITHSCORES"]),
+            error("ERR syntax error")
+        );
+
+        match run(&mut keyspace, &["HRANDFIELD", "h", "4611686018427387903"]) {
+            Value::Array(fields) => assert_eq!(fields.len(), 3),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        for count in ["4611686018427387904", "-9223372036854775808"] {
+            assert_eq!(
+                run(&mut keyspace, &["HRANDFIELD", "h", count, "WITHVALUES"]),
+                error("ERR value is out of range")
+            );
+        }
+    }
+
+    fn integers(values: &[i64]) -> Value {
+        Value::Array(values.iter().map(|value| Value::Integer(*value)).collect())
+    }
+
+    #[test]
+    fn test_hash_field_ttl() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(
+            run(&mut keyspace, &["HEXPIRE", "h", "10", "FIELDS", "1", "a"]),
+            integers(&[-2])
+        );
+
+        run(&mut keyspace, &["HSET", "h", "a", "1", "b", "2", "c", "3"]);
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HEXPIRE", "h", "100", "FIELDS", "2", "a", "x"]
+            ),
+            integers(&[1, -2])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HEXPIRE", "h", "50", "GT", "FIELDS", "2", "a", "b"]
+            ),
+            integers(&[0, 0])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HPEXPIRE", "h", "50000", "LT", "FIELDS", "2", "a", "b"]
+            ),
+            integers(&[1, 1])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HEXPIRE", "h", "10", "NX", "FIELDS", "1", "a"]
+            ),
+            integers(&[0])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HTTL", "h", "FIELDS", "3", "a", "c", "x"]),
+            integers(&[50, -1, -2])
+        );
+        match run(&mut keyspace, &["HPTTL", "h", "FIELDS", "1", "b"]) {
+            Value::Array(ttls) => {
+                assert!(matches!(ttls[0], Value::Integer(ttl) if ttl > 49000 && ttl <= 50000))
+            }
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HPERSIST", "h", "FIELDS", "3", "a", "c", "x"]
+            ),
+            integers(&[1, -1, -2])
+        );
+
+        // Writing a field clears its TTL, incrementing it keeps it
+        run(&mut keyspace, &["HSET", "h", "b", "5"]);
+        run(
+            &mut keyspace,
+            &["HPEXPIRE", "h", "50000", "FIELDS", "1", "c"],
+        );
+        run(&mut keyspace, &["HINCRBY", "h", "c", "1"]);
+        assert_eq!(
+            run(&mut keyspace, &["HTTL", "h", "FIELDS", "2", "b", "c"]),
+            integers(&[-1, 50])
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["HEXPIREAT", "h", "1", "FIELDS", "1", "a"]),
+            integers(&[2])
+        );
+        assert_eq!(run(&mut keyspace, &["HGET", "h", "a"]), Value::Null);
+        run(
+            &mut keyspace,
+            &["HPEXPIRE", "h", "0", "FIELDS", "2", "b", "c"],
+        );
+        assert!(keyspace.is_empty());
+
+        assert_eq!(
+            run(&mut keyspace, &["HEXPIRE", "h", "-1", "FIELDS", "1", "a"]),
+            error("ERR invalid expire time, must be >= 0")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HEXPIRE", "h", "9223372036854775", "FIELDS", "1", "a"]
+            ),
+            error("ERR invalid expire time in 'hexpire' command")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HEXPIRE", "h", "10", "FIELD", "1", "a"]),
+            error("ERR Mandatory argument FIELDS is missing or not at the right position")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HEXPIRE", "h", "10", "FIELDS", "0", "a"]),
+            error("ERR Parameter `numFields` should be greater than 0")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HTTL", "h", "FIELDS", "2", "a"]),
+            error("ERR The `numfields` parameter must match the number of arguments")
+        );
+    }
+
+    #[test]
+    fn test_hash_propagates_effects() {
+        let mut keyspace = Keyspace::new();
+
+        run(&mut keyspace, &["HINCRBYFLOAT", "h", "f", "1.5"]);
+        assert_eq!(
+            propagated(&mut keyspace),
+            Some(vec![["HSET", "h", "f", "1.5"].map(String::from).to_vec()])
+        );
+
+        let before = now_millis();
+        run(
+            &mut keyspace,
+            &["HPEXPIRE", "h", "50000", "NX", "FIELDS", "1", "f"],
+        );
+        let mut commands = propagated(&mut keyspace).unwrap();
+        let at = commands[0].remove(2);
+        assert!((before + 50000..=now_millis() + 50000).contains(&at.parse().unwrap()));
+        assert_eq!(commands[0], ["HPEXPIREAT", "h", "NX", "FIELDS", "1", "f"]);
+
+        // the increment keeps the field's expiration
+        run(&mut keyspace, &["HINCRBYFLOAT", "h", "f", "1"]);
+        assert_eq!(
+            propagated(&mut keyspace),
+            Some(vec![
+                ["HSET", "h", "f", "2.5"].map(String::from).to_vec(),
+                ["HPEXPIREAT", "h", &at, "FIELDS", "1", "f"]
+                    .map(String::from)
+                    .to_vec()
+            ])
+        );
+
+        run(
+            &mut keyspace,
+            &["HGETEX", "h", "PX", "50000", "FIELDS", "1", "f"],
+        );
+        assert_eq!(propagated(&mut keyspace).unwrap()[0][0], "HPEXPIREAT");
+        run(
+            &mut keyspace,
+            &["HSETEX", "h", "FXX", "EX", "50", "FIELDS", "1", "f", "v"],
+        );
+        let mut commands = propagated(&mut keyspace).unwrap();
+        commands[0].remove(4);
+        assert_eq!(
+            commands[0],
+            ["HSETEX", "h", "FXX", "PXAT", "FIELDS", "1", "f", "v"]
+        );
+        run(
+            &mut keyspace,
+            &["HGETEX", "h", "PERSIST", "FIELDS", "1", "f"],
+        );
+        assert_eq!(propagated(&mut keyspace), None);
+    }
+
+    #[test]
+    fn test_hash_getex_setex() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HSETEX", "h", "FXX", "FIELDS", "1", "a", "1"]
+            ),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HSETEX", "h", "FNX", "EX", "100", "FIELDS", "2", "a", "1", "b", "2"]
+            ),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HSETEX", "h", "FNX", "FIELDS", "1", "a", "3"]
+            ),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HSETEX", "h", "KEEPTTL", "FIELDS", "1", "a", "3"]
+            ),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HSETEX", "h", "FIELDS", "1", "b", "4"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HTTL", "h", "FIELDS", "2", "a", "b"]),
+            integers(&[100, -1])
+        );
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HGETEX", "h", "PERSIST", "FIELDS", "2", "a", "x"]
+            ),
+            Value::Array(vec![bulk("3"), Value::Null])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HGETEX", "h", "PX", "5000", "FIELDS", "1", "b"]
+            ),
+            Value::Array(vec![bulk("4")])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["HTTL", "h", "FIELDS", "2", "a", "b"]),
+            integers(&[-1, 5])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HGETEX", "h", "EXAT", "1", "FIELDS", "1", "a"]
+            ),
+            Value::Array(vec![bulk("3")])
+        );
+        assert_eq!(run(&mut keyspace, &["HLEN", "h"]), Value::Integer(1));
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HGETEX", "h", "EX", "0", "FIELDS", "1", "a"]
+            ),
+            error("ERR invalid expire time in 'hgetex' command")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HSETEX", "h", "EX", "1", "PX", "1", "FIELDS", "1", "a", "1"]
+            ),
+            error("ERR syntax error")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["HSETEX", "h", "FIELDS", "2", "a", "1", "b"]
+            ),
+            error("ERR The `numfields` parameter must match the number of arguments")
+        );
+    }
+
+    #[test]
+    fn test_hash_wrong_type() {
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(
+            Bytes::from("s"),
+            Data::String(StringData::new(Bytes::from("v"))),
+        );
+
+        let wrong_type = RusdisError::WrongType.to_reply();
+        assert_eq!(run(&mut keyspace, &["HSET", "s", "a", "1"]), wrong_type);
+        assert_eq!(run(&mut keyspace, &["HGET", "s", "a"]), wrong_type);
+        assert_eq!(run(&mut keyspace, &["HGETALL", "s"]), wrong_type);
+    }
+}
@@ -0,0 +1,537 @@
+use crate::command_parser::Command;
+use crate::data::{get_live, Data, Keyspace, StringData};
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+
+/// Bits of the hash picking the register.
+const HLL_P: u32 = 14;
+/// Bits of the hash left to count leading zeros in.
+const HLL_Q: u32 = 64 - HLL_P;
+const HLL_REGISTERS: usize = 1 << HLL_P;
+const HLL_BITS: usize = 6;
+const HLL_HDR_SIZE: usize = 16;
+const HLL_DENSE_SIZE: usize = HLL_HDR_SIZE + (HLL_REGISTERS * HLL_BITS).div_ceil(8);
+const HLL_DENSE: u8 = 0;
+const HLL_SPARSE: u8 = 1;
+/// Largest sparse value, header included, before it turns dense.
+const HLL_SPARSE_MAX_BYTES: usize = 3000;
+/// Largest register value the sparse VAL opcode holds.
+const HLL_SPARSE_VAL_MAX: u8 = 32;
+/// 0.5 / ln(2)
+const HLL_ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HllCommand {
+    Add {
+        key: Bytes,
+        elements: Vec<Bytes>,
+    },
+    /// PFCOUNT, the union of the keys when there are several
+    Count(Vec<Bytes>),
+    Merge {
+        destination: Bytes,
+        sources: Vec<Bytes>,
+    },
+}
+
+pub(crate) fn parse_pfadd_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::HyperLogLog(HllCommand::Add {
+        key: argv[1].clone(),
+        elements: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_pfcount_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::HyperLogLog(HllCommand::Count(argv[1..].to_vec())))
+}
+
+pub(crate) fn parse_pfmerge_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::HyperLogLog(HllCommand::Merge {
+        destination: argv[1].clone(),
+        sources: argv[2..].to_vec(),
+    }))
+}
+
+/// MurmurHash2, 64 bit version, reading blocks as little endian.
+fn murmurhash64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (key.len() as u64).wrapping_mul(M);
+    let mut chunks = key.chunks_exact(8);
+    for chunk in chunks.by_ref() {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u64) << (i * 8);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+/// The register `element` falls in, and the length of the run of zeros in the
+/// rest of its hash plus one.
+fn pattern_len(element: &[u8]) -> (usize, u8) {
+    let hash = murmurhash64a(element, 0xadc8_3b19);
+    let index = (hash & (HLL_REGISTERS as u64 - 1)) as usize;
+    // the set bit past the end bounds the count to Q + 1
+    let hash = (hash >> HLL_P) | (1 << HLL_Q);
+
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+/// Registers of a HyperLogLog, one byte each.
+struct Hll {
+    registers: Vec<u8>,
+    dense: bool,
+}
+
+impl Hll {
+    fn new() -> Self {
+        Hll {
+            registers: vec![0; HLL_REGISTERS],
+            dense: false,
+        }
+    }
+
+    /// Decodes a HyperLogLog string, dense or sparse.
+    fn decode(bytes: &[u8]) -> Result<Self, RusdisError> {
+        let registers = if is_dense(bytes) {
+            (0..HLL_REGISTERS)
+                .map(|index| dense_get(&bytes[HLL_HDR_SIZE..], index))
+                .collect()
+        } else {
+            decode_sparse(&bytes[HLL_HDR_SIZE..])?
+        };
+
+        Ok(Hll {
+            registers,
+            dense: is_dense(bytes),
+        })
+    }
+
+    /// Raises the register of `element` if its pattern is longer. Returns
+    /// whether the register changed.
+    fn add(&mut self, element: &[u8]) -> bool {
+        let (index, count) = pattern_len(element);
+        if self.registers[index] >= count {
+            return false;
+        }
+        self.registers[index] = count;
+
+        true
+    }
+
+    fn merge(&mut self, other: &Hll) {
+        for (register, other) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    /// Encodes the registers sparse when they fit in it and were not dense
+    /// already, with `card` as the cached cardinality.
+    fn encode(&self, card: Option<u64>) -> Bytes {
+        let sparse = if self.dense {
+            None
+        } else {
+            encode_sparse(&self.registers)
+                .filter(|sparse| HLL_HDR_SIZE + sparse.len() <= HLL_SPARSE_MAX_BYTES)
+        };
+
+        let mut buf = Vec::with_capacity(HLL_DENSE_SIZE);
+        buf.extend(b"HYLL");
+        buf.push(if sparse.is_some() {
+            HLL_SPARSE
+        } else {
+            HLL_DENSE
+        });
+        buf.extend([0; 3]);
+        buf.extend(card_bytes(card));
+        match sparse {
+            Some(sparse) => buf.extend(sparse),
+            None => {
+                buf.resize(HLL_DENSE_SIZE, 0);
+                for (index, value) in self.registers.iter().enumerate() {
+                    dense_set(&mut buf[HLL_HDR_SIZE..], index, *value);
+                }
+            }
+        }
+
+        Bytes::from(buf)
+    }
+
+    /// Estimates the cardinality as in "New cardinality estimation algorithms
+    /// for HyperLogLog sketches", Otmar Ertl.
+    fn count(&self) -> u64 {
+        let m = HLL_REGISTERS as f64;
+        let mut histogram = [0_u32; HLL_Q as usize + 2];
+        for register in self.registers.iter() {
+            histogram[*register as usize] += 1;
+        }
+
+        let mut z = m * tau((m - histogram[HLL_Q as usize + 1] as f64) / m);
+        for j in (1..=HLL_Q as usize).rev() {
+            z += histogram[j] as f64;
+            z *= 0.5;
+        }
+        z += m * sigma(histogram[0] as f64 / m);
+
+        (HLL_ALPHA_INF * m * m / z).round() as u64
+    }
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let (mut y, mut z) = (1.0, x);
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let (mut y, mut z) = (1.0, 1.0 - x);
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+/// Cached cardinality bytes of the header, little endian; the top bit of the
+/// last byte marks it stale.
+fn card_bytes(card: Option<u64>) -> [u8; 8] {
+    match card {
+        Some(card) => card.to_le_bytes(),
+        None => [0, 0, 0, 0, 0, 0, 0, 0x80],
+    }
+}
+
+fn is_dense(bytes: &[u8]) -> bool {
+    bytes[4] == HLL_DENSE
+}
+
+/// Checks the header of a HyperLogLog string.
+fn check_hll(bytes: &[u8]) -> Result<(), RusdisError> {
+    let valid = bytes.len() >= HLL_HDR_SIZE
+        && bytes.starts_with(b"HYLL")
+        && match bytes[4] {
+            HLL_DENSE => bytes.len() == HLL_DENSE_SIZE,
+            HLL_SPARSE => true,
+            _ => false,
+        };
+
+    valid.then_some(()).ok_or(RusdisError::InvalidHll)
+}
+
+/// Register `index` of dense registers, packed 6 bits each from the least
+/// significant bit of each byte on.
+fn dense_get(registers: &[u8], index: usize) -> u8 {
+    let byte = index * HLL_BITS / 8;
+    let shift = index * HLL_BITS % 8;
+    let low = registers[byte] as u16;
+    let high = registers.get(byte + 1).copied().unwrap_or(0) as u16;
+
+    (((low | high << 8) >> shift) & 0x3f) as u8
+}
+
+fn dense_set(registers: &mut [u8], index: usize, value: u8) {
+    let byte = index * HLL_BITS / 8;
+    let shift = index * HLL_BITS % 8;
+    let bits = (value as u16 & 0x3f) << shift;
+    let mask = !(0x3f_u16 << shift);
+
+    registers[byte] = (registers[byte] & mask as u8) | bits as u8;
+    if let Some(high) = registers.get_mut(byte + 1) {
+        *high = (*high & (mask >> 8) as u8) | (bits >> 8) as u8;
+    }
+}
+
+/// Decodes sparse registers: ZERO `00xxxxxx` and XZERO `01xxxxxx yyyyyyyy`
+/// are runs of zeros, VAL `1vvvvvxx` a run of up to 4 registers of value up
+/// to 32.
+fn decode_sparse(opcodes: &[u8]) -> Result<Vec<u8>, RusdisError> {
+    let mut registers = Vec::with_capacity(HLL_REGISTERS);
+    let mut iter = opcodes.iter();
+    while let Some(opcode) = iter.next() {
+        let (value, run) = match opcode >> 6 {
+            0b00 => (0, (opcode & 0x3f) as usize + 1),
+            0b01 => {
+                let low = *iter.next().ok_or(RusdisError::CorruptedHll)?;
+                (0, (((opcode & 0x3f) as usize) << 8 | low as usize) + 1)
+            }
+            _ => (((opcode >> 2) & 0x1f) + 1, (opcode & 0x3) as usize + 1),
+        };
+        if registers.len() + run > HLL_REGISTERS {
+            return Err(RusdisError::CorruptedHll);
+        }
+        registers.resize(registers.len() + run, value);
+    }
+
+    if registers.len() != HLL_REGISTERS {
+        return Err(RusdisError::CorruptedHll);
+    }
+    Ok(registers)
+}
+
+/// Encodes registers as sparse opcodes, `None` if a value is too large for it.
+fn encode_sparse(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut opcodes = vec![];
+    let mut index = 0;
+    while index < registers.len() {
+        let value = registers[index];
+        let run = registers[index..]
+            .iter()
+            .take_while(|register| **register == value)
+            .count();
+        index += run;
+
+        let mut left = run;
+        while left > 0 {
+            let len = match value {
+                0 if left > 64 => {
+                    let len = left.min(HLL_REGISTERS);
+                    opcodes.extend([0x40 | ((len - 1) >> 8) as u8, (len - 1) as u8]);
+                    len
+                }
+                0 => {
+                    opcodes.push((left - 1) as u8);
+                    left
+                }
+                value if value <= HLL_SPARSE_VAL_MAX => {
+                    let len = left.min(4);
+                    opcodes.push(0x80 | (value - 1) << 2 | (len - 1) as u8);
+                    len
+                }
+                _ => return None,
+            };
+            left -= len;
+        }
+    }
+
+    Some(opcodes)
+}
+
+/// The HyperLogLog stored at `key`, if any, as a validated string.
+fn get_hll(keyspace: &mut Keyspace, key: &Bytes) -> Result<Option<Bytes>, RusdisError> {
+    match get_live(keyspace, key) {
+        Some(Data::String(data)) => {
+            check_hll(data.get_data())?;
+            Ok(Some(data.get_data().clone()))
+        }
+        Some(_) => Err(RusdisError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// Stores `bytes` at `key`, keeping the expiration of a string already there.
+fn store(keyspace: &mut Keyspace, key: Bytes, bytes: Bytes) {
+    match get_live(keyspace, &key) {
+        Some(Data::String(data)) => data.set_data(bytes),
+        _ => {
+            keyspace.insert(key, Data::String(StringData::new(bytes)));
+        }
+    }
+}
+
+pub fn execute_hll_command(keyspace: &mut Keyspace, cmd: HllCommand) -> Result<Value, RusdisError> {
+    match cmd {
+        HllCommand::Add { key, elements } => {
+            let (mut hll, created) = match get_hll(keyspace, &key)? {
+                Some(bytes) => (Hll::decode(&bytes)?, false),
+                None => (Hll::new(), true),
+            };
+
+            let mut updated = false;
+            for element in elements.iter() {
+                updated |= hll.add(element);
+            }
+            if updated || created {
+                let card = if updated { None } else { Some(0) };
+                store(keyspace, key, hll.encode(card));
+            }
+
+            Ok(Value::Integer((updated || created) as i64))
+        }
+        HllCommand::Count(keys) => {
+            if let [key] = &keys[..] {
+                let bytes = match get_hll(keyspace, key)? {
+                    Some(bytes) => bytes,
+                    None => return Ok(Value::Integer(0)),
+                };
+                let cached = &bytes[8..16];
+                if cached[7] & 0x80 == 0 {
+                    return Ok(Value::Integer(
+                        u64::from_le_bytes(cached.try_into().unwrap()) as i64,
+                    ));
+                }
+
+                let card = Hll::decode(&bytes)?.count();
+                let mut bytes = bytes.to_vec();
+                bytes[8..16].copy_from_slice(&card_bytes(Some(card)));
+                store(keyspace, key.clone(), Bytes::from(bytes));
+                return Ok(Value::Integer(card as i64));
+            }
+
+            // the union of several keys is counted without being stored
+            let mut union = Hll::new();
+            for key in keys.iter() {
+                if let Some(bytes) = get_hll(keyspace, key)? {
+                    union.merge(&Hll::decode(&bytes)?);
+                }
+            }
+
+            Ok(Value::Integer(union.count() as i64))
+        }
+        HllCommand::Merge {
+            destination,
+            sources,
+        } => {
+            let mut union = Hll::new();
+            for key in std::iter::once(&destination).chain(sources.iter()) {
+                if let Some(bytes) = get_hll(keyspace, key)? {
+                    let hll = Hll::decode(&bytes)?;
+                    // dense inputs make a dense result
+                    union.dense |= hll.dense;
+                    union.merge(&hll);
+                }
+            }
+            store(keyspace, destination, union.encode(None));
+
+            Ok(Value::SimpleString("OK".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{run, string};
+
+    fn count(keyspace: &mut Keyspace, keys: &[&str]) -> i64 {
+        let args: Vec<&str> = std::iter::once("PFCOUNT")
+            .chain(keys.iter().copied())
+            .collect();
+        match run(keyspace, &args) {
+            Value::Integer(count) => count,
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+    }
+
+    #[test]
+    fn test_hyperloglog_encoding() {
+        let mut registers = vec![0; HLL_REGISTERS];
+        registers[0] = 3;
+        registers[1] = 3;
+        registers[HLL_REGISTERS - 1] = 32;
+        let sparse = encode_sparse(&registers).unwrap();
+        // VAL 3 x2, XZERO 16381, VAL 32 x1
+        assert_eq!(sparse, vec![0x89, 0x7f, 0xfc, 0xfc]);
+        assert_eq!(decode_sparse(&sparse).unwrap(), registers);
+        registers[5] = 33;
+        assert_eq!(encode_sparse(&registers), None);
+
+        let mut dense = vec![0; HLL_DENSE_SIZE - HLL_HDR_SIZE];
+        for (index, value) in [(0, 63), (1, 1), (2, 42), (HLL_REGISTERS - 1, 51)] {
+            dense_set(&mut dense, index, value);
+        }
+        assert_eq!(&dense[..3], &[0x7f, 0xa0, 0x02]);
+        assert_eq!(dense_get(&dense, 0), 63);
+        assert_eq!(dense_get(&dense, 1), 1);
+        assert_eq!(dense_get(&dense, 2), 42);
+        assert_eq!(dense_get(&dense, 3), 0);
+        assert_eq!(dense_get(&dense, HLL_REGISTERS - 1), 51);
+
+        assert!(decode_sparse(&[0x7f, 0xff, 0x00]).is_err());
+        assert!(decode_sparse(&[0x7f]).is_err());
+    }
+
+    #[test]
+    fn test_hyperloglog_commands() {
+        let mut keyspace = Keyspace::new();
+
+        assert_eq!(run(&mut keyspace, &["PFADD", "empty"]), Value::Integer(1));
+        assert_eq!(run(&mut keyspace, &["PFADD", "empty"]), Value::Integer(0));
+        assert_eq!(
+            string(&keyspace, "empty").as_ref(),
+            b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff"
+        );
+        assert_eq!(count(&mut keyspace, &["empty"]), 0);
+
+        assert_eq!(
+            run(&mut keyspace, &["PFADD", "h", "a", "b", "c", "a"]),
+            Value::Integer(1)
+        );
+        assert_eq!(run(&mut keyspace, &["PFADD", "h", "b"]), Value::Integer(0));
+        assert_eq!(string(&keyspace, "h")[15], 0x80);
+        assert_eq!(count(&mut keyspace, &["h"]), 3);
+        // the count is cached in the header
+        assert_eq!(&string(&keyspace, "h")[8..16], &3_u64.to_le_bytes());
+
+        let mut args = vec!["PFADD".to_string(), "big".to_string()];
+        args.extend((0..10000).map(|i| format!("element:{}", i)));
+        let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+        run(&mut keyspace, &args);
+        assert_eq!(string(&keyspace, "big")[4], HLL_DENSE);
+        let estimate = count(&mut keyspace, &["big"]);
+        assert!((estimate - 10000).abs() < 200, "estimate {}", estimate);
+
+        // the union of h and big
+        let union = count(&mut keyspace, &["h", "big", "none"]);
+        assert!((union - estimate).abs() <= 5, "union {}", union);
+        assert_eq!(
+            run(&mut keyspace, &["PFMERGE", "merged", "h", "big"]),
+            Value::SimpleString("OK".to_string())
+        );
+        assert_eq!(string(&keyspace, "merged")[4], HLL_DENSE);
+        assert_eq!(count(&mut keyspace, &["merged"]), union);
+        run(&mut keyspace, &["PFMERGE", "small", "h", "empty"]);
+        assert_eq!(string(&keyspace, "small")[4], HLL_SPARSE);
+        assert_eq!(count(&mut keyspace, &["small"]), 3);
+
+        keyspace.insert(
+            Bytes::from("text"),
+            Data::String(StringData::new(Bytes::from("hello"))),
+        );
+        assert_eq!(
+            run(&mut keyspace, &["PFADD", "text", "a"]),
+            RusdisError::InvalidHll.to_reply()
+        );
+        keyspace.insert(
+            Bytes::from("corrupt"),
+            Data::String(StringData::new(Bytes::from_static(
+                b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x7f",
+            ))),
+        );
+        assert_eq!(
+            run(&mut keyspace, &["PFCOUNT", "corrupt"]),
+            Value::SimpleError("INVALIDOBJ Corrupted HLL object detected".to_string())
+        );
+    }
+}
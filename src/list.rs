@@ -0,0 +1,940 @@
+use crate::blocking::{parse_timeout, BlockingOp};
+use crate::command_parser::{parse_number, to_keyword, Command};
+use crate::data::{get_live, Data, Keyspace, ListData};
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+use std::collections::VecDeque;
+
+/// End of a list an element is pushed to or popped from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum End {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListCommand {
+    /// LPUSH, RPUSH and, with `only_existing`, LPUSHX and RPUSHX
+    Push {
+        key: Bytes,
+        end: End,
+        elements: Vec<Bytes>,
+        only_existing: bool,
+    },
+    /// LPOP and RPOP; `count` is set when given, even if it is 1
+    Pop {
+        key: Bytes,
+        end: End,
+        count: Option<usize>,
+    },
+    Range {
+        key: Bytes,
+        start: i64,
+        stop: i64,
+    },
+    Len(Bytes),
+    Index {
+        key: Bytes,
+        index: i64,
+    },
+    Set {
+        key: Bytes,
+        index: i64,
+        element: Bytes,
+    },
+    Insert {
+        key: Bytes,
+        before: bool,
+        pivot: Bytes,
+        element: Bytes,
+    },
+    Rem {
+        key: Bytes,
+        count: i64,
+        element: Bytes,
+    },
+    Trim {
+        key: Bytes,
+        start: i64,
+        stop: i64,
+    },
+    Pos {
+        key: Bytes,
+        element: Bytes,
+        rank: i64,
+        count: Option<usize>,
+        maxlen: usize,
+    },
+    Move {
+        source: Bytes,
+        destination: Bytes,
+        from: End,
+        to: End,
+    },
+    MPop {
+        keys: Vec<Bytes>,
+        end: End,
+        count: usize,
+    },
+}
+
+fn generic_error(msg: &str) -> RusdisError {
+    RusdisError::Generic {
+        msg: msg.to_string(),
+    }
+}
+
+fn parse_end(bytes: &Bytes) -> Result<End, RusdisError> {
+    match to_keyword(bytes).as_str() {
+        "LEFT" => Ok(End::Left),
+        "RIGHT" => Ok(End::Right),
+        _ => Err(RusdisError::SyntaxError),
+    }
+}
+
+/// Parses a count that must be at least `min`, replying with `msg` otherwise.
+pub(crate) fn parse_bounded_count(
+    bytes: &Bytes,
+    min: i64,
+    msg: &str,
+) -> Result<usize, RusdisError> {
+    match parse_number::<i64>(bytes) {
+        Ok(count) if count >= min => Ok(count as usize),
+        _ => Err(generic_error(msg)),
+    }
+}
+
+fn parse_push(argv: &[Bytes], end: End, only_existing: bool) -> Result<Command, RusdisError> {
+    Ok(Command::List(ListCommand::Push {
+        key: argv[1].clone(),
+        end,
+        elements: argv[2..].to_vec(),
+        only_existing,
+    }))
+}
+
+pub(crate) fn parse_lpush_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_push(argv, End::Left, false)
+}
+
+pub(crate) fn parse_rpush_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_push(argv, End::Right, false)
+}
+
+pub(crate) fn parse_lpushx_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_push(argv, End::Left, true)
+}
+
+pub(crate) fn parse_rpushx_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_push(argv, End::Right, true)
+}
+
+fn parse_pop(argv: &[Bytes], end: End) -> Result<Command, RusdisError> {
+    if argv.len() > 3 {
+        return Err(RusdisError::SyntaxError);
+    }
+
+    let count = match argv.get(2) {
+        Some(count) => {
+            let count = parse_number::<i64>(count)?;
+            if count < 0 {
+                return Err(generic_error("value is out of range, must be positive"));
+            }
+            Some(count as usize)
+        }
+        None => None,
+    };
+
+    Ok(Command::List(ListCommand::Pop {
+        key: argv[1].clone(),
+        end,
+        count,
+    }))
+}
+
+pub(crate) fn parse_lpop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_pop(argv, End::Left)
+}
+
+pub(crate) fn parse_rpop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_pop(argv, End::Right)
+}
+
+pub(crate) fn parse_lrange_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::List(ListCommand::Range {
+        key: argv[1].clone(),
+        start: parse_number(&argv[2])?,
+        stop: parse_number(&argv[3])?,
+    }))
+}
+
+pub(crate) fn parse_llen_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::List(ListCommand::Len(argv[1].clone())))
+}
+
+pub(crate) fn parse_lindex_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::List(ListCommand::Index {
+        key: argv[1].clone(),
+        index: parse_number(&argv[2])?,
+    }))
+}
+
+pub(crate) fn parse_lset_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::List(ListCommand::Set {
+        key: argv[1].clone(),
+        index: parse_number(&argv[2])?,
+        element: argv[3].clone(),
+    }))
+}
+
+pub(crate) fn parse_linsert_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let before = match to_keyword(&argv[2]).as_str() {
+        "BEFORE" => true,
+        "AFTER" => false,
+        _ => return Err(RusdisError::SyntaxError),
+    };
+
+    Ok(Command::List(ListCommand::Insert {
+        key: argv[1].clone(),
+        before,
+        pivot: argv[3].clone(),
+        element: argv[4].clone(),
+    }))
+}
+
+pub(crate) fn parse_lrem_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::List(ListCommand::Rem {
+        key: argv[1].clone(),
+        count: parse_number(&argv[2])?,
+        element: argv[3].clone(),
+    }))
+}
+
+pub(crate) fn parse_ltrim_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::List(ListCommand::Trim {
+        key: argv[1].clone(),
+        start: parse_number(&argv[2])?,
+        stop: parse_number(&argv[3])?,
+    }))
+}
+
+pub(crate) fn parse_lpos_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let mut rank = 1;
+    let mut count = None;
+    let mut maxlen = 0;
+
+    let mut args = argv[3..].iter();
+    while let Some(option) = args.next() {
+        let value = match args.next() {
+            Some(value) => value,
+            None => return Err(RusdisError::SyntaxError),
+        };
+        match to_keyword(option).as_str() {
+            "RANK" => {
+                rank = parse_number::<i64>(value)?;
+                if rank == 0 {
+                    return Err(generic_error(
+                        "RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list",
+                    ));
+                }
+            }
+            "COUNT" => count = Some(parse_bounded_count(value, 0, "COUNT can't be negative")?),
+            "MAXLEN" => maxlen = parse_bounded_count(value, 0, "MAXLEN can't be negative")?,
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    Ok(Command::List(ListCommand::Pos {
+        key: argv[1].clone(),
+        element: argv[2].clone(),
+        rank,
+        count,
+        maxlen,
+    }))
+}
+
+pub(crate) fn parse_lmove_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::List(ListCommand::Move {
+        source: argv[1].clone(),
+        destination: argv[2].clone(),
+        from: parse_end(&argv[3])?,
+        to: parse_end(&argv[4])?,
+    }))
+}
+
+/// Parses the `numkeys key [key ...] LEFT|RIGHT [COUNT count]` tail of LMPOP,
+/// starting at `argv[idx]`.
+pub(crate) fn parse_mpop_args(
+    argv: &[Bytes],
+    idx: usize,
+) -> Result<(Vec<Bytes>, End, usize), RusdisError> {
+    let numkeys = parse_bounded_count(&argv[idx], 1, "numkeys should be greater than 0")?;
+    if numkeys > argv.len() - idx - 2 {
+        return Err(RusdisError::SyntaxError);
+    }
+    let keys = argv[idx + 1..idx + 1 + numkeys].to_vec();
+    let end = parse_end(&argv[idx + 1 + numkeys])?;
+
+    let mut count = None;
+    let mut args = argv[idx + 2 + numkeys..].iter();
+    while let Some(option) = args.next() {
+        match (to_keyword(option).as_str(), args.next()) {
+            ("COUNT", Some(value)) if count.is_none() => {
+                count = Some(parse_bounded_count(
+                    value,
+                    1,
+                    "count should be greater than 0",
+                )?)
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    Ok((keys, end, count.unwrap_or(1)))
+}
+
+pub(crate) fn parse_lmpop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (keys, end, count) = parse_mpop_args(argv, 1)?;
+
+    Ok(Command::List(ListCommand::MPop { keys, end, count }))
+}
+
+fn parse_bpop(argv: &[Bytes], end: End) -> Result<Command, RusdisError> {
+    Ok(Command::Block {
+        keys: argv[1..argv.len() - 1].to_vec(),
+        op: BlockingOp::Pop(end),
+        timeout: parse_timeout(&argv[argv.len() - 1])?,
+    })
+}
+
+pub(crate) fn parse_blpop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_bpop(argv, End::Left)
+}
+
+pub(crate) fn parse_brpop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_bpop(argv, End::Right)
+}
+
+pub(crate) fn parse_blmove_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (from, to) = (parse_end(&argv[3])?, parse_end(&argv[4])?);
+
+    Ok(Command::Block {
+        keys: vec![argv[1].clone()],
+        op: BlockingOp::Move {
+            destination: argv[2].clone(),
+            from,
+            to,
+        },
+        timeout: parse_timeout(&argv[5])?,
+    })
+}
+
+pub(crate) fn parse_blmpop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let timeout = parse_timeout(&argv[1])?;
+    let (keys, end, count) = parse_mpop_args(argv, 2)?;
+
+    Ok(Command::Block {
+        keys,
+        op: BlockingOp::MPop { end, count },
+        timeout,
+    })
+}
+
+/// The list stored at `key`, if any.
+fn get_list<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<Option<&'a mut VecDeque<Bytes>>, RusdisError> {
+    match get_live(keyspace, key) {
+        Some(Data::List(data)) => Ok(Some(data.get_list_mut())),
+        Some(_) => Err(RusdisError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// Deletes `key` if it holds an empty list, as Redis never keeps empty lists.
+fn remove_if_empty(keyspace: &mut Keyspace, key: &Bytes) {
+    if let Some(Data::List(data)) = keyspace.get(key) {
+        if data.get_list().is_empty() {
+            keyspace.remove(key);
+        }
+    }
+}
+
+/// Pushes `elements` one by one and returns the new length of the list.
+pub fn push(keyspace: &mut Keyspace, key: &Bytes, end: End, elements: Vec<Bytes>) -> usize {
+    let list = match keyspace.get_mut(key) {
+        Some(Data::List(data)) => data.get_list_mut(),
+        _ => {
+            keyspace.insert(key.clone(), Data::List(ListData::new(VecDeque::new())));
+            match keyspace.get_mut(key) {
+                Some(Data::List(data)) => data.get_list_mut(),
+                _ => unreachable!(),
+            }
+        }
+    };
+
+    for element in elements.into_iter() {
+        match end {
+            End::Left => list.push_front(element),
+            End::Right => list.push_back(element),
+        }
+    }
+
+    list.len()
+}
+
+/// Pops up to `count` elements, deleting the key once the list is empty.
+pub fn pop(
+    keyspace: &mut Keyspace,
+    key: &Bytes,
+    end: End,
+    count: usize,
+) -> Result<Option<Vec<Bytes>>, RusdisError> {
+    let list = match get_list(keyspace, key)? {
+        Some(list) => list,
+        None => return Ok(None),
+    };
+
+    let count = count.min(list.len());
+    let elements = match end {
+        End::Left => list.drain(..count).collect(),
+        End::Right => list.drain(list.len() - count..).rev().collect(),
+    };
+    remove_if_empty(keyspace, key);
+
+    Ok(Some(elements))
+}
+
+/// Moves one element from `source` to `destination`, which may be the same list.
+pub fn move_element(
+    keyspace: &mut Keyspace,
+    source: &Bytes,
+    destination: &Bytes,
+    from: End,
+    to: End,
+) -> Result<Option<Bytes>, RusdisError> {
+    if get_list(keyspace, source)?.is_none() {
+        return Ok(None);
+    }
+    get_list(keyspace, destination)?;
+
+    let element = match pop(keyspace, source, from, 1)? {
+        Some(mut elements) => elements.pop(),
+        None => None,
+    };
+    if let Some(element) = &element {
+        push(keyspace, destination, to, vec![element.clone()]);
+    }
+
+    Ok(element)
+}
+
+/// Pops from the first non-empty list among `keys`, replying `[key, elements]`.
+pub fn mpop(
+    keyspace: &mut Keyspace,
+    keys: &[Bytes],
+    end: End,
+    count: usize,
+) -> Result<Value, RusdisError> {
+    for key in keys.iter() {
+        if let Some(elements) = pop(keyspace, key, end, count)? {
+            return Ok(Value::Array(vec![
+                Value::BulkString(key.clone()),
+                Value::Array(elements.into_iter().map(Value::BulkString).collect()),
+            ]));
+        }
+    }
+
+    Ok(Value::NullArray)
+}
+
+/// Resolves inclusive `start` and `stop` indexes, negative ones counting from
+/// the end, into a range of the list.
+pub(crate) fn resolve_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (start + len).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        stop + len
+    } else {
+        stop.min(len - 1)
+    };
+
+    if start > stop || start >= len {
+        None
+    } else {
+        Some((start as usize, stop as usize))
+    }
+}
+
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    if index >= 0 && index < len as i64 {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+fn bulk_array(elements: Vec<Bytes>) -> Value {
+    Value::Array(elements.into_iter().map(Value::BulkString).collect())
+}
+
+pub fn execute_list_command(
+    keyspace: &mut Keyspace,
+    cmd: ListCommand,
+) -> Result<Value, RusdisError> {
+    match cmd {
+        ListCommand::Push {
+            key,
+            end,
+            elements,
+            only_existing,
+        } => {
+            if get_list(keyspace, &key)?.is_none() && only_existing {
+                return Ok(Value::Integer(0));
+            }
+
+            Ok(Value::Integer(push(keyspace, &key, end, elements) as i64))
+        }
+        ListCommand::Pop { key, end, count } => {
+            match (pop(keyspace, &key, end, count.unwrap_or(1))?, count) {
+                (None, None) => Ok(Value::Null),
+                (None, Some(_)) => Ok(Value::NullArray),
+                (Some(mut elements), None) => {
+                    Ok(elements.pop().map_or(Value::Null, Value::BulkString))
+                }
+                (Some(elements), Some(_)) => Ok(bulk_array(elements)),
+            }
+        }
+        ListCommand::Range { key, start, stop } => {
+            let list = match get_list(keyspace, &key)? {
+                Some(list) => list,
+                None => return Ok(Value::Array(vec![])),
+            };
+
+            Ok(match resolve_range(start, stop, list.len()) {
+                Some((start, stop)) => bulk_array(list.range(start..=stop).cloned().collect()),
+                None => Value::Array(vec![]),
+            })
+        }
+        ListCommand::Len(key) => Ok(Value::Integer(
+            get_list(keyspace, &key)?.map_or(0, |list| list.len()) as i64,
+        )),
+        ListCommand::Index { key, index } => {
+            let list = match get_list(keyspace, &key)? {
+                Some(list) => list,
+                None => return Ok(Value::Null),
+            };
+
+            Ok(match resolve_index(index, list.len()) {
+                Some(index) => Value::BulkString(list[index].clone()),
+                None => Value::Null,
+            })
+        }
+        ListCommand::Set {
+            key,
+            index,
+            element,
+        } => {
+            let list = match get_list(keyspace, &key)? {
+                Some(list) => list,
+                None => return Err(generic_error("no such key")),
+            };
+
+            match resolve_index(index, list.len()) {
+                Some(index) => {
+                    list[index] = element;
+                    Ok(Value::SimpleString("OK".to_string()))
+                }
+                None => Err(generic_error("index out of range")),
+            }
+        }
+        ListCommand::Insert {
+            key,
+            before,
+            pivot,
+            element,
+        } => {
+            let list = match get_list(keyspace, &key)? {
+                Some(list) => list,
+                None => return Ok(Value::Integer(0)),
+            };
+
+            match list.iter().position(|e| *e == pivot) {
+                Some(idx) => {
+                    list.insert(if before { idx } else { idx + 1 }, element);
+                    Ok(Value::Integer(list.len() as i64))
+                }
+                None => Ok(Value::Integer(-1)),
+            }
+        }
+        ListCommand::Rem {
+            key,
+            count,
+            element,
+        } => {
+            let list = match get_list(keyspace, &key)? {
+                Some(list) => list,
+                None => return Ok(Value::Integer(0)),
+            };
+
+            let limit = if count == 0 {
+                usize::MAX
+            } else {
+                count.unsigned_abs() as usize
+            };
+            let mut removed = 0;
+            if count >= 0 {
+                let mut idx = 0;
+                while idx < list.len() && removed < limit {
+                    if list[idx] == element {
+                        list.remove(idx);
+                        removed += 1;
+                    } else {
+                        idx += 1;
+                    }
+                }
+            } else {
+                let mut idx = list.len();
+                while idx > 0 && removed < limit {
+                    idx -= 1;
+                    if list[idx] == element {
+                        list.remove(idx);
+                        removed += 1;
+                    }
+                }
+            }
+            remove_if_empty(keyspace, &key);
+
+            Ok(Value::Integer(removed as i64))
+        }
+        ListCommand::Trim { key, start, stop } => {
+            if let Some(list) = get_list(keyspace, &key)? {
+                match resolve_range(start, stop, list.len()) {
+                    Some((start, stop)) => {
+                        list.truncate(stop + 1);
+                        list.drain(..start);
+                    }
+                    None => list.clear(),
+                }
+                remove_if_empty(keyspace, &key);
+            }
+
+            Ok(Value::SimpleString("OK".to_string()))
+        }
+        ListCommand::Pos {
+            key,
+            element,
+            rank,
+            count,
+            maxlen,
+        } => {
+            let mut matches = vec![];
+            if let Some(list) = get_list(keyspace, &key)? {
+                let limit = match count {
+                    Some(0) => usize::MAX,
+                    Some(count) => count,
+                    None => 1,
+                };
+                let maxlen = if maxlen == 0 { list.len() } else { maxlen };
+                let mut skip = rank.unsigned_abs() - 1;
+
+                let indexes: Box<dyn Iterator<Item = usize>> = if rank > 0 {
+                    Box::new(0..list.len())
+                } else {
+                    Box::new((0..list.len()).rev())
+                };
+                for idx in indexes.take(maxlen) {
+                    if list[idx] != element {
+                        continue;
+                    }
+                    if skip > 0 {
+                        skip -= 1;
+                        continue;
+                    }
+                    matches.push(Value::Integer(idx as i64));
+                    if matches.len() == limit {
+                        break;
+                    }
+                }
+            }
+
+            Ok(match count {
+                Some(_) => Value::Array(matches),
+                None => matches.pop().unwrap_or(Value::Null),
+            })
+        }
+        ListCommand::Move {
+            source,
+            destination,
+            from,
+            to,
+        } => Ok(move_element(keyspace, &source, &destination, from, to)?
+            .map_or(Value::Null, Value::BulkString)),
+        ListCommand::MPop { keys, end, count } => mpop(keyspace, &keys, end, count),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::StringData;
+    use crate::test_util::{bulks, error, run};
+
+    #[test]
+    fn test_list_push_pop() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(run(&mut keyspace, &["LPUSHX", "l", "a"]), Value::Integer(0));
+        assert!(keyspace.is_empty());
+
+        assert_eq!(
+            run(&mut keyspace, &["LPUSH", "l", "b", "a"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["RPUSH", "l", "c", "d"]),
+            Value::Integer(4)
+        );
+        assert_eq!(run(&mut keyspace, &["RPUSHX", "l", "e"]), Value::Integer(5));
+        assert_eq!(
+            run(&mut keyspace, &["LRANGE", "l", "0", "-1"]),
+            bulks(&["a", "b", "c", "d", "e"])
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["LPOP", "l"]),
+            Value::BulkString(Bytes::from("a"))
+        );
+        assert_eq!(run(&mut keyspace, &["RPOP", "l", "2"]), bulks(&["e", "d"]));
+        assert_eq!(run(&mut keyspace, &["LPOP", "l", "0"]), bulks(&[]));
+        assert_eq!(run(&mut keyspace, &["LPOP", "l", "5"]), bulks(&["b", "c"]));
+        assert!(keyspace.is_empty());
+
+        assert_eq!(run(&mut keyspace, &["LPOP", "l"]), Value::Null);
+        assert_eq!(run(&mut keyspace, &["LPOP", "l", "1"]), Value::NullArray);
+        assert_eq!(
+            run(&mut keyspace, &["LPOP", "l", "-1"]),
+            error("ERR value is out of range, must be positive")
+        );
+    }
+
+    #[test]
+    fn test_list_index_access() {
+        let mut keyspace = Keyspace::new();
+        run(&mut keyspace, &["RPUSH", "l", "a", "b", "c", "d"]);
+
+        assert_eq!(run(&mut keyspace, &["LLEN", "l"]), Value::Integer(4));
+        assert_eq!(run(&mut keyspace, &["LLEN", "missing"]), Value::Integer(0));
+        assert_eq!(
+            run(&mut keyspace, &["LRANGE", "l", "-3", "1"]),
+            bulks(&["b"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LRANGE", "l", "2", "100"]),
+            bulks(&["c", "d"])
+        );
+        assert_eq!(run(&mut keyspace, &["LRANGE", "l", "3", "1"]), bulks(&[]));
+        assert_eq!(
+            run(&mut keyspace, &["LINDEX", "l", "-1"]),
+            Value::BulkString(Bytes::from("d"))
+        );
+        assert_eq!(run(&mut keyspace, &["LINDEX", "l", "4"]), Value::Null);
+
+        assert_eq!(
+            run(&mut keyspace, &["LSET", "l", "-2", "x"]),
+            Value::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LSET", "l", "9", "x"]),
+            error("ERR index out of range")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LSET", "missing", "0", "x"]),
+            error("ERR no such key")
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["LINSERT", "l", "BEFORE", "x", "y"]),
+            Value::Integer(5)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LINSERT", "l", "after", "d", "z"]),
+            Value::Integer(6)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LINSERT", "l", "AFTER", "nope", "z"]),
+            Value::Integer(-1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LINSERT", "l", "MIDDLE", "d", "z"]),
+            error("ERR syntax error")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LRANGE", "l", "0", "-1"]),
+            bulks(&["a", "b", "y", "x", "d", "z"])
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["LTRIM", "l", "1", "-2"]),
+            Value::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LRANGE", "l", "0", "-1"]),
+            bulks(&["b", "y", "x", "d"])
+        );
+        run(&mut keyspace, &["LTRIM", "l", "5", "10"]);
+        assert!(keyspace.is_empty());
+    }
+
+    #[test]
+    fn test_list_rem_and_pos() {
+        let mut keyspace = Keyspace::new();
+        run(&mut keyspace, &["RPUSH", "l", "a", "b", "a", "c", "a", "b"]);
+
+        assert_eq!(run(&mut keyspace, &["LPOS", "l", "a"]), Value::Integer(0));
+        assert_eq!(
+            run(&mut keyspace, &["LPOS", "l", "a", "RANK", "2"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LPOS", "l", "a", "RANK", "-1"]),
+            Value::Integer(4)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LPOS", "l", "a", "COUNT", "0"]),
+            Value::Array(vec![
+                Value::Integer(0),
+                Value::Integer(2),
+                Value::Integer(4)
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["LPOS", "l", "a", "COUNT", "0", "MAXLEN", "3"]
+            ),
+            Value::Array(vec![Value::Integer(0), Value::Integer(2)])
+        );
+        assert_eq!(run(&mut keyspace, &["LPOS", "l", "z"]), Value::Null);
+        assert_eq!(
+            run(&mut keyspace, &["LPOS", "l", "z", "COUNT", "1"]),
+            Value::Array(vec![])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LPOS", "l", "a", "RANK", "0"]),
+            error("ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the end of the list")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LPOS", "l", "a", "COUNT", "-1"]),
+            error("ERR COUNT can't be negative")
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["LREM", "l", "-1", "a"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LREM", "l", "1", "b"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LRANGE", "l", "0", "-1"]),
+            bulks(&["a", "a", "c", "b"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LREM", "l", "0", "a"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LREM", "l", "0", "c"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LREM", "l", "0", "b"]),
+            Value::Integer(1)
+        );
+        assert!(keyspace.is_empty());
+    }
+
+    #[test]
+    fn test_list_move_and_mpop() {
+        let mut keyspace = Keyspace::new();
+        run(&mut keyspace, &["RPUSH", "src", "a", "b", "c"]);
+
+        assert_eq!(
+            run(&mut keyspace, &["LMOVE", "src", "src", "LEFT", "RIGHT"]),
+            Value::BulkString(Bytes::from("a"))
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LMOVE", "src", "dst", "RIGHT", "LEFT"]),
+            Value::BulkString(Bytes::from("a"))
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LRANGE", "src", "0", "-1"]),
+            bulks(&["b", "c"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LRANGE", "dst", "0", "-1"]),
+            bulks(&["a"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LMOVE", "missing", "dst", "LEFT", "LEFT"]),
+            Value::Null
+        );
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["LMPOP", "2", "missing", "src", "RIGHT", "COUNT", "5"]
+            ),
+            Value::Array(vec![
+                Value::BulkString(Bytes::from("src")),
+                bulks(&["c", "b"])
+            ])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LMPOP", "1", "src", "LEFT"]),
+            Value::NullArray
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LMPOP", "0", "src", "LEFT"]),
+            error("ERR numkeys should be greater than 0")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LMPOP", "3", "a", "b", "LEFT"]),
+            error("ERR syntax error")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LMPOP", "1", "a", "LEFT", "COUNT", "0"]),
+            error("ERR count should be greater than 0")
+        );
+    }
+
+    #[test]
+    fn test_list_wrong_type() {
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(
+            Bytes::from("s"),
+            Data::String(StringData::new(Bytes::from("v"))),
+        );
+        run(&mut keyspace, &["RPUSH", "l", "a"]);
+
+        let wrong_type = RusdisError::WrongType.to_reply();
+        assert_eq!(run(&mut keyspace, &["LPUSH", "s", "a"]), wrong_type);
+        assert_eq!(run(&mut keyspace, &["LLEN", "s"]), wrong_type);
+        assert_eq!(
+            run(&mut keyspace, &["LMOVE", "l", "s", "LEFT", "LEFT"]),
+            wrong_type
+        );
+        assert_eq!(run(&mut keyspace, &["LLEN", "l"]), Value::Integer(1));
+        assert_eq!(
+            run(&mut keyspace, &["LMPOP", "2", "s", "l", "LEFT"]),
+            wrong_type
+        );
+    }
+}
@@ -1,92 +1,122 @@
+mod bitmap;
+mod blocking;
 mod cli_parser;
 mod command_parser;
+mod command_table;
 mod data;
+mod encoder;
 mod error;
+mod geo;
+mod hash;
+mod hyperloglog;
+mod list;
 mod parser;
 mod rdb_file_reader;
+mod set;
+mod sorted_set;
+mod stream;
+mod string;
+#[cfg(test)]
+mod test_util;
 mod utils;
+mod zset;
 
+use crate::bitmap::execute_bitmap_command;
+use crate::blocking::{try_serve, BlockedClients};
 use crate::cli_parser::Args;
-use crate::command_parser::{parse_command, Command, ReplconfSubcommand};
-use crate::data::{Admin, Database, ReplicaRole, ReplicationInfo, StringData};
+use crate::command_parser::{parse_command, to_argv, Command, ReplconfSubcommand};
+use crate::command_table::{
+    command_docs, command_help, command_info, find_command, get_keys, lookup_command, CommandFlag,
+    CommandSpec, COMMAND_TABLE,
+};
+use crate::data::{get_live, Admin, Data, Keyspace, ReplicaRole, ReplicationInfo, StringData};
+use crate::encoder::{encode, encode_to_bytes};
 use crate::error::RusdisError;
-use crate::parser::{parse, parse_multi_array, ParserError, Value};
+use crate::geo::execute_geo_command;
+use crate::hash::{execute_hash_command, FieldExpiry};
+use crate::hyperloglog::execute_hll_command;
+use crate::list::execute_list_command;
+use crate::parser::{Decoder, ParserError, Value};
 use crate::rdb_file_reader::read_rdb;
-use crate::utils::generate_resp;
+use crate::set::execute_set_command;
+use crate::stream::execute_stream_command;
+use crate::string::execute_string_command;
+use crate::utils::{bulk_string_array, generate_resp, unix_millis};
+use crate::zset::execute_zset_command;
+use bytes::{Bytes, BytesMut};
 use clap::Parser;
-use command_parser::{ConfigGetOption, ConfigSubcommand, InfoSection};
+use command_parser::{CommandSubcommand, ConfigGetOption, ConfigSubcommand, InfoSection};
 use lazy_static::lazy_static;
-use regex::Regex;
-use std::collections::{HashMap, VecDeque};
+use regex::bytes::Regex;
 use std::fs::File;
 use std::io::prelude::*;
-use std::iter::Peekable;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
-use tokio::net::tcp::{ReadHalf, WriteHalf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::broadcast::{channel, Sender};
 use tokio::sync::{Mutex, RwLock};
 use tokio::task;
 
 lazy_static! {
-    //static ref START_WITH_SPECIAL: Regex = Regex::new(r#"^([\+-:$\*_#,=\(!%`>~])"#).unwrap();
-    //static ref ARRAY_STRUCT: Regex = Regex::new(r#"^*"#).unwrap();
-    //static ref BULK_STRING_STRUCT: Regex = Regex::new(r#"^$"#).unwrap();
     static ref SLAVES_COUNT: RwLock<usize> = RwLock::new(0);
-    static ref BROADCAST_CHANNEL: Sender<String> = channel(100).0;
+    static ref BROADCAST_CHANNEL: Sender<Bytes> = channel(100).0;
     static ref ARGS: RwLock<Args> = RwLock::new(Args::new());
     static ref ADMIN: Arc<Mutex<Admin>> = Arc::new(Mutex::new(Admin::new(vec![])));
-    static ref DIR: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
-    static ref DBFILENAME: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
     static ref REPLICATION_INFO: Arc<RwLock<ReplicationInfo>> = Arc::new(RwLock::new(ReplicationInfo::new()));
+    /// Only locked while the keyspace lock is held, and never across an await.
+    static ref BLOCKED_CLIENTS: std::sync::Mutex<BlockedClients> = std::sync::Mutex::new(BlockedClients::default());
+}
+
+const SERVER_VERSION: &str = "7.2.0";
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// State a client negotiates for its own connection.
+struct ClientState {
+    id: u64,
+    protocol: u8,
+    name: Option<Bytes>,
+}
+
+impl ClientState {
+    fn new() -> Self {
+        ClientState {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            protocol: 2,
+            name: None,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), RusdisError> {
-    let mut args = Args::parse();
-    //if args.port.is_none() {
-    //    args.port = Some("6379".to_string())
-    //}
+    let args = Args::parse();
     let mut args_writer = ARGS.write().await;
     *args_writer = args;
     drop(args_writer);
-    //tokio::join!(
-    //    async {
-    //        let mut dir_handle = DIR.lock().await;
-    //        *dir_handle = args.dir.clone();
-    //    },
-    //    async {
-    //        let mut dbfilename_handle = DBFILENAME.lock().await;
-    //        *dbfilename_handle = args.dbfilename.clone();
-    //    },
-    //);
-
-    match ARGS.read().await.replicaof.clone() {
-        Some(s) => {
-            let mut replication_info_handle = REPLICATION_INFO.write().await;
-            replication_info_handle.change_role(ReplicaRole::Slave);
-            drop(replication_info_handle);
-
-            let split_idx = s.find(" ");
-            if split_idx.is_none() {
-                eprintln!("Invalid --replicaif parameters");
-                return Ok(());
-            }
 
-            let (host, port) = s.split_at(split_idx.unwrap());
-            let master_stream = TcpStream::connect(format!("{}:{}", host, port.trim())).await;
+    if let Some(s) = ARGS.read().await.replicaof.clone() {
+        let mut replication_info_handle = REPLICATION_INFO.write().await;
+        replication_info_handle.change_role(ReplicaRole::Slave);
+        drop(replication_info_handle);
 
-            if master_stream.is_err() {
-                eprintln!("Master is offline");
-                return Ok(());
-            }
+        let split_idx = s.find(" ");
+        if split_idx.is_none() {
+            eprintln!("Invalid --replicaif parameters");
+            return Ok(());
+        }
+
+        let (host, port) = s.split_at(split_idx.unwrap());
+        let master_stream = TcpStream::connect(format!("{}:{}", host, port.trim())).await;
 
-            let master_stream = master_stream.unwrap();
-            connect_master(master_stream).await?;
+        if master_stream.is_err() {
+            eprintln!("Master is offline");
+            return Ok(());
         }
-        None => {}
+
+        let master_stream = master_stream.unwrap();
+        connect_master(master_stream).await?;
     }
 
     let (dir_option, dbfilename_option) = tokio::join!(
@@ -100,37 +130,45 @@ async fn main() -> Result<(), RusdisError> {
         },
     );
 
-    match (dir_option, dbfilename_option) {
-        (Some(dir), Some(dbfilename)) => {
-            let f = File::open(dir + "/" + &dbfilename);
-            if let Ok(f) = f {
-                let mut reader = std::io::BufReader::new(f);
-                let mut buf = vec![];
-                let _length = reader.read_to_end(&mut buf)?;
-                let iter = buf.into_iter().peekable();
-
-                let res = read_rdb(iter);
-                match res {
-                    Ok(rdb_file) => {
-                        dbg!(&rdb_file.datasets);
-                        let new_admin = Admin::new(rdb_file.datasets);
-
-                        let mut admin_handle = ADMIN.lock().await;
-                        *admin_handle = new_admin;
-                    }
-                    Err(e) => {
-                        dbg!(e);
-                    }
+    if let (Some(dir), Some(dbfilename)) = (dir_option, dbfilename_option) {
+        let f = File::open(dir + "/" + &dbfilename);
+        if let Ok(f) = f {
+            let mut reader = std::io::BufReader::new(f);
+            let mut buf = vec![];
+            let _length = reader.read_to_end(&mut buf)?;
+            let iter = buf.into_iter().peekable();
+
+            let res = read_rdb(iter);
+            match res {
+                Ok(rdb_file) => {
+                    let new_admin = Admin::new(rdb_file.datasets);
+
+                    let mut admin_handle = ADMIN.lock().await;
+                    *admin_handle = new_admin;
+                }
+                Err(e) => {
+                    eprintln!("Failed to read the RDB file: {:?}", e);
                 }
             }
         }
-        _ => {}
     }
 
     let admin_handle = ADMIN.lock().await;
-    let string_data_arc = admin_handle.get_string_data_map();
+    let _keyspace_arc = admin_handle.get_keyspace();
     drop(admin_handle);
 
+    // Active expiry of hash fields
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(100));
+        loop {
+            interval.tick().await;
+            let admin_handle = ADMIN.lock().await;
+            let keyspace_arc = admin_handle.get_keyspace();
+            drop(admin_handle);
+            data::remove_expired_fields(&mut *keyspace_arc.lock().await);
+        }
+    });
+
     // You can use print statements as follows for debugging, they'll be visible when running tests.
     println!("Logs from your program will appear here!");
 
@@ -147,9 +185,11 @@ async fn main() -> Result<(), RusdisError> {
 
         match res {
             Ok((stream, addr)) => {
-                println!("accepted new connection: {}", addr.to_string());
+                println!("accepted new connection: {}", addr);
                 task::spawn(async move {
-                    handle_commands(stream, addr.to_string()).await;
+                    if let Err(e) = handle_commands(stream, addr.to_string()).await {
+                        eprintln!("connection {} closed with error: {}", addr, e);
+                    }
                 });
             }
             Err(e) => {
@@ -159,15 +199,29 @@ async fn main() -> Result<(), RusdisError> {
     }
 }
 
+/// Reads from the stream until the decoder yields a complete frame.
+async fn read_frame(stream: &mut TcpStream, decoder: &mut Decoder) -> Result<Value, RusdisError> {
+    loop {
+        if let Some(value) = decoder.decode()? {
+            return Ok(value);
+        }
+
+        if stream.read_buf(decoder.buffer_mut()).await? == 0 {
+            return Err(RusdisError::MasterConnectionError {
+                msg: "Connection closed by master".to_string(),
+            });
+        }
+    }
+}
+
 async fn connect_master(mut stream: TcpStream) -> Result<(), RusdisError> {
-    let (reader, mut writer) = stream.split();
-    let mut reader = BufReader::new(reader);
+    let mut decoder = Decoder::new();
 
     // PING Master
-    writer.write_all(b"*1\r\n$4\r\nPING\r\n").await?;
-    let buf = Vec::from(reader.fill_buf().await?);
-    reader.consume(buf.len());
-    let response = parse(String::from_utf8_lossy(&buf).to_string())?;
+    stream
+        .write_all(&encode_to_bytes(&bulk_string_array(&[b"PING"]), 2))
+        .await?;
+    let response = read_frame(&mut stream, &mut decoder).await?;
     if let Value::SimpleString(r) = response {
         let r = r.to_uppercase();
         if r.as_str() != "PONG" {
@@ -183,19 +237,11 @@ async fn connect_master(mut stream: TcpStream) -> Result<(), RusdisError> {
     };
 
     // REPLCONF listening-port
-    writer
-        .write_all(
-            format!(
-                "*3\r\n$8\r\nREPLCONF\r\n$14\r\nlistening-port\r\n${}\r\n{}\r\n",
-                port.len(),
-                port
-            )
-            .as_bytes(),
-        )
+    let replconf_port = bulk_string_array(&[b"REPLCONF", b"listening-port", port.as_bytes()]);
+    stream
+        .write_all(&encode_to_bytes(&replconf_port, 2))
         .await?;
-    let buf = Vec::from(reader.fill_buf().await?);
-    reader.consume(buf.len());
-    let response = parse(String::from_utf8_lossy(&buf).to_string())?;
+    let response = read_frame(&mut stream, &mut decoder).await?;
     if let Value::SimpleString(r) = response {
         let r = r.to_uppercase();
         if r.as_str() != "OK" {
@@ -206,13 +252,11 @@ async fn connect_master(mut stream: TcpStream) -> Result<(), RusdisError> {
     }
 
     // REPLCONF capa
-    writer
-        .write_all(b"*3\r\n$8\r\nREPLCONF\r\n$4\r\ncapa\r\n$6\r\npsync2\r\n")
+    let replconf_capa = bulk_string_array(&[b"REPLCONF", b"capa", b"psync2"]);
+    stream
+        .write_all(&encode_to_bytes(&replconf_capa, 2))
         .await?;
-
-    let buf = Vec::from(reader.fill_buf().await?);
-    reader.consume(buf.len());
-    let response = parse(String::from_utf8_lossy(&buf).to_string())?;
+    let response = read_frame(&mut stream, &mut decoder).await?;
     if let Value::SimpleString(r) = response {
         let r = r.to_uppercase();
         if r.as_str() != "OK" {
@@ -222,81 +266,72 @@ async fn connect_master(mut stream: TcpStream) -> Result<(), RusdisError> {
         }
     }
 
-    writer
-        .write_all(b"*3\r\n$5\r\nPSYNC\r\n$1\r\n?\r\n$2\r\n-1\r\n")
+    stream
+        .write_all(&encode_to_bytes(
+            &bulk_string_array(&[b"PSYNC", b"?", b"-1"]),
+            2,
+        ))
         .await?;
-    let mut buf = vec![];
-    reader.read_until('\n' as u8, &mut buf).await?;
-
-    let psync_response = parse(String::from_utf8_lossy(&buf).to_string())?;
-    dbg!(&psync_response);
-
-    buf.clear();
-    reader.read_until('\n' as u8, &mut buf).await?;
-    let idx = buf.iter().position(|&x| x == '\r' as u8);
-    if idx.is_none() || buf.len() < 4 || buf[0] != ('$' as u8) {
-        return Err(RusdisError::MasterConnectionError {
-            msg: "Invalid RDB file format".to_string(),
-        });
+    let psync_response = read_frame(&mut stream, &mut decoder).await?;
+    if let Value::SimpleString(r) = psync_response {
+        // +FULLRESYNC <replid> <offset>
+        if let Some(replid) = r.split(' ').nth(1) {
+            REPLICATION_INFO
+                .write()
+                .await
+                .set_master_replid(replid.to_string());
+        }
     }
 
-    let idx = idx.unwrap();
-    let length_str = String::from_utf8_lossy(&buf[1..idx]).to_string();
-    let length = length_str.parse::<usize>()?;
+    let rdb_payload = loop {
+        if let Some(payload) = decoder.decode_rdb_payload()? {
+            break payload;
+        }
 
-    let mut buf = vec![0_u8; length];
-    reader.read_exact(&mut buf).await?;
-    let rdb_file = read_rdb(buf.into_iter().peekable());
+        if stream.read_buf(decoder.buffer_mut()).await? == 0 {
+            return Err(RusdisError::MasterConnectionError {
+                msg: "Invalid RDB file format".to_string(),
+            });
+        }
+    };
+    let _rdb_file = read_rdb(Vec::from(rdb_payload).into_iter().peekable());
 
     tokio::spawn(async move {
-        let (reader, mut writer) = stream.split();
-        let mut reader = BufReader::new(reader);
+        let mut replies = BytesMut::new();
         loop {
-            if let Ok(buf) = reader.fill_buf().await {
-                let buf = Vec::from(buf);
-                let buf_length = buf.len();
-                if buf.len() == 0 {
-                    break;
-                }
-                reader.consume(buf.len());
-                let commands = String::from_utf8_lossy(&buf).to_string();
-                dbg!(&commands);
-                let parse_res = parse_multi_array(commands);
-
-                if let Ok(array_vec) = parse_res {
-                    for value in array_vec.into_iter() {
-                        if let Value::Array(bulk_string_vec) = value {
-                            let parse_res = parse_command(bulk_string_vec);
-                            dbg!(&parse_res);
-                            if let Ok(cmd) = parse_res {
-                                let reply_msg =
-                                    execute_multi_commands(vec![cmd.clone()], false).await;
-
-                                dbg!(&reply_msg);
-
-                                if let Command::Replconf(ReplconfSubcommand::Getack(_)) = cmd {
-                                    writer.write_all(reply_msg.as_bytes()).await;
-                                } else if let Command::Set { key, value, px } = cmd {
-                                    let mut buf_length = 4;
-                                    buf_length += key.len() + key.len().to_string().len() + 5;
-                                    buf_length += value.len() + value.len().to_string().len() + 5;
-                                    match px {
-                                        Some(millis) => {
-                                            buf_length += 8
-                                                + millis.to_string().len()
-                                                + millis.to_string().len().to_string().len()
-                                                + 5;
-                                        }
-                                        None => {}
-                                    }
-
-                                    let mut replication_info_write = REPLICATION_INFO.write().await;
-                                    replication_info_write.increment_offset(buf_length as u64);
-                                }
-                            }
+            while let Ok(Some((value, frame_length))) = decoder.decode_frame() {
+                if let Value::Array(bulk_string_vec) = value {
+                    // clients blocked on the replica are served by the writes
+                    // it receives
+                    let mut ready_keys = to_argv(bulk_string_vec.clone())
+                        .and_then(|argv| get_keys(&argv))
+                        .unwrap_or_default();
+                    if let Ok(cmd) = parse_command(bulk_string_vec) {
+                        let reply = execute_command(cmd.clone()).await;
+                        serve_ready_keys(&mut ready_keys).await;
+
+                        if let Command::Replconf(ReplconfSubcommand::Getack(_)) = cmd {
+                            encode(&reply, &mut replies, 2);
                         }
                     }
                 }
+
+                // The offset covers every command processed so far, excluding the current
+                // GETACK, so it is only advanced once the command has been handled.
+                let mut replication_info_write = REPLICATION_INFO.write().await;
+                replication_info_write.increment_offset(frame_length as u64);
+            }
+
+            if !replies.is_empty() {
+                if stream.write_all(&replies).await.is_err() {
+                    break;
+                }
+                replies.clear();
+            }
+
+            match stream.read_buf(decoder.buffer_mut()).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
             }
         }
     });
@@ -304,133 +339,269 @@ async fn connect_master(mut stream: TcpStream) -> Result<(), RusdisError> {
     Ok(())
 }
 
-async fn handle_commands(mut stream: TcpStream, addr: String) -> Result<(), RusdisError> {
-    let (reader, mut writer) = stream.split();
-    let mut reader = BufReader::new(reader);
+async fn handle_commands(mut stream: TcpStream, _addr: String) -> Result<(), RusdisError> {
+    let mut decoder = Decoder::with_limits(ARGS.read().await.decoder_limits());
+    let mut client = ClientState::new();
     let mut is_multi = false;
+    // Set when a command inside MULTI was rejected, so EXEC discards the transaction.
+    let mut is_multi_aborted = false;
     let mut queue = vec![];
+    // Keys written by the current command or transaction, whose blocked
+    // clients are served once it finishes.
+    let mut ready_keys = vec![];
+    // Replies to every command decoded from one read are collected here and
+    // written back together, so a pipelined batch costs a single socket write.
+    let mut replies = BytesMut::new();
 
     loop {
-        let mut buf = Vec::from(reader.fill_buf().await?);
-        if buf.len() == 0 {
+        if stream.read_buf(decoder.buffer_mut()).await? == 0 {
             break;
         }
-        reader.consume(buf.len());
-        let commands = String::from_utf8_lossy(&buf).to_string();
-        println!("{}", commands);
-        // wrong protocol: need to disconnect
-        let value = parse(commands)?;
 
-        match value {
-            Value::Array(cmds) => {
-                let cmd = parse_command(cmds);
-                if cmd.is_err() {
+        loop {
+            let value = match decoder.decode_command() {
+                Ok(Some(value)) => value,
+                Ok(None) => break,
+                Err(e) => {
+                    // wrong protocol: reply to what was already processed and
+                    // report the error, then disconnect
+                    let e = RusdisError::from(e);
+                    encode(&e.to_reply(), &mut replies, client.protocol);
+                    stream.write_all(&replies).await?;
+                    return Err(e);
+                }
+            };
+
+            if let Value::Array(cmds) = value {
+                // an empty multibulk is skipped without a reply
+                if cmds.is_empty() {
                     continue;
                 }
 
-                let cmd = cmd.unwrap();
+                let call = match Call::parse(cmds).await {
+                    Ok(call) => call,
+                    Err(e) => {
+                        is_multi_aborted |= is_multi;
+                        encode(&e.to_reply(), &mut replies, client.protocol);
+                        continue;
+                    }
+                };
+
+                if is_multi && call.spec.has_flag(CommandFlag::NoMulti) {
+                    is_multi_aborted = true;
+                    encode(
+                        &RusdisError::Generic {
+                            msg: "Command not allowed inside a transaction".to_string(),
+                        }
+                        .to_reply(),
+                        &mut replies,
+                        client.protocol,
+                    );
+                    continue;
+                }
 
-                match cmd {
-                    Command::Psync { id, offset } => match id {
-                        Some(master_id) => {}
+                let reply = match call.cmd {
+                    Command::Psync { id, offset: _ } => match id {
+                        Some(_master_id) => continue,
                         None => {
                             let replica_info_read = REPLICATION_INFO.read().await;
                             let (master_id, cur_offset) = (
-                                replica_info_read.get_master_replid(),
+                                replica_info_read.get_master_replid().clone(),
                                 replica_info_read.get_master_repl_offset(),
                             );
-
-                            writer
-                                .write_all(
-                                    format!("+FULLRESYNC {} {}\r\n", master_id, cur_offset)
-                                        .as_bytes(),
-                                )
-                                .await;
-
                             drop(replica_info_read);
 
+                            encode(
+                                &Value::SimpleString(format!(
+                                    "FULLRESYNC {} {}",
+                                    master_id, cur_offset
+                                )),
+                                &mut replies,
+                                client.protocol,
+                            );
+                            stream.write_all(&replies).await?;
+                            replies.clear();
+
                             // Transfer RDB file
                             let file = "524544495330303131fa0972656469732d76657205372e322e30fa0a72656469732d62697473c040fa056374696d65c26d08bc65fa08757365642d6d656dc2b0c41000fa08616f662d62617365c000fff06e3bfec0ff5aa2";
 
                             let data_slice = file.chars().collect::<Vec<char>>();
                             let data_vec = data_slice
                                 .chunks(2)
-                                .into_iter()
                                 .map(|a| {
-                                    let x = a.into_iter().collect::<String>();
+                                    let x = a.iter().collect::<String>();
                                     u8::from_str_radix(&x, 16).unwrap()
                                 })
                                 .collect::<Vec<u8>>();
+                            // The payload is framed like a bulk string without the trailing CRLF.
                             let prefix = format!("${}\r\n", data_vec.len());
-                            writer
+                            stream
                                 .write_all(
                                     [prefix.as_bytes(), data_vec.as_slice()].concat().as_slice(),
                                 )
-                                .await;
+                                .await?;
                             build_replica_pipe(stream);
                             return Ok(());
                         }
                     },
-                    // Maybe can move this part to execuate_multi_commands
-                    //Command::Replconf(subcommand) => match subcommand {
-                    //    ReplconfSubcommand::ListeningPort(port) => {
-                    //        // Store the replica's port
-                    //        writer.write_all(b"+OK\r\n").await;
-                    //    }
-                    //    ReplconfSubcommand::Capa(options) => {
-                    //        // Configure capa?
-                    //        writer.write_all(b"+OK\r\n").await;
-                    //    }
-                    //    _ => {}
-                    //},
+                    Command::Hello {
+                        protover,
+                        auth,
+                        setname,
+                    } => execute_hello(&mut client, protover, auth, setname).await,
                     Command::Multi => {
-                        is_multi = true;
-                        writer.write_all(b"+OK\r\n").await;
+                        if is_multi {
+                            RusdisError::Generic {
+                                msg: "MULTI calls can not be nested".to_string(),
+                            }
+                            .to_reply()
+                        } else {
+                            is_multi = true;
+                            Value::SimpleString("OK".to_string())
+                        }
                     }
                     Command::Exec => {
                         if !is_multi {
-                            writer.write_all(b"-ERR EXEC without MULTI\r\n").await;
-                            continue;
+                            RusdisError::Generic {
+                                msg: "EXEC without MULTI".to_string(),
+                            }
+                            .to_reply()
+                        } else {
+                            is_multi = false;
+                            let queue = std::mem::take(&mut queue);
+                            if std::mem::take(&mut is_multi_aborted) {
+                                RusdisError::ExecAbort.to_reply()
+                            } else {
+                                execute_multi_commands(queue, &mut ready_keys).await
+                            }
                         }
-
-                        let reply_string = execute_multi_commands(queue, true).await;
-                        queue = vec![];
-                        is_multi = false;
-                        writer.write_all(reply_string.as_bytes()).await;
                     }
                     Command::Discard => {
                         if !is_multi {
-                            writer.write_all(b"-ERR DISCARD without MULTI\r\n").await;
-                            continue;
+                            RusdisError::Generic {
+                                msg: "DISCARD without MULTI".to_string(),
+                            }
+                            .to_reply()
+                        } else {
+                            queue.clear();
+                            is_multi = false;
+                            is_multi_aborted = false;
+                            Value::SimpleString("OK".to_string())
                         }
-
-                        queue.clear();
-                        is_multi = false;
-                        writer.write_all(b"+OK\r\n").await;
                     }
-                    other => {
+                    Command::Block { .. } if !is_multi => {
+                        // replies to the commands before it go out before blocking
+                        stream.write_all(&replies).await?;
+                        replies.clear();
+
+                        match execute_blocking(call, &mut stream, &mut decoder, &mut ready_keys)
+                            .await?
+                        {
+                            Some(reply) => reply,
+                            None => return Ok(()),
+                        }
+                    }
+                    _ => {
                         if !is_multi {
-                            //execute_commands(other, &mut writer).await;
-                            let reply_string = execute_multi_commands(vec![other], false).await;
-                            writer.write_all(reply_string.as_bytes()).await;
+                            call.execute(&mut ready_keys).await
                         } else {
-                            queue.push(other);
-                            writer.write_all(b"+QUEUED\r\n").await;
+                            queue.push(call);
+                            Value::SimpleString("QUEUED".to_string())
                         }
                     }
-                }
+                };
+                serve_ready_keys(&mut ready_keys).await;
+
+                encode(&reply, &mut replies, client.protocol);
             }
-            _ => {}
+        }
+
+        if !replies.is_empty() {
+            stream.write_all(&replies).await?;
+            replies.clear();
         }
     }
     Ok(())
 }
 
+/// Switches the connection's protocol and replies with the server's handshake map.
+async fn execute_hello(
+    client: &mut ClientState,
+    protover: Option<i64>,
+    auth: Option<(Bytes, Bytes)>,
+    setname: Option<Bytes>,
+) -> Value {
+    if let Some(protover) = protover {
+        if protover != 2 && protover != 3 {
+            return RusdisError::NoProto.to_reply();
+        }
+    }
+
+    // No password is configured, so the default user accepts any password.
+    if let Some((username, _password)) = auth {
+        if username.as_ref() != b"default" {
+            return RusdisError::WrongPass.to_reply();
+        }
+    }
+
+    if let Some(name) = setname.as_ref() {
+        if name.iter().any(|b| !(b'!'..=b'~').contains(b)) {
+            return RusdisError::Generic {
+                msg: "Client names cannot contain spaces, newlines or special characters."
+                    .to_string(),
+            }
+            .to_reply();
+        }
+    }
+
+    if let Some(protover) = protover {
+        client.protocol = protover as u8;
+    }
+    if setname.is_some() {
+        client.name = setname;
+    }
+
+    let role = match REPLICATION_INFO.read().await.get_role() {
+        ReplicaRole::Master => "master",
+        ReplicaRole::Slave => "replica",
+    };
+
+    Value::Map(vec![
+        (
+            Value::BulkString(Bytes::from("server")),
+            Value::BulkString(Bytes::from("redis")),
+        ),
+        (
+            Value::BulkString(Bytes::from("version")),
+            Value::BulkString(Bytes::from(SERVER_VERSION)),
+        ),
+        (
+            Value::BulkString(Bytes::from("proto")),
+            Value::Integer(client.protocol as i64),
+        ),
+        (
+            Value::BulkString(Bytes::from("id")),
+            Value::Integer(client.id as i64),
+        ),
+        (
+            Value::BulkString(Bytes::from("mode")),
+            Value::BulkString(Bytes::from("standalone")),
+        ),
+        (
+            Value::BulkString(Bytes::from("role")),
+            Value::BulkString(Bytes::from(role)),
+        ),
+        (
+            Value::BulkString(Bytes::from("modules")),
+            Value::Array(vec![]),
+        ),
+    ])
+}
+
 fn build_replica_pipe(mut stream: TcpStream) {
     let mut rx = BROADCAST_CHANNEL.subscribe();
     tokio::spawn(async move {
-        let (reader, mut writer) = stream.split();
-        let mut reader = BufReader::new(reader);
+        let mut decoder = Decoder::new();
         let mut slaves_count_write = SLAVES_COUNT.write().await;
         *slaves_count_write += 1;
         drop(slaves_count_write);
@@ -438,9 +609,17 @@ fn build_replica_pipe(mut stream: TcpStream) {
             tokio::select! {
                 result = rx.recv() => {
                     if let Ok(cmd) = result {
-                        dbg!(&cmd);
-
-                        writer.write_all(cmd.as_bytes()).await;
+                        if stream.write_all(&cmd).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                // Replies from the replica (REPLCONF ACK) are drained so the socket does
+                // not back up, and a closed connection ends the pipe.
+                result = stream.read_buf(decoder.buffer_mut()) => {
+                    match result {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => while let Ok(Some(_)) = decoder.decode() {},
                     }
                 }
             }
@@ -451,385 +630,454 @@ fn build_replica_pipe(mut stream: TcpStream) {
     });
 }
 
-async fn execute_multi_commands(commands: Vec<Command>, is_multi: bool) -> String {
-    let mut res = String::new();
-    if is_multi {
-        res = format!("*{}\r\n", commands.len());
+/// A command a client sent, with the arguments it was sent with.
+struct Call {
+    spec: &'static CommandSpec,
+    argv: Vec<Bytes>,
+    cmd: Command,
+}
+
+impl Call {
+    /// Looks the command up in the command table and parses its arguments.
+    ///
+    /// Write commands are refused on replicas, whose data only comes from the master.
+    async fn parse(cmds: Vec<Value>) -> Result<Call, RusdisError> {
+        let argv = to_argv(cmds)?;
+        let spec = lookup_command(&argv)?;
+        let cmd = spec.parse(&argv)?;
+
+        if spec.has_flag(CommandFlag::Write)
+            && REPLICATION_INFO.read().await.get_role() == ReplicaRole::Slave
+        {
+            return Err(RusdisError::ReadOnly);
+        }
+
+        Ok(Call { spec, argv, cmd })
     }
 
-    for cmd in commands.into_iter() {
-        match cmd {
-            Command::Ping => {
-                res += "+PONG\r\n";
-            }
-            Command::Echo(words) => {
-                res += format!("+{}\r\n", words).as_str();
+    /// Runs the command and, if it wrote to the dataset, propagates its
+    /// effect to the replicas before another command can run. The keys it
+    /// wrote to are added to `ready_keys`.
+    ///
+    /// Blocking calls do not block here, and are propagated as the
+    /// non-blocking commands they ran.
+    async fn execute(self, ready_keys: &mut Vec<Bytes>) -> Value {
+        if !self.spec.has_flag(CommandFlag::Write) {
+            return execute_command(self.cmd).await;
+        }
+
+        let admin_handle = ADMIN.lock().await;
+        let keyspace_arc = admin_handle.get_keyspace();
+        drop(admin_handle);
+        let mut keyspace_handle = keyspace_arc.lock().await;
+
+        let reply = execute_keyspace_command(&mut keyspace_handle, self.cmd);
+        let propagated = keyspace_handle
+            .take_propagate_as()
+            .unwrap_or_else(|| vec![self.argv.clone()]);
+        if !matches!(reply, Value::SimpleError(_)) && !propagated.is_empty() {
+            for argv in propagated.iter() {
+                propagate(argv).await;
             }
-            Command::Config(subcommand) => match subcommand {
-                ConfigSubcommand::Get(option) => match option {
-                    ConfigGetOption::Dir => {
-                        let dir_ref = &ARGS.read().await.dir;
-                        match dir_ref {
-                            Some(dir) => {
-                                res += format!("*2\r\n$3\r\ndir\r\n${}\r\n{}\r\n", dir.len(), dir)
-                                    .as_str();
-                            }
-                            None => {
-                                res += "*2\r\n$3\r\ndir\r\n$-1\r\n";
-                            }
-                        }
-                    }
-                    ConfigGetOption::DbFilename => {
-                        let dbfilename_ref = &ARGS.read().await.dbfilename;
-                        match dbfilename_ref {
-                            Some(dbfilename) => {
-                                res += format!(
-                                    "*2\r\n$10\r\ndbfilename\r\n${}\r\n{}\r\n",
-                                    dbfilename.len(),
-                                    dbfilename
-                                )
-                                .as_str();
-                            }
-                            None => {
-                                res += "*2\r\n$10\r\ndbfilename\r\n$-1\r\n";
-                            }
-                        }
-                    }
-                },
-            },
-            Command::Set { key, value, px } => {
-                // Todo: implement "active" or "passive" way to delete data
-                let mut expiration = None;
-
-                if let Some(mills) = px {
-                    let now = SystemTime::now();
-                    let fu = now.checked_add(Duration::from_millis(mills as u64));
-                    if fu.is_none() {
-                        res += "-ERR Instant Addtion Error";
-                        continue;
-                    }
+            ready_keys.extend(self.spec.keys(&self.argv));
+        }
 
-                    expiration = fu;
-                }
+        reply
+    }
+}
 
-                let admin_handle = ADMIN.lock().await;
-                let string_data_arc = admin_handle.get_string_data_map();
-                drop(admin_handle);
-                let mut string_data_handle = string_data_arc.lock().await;
-                let _ = string_data_handle
-                    .insert(key.clone(), StringData::new(value.clone(), expiration));
-                res += "+OK\r\n";
-
-                if REPLICATION_INFO.read().await.get_role() == ReplicaRole::Master
-                    && *SLAVES_COUNT.read().await != 0
-                {
-                    let _ = BROADCAST_CHANNEL.send(generate_resp(Command::Set { key, value, px }));
+async fn propagate(argv: &[Bytes]) {
+    if REPLICATION_INFO.read().await.get_role() == ReplicaRole::Master
+        && *SLAVES_COUNT.read().await != 0
+    {
+        let _ = BROADCAST_CHANNEL.send(generate_resp(argv));
+    }
+}
+
+/// Serves the clients blocked on `ready_keys`, and propagates the commands
+/// run for them.
+async fn serve_ready_keys(ready_keys: &mut Vec<Bytes>) {
+    if ready_keys.is_empty() {
+        return;
+    }
+
+    let admin_handle = ADMIN.lock().await;
+    let keyspace_arc = admin_handle.get_keyspace();
+    drop(admin_handle);
+
+    let mut keyspace_handle = keyspace_arc.lock().await;
+    let served = BLOCKED_CLIENTS
+        .lock()
+        .unwrap()
+        .serve(&mut keyspace_handle, std::mem::take(ready_keys));
+    for argv in served.iter() {
+        propagate(argv).await;
+    }
+}
+
+/// Runs a blocking command outside MULTI, waiting for one of its keys to get
+/// data when none has any. Commands the client sends meanwhile stay buffered
+/// in `decoder`, up to the query buffer limit. Returns `None` if the client
+/// disconnects while blocked.
+async fn execute_blocking(
+    call: Call,
+    stream: &mut TcpStream,
+    decoder: &mut Decoder,
+    ready_keys: &mut Vec<Bytes>,
+) -> Result<Option<Value>, RusdisError> {
+    let written_keys = call.spec.keys(&call.argv);
+    let (keys, mut op, timeout) = match call.cmd {
+        Command::Block { keys, op, timeout } => (keys, op, timeout),
+        cmd => return Ok(Some(execute_command(cmd).await)),
+    };
+    let timeout_reply = op.timeout_reply();
+
+    let admin_handle = ADMIN.lock().await;
+    let keyspace_arc = admin_handle.get_keyspace();
+    drop(admin_handle);
+
+    // checking the keys and blocking happen under one keyspace lock, so no
+    // push can slip in between
+    let blocked = {
+        let mut keyspace_handle = keyspace_arc.lock().await;
+        op.prepare(&mut keyspace_handle);
+        match try_serve(&mut keyspace_handle, &keys, &op) {
+            Ok(Some((reply, argv))) => {
+                if let Some(argv) = argv {
+                    propagate(&argv).await;
+                    ready_keys.extend(written_keys);
                 }
+                Err(reply)
             }
-            Command::Get(key) => {
-                let admin_handle = ADMIN.lock().await;
-                let string_data_arc = admin_handle.get_string_data_map();
-                drop(admin_handle);
-                let mut string_data_handle = string_data_arc.lock().await;
-
-                match string_data_handle.get(&key) {
-                    Some(data) => {
-                        if data.is_expired() {
-                            let _ = string_data_handle.remove(&key);
-                            res += "$-1\r\n";
-                        } else {
-                            res += format!("${}\r\n{}\r\n", data.get_data().len(), data.get_data())
-                                .as_str();
-                        }
-                    }
-                    None => {
-                        res += "$-1\r\n";
-                    }
+            Ok(None) => Ok(BLOCKED_CLIENTS.lock().unwrap().block(keys, op)),
+            Err(e) => Err(e.to_reply()),
+        }
+    };
+    let (id, mut rx) = match blocked {
+        Ok(blocked) => blocked,
+        Err(reply) => return Ok(Some(reply)),
+    };
+
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
+
+    loop {
+        tokio::select! {
+            reply = &mut rx => return Ok(Some(reply.unwrap_or(timeout_reply))),
+            _ = &mut deadline => {
+                let unblocked = {
+                    let _keyspace_handle = keyspace_arc.lock().await;
+                    BLOCKED_CLIENTS.lock().unwrap().unblock(id)
+                };
+                if unblocked {
+                    return Ok(Some(timeout_reply));
                 }
+                // served right before the deadline
+                return Ok(Some(rx.await.unwrap_or(timeout_reply)));
             }
-            Command::Keys(pattern_string) => {
-                let pattern = Regex::new(&pattern_string);
-                if pattern.is_err() {
-                    res += "-ERR Invalid Regex Format";
-                    continue;
+            res = stream.read_buf(decoder.buffer_mut()) => {
+                let over_limit = decoder.check_buffer_limit();
+                if matches!(res, Ok(0) | Err(_)) || over_limit.is_err() {
+                    let _keyspace_handle = keyspace_arc.lock().await;
+                    BLOCKED_CLIENTS.lock().unwrap().unblock(id);
+                    over_limit?;
+                    return Ok(None);
                 }
-                let pattern = pattern.unwrap();
+            }
+        }
+    }
+}
 
-                let admin_handle = ADMIN.lock().await;
-                let string_data_arc = admin_handle.get_string_data_map();
-                drop(admin_handle);
-                let string_data_handle = string_data_arc.lock().await;
-                let mut res_vec = vec![];
+/// Runs the commands queued by MULTI and collects their replies for EXEC.
+async fn execute_multi_commands(calls: Vec<Call>, ready_keys: &mut Vec<Bytes>) -> Value {
+    let mut res = vec![];
+    for call in calls.into_iter() {
+        res.push(call.execute(ready_keys).await);
+    }
 
-                for key in string_data_handle.keys() {
-                    if pattern.is_match(key) {
-                        res_vec.push(key);
-                    }
-                }
+    Value::Array(res)
+}
 
-                let mut reply_string = format!("*{}\r\n", res_vec.len());
-                for matched_key in res_vec.into_iter() {
-                    reply_string +=
-                        format!("${}\r\n{}\r\n", matched_key.len(), matched_key).as_str();
+async fn execute_command(cmd: Command) -> Value {
+    match cmd {
+        Command::Ping => Value::SimpleString("PONG".to_string()),
+        Command::Echo(words) => Value::BulkString(words),
+        Command::Config(subcommand) => match subcommand {
+            ConfigSubcommand::Get(options) => {
+                let args = ARGS.read().await;
+                let mut pairs = vec![];
+                for option in options.into_iter() {
+                    let (name, value) = match option {
+                        ConfigGetOption::Dir => ("dir", args.dir.clone()),
+                        ConfigGetOption::DbFilename => ("dbfilename", args.dbfilename.clone()),
+                        ConfigGetOption::ProtoMaxBulkLen => (
+                            "proto-max-bulk-len",
+                            Some(args.proto_max_bulk_len.to_string()),
+                        ),
+                        ConfigGetOption::ProtoMaxMultibulkLen => (
+                            "proto-max-multibulk-len",
+                            Some(args.proto_max_multibulk_len.to_string()),
+                        ),
+                        ConfigGetOption::ClientQueryBufferLimit => (
+                            "client-query-buffer-limit",
+                            Some(args.client_query_buffer_limit.to_string()),
+                        ),
+                    };
+
+                    pairs.push((
+                        Value::BulkString(Bytes::from(name)),
+                        match value {
+                            Some(value) => Value::BulkString(Bytes::from(value)),
+                            None => Value::Null,
+                        },
+                    ));
                 }
 
-                res += reply_string.as_str();
+                Value::Map(pairs)
             }
-            Command::Incr(key) => {
-                let admin_handle = ADMIN.lock().await;
-                let string_data_arc = admin_handle.get_string_data_map();
-                drop(admin_handle);
-
-                let mut string_data_handle = string_data_arc.lock().await;
-
-                let data = string_data_handle
-                    .entry(key)
-                    .or_insert(StringData::new("0".to_string(), None));
-                let num_str = data.get_data();
-                match num_str.parse::<i64>() {
-                    Ok(mut num) => {
-                        if num < i64::MAX {
-                            num += 1;
-                        }
+        },
+        Command::Info(sections) => {
+            let mut string = String::new();
+            for section in sections.into_iter() {
+                match section {
+                    InfoSection::Replication => {
+                        let replication_info_handle = REPLICATION_INFO.read().await;
+                        let role = format!(
+                            "role:{}\n",
+                            match replication_info_handle.get_role() {
+                                ReplicaRole::Master => "master",
+                                ReplicaRole::Slave => "slave",
+                            }
+                        );
+                        string += role.as_str();
 
-                        data.set_data(format!("{}", num));
-                        res += format!(":{}\r\n", num).as_str();
-                    }
-                    Err(_) => {
-                        res += "-ERR value is not an integer or out of range\r\n";
+                        let master_replid = format!(
+                            "master_replid:{}\n",
+                            replication_info_handle.get_master_replid()
+                        );
+                        string += master_replid.as_str();
+
+                        let master_repl_offset = format!(
+                            "master_repl_offset:{}\n",
+                            replication_info_handle.get_master_repl_offset()
+                        );
+
+                        string += master_repl_offset.as_str();
                     }
                 }
             }
-            Command::Info(sections) => {
-                let mut string = String::new();
-                let mut cnt = 0;
-                for section in sections.into_iter() {
-                    match section {
-                        InfoSection::Replication => {
-                            let replication_info_handle = REPLICATION_INFO.read().await;
-                            let role = format!(
-                                "role:{}\n",
-                                match replication_info_handle.get_role() {
-                                    ReplicaRole::Master => "master",
-                                    ReplicaRole::Slave => "slave",
-                                }
-                            );
-                            string += role.as_str();
-
-                            cnt += role.len();
+            string.pop();
 
-                            let master_replid = format!(
-                                "master_replid:{}\n",
-                                replication_info_handle.get_master_replid()
-                            );
-                            string += master_replid.as_str();
-                            cnt += master_replid.len();
-
-                            let master_repl_offset = format!(
-                                "master_repl_offset:{}\n",
-                                replication_info_handle.get_master_repl_offset()
-                            );
+            Value::VerbatimString("txt".to_string(), Bytes::from(string))
+        }
+        Command::Replconf(subcommand) => match subcommand {
+            ReplconfSubcommand::ListeningPort(_port) => {
+                // Store the replica's port
+                Value::SimpleString("OK".to_string())
+            }
+            ReplconfSubcommand::Capa(_options) => {
+                // Configure capa?
+                Value::SimpleString("OK".to_string())
+            }
+            ReplconfSubcommand::Getack(_) => {
+                let offset = REPLICATION_INFO
+                    .read()
+                    .await
+                    .get_master_repl_offset()
+                    .to_string();
+
+                bulk_string_array(&[b"REPLCONF", b"ACK", offset.as_bytes()])
+            }
+            ReplconfSubcommand::Ack(_) => Value::SimpleString("OK".to_string()),
+        },
+        Command::Introspect(subcommand) => match subcommand {
+            CommandSubcommand::All => {
+                Value::Array(COMMAND_TABLE.iter().map(command_info).collect())
+            }
+            CommandSubcommand::Count => Value::Integer(COMMAND_TABLE.len() as i64),
+            CommandSubcommand::Info(names) => {
+                if names.is_empty() {
+                    Value::Array(COMMAND_TABLE.iter().map(command_info).collect())
+                } else {
+                    Value::Array(
+                        names
+                            .iter()
+                            .map(|name| find_command(name).map_or(Value::Null, command_info))
+                            .collect(),
+                    )
+                }
+            }
+            CommandSubcommand::Docs(names) => {
+                let specs: Vec<&CommandSpec> = if names.is_empty() {
+                    COMMAND_TABLE.iter().collect()
+                } else {
+                    names.iter().filter_map(|name| find_command(name)).collect()
+                };
+
+                Value::Map(
+                    specs
+                        .into_iter()
+                        .map(|spec| {
+                            (
+                                Value::BulkString(Bytes::from(spec.name)),
+                                command_docs(spec),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            CommandSubcommand::GetKeys(argv) => match get_keys(&argv) {
+                Ok(keys) => Value::Array(keys.into_iter().map(Value::BulkString).collect()),
+                Err(e) => e.to_reply(),
+            },
+        },
+        Command::Help(container) => find_command(&container).map_or(Value::Null, command_help),
+        // commands on the dataset
+        cmd => {
+            let admin_handle = ADMIN.lock().await;
+            let keyspace_arc = admin_handle.get_keyspace();
+            drop(admin_handle);
+            let mut keyspace_handle = keyspace_arc.lock().await;
+
+            let reply = execute_keyspace_command(&mut keyspace_handle, cmd);
+            // only calls from clients propagate
+            keyspace_handle.take_propagate_as();
+
+            reply
+        }
+    }
+}
 
-                            string += master_repl_offset.as_str();
-                            cnt += master_repl_offset.len();
-                        }
+/// Runs a command on the dataset, with the keyspace locked.
+fn execute_keyspace_command(keyspace: &mut Keyspace, cmd: Command) -> Value {
+    match cmd {
+        Command::Set {
+            key,
+            value,
+            expiry,
+            keep_ttl,
+        } => {
+            // Todo: implement "active" or "passive" way to delete data
+            let mut expiration = None;
+
+            if let Some(expiry) = expiry {
+                let fu = match expiry {
+                    FieldExpiry::In(mills) => {
+                        SystemTime::now().checked_add(Duration::from_millis(mills))
                     }
+                    FieldExpiry::At(mills) => UNIX_EPOCH.checked_add(Duration::from_millis(mills)),
+                };
+                if fu.is_none() {
+                    return RusdisError::InstantAdditionError.to_reply();
                 }
-                string.pop();
-                cnt -= 1;
-                string += "\r\n";
-                string = format!("${}\r\n", cnt) + string.as_str();
-                res += string.as_str();
+
+                expiration = fu;
+            } else if keep_ttl && get_live(keyspace, &key).is_some() {
+                expiration = keyspace.get_expiration(&key);
+            }
+
+            keyspace.insert(key.clone(), Data::String(StringData::new(value.clone())));
+            keyspace.set_expiration(&key, expiration);
+            if let (Some(expiration), Some(_)) = (expiration, expiry) {
+                // a relative expiration would be counted from when a replica applies it
+                keyspace.propagate_as(vec![vec![
+                    Bytes::from("SET"),
+                    key,
+                    value,
+                    Bytes::from("PXAT"),
+                    unix_millis(expiration),
+                ]]);
+            }
+
+            Value::SimpleString("OK".to_string())
+        }
+        Command::Get(key) => match get_live(keyspace, &key) {
+            Some(Data::String(data)) => Value::BulkString(data.get_data().clone()),
+            Some(_) => RusdisError::WrongType.to_reply(),
+            None => Value::Null,
+        },
+        Command::String(cmd) => {
+            execute_string_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply())
+        }
+        Command::Type(key) => Value::SimpleString(
+            get_live(keyspace, &key)
+                .map_or("none", |data| data.type_name())
+                .to_string(),
+        ),
+        Command::Keys(pattern_string) => {
+            let pattern = match Regex::new(&pattern_string) {
+                Ok(pattern) => pattern,
+                Err(e) => return RusdisError::from(e).to_reply(),
+            };
+
+            Value::Array(
+                keyspace
+                    .iter()
+                    .filter(|(key, _)| !keyspace.is_expired(key) && pattern.is_match(key))
+                    .map(|(key, _)| Value::BulkString(key.clone()))
+                    .collect(),
+            )
+        }
+        Command::Incr(key) => {
+            if get_live(keyspace, &key).is_none() {
+                keyspace.insert(key.clone(), Data::String(StringData::new(Bytes::from("0"))));
             }
-            Command::Replconf(subcommand) => match subcommand {
-                ReplconfSubcommand::ListeningPort(port) => {
-                    // Store the replica's port
-                    res += "+OK\r\n";
+            let data = match keyspace.get_mut(&key) {
+                Some(Data::String(data)) => data,
+                _ => return RusdisError::WrongType.to_reply(),
+            };
+            let num_str = String::from_utf8_lossy(data.get_data());
+            match num_str.parse::<i64>() {
+                Ok(mut num) => {
+                    num = num.saturating_add(1);
+
+                    data.set_data(Bytes::from(num.to_string()));
+                    Value::Integer(num)
                 }
-                ReplconfSubcommand::Capa(options) => {
-                    // Configure capa?
-                    res += "+OK\r\n";
+                Err(_) => RusdisError::NotInteger.to_reply(),
+            }
+        }
+        Command::List(cmd) => execute_list_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply()),
+        Command::Hash(cmd) => execute_hash_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply()),
+        Command::SetType(cmd) => {
+            execute_set_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply())
+        }
+        Command::ZSet(cmd) => execute_zset_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply()),
+        Command::Bitmap(cmd) => {
+            execute_bitmap_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply())
+        }
+        Command::HyperLogLog(cmd) => {
+            execute_hll_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply())
+        }
+        Command::Geo(cmd) => execute_geo_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply()),
+        Command::Stream(cmd) => {
+            execute_stream_command(keyspace, cmd).unwrap_or_else(|e| e.to_reply())
+        }
+        // inside MULTI, or from the master link, blocking commands do not block
+        Command::Block { keys, mut op, .. } => {
+            op.prepare(keyspace);
+
+            match try_serve(keyspace, &keys, &op) {
+                Ok(Some((reply, argv))) => {
+                    keyspace.propagate_as(argv.into_iter().collect());
+                    reply
                 }
-                ReplconfSubcommand::Getack(_) => {
-                    let offset = REPLICATION_INFO
-                        .read()
-                        .await
-                        .get_master_repl_offset()
-                        .to_string();
-                    dbg!(&offset);
-                    let ack_msg = format!(
-                        "*3\r\n$8\r\nREPLCONF\r\n$3\r\nACK\r\n${}\r\n{}\r\n",
-                        offset.len(),
-                        offset
-                    );
-
-                    res += ack_msg.as_str();
+                Ok(None) => {
+                    keyspace.propagate_as(vec![]);
+                    op.timeout_reply()
                 }
-                _ => {}
-            },
-            _ => {
-                res += "-ERR not supported command";
+                Err(e) => e.to_reply(),
             }
         }
+        // connection-level commands queued inside MULTI
+        _ => RusdisError::Generic {
+            msg: "Command not allowed inside a transaction".to_string(),
+        }
+        .to_reply(),
     }
-
-    res
 }
-
-// Review it and delete
-//async fn execute_commands(command: Command, writer: &mut WriteHalf<'_>) -> Result<(), RusdisError> {
-//    dbg!(&command);
-//
-//    match command {
-//        Command::Ping => {
-//            writer.write_all(b"+PONG\r\n").await?;
-//        }
-//        Command::Echo(words) => {
-//            writer
-//                .write_all(format!("+{}\r\n", words).as_bytes())
-//                .await?;
-//        }
-//        Command::Config(subcommand) => match subcommand {
-//            ConfigSubcommand::Get(option) => match option {
-//                ConfigGetOption::Dir => {
-//                    let dir_handle = DIR.lock().await;
-//                    let dir_ref = dir_handle.as_ref();
-//                    match dir_ref {
-//                        Some(dir) => {
-//                            writer
-//                                .write_all(
-//                                    format!("*2\r\n$3\r\ndir\r\n${}\r\n{}\r\n", dir.len(), dir)
-//                                        .as_bytes(),
-//                                )
-//                                .await?;
-//                        }
-//                        None => {
-//                            writer.write_all(b"*2\r\n$3\r\ndir\r\n$-1\r\n").await?;
-//                        }
-//                    }
-//                }
-//                ConfigGetOption::DbFilename => {
-//                    let dbfilename_handle = DBFILENAME.lock().await;
-//                    let dbfilename_ref = dbfilename_handle.as_ref();
-//                    match dbfilename_ref {
-//                        Some(dbfilename) => {
-//                            writer
-//                                .write_all(
-//                                    format!(
-//                                        "*2\r\n$10\r\ndbfilename\r\n${}\r\n{}\r\n",
-//                                        dbfilename.len(),
-//                                        dbfilename
-//                                    )
-//                                    .as_bytes(),
-//                                )
-//                                .await?;
-//                        }
-//                        None => {
-//                            writer
-//                                .write_all(b"*2\r\n$10\r\ndbfilename\r\n$-1\r\n")
-//                                .await?;
-//                        }
-//                    }
-//                }
-//            },
-//        },
-//        Command::Set { key, value, px } => {
-//            // Todo: implement "active" or "passive" way to delete data
-//            let mut expiration = None;
-//
-//            if let Some(mills) = px {
-//                let now = SystemTime::now();
-//                let fu = now.checked_add(Duration::from_millis(mills as u64));
-//                if fu.is_none() {
-//                    return Err(RusdisError::InstantAdditionError);
-//                }
-//
-//                expiration = fu;
-//            }
-//
-//            let admin_handle = ADMIN.lock().await;
-//            let string_data_arc = admin_handle.get_string_data_map();
-//            drop(admin_handle);
-//            let mut string_data_handle = string_data_arc.lock().await;
-//            let _ = string_data_handle.insert(key, StringData::new(value, expiration));
-//            writer.write_all(b"+OK\r\n").await?;
-//        }
-//        Command::Get(key) => {
-//            let admin_handle = ADMIN.lock().await;
-//            let string_data_arc = admin_handle.get_string_data_map();
-//            drop(admin_handle);
-//            let mut string_data_handle = string_data_arc.lock().await;
-//
-//            match string_data_handle.get(&key) {
-//                Some(data) => {
-//                    if data.is_expired() {
-//                        let _ = string_data_handle.remove(&key);
-//                        writer.write_all(b"$-1\r\n").await?;
-//                    } else {
-//                        writer
-//                            .write_all(
-//                                format!("${}\r\n{}\r\n", data.get_data().len(), data.get_data())
-//                                    .as_bytes(),
-//                            )
-//                            .await?;
-//                    }
-//                }
-//                None => {
-//                    writer.write_all(b"$-1\r\n").await?;
-//                }
-//            }
-//        }
-//        Command::Keys(pattern_string) => {
-//            let pattern = Regex::new(&pattern_string)?;
-//            let admin_handle = ADMIN.lock().await;
-//            let string_data_arc = admin_handle.get_string_data_map();
-//            drop(admin_handle);
-//            let string_data_handle = string_data_arc.lock().await;
-//            let mut res = vec![];
-//
-//            for key in string_data_handle.keys() {
-//                if pattern.is_match(key) {
-//                    res.push(key);
-//                }
-//            }
-//
-//            let mut reply_string = format!("*{}\r\n", res.len());
-//            for matched_key in res.into_iter() {
-//                reply_string += format!("${}\r\n{}\r\n", matched_key.len(), matched_key).as_str();
-//            }
-//
-//            writer.write_all(reply_string.as_bytes()).await?;
-//        }
-//        Command::Incr(key) => {
-//            let admin_handle = ADMIN.lock().await;
-//            let string_data_arc = admin_handle.get_string_data_map();
-//            drop(admin_handle);
-//
-//            let mut string_data_handle = string_data_arc.lock().await;
-//            //let a = string_data_handle.get_mut(&key);
-//
-//            let data = string_data_handle
-//                .entry(key)
-//                .or_insert(StringData::new("0".to_string(), None));
-//            let num_str = data.get_data();
-//            match num_str.parse::<i64>() {
-//                Ok(mut num) => {
-//                    if num < i64::MAX {
-//                        num += 1;
-//                    }
-//
-//                    data.set_data(format!("{}", num));
-//                    writer.write_all(format!(":{}\r\n", num).as_bytes()).await?;
-//                }
-//                Err(_) => {
-//                    writer
-//                        .write_all(b"-ERR value is not an integer or out of range\r\n")
-//                        .await?;
-//                }
-//            }
-//        }
-//        _ => {}
-//    }
-//
-//    Ok(())
-//}
@@ -1,12 +1,27 @@
+use bytes::{Buf, Bytes, BytesMut};
 use thiserror::Error;
 
+const CRLF: &[u8] = b"\r\n";
+/// Longest inline command or header line accepted, Redis' `PROTO_INLINE_MAX_SIZE`.
+const MAX_LINE_LEN: usize = 64 * 1024;
+/// Deepest nesting of aggregates the decoder follows before giving up.
+const MAX_NESTING_DEPTH: usize = 128;
+
+pub const DEFAULT_MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+pub const DEFAULT_MAX_MULTIBULK_LEN: usize = 1024 * 1024;
+pub const DEFAULT_MAX_BUFFER_LEN: usize = 1024 * 1024 * 1024;
+
+/// A parsed item and the position right after it, or `None` when the buffer
+/// ends before the item does.
+type Parsed<T> = Result<Option<(T, usize)>, ParserError>;
+
 #[derive(Error, Debug)]
 pub enum ParserError {
-    #[error("Invalid protocol: {msg:?}")]
+    #[error("Protocol error: {msg}")]
     InvalidProtocol { msg: String },
     #[error("IO errors")]
     IO(#[from] std::io::Error),
-    #[error("Parse int errors")]
+    #[error("Protocol error: invalid number")]
     ParseIntError(#[from] std::num::ParseIntError),
 }
 
@@ -14,357 +29,1040 @@ pub enum ParserError {
 pub enum Value {
     SimpleString(String),
     SimpleError(String),
-    Integer(i32),
-    BulkString(String),
+    Integer(i64),
+    BulkString(Bytes),
     Array(Vec<Value>),
     Null,
+    /// Null reply of array-shaped commands, `*-1` in RESP2. Never produced by the decoder.
+    NullArray,
+    /// Pairs such as field and value, sent as two element arrays in RESP3 and
+    /// flattened in RESP2. Never produced by the decoder.
+    Pairs(Vec<(Value, Value)>),
+    /// A map in RESP3, sent as an array of key and value arrays in RESP2, as
+    /// XREAD replies. Never produced by the decoder.
+    ArrayMap(Vec<(Value, Value)>),
+    // RESP3 types
+    Map(Vec<(Value, Value)>),
+    Set(Vec<Value>),
+    Double(f64),
+    Boolean(bool),
+    BigNumber(String),
+    /// Text with a three character format hint such as `txt` or `mkd`.
+    VerbatimString(String, Bytes),
+    /// Out-of-band attributes attached to the value that follows them.
+    Attribute(Vec<(Value, Value)>, Box<Value>),
+    Push(Vec<Value>),
 }
 
-pub fn parse_multi_array(protocol: String) -> Result<Vec<Value>, ParserError> {
-    let mut first = true;
-    let mut res_vec = vec![];
-    let mut pre = 0;
-    let char_vec = protocol.chars().collect::<Vec<char>>();
+/// Bounds on what a peer can make the decoder accept.
+#[derive(Debug, Clone, Copy)]
+pub struct DecoderLimits {
+    /// Longest bulk string, Redis' `proto-max-bulk-len`.
+    pub max_bulk_len: usize,
+    /// Most elements in a single array, set, push or map.
+    pub max_multibulk_len: usize,
+    /// Most bytes buffered while a frame is incomplete, Redis' `client-query-buffer-limit`.
+    pub max_buffer_len: usize,
+}
 
-    for idx in 0..char_vec.len() {
-        if idx == char_vec.len() - 1 {
-            let slice = &char_vec[pre..];
-            res_vec.push(parse(slice.into_iter().collect::<String>())?)
+impl Default for DecoderLimits {
+    fn default() -> Self {
+        DecoderLimits {
+            max_bulk_len: DEFAULT_MAX_BULK_LEN,
+            max_multibulk_len: DEFAULT_MAX_MULTIBULK_LEN,
+            max_buffer_len: DEFAULT_MAX_BUFFER_LEN,
         }
+    }
+}
 
-        if char_vec[idx] == '*' && char_vec[idx + 1].is_numeric() {
-            if first {
-                first = false;
-            } else {
-                let slice = &char_vec[pre..idx];
-                res_vec.push(parse(slice.into_iter().collect::<String>())?);
-                pre = idx;
+/// Stateful RESP decoder.
+///
+/// Bytes read from the socket are appended to an internal buffer and frames
+/// are taken off the front of it one at a time. Elements of an aggregate are
+/// taken off as soon as they are complete and kept until the aggregate is, so
+/// a frame arriving over many reads is only parsed once.
+///
+/// Input breaking the protocol or the configured limits is reported as an
+/// error; the connection is not usable afterwards.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buf: BytesMut,
+    limits: DecoderLimits,
+    /// Aggregates of the frame being decoded whose elements are still
+    /// arriving, outermost first.
+    partial: Vec<PartialAggregate>,
+    /// Bytes of the frame being decoded already taken off `buf`.
+    consumed: usize,
+}
+
+/// An aggregate header that has been read, with the elements parsed so far.
+#[derive(Debug)]
+struct PartialAggregate {
+    symbol: u8,
+    remaining: usize,
+    values: Vec<Value>,
+}
+
+impl PartialAggregate {
+    fn finish(self) -> Value {
+        let mut values = self.values;
+        match self.symbol {
+            b'~' => Value::Set(values),
+            b'>' => Value::Push(values),
+            b'%' => Value::Map(into_pairs(values)),
+            b'|' => {
+                let value = values.pop().unwrap();
+                Value::Attribute(into_pairs(values), Box::new(value))
             }
+            _ => Value::Array(values),
         }
     }
-    dbg!(&res_vec);
+}
 
-    Ok(res_vec)
+/// What a single header, and the payload following it, decodes to.
+enum Item {
+    Value(Value),
+    /// An aggregate with this symbol and number of elements to come.
+    Aggregate(u8, usize),
 }
 
-pub fn parse(protocol: String) -> Result<Value, ParserError> {
-    let symbol = protocol.chars().nth(0);
+impl Decoder {
+    pub fn new() -> Self {
+        Decoder::with_limits(DecoderLimits::default())
+    }
 
-    if symbol.is_none() {
-        return Err(ParserError::InvalidProtocol {
-            msg: "No leading symbol".to_string(),
-        });
+    pub fn with_limits(limits: DecoderLimits) -> Self {
+        Decoder {
+            buf: BytesMut::with_capacity(4096),
+            limits,
+            partial: vec![],
+            consumed: 0,
+        }
     }
 
-    let symbol = symbol.unwrap();
+    /// Buffer to read socket data into, e.g. with `AsyncReadExt::read_buf`.
+    pub fn buffer_mut(&mut self) -> &mut BytesMut {
+        &mut self.buf
+    }
 
-    match symbol {
-        '+' => Ok(parse_simple_string(protocol)?),
-        ':' => Ok(parse_integer(protocol)?),
-        '$' => Ok(parse_bulk_string(protocol)?),
-        '*' => {
-            //Ok(Value::Integer(3))
-            Ok(parse_array(protocol)?)
+    /// Fails when more is buffered than the query buffer limit allows, e.g.
+    /// while a blocked client keeps sending commands.
+    pub fn check_buffer_limit(&self) -> Result<(), ParserError> {
+        if self.consumed + self.buf.len() > self.limits.max_buffer_len {
+            return Err(ParserError::InvalidProtocol {
+                msg: "query buffer limit exceeded".to_string(),
+            });
         }
-        _ => Err(ParserError::InvalidProtocol {
-            msg: "Invalid symbol".to_string(),
-        }),
+
+        Ok(())
     }
-}
 
-fn parse_simple_string(s: String) -> Result<Value, ParserError> {
-    let crlf = s.find("\r\n");
-    if crlf.is_none() {
-        return Err(ParserError::InvalidProtocol {
-            msg: "No CRLF".to_string(),
-        });
+    /// Returns the next complete frame, or `None` if more bytes are needed.
+    pub fn decode(&mut self) -> Result<Option<Value>, ParserError> {
+        Ok(self.decode_frame()?.map(|(value, _)| value))
+    }
+
+    /// Same as `decode`, but also reports how many bytes the frame took on the wire.
+    pub fn decode_frame(&mut self) -> Parsed<Value> {
+        loop {
+            if self.partial.len() > MAX_NESTING_DEPTH {
+                return Err(ParserError::InvalidProtocol {
+                    msg: "too deep nesting".to_string(),
+                });
+            }
+
+            let mut value = match parse_item(&self.buf, &self.limits)? {
+                Some((item, end)) => {
+                    self.buf.advance(end);
+                    self.consumed += end;
+                    match item {
+                        Item::Value(value) => value,
+                        Item::Aggregate(symbol, remaining) => {
+                            let aggregate = PartialAggregate {
+                                symbol,
+                                remaining,
+                                values: vec![],
+                            };
+                            if remaining > 0 {
+                                self.partial.push(aggregate);
+                                continue;
+                            }
+                            aggregate.finish()
+                        }
+                    }
+                }
+                None => return self.check_buffer_limit().map(|_| None),
+            };
+
+            // Hand the value to its aggregate, completing as many as it finishes.
+            loop {
+                let Some(aggregate) = self.partial.last_mut() else {
+                    let length = std::mem::take(&mut self.consumed);
+                    return Ok(Some((value, length)));
+                };
+
+                aggregate.values.push(value);
+                aggregate.remaining -= 1;
+                if aggregate.remaining > 0 {
+                    break;
+                }
+                value = self.partial.pop().unwrap().finish();
+            }
+        }
+    }
+
+    /// Returns the next command sent by a client, or `None` if more bytes are needed.
+    ///
+    /// Besides RESP arrays, clients may send inline commands: space separated
+    /// arguments terminated by a newline, as typed into telnet or netcat. Those
+    /// are returned as an array of bulk strings just like a RESP command.
+    pub fn decode_command(&mut self) -> Result<Option<Value>, ParserError> {
+        loop {
+            match self.buf.first() {
+                _ if !self.partial.is_empty() => return self.decode().and_then(check_command),
+                None => return Ok(None),
+                Some(b'*') => return self.decode().and_then(check_command),
+                Some(_) => {}
+            }
+
+            let end = match self.buf.iter().position(|b| *b == b'\n') {
+                Some(end) => end,
+                None if self.buf.len() > MAX_LINE_LEN => {
+                    return Err(ParserError::InvalidProtocol {
+                        msg: "too big inline request".to_string(),
+                    })
+                }
+                None => return Ok(None),
+            };
+            let line = self.buf.split_to(end + 1);
+            let line = line[..end].strip_suffix(b"\r").unwrap_or(&line[..end]);
+
+            let args = split_inline_args(line)?;
+            // Empty lines are ignored, like Redis does.
+            if !args.is_empty() {
+                return Ok(Some(Value::Array(
+                    args.into_iter().map(Value::BulkString).collect(),
+                )));
+            }
+        }
+    }
+
+    /// Decodes the RDB payload a master sends after `+FULLRESYNC`.
+    ///
+    /// It is framed like a bulk string but has no trailing CRLF.
+    pub fn decode_rdb_payload(&mut self) -> Result<Option<Bytes>, ParserError> {
+        if self.buf.is_empty() {
+            return Ok(None);
+        }
+
+        if self.buf[0] != b'$' {
+            return Err(ParserError::InvalidProtocol {
+                msg: "Invalid RDB file format".to_string(),
+            });
+        }
+
+        let (line, next) = match read_line(&self.buf, 1) {
+            Some(res) => res,
+            None => return Ok(None),
+        };
+        let length = parse_number::<usize>(line)?;
+
+        if self.buf.len() < next + length {
+            return Ok(None);
+        }
+
+        self.buf.advance(next);
+        Ok(Some(self.buf.split_to(length).freeze()))
     }
-    Ok(Value::SimpleString(s[1..crlf.unwrap()].to_string()))
 }
 
-fn parse_integer(s: String) -> Result<Value, ParserError> {
-    let crlf = s.find("\r\n");
-    if crlf.is_none() {
-        return Err(ParserError::InvalidProtocol {
-            msg: "No CRLF".to_string(),
-        });
+/// Returns the line starting at `pos` without its CRLF, plus the position right after it.
+fn read_line(buf: &[u8], pos: usize) -> Option<(&[u8], usize)> {
+    buf[pos..]
+        .windows(CRLF.len())
+        .position(|window| window == CRLF)
+        .map(|idx| (&buf[pos..pos + idx], pos + idx + CRLF.len()))
+}
+
+fn parse_number<T>(line: &[u8]) -> Result<T, ParserError>
+where
+    T: std::str::FromStr<Err = std::num::ParseIntError>,
+{
+    let num_str = std::str::from_utf8(line).map_err(|_| ParserError::InvalidProtocol {
+        msg: "Invalid number".to_string(),
+    })?;
+
+    Ok(num_str.parse::<T>()?)
+}
+
+/// Client commands are arrays of bulk strings, anything else is a protocol error.
+fn check_command(value: Option<Value>) -> Result<Option<Value>, ParserError> {
+    if let Some(Value::Array(args)) = value.as_ref() {
+        match args.iter().find(|arg| !matches!(arg, Value::BulkString(_))) {
+            Some(Value::Null) => {
+                return Err(ParserError::InvalidProtocol {
+                    msg: "invalid bulk length".to_string(),
+                })
+            }
+            Some(arg) => {
+                return Err(ParserError::InvalidProtocol {
+                    msg: format!("expected '$', got '{}'", first_byte(arg)),
+                })
+            }
+            None => {}
+        }
     }
 
-    let num_str = s[1..crlf.unwrap()].to_string();
-    let num = num_str.parse::<i32>()?;
+    Ok(value)
+}
 
-    Ok(Value::Integer(num))
+/// The type byte `arg` was sent with.
+fn first_byte(arg: &Value) -> char {
+    match arg {
+        Value::SimpleString(_) => '+',
+        Value::SimpleError(_) => '-',
+        Value::Integer(_) => ':',
+        Value::BulkString(_) | Value::Null => '$',
+        Value::Array(_) | Value::NullArray | Value::Pairs(_) => '*',
+        Value::Map(_) | Value::ArrayMap(_) => '%',
+        Value::Set(_) => '~',
+        Value::Double(_) => ',',
+        Value::Boolean(_) => '#',
+        Value::BigNumber(_) => '(',
+        Value::VerbatimString(_, _) => '=',
+        Value::Attribute(_, _) => '|',
+        Value::Push(_) => '>',
+    }
 }
 
-fn parse_bulk_string(s: String) -> Result<Value, ParserError> {
-    let mut protocol_iter = s.split("\r\n").into_iter();
+/// Splits an inline command into arguments the way `sdssplitargs` does.
+///
+/// Arguments are separated by whitespace and may be quoted. Double quoted
+/// arguments understand `\n`, `\r`, `\t`, `\b`, `\a` and `\xHH` escapes, single
+/// quoted ones only `\'`. A closing quote must be followed by whitespace.
+fn split_inline_args(line: &[u8]) -> Result<Vec<Bytes>, ParserError> {
+    let unbalanced = || ParserError::InvalidProtocol {
+        msg: "unbalanced quotes in request".to_string(),
+    };
+
+    let mut args = vec![];
+    let mut pos = 0;
+    loop {
+        while pos < line.len() && line[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos == line.len() {
+            return Ok(args);
+        }
+
+        let mut arg = vec![];
+        let mut in_double_quotes = false;
+        let mut in_single_quotes = false;
+        loop {
+            let byte = line.get(pos).copied();
+            if in_double_quotes {
+                match byte {
+                    None => return Err(unbalanced()),
+                    Some(b'\\')
+                        if pos + 3 < line.len()
+                            && line[pos + 1] == b'x'
+                            && line[pos + 2].is_ascii_hexdigit()
+                            && line[pos + 3].is_ascii_hexdigit() =>
+                    {
+                        let hex = std::str::from_utf8(&line[pos + 2..pos + 4]).unwrap();
+                        arg.push(u8::from_str_radix(hex, 16).unwrap());
+                        pos += 3;
+                    }
+                    Some(b'\\') if pos + 1 < line.len() => {
+                        pos += 1;
+                        arg.push(match line[pos] {
+                            b'n' => b'\n',
+                            b'r' => b'\r',
+                            b't' => b'\t',
+                            b'b' => 0x08,
+                            b'a' => 0x07,
+                            other => other,
+                        });
+                    }
+                    Some(b'"') => {
+                        // closing quote must be followed by a space or nothing at all
+                        if line.get(pos + 1).is_some_and(|b| !b.is_ascii_whitespace()) {
+                            return Err(unbalanced());
+                        }
+                        pos += 1;
+                        break;
+                    }
+                    Some(byte) => arg.push(byte),
+                }
+            } else if in_single_quotes {
+                match byte {
+                    None => return Err(unbalanced()),
+                    Some(b'\\') if line.get(pos + 1) == Some(&b'\'') => {
+                        arg.push(b'\'');
+                        pos += 1;
+                    }
+                    Some(b'\'') => {
+                        if line.get(pos + 1).is_some_and(|b| !b.is_ascii_whitespace()) {
+                            return Err(unbalanced());
+                        }
+                        pos += 1;
+                        break;
+                    }
+                    Some(byte) => arg.push(byte),
+                }
+            } else {
+                match byte {
+                    None => break,
+                    Some(byte) if byte.is_ascii_whitespace() => break,
+                    Some(b'"') => in_double_quotes = true,
+                    Some(b'\'') => in_single_quotes = true,
+                    Some(byte) => arg.push(byte),
+                }
+            }
+            pos += 1;
+        }
+
+        args.push(Bytes::from(arg));
+    }
+}
 
-    let (first, second) = (protocol_iter.next(), protocol_iter.next());
+/// Parses the header at the start of `buf` and, for anything but an
+/// aggregate, the payload that follows it.
+///
+/// Returns the item and the position right after it, or `None` when the
+/// buffer ends before the item does.
+fn parse_item(buf: &[u8], limits: &DecoderLimits) -> Parsed<Item> {
+    let symbol = match buf.first() {
+        Some(symbol) => *symbol,
+        None => return Ok(None),
+    };
 
-    match (first, second) {
-        (Some(length_str), Some(data)) => {
-            let length_str = length_str[1..].to_string();
-            let length = length_str.parse::<i32>()?;
+    let (line, next) = match read_line(buf, 1) {
+        Some((line, _)) if line.len() > MAX_LINE_LEN => return Err(too_big_line(symbol)),
+        Some(res) => res,
+        None if buf.len() > MAX_LINE_LEN + 1 => return Err(too_big_line(symbol)),
+        None => return Ok(None),
+    };
 
-            if length == -1 {
-                return Ok(Value::Null);
-            } else if length < -1 {
+    let value = match symbol {
+        b'+' => Value::SimpleString(String::from_utf8_lossy(line).to_string()),
+        b'-' => Value::SimpleError(String::from_utf8_lossy(line).to_string()),
+        b':' => Value::Integer(parse_number::<i64>(line)?),
+        b'$' => {
+            return Ok(parse_bulk_string(buf, line, next, limits)?
+                .map(|(value, next)| (Item::Value(value), next)))
+        }
+        b'*' | b'~' | b'>' => match parse_aggregate_length(line, limits)? {
+            Some(length) => return Ok(Some((Item::Aggregate(symbol, length), next))),
+            // the RESP2 null array
+            None if symbol == b'*' => Value::Null,
+            None => return Ok(Some((Item::Aggregate(symbol, 0), next))),
+        },
+        b'%' | b'|' => {
+            let length = parse_number::<usize>(line)?;
+            if length > limits.max_multibulk_len {
                 return Err(ParserError::InvalidProtocol {
-                    msg: "Invalid Bulk String length".to_string(),
+                    msg: "invalid multibulk length".to_string(),
                 });
             }
-
-            Ok(Value::BulkString(data[..length as usize].to_string()))
+            // an attribute is followed by the value it is attached to
+            let elements = if symbol == b'|' {
+                length * 2 + 1
+            } else {
+                length * 2
+            };
+            return Ok(Some((Item::Aggregate(symbol, elements), next)));
         }
-        _ => Err(ParserError::InvalidProtocol {
-            msg: "Invalid Bulk String format".to_string(),
-        }),
+        b'_' => {
+            if !line.is_empty() {
+                return Err(ParserError::InvalidProtocol {
+                    msg: "Invalid Null format".to_string(),
+                });
+            }
+            Value::Null
+        }
+        b'#' => match line {
+            b"t" => Value::Boolean(true),
+            b"f" => Value::Boolean(false),
+            _ => {
+                return Err(ParserError::InvalidProtocol {
+                    msg: "Invalid Boolean format".to_string(),
+                })
+            }
+        },
+        b',' => Value::Double(parse_double(line)?),
+        b'(' => parse_big_number(line)?,
+        b'=' => {
+            return Ok(parse_verbatim_string(buf, line, next, limits)?
+                .map(|(value, next)| (Item::Value(value), next)))
+        }
+        _ => {
+            return Err(ParserError::InvalidProtocol {
+                msg: "Invalid symbol".to_string(),
+            })
+        }
+    };
+
+    Ok(Some((Item::Value(value), next)))
+}
+
+/// Error for a header line that is too long, named after what it holds like Redis does.
+fn too_big_line(symbol: u8) -> ParserError {
+    let what = match symbol {
+        b'*' | b'~' | b'>' | b'%' | b'|' => "mbulk count string",
+        b'$' | b'=' => "bulk count string",
+        _ => "line",
+    };
+
+    ParserError::InvalidProtocol {
+        msg: format!("too big {}", what),
     }
 }
 
-fn parse_array(protocol: String) -> Result<Value, ParserError> {
-    let x = protocol.clone();
-    let x_v = x.split("\r\n").collect::<Vec<&str>>();
-    dbg!(x_v);
-    let mut protocol_iter = protocol.split("\r\n").into_iter();
+/// Reads the payload of a length-prefixed blob (bulk or verbatim string).
+///
+/// The outer `Option` is `None` when the payload has not fully arrived, the
+/// inner one is `None` for the RESP2 null blob `-1`.
+fn parse_blob<'a>(
+    buf: &'a [u8],
+    length_line: &[u8],
+    pos: usize,
+    limits: &DecoderLimits,
+) -> Parsed<Option<&'a [u8]>> {
+    let length = parse_number::<i64>(length_line)?;
 
-    let first = protocol_iter.next();
-    if first.is_none() {
+    if length == -1 {
+        return Ok(Some((None, pos)));
+    } else if length < -1 || length as u64 > limits.max_bulk_len as u64 {
         return Err(ParserError::InvalidProtocol {
-            msg: "Invalid Array format".to_string(),
+            msg: "invalid bulk length".to_string(),
         });
     }
 
-    let length_str = first.unwrap();
-    let length_str = length_str[1..].to_string();
-    let mut left = length_str.parse::<i32>()?;
+    let end = pos + length as usize;
+    if buf.len() < end + CRLF.len() {
+        return Ok(None);
+    }
 
-    // handle -1 case
-    if left == -1 {
-        return Ok(Value::Null);
-    } else if left < -1 {
+    if &buf[end..end + CRLF.len()] != CRLF {
         return Err(ParserError::InvalidProtocol {
-            msg: "Invalid Array length".to_string(),
+            msg: "Invalid Bulk String format".to_string(),
         });
     }
 
-    let mut protocol_vec = vec![];
-    let mut tmp = String::new();
-    let mut qoda = 1;
-
-    while let Some(p) = protocol_iter.next() {
-        if left == 0 {
-            break;
-        }
+    Ok(Some((Some(&buf[pos..end]), end + CRLF.len())))
+}
 
-        qoda += count_qoda(p);
-        tmp += p;
-        tmp += "\r\n";
-        qoda -= 1;
+fn parse_bulk_string(
+    buf: &[u8],
+    length_line: &[u8],
+    pos: usize,
+    limits: &DecoderLimits,
+) -> Parsed<Value> {
+    Ok(
+        parse_blob(buf, length_line, pos, limits)?.map(|(data, next)| {
+            let value = match data {
+                Some(data) => Value::BulkString(Bytes::copy_from_slice(data)),
+                None => Value::Null,
+            };
+            (value, next)
+        }),
+    )
+}
 
-        if qoda == 0 {
-            if !tmp.is_empty() {
-                protocol_vec.push(tmp.clone());
-                tmp.clear();
-                left -= 1;
-            }
-            qoda = 1;
+fn parse_verbatim_string(
+    buf: &[u8],
+    length_line: &[u8],
+    pos: usize,
+    limits: &DecoderLimits,
+) -> Parsed<Value> {
+    let (data, next) = match parse_blob(buf, length_line, pos, limits)? {
+        Some((Some(data), next)) => (data, next),
+        Some((None, _)) => {
+            return Err(ParserError::InvalidProtocol {
+                msg: "Invalid Verbatim String length".to_string(),
+            })
         }
+        None => return Ok(None),
+    };
+
+    if data.len() < 4 || data[3] != b':' {
+        return Err(ParserError::InvalidProtocol {
+            msg: "Invalid Verbatim String format".to_string(),
+        });
     }
 
-    let mut res_vec = vec![];
+    Ok(Some((
+        Value::VerbatimString(
+            String::from_utf8_lossy(&data[..3]).to_string(),
+            Bytes::copy_from_slice(&data[4..]),
+        ),
+        next,
+    )))
+}
+
+fn parse_double(line: &[u8]) -> Result<f64, ParserError> {
+    let invalid = || ParserError::InvalidProtocol {
+        msg: "Invalid Double format".to_string(),
+    };
 
-    for p in protocol_vec.into_iter() {
-        res_vec.push(parse(p)?);
+    let num_str = std::str::from_utf8(line).map_err(|_| invalid())?;
+    match num_str {
+        "inf" => Ok(f64::INFINITY),
+        "-inf" => Ok(f64::NEG_INFINITY),
+        "nan" => Ok(f64::NAN),
+        _ => num_str.parse::<f64>().map_err(|_| invalid()),
     }
-
-    Ok(Value::Array(res_vec))
 }
 
-fn count_qoda(s: &str) -> i32 {
-    if s.len() < 1 {
-        return 0;
+fn parse_big_number(line: &[u8]) -> Result<Value, ParserError> {
+    let digits = line.strip_prefix(b"-").unwrap_or(line);
+    if digits.is_empty() || !digits.iter().all(|b| b.is_ascii_digit()) {
+        return Err(ParserError::InvalidProtocol {
+            msg: "Invalid Big Number format".to_string(),
+        });
     }
-    let symbol = &s[..1];
 
-    match symbol {
-        "$" => {
-            if s.len() < 2 {
-                return 0;
-            }
+    Ok(Value::BigNumber(String::from_utf8_lossy(line).to_string()))
+}
 
-            let num_str = &s[1..];
-            if let Ok(num) = num_str.parse::<i32>() {
-                if num < 0 {
-                    0
-                } else {
-                    1
-                }
-            } else {
-                0
-            }
-        }
-        "*" => {
-            if s.len() < 2 {
-                return 0;
-            }
+/// Element count of an array, set or push. `None` is the RESP2 null array.
+fn parse_aggregate_length(
+    length_line: &[u8],
+    limits: &DecoderLimits,
+) -> Result<Option<usize>, ParserError> {
+    let length = parse_number::<i64>(length_line)?;
 
-            let num_str = &s[1..];
-            if let Ok(num) = num_str.parse::<i32>() {
-                if num < 0 {
-                    0
-                } else {
-                    num
-                }
-            } else {
-                0
-            }
-        }
-        _ => 0,
+    if length == -1 {
+        Ok(None)
+    } else if length < -1 || length as u64 > limits.max_multibulk_len as u64 {
+        Err(ParserError::InvalidProtocol {
+            msg: "invalid multibulk length".to_string(),
+        })
+    } else {
+        Ok(Some(length as usize))
+    }
+}
+
+/// Groups the elements of a map or attribute into key-value pairs.
+fn into_pairs(values: Vec<Value>) -> Vec<(Value, Value)> {
+    let mut values = values.into_iter();
+    let mut pairs = vec![];
+    while let (Some(key), Some(value)) = (values.next(), values.next()) {
+        pairs.push((key, value));
     }
+    pairs
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn parse(protocol: &str) -> Result<Option<Value>, ParserError> {
+        let mut decoder = Decoder::new();
+        decoder.buffer_mut().extend_from_slice(protocol.as_bytes());
+        decoder.decode()
+    }
+
     #[test]
     fn parser_test_parse_simple_string() {
-        let test_string = "+OK\r\n".to_string();
-        let res = parse(test_string);
-        if res.is_err() {
-            assert!(false);
-        }
+        let res = parse("+OK\r\n").unwrap();
 
-        let res = res.unwrap();
-
-        assert_eq!(res, Value::SimpleString("OK".to_string()))
+        assert_eq!(res, Some(Value::SimpleString("OK".to_string())))
     }
 
     #[test]
     fn parser_test_parse_integer() {
-        let test_string = ":2\r\n".to_string();
-        let res = parse(test_string);
-        if res.is_err() {
-            assert!(false);
-        }
+        let res = parse(":2\r\n").unwrap();
 
-        let res = res.unwrap();
-
-        assert_eq!(res, Value::Integer(2))
+        assert_eq!(res, Some(Value::Integer(2)))
     }
 
     #[test]
     fn parser_test_parse_array() {
-        let test_string =
-            "*4\r\n*3\r\n+PING\r\n*1\r\n:2\r\n$3\r\nhey\r\n:1\r\n*2\r\n:3\r\n$2\r\nhi\r\n:2\r\n"
-                .to_string();
-        let res = parse(test_string);
-        if res.is_err() {
-            assert!(false);
-        }
-
-        let res = res.unwrap();
+        let res = parse(
+            "*4\r\n*3\r\n+PING\r\n*1\r\n:2\r\n$3\r\nhey\r\n:1\r\n*2\r\n:3\r\n$2\r\nhi\r\n:2\r\n",
+        )
+        .unwrap();
 
         assert_eq!(
             res,
-            Value::Array(vec![
+            Some(Value::Array(vec![
                 Value::Array(vec![
                     Value::SimpleString("PING".to_string()),
                     Value::Array(vec![Value::Integer(2)]),
-                    Value::BulkString("hey".to_string())
+                    Value::BulkString(Bytes::from("hey"))
                 ]),
                 Value::Integer(1),
-                Value::Array(vec![Value::Integer(3), Value::BulkString("hi".to_string())]),
+                Value::Array(vec![
+                    Value::Integer(3),
+                    Value::BulkString(Bytes::from("hi"))
+                ]),
                 Value::Integer(2)
-            ])
+            ]))
         )
     }
 
     #[test]
     fn test_parser_array_with_null_bulk_string() {
-        let test_string = "*3\r\n$5\r\nhello\r\n$-1\r\n$5\r\nworld\r\n".to_string();
-        let res = parse(test_string);
+        let res = parse("*3\r\n$5\r\nhello\r\n$-1\r\n$5\r\nworld\r\n");
         assert!(res.is_ok());
 
         let res = res.unwrap();
         assert_eq!(
             res,
-            Value::Array(vec![
-                Value::BulkString("hello".to_string()),
+            Some(Value::Array(vec![
+                Value::BulkString(Bytes::from("hello")),
                 Value::Null,
-                Value::BulkString("world".to_string())
-            ])
+                Value::BulkString(Bytes::from("world"))
+            ]))
         );
     }
 
     #[test]
     fn test_parser_null_array() {
-        let test_string = "*-1\r\n".to_string();
-        let res = parse(test_string);
+        let res = parse("*-1\r\n");
         assert!(res.is_ok());
 
-        let res = res.unwrap();
-
-        assert_eq!(res, Value::Null);
+        assert_eq!(res.unwrap(), Some(Value::Null));
     }
 
     #[test]
     fn test_parser_empty_array() {
-        let test_string = "*0\r\n".to_string();
-        let res = parse(test_string);
+        let res = parse("*0\r\n");
         assert!(res.is_ok());
 
-        let res = res.unwrap();
-        assert_eq!(res, Value::Array(vec![]));
+        assert_eq!(res.unwrap(), Some(Value::Array(vec![])));
     }
 
     #[test]
     fn test_parser_null_bulk_string() {
-        let test_string = "$-1\r\n".to_string();
-        let res = parse(test_string);
+        let res = parse("$-1\r\n");
         assert!(res.is_ok());
 
-        let res = res.unwrap();
-        assert_eq!(res, Value::Null);
+        assert_eq!(res.unwrap(), Some(Value::Null));
     }
 
     #[test]
     fn test_parser_empty_bulk_string() {
-        let test_string = "$0\r\n\r\n".to_string();
-        let res = parse(test_string);
+        let res = parse("$0\r\n\r\n");
         assert!(res.is_ok());
 
-        let res = res.unwrap();
-        assert_eq!(res, Value::BulkString("".to_string()));
+        assert_eq!(res.unwrap(), Some(Value::BulkString(Bytes::from(""))));
     }
 
     #[test]
     fn test_parser_multi_array() {
-        let test_string = "*3\r\n$3\r\nSET\r\n$3\r\nfoo\r\n$3\r\n123\r\n*3\r\n$3\r\nSET\r\n$3\r\nbar\r\n$3\r\n456\r\n*3\r\n$3\r\nSET\r\n$3\r\nbaz\r\n$3\r\n789\r\n".to_string();
-        let res = parse_multi_array(test_string);
-        assert!(res.is_ok());
+        let mut decoder = Decoder::new();
+        decoder.buffer_mut().extend_from_slice(b"*3\r\n$3\r\nSET\r\n$3\r\nfoo\r\n$3\r\n123\r\n*3\r\n$3\r\nSET\r\n$3\r\nbar\r\n$3\r\n456\r\n*3\r\n$3\r\nSET\r\n$3\r\nbaz\r\n$3\r\n789\r\n");
+
+        let mut res = vec![];
+        while let Some(value) = decoder.decode().unwrap() {
+            res.push(value);
+        }
 
-        let res = res.unwrap();
         assert_eq!(
             res,
             vec![
                 Value::Array(vec![
-                    Value::BulkString("SET".to_string()),
-                    Value::BulkString("foo".to_string()),
-                    Value::BulkString("123".to_string())
+                    Value::BulkString(Bytes::from("SET")),
+                    Value::BulkString(Bytes::from("foo")),
+                    Value::BulkString(Bytes::from("123"))
                 ]),
                 Value::Array(vec![
-                    Value::BulkString("SET".to_string()),
-                    Value::BulkString("bar".to_string()),
-                    Value::BulkString("456".to_string())
+                    Value::BulkString(Bytes::from("SET")),
+                    Value::BulkString(Bytes::from("bar")),
+                    Value::BulkString(Bytes::from("456"))
                 ]),
                 Value::Array(vec![
-                    Value::BulkString("SET".to_string()),
-                    Value::BulkString("baz".to_string()),
-                    Value::BulkString("789".to_string())
+                    Value::BulkString(Bytes::from("SET")),
+                    Value::BulkString(Bytes::from("baz")),
+                    Value::BulkString(Bytes::from("789"))
                 ])
             ]
         )
     }
+
+    #[test]
+    fn test_parser_binary_bulk_string() {
+        let mut decoder = Decoder::new();
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"$4\r\n\xff\x00\r\xfe\r\n");
+
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Some(Value::BulkString(Bytes::from_static(b"\xff\x00\r\xfe")))
+        );
+    }
+
+    #[test]
+    fn test_decoder_partial_frames() {
+        let mut decoder = Decoder::new();
+        let protocol = b"*2\r\n$4\r\nECHO\r\n$12\r\nhello\r\nworld\r\n";
+
+        for byte in &protocol[..protocol.len() - 1] {
+            decoder.buffer_mut().extend_from_slice(&[*byte]);
+            assert_eq!(decoder.decode().unwrap(), None);
+        }
+
+        decoder
+            .buffer_mut()
+            .extend_from_slice(&protocol[protocol.len() - 1..]);
+        assert_eq!(
+            decoder.decode_frame().unwrap(),
+            Some((
+                Value::Array(vec![
+                    Value::BulkString(Bytes::from("ECHO")),
+                    Value::BulkString(Bytes::from("hello\r\nworld"))
+                ]),
+                protocol.len()
+            ))
+        );
+        assert_eq!(decoder.decode().unwrap(), None);
+    }
+
+    #[test]
+    fn test_decoder_resumes_partial_aggregates() {
+        let mut decoder = Decoder::new();
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"*2\r\n*2\r\n$3\r\nfoo\r\n$3\r\nba");
+        assert_eq!(decoder.decode_frame().unwrap(), None);
+        // complete elements are taken off the buffer and not parsed again
+        assert_eq!(&decoder.buffer_mut()[..], b"$3\r\nba");
+
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"r\r\n:1\r\n+OK\r\n");
+        assert_eq!(
+            decoder.decode_frame().unwrap(),
+            Some((
+                Value::Array(vec![
+                    Value::Array(vec![
+                        Value::BulkString(Bytes::from("foo")),
+                        Value::BulkString(Bytes::from("bar"))
+                    ]),
+                    Value::Integer(1)
+                ]),
+                30
+            ))
+        );
+        assert_eq!(
+            decoder.decode_frame().unwrap(),
+            Some((Value::SimpleString("OK".to_string()), 5))
+        );
+    }
+
+    #[test]
+    fn test_decoder_rdb_payload() {
+        let mut decoder = Decoder::new();
+        decoder.buffer_mut().extend_from_slice(b"$5\r\nRED");
+        assert_eq!(decoder.decode_rdb_payload().unwrap(), None);
+
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"IS*1\r\n$4\r\nPING\r\n");
+        assert_eq!(
+            decoder.decode_rdb_payload().unwrap(),
+            Some(Bytes::from_static(b"REDIS"))
+        );
+        assert_eq!(
+            decoder.decode().unwrap(),
+            Some(Value::Array(vec![Value::BulkString(Bytes::from("PING"))]))
+        );
+    }
+
+    #[test]
+    fn test_parser_resp3_scalars() {
+        assert_eq!(parse("_\r\n").unwrap(), Some(Value::Null));
+        assert_eq!(parse("#t\r\n").unwrap(), Some(Value::Boolean(true)));
+        assert_eq!(parse("#f\r\n").unwrap(), Some(Value::Boolean(false)));
+        assert_eq!(parse(",3.25\r\n").unwrap(), Some(Value::Double(3.25)));
+        assert_eq!(
+            parse(",-inf\r\n").unwrap(),
+            Some(Value::Double(f64::NEG_INFINITY))
+        );
+        assert_eq!(
+            parse("(-3492890328409238509324850943850943825024385\r\n").unwrap(),
+            Some(Value::BigNumber(
+                "-3492890328409238509324850943850943825024385".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("=15\r\ntxt:Some string\r\n").unwrap(),
+            Some(Value::VerbatimString(
+                "txt".to_string(),
+                Bytes::from("Some string")
+            ))
+        );
+        assert_eq!(
+            parse(":9223372036854775807\r\n").unwrap(),
+            Some(Value::Integer(i64::MAX))
+        );
+        assert!(parse("#x\r\n").is_err());
+        assert!(parse("(12a\r\n").is_err());
+    }
+
+    #[test]
+    fn test_parser_resp3_aggregates() {
+        assert_eq!(
+            parse("%2\r\n+first\r\n:1\r\n+second\r\n~2\r\n#t\r\n_\r\n").unwrap(),
+            Some(Value::Map(vec![
+                (Value::SimpleString("first".to_string()), Value::Integer(1)),
+                (
+                    Value::SimpleString("second".to_string()),
+                    Value::Set(vec![Value::Boolean(true), Value::Null])
+                ),
+            ]))
+        );
+        assert_eq!(
+            parse(">2\r\n+message\r\n$5\r\nhello\r\n").unwrap(),
+            Some(Value::Push(vec![
+                Value::SimpleString("message".to_string()),
+                Value::BulkString(Bytes::from("hello"))
+            ]))
+        );
+        assert_eq!(
+            parse("|1\r\n+ttl\r\n:3600\r\n*1\r\n:2\r\n").unwrap(),
+            Some(Value::Attribute(
+                vec![(Value::SimpleString("ttl".to_string()), Value::Integer(3600))],
+                Box::new(Value::Array(vec![Value::Integer(2)]))
+            ))
+        );
+        assert_eq!(parse("%1\r\n+key\r\n").unwrap(), None);
+        assert_eq!(parse("|1\r\n+key\r\n:1\r\n").unwrap(), None);
+    }
+
+    #[test]
+    fn test_decoder_inline_command() {
+        let mut decoder = Decoder::new();
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"PING\r\n\r\n  SET  foo bar\n*1\r\n$4\r\nPING\r\nECHO hi");
+
+        assert_eq!(
+            decoder.decode_command().unwrap(),
+            Some(Value::Array(vec![Value::BulkString(Bytes::from("PING"))]))
+        );
+        assert_eq!(
+            decoder.decode_command().unwrap(),
+            Some(Value::Array(vec![
+                Value::BulkString(Bytes::from("SET")),
+                Value::BulkString(Bytes::from("foo")),
+                Value::BulkString(Bytes::from("bar"))
+            ]))
+        );
+        assert_eq!(
+            decoder.decode_command().unwrap(),
+            Some(Value::Array(vec![Value::BulkString(Bytes::from("PING"))]))
+        );
+        // no newline yet
+        assert_eq!(decoder.decode_command().unwrap(), None);
+    }
+
+    #[test]
+    fn test_decoder_inline_command_quoting() {
+        let mut decoder = Decoder::new();
+        decoder.buffer_mut().extend_from_slice(
+            b"SET \"a b\\x41\\n\" 'it\\'s' \"\" \"\\\"q\\\"\"\r\nSET \"foo\"bar\r\nSET 'foo\r\n",
+        );
+
+        assert_eq!(
+            decoder.decode_command().unwrap(),
+            Some(Value::Array(vec![
+                Value::BulkString(Bytes::from("SET")),
+                Value::BulkString(Bytes::from("a bA\n")),
+                Value::BulkString(Bytes::from("it's")),
+                Value::BulkString(Bytes::from("")),
+                Value::BulkString(Bytes::from("\"q\"")),
+            ]))
+        );
+        assert!(decoder.decode_command().is_err());
+        assert!(decoder.decode_command().is_err());
+    }
+
+    #[test]
+    fn test_decoder_limits() {
+        let limits = DecoderLimits {
+            max_bulk_len: 4,
+            max_multibulk_len: 2,
+            max_buffer_len: 16,
+        };
+
+        let decode = |protocol: &[u8]| {
+            let mut decoder = Decoder::with_limits(limits);
+            decoder.buffer_mut().extend_from_slice(protocol);
+            decoder.decode_command()
+        };
+
+        assert!(decode(b"*1\r\n$4\r\nPING\r\n").unwrap().is_some());
+        // rejected from the header alone, before the payload arrives
+        assert!(decode(b"*1\r\n$5\r\n").is_err());
+        assert!(decode(b"*3\r\n").is_err());
+        assert!(decode(b"*1\r\n$99999999999999999999\r\n").is_err());
+        assert!(decode(b"*1\r\n$-5\r\n").is_err());
+        assert!(decode(b"*2\r\n$4\r\nPING\r\n$4\r\nPI").is_err());
+        assert!(decode(&[b'P'; MAX_LINE_LEN + 1]).is_err());
+
+        // complete commands count too while they wait to be run
+        let mut decoder = Decoder::with_limits(limits);
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"*1\r\n$4\r\nPING\r\n");
+        assert!(decoder.check_buffer_limit().is_ok());
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"*1\r\n$4\r\nPING\r\n");
+        assert!(decoder.check_buffer_limit().is_err());
+    }
+
+    #[test]
+    fn test_decoder_rejects_long_header_lines() {
+        let decode = |symbol: u8| {
+            let mut decoder = Decoder::new();
+            decoder.buffer_mut().extend_from_slice(&[symbol]);
+            decoder
+                .buffer_mut()
+                .extend_from_slice(&[b'1'; MAX_LINE_LEN + 1]);
+            decoder.decode()
+        };
+
+        assert!(decode(b'*')
+            .is_err_and(|e| e.to_string() == "Protocol error: too big mbulk count string"));
+        assert!(decode(b'$')
+            .is_err_and(|e| e.to_string() == "Protocol error: too big bulk count string"));
+        assert!(decode(b':').is_err_and(|e| e.to_string() == "Protocol error: too big line"));
+
+        // still waiting for a CRLF within the limit
+        let mut decoder = Decoder::new();
+        decoder.buffer_mut().extend_from_slice(b"*1\r\n$");
+        decoder
+            .buffer_mut()
+            .extend_from_slice(&[b'1'; MAX_LINE_LEN]);
+        assert!(decoder.decode().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_decoder_rejects_deep_nesting() {
+        let mut decoder = Decoder::new();
+        for _ in 0..=MAX_NESTING_DEPTH {
+            decoder.buffer_mut().extend_from_slice(b"*1\r\n");
+        }
+        decoder.buffer_mut().extend_from_slice(b":1\r\n");
+
+        assert!(decoder.decode().is_err());
+    }
+
+    #[test]
+    fn test_decoder_command_arguments_must_be_bulk_strings() {
+        let mut decoder = Decoder::new();
+        decoder
+            .buffer_mut()
+            .extend_from_slice(b"*2\r\n$4\r\nECHO\r\n:1\r\n");
+
+        assert!(decoder
+            .decode_command()
+            .is_err_and(|e| e.to_string() == "Protocol error: expected '$', got ':'"));
+    }
 }
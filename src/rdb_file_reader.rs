@@ -1,10 +1,12 @@
 use crate::error::RusdisError;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
+use crate::stream::{ConsumerGroup, Fields, Stream, StreamId};
+use bytes::Bytes;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::iter::Peekable;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+type ByteIter = Peekable<std::vec::IntoIter<u8>>;
+
 pub enum Phase {
     Header,
     Metadata,
@@ -14,28 +16,36 @@ pub enum Phase {
 
 #[derive(Debug)]
 pub struct RDBFile {
+    #[allow(dead_code)]
     pub rdb_version: String,
-    pub aux_fields: Vec<(String, String)>,
+    #[allow(dead_code)]
+    pub aux_fields: Vec<(Bytes, Bytes)>,
     pub datasets: Vec<Dataset>,
 }
 
 #[derive(Debug)]
 pub struct Dataset {
-    pub pairs: Vec<(String, ValueType, Option<SystemTime>)>,
+    pub pairs: Vec<(Bytes, ValueType, Option<SystemTime>)>,
 }
 
 impl Dataset {
-    pub fn get_pairs(self) -> Vec<(String, ValueType, Option<SystemTime>)> {
+    pub fn get_pairs(self) -> Vec<(Bytes, ValueType, Option<SystemTime>)> {
         self.pairs
     }
 }
 
 #[derive(Clone, Debug)]
 pub enum ValueType {
-    String(String),
+    String(Bytes),
+    List(VecDeque<Bytes>),
+    Hash(HashMap<Bytes, Bytes>),
+    Set(HashSet<Bytes>),
+    /// Members of a sorted set with their scores
+    ZSet(Vec<(Bytes, f64)>),
+    Stream(Stream),
 }
 
-pub fn read_rdb(mut iter: Peekable<std::vec::IntoIter<u8>>) -> Result<RDBFile, RusdisError> {
+pub fn read_rdb(mut iter: ByteIter) -> Result<RDBFile, RusdisError> {
     //let f = File::open(f_path)?;
     //let mut reader = BufReader::new(f);
     //
@@ -53,14 +63,15 @@ pub fn read_rdb(mut iter: Peekable<std::vec::IntoIter<u8>>) -> Result<RDBFile, R
             Phase::Header => {
                 // Magic String
                 let mut slice = [0; 5];
-                for i in 0..5 {
-                    let byte = iter.next();
-                    if byte.is_none() {
-                        return Err(RusdisError::RDBFileParserError {
-                            msg: "Wrong Magic String".to_string(),
-                        });
+                for byte in slice.iter_mut() {
+                    match iter.next() {
+                        Some(b) => *byte = b,
+                        None => {
+                            return Err(RusdisError::RDBFileParserError {
+                                msg: "Wrong Magic String".to_string(),
+                            })
+                        }
                     }
-                    slice[i] = byte.unwrap();
                 }
                 let magic_string = String::from_utf8_lossy(&slice);
                 if magic_string != "REDIS" {
@@ -71,14 +82,15 @@ pub fn read_rdb(mut iter: Peekable<std::vec::IntoIter<u8>>) -> Result<RDBFile, R
 
                 // RDB Version
                 let mut slice = [0; 4];
-                for i in 0..4 {
-                    let byte = iter.next();
-                    if byte.is_none() {
-                        return Err(RusdisError::RDBFileParserError {
-                            msg: "Invalid RDB Version".to_string(),
-                        });
+                for byte in slice.iter_mut() {
+                    match iter.next() {
+                        Some(b) => *byte = b,
+                        None => {
+                            return Err(RusdisError::RDBFileParserError {
+                                msg: "Invalid RDB Version".to_string(),
+                            })
+                        }
                     }
-                    slice[i] = byte.unwrap();
                 }
                 rdb_version = String::from_utf8_lossy(&slice).to_string();
                 phase = Phase::Metadata;
@@ -126,7 +138,7 @@ pub fn read_rdb(mut iter: Peekable<std::vec::IntoIter<u8>>) -> Result<RDBFile, R
                         // skip FE flag
                         let _ = iter.next();
                         let phantom_iter = iter;
-                        let (mut phantom_iter, db_index) = decode_length(phantom_iter)?;
+                        let (mut phantom_iter, _db_index) = decode_length(phantom_iter)?;
                         // skip FB flag
                         let _ = phantom_iter.next();
 
@@ -225,10 +237,7 @@ pub fn read_rdb(mut iter: Peekable<std::vec::IntoIter<u8>>) -> Result<RDBFile, R
     Ok(rdb_file)
 }
 
-fn parse_data(
-    mut iter: Peekable<std::vec::IntoIter<u8>>,
-) -> Result<(Peekable<std::vec::IntoIter<u8>>, (String, ValueType)), RusdisError> {
-    dbg!(iter.peek());
+fn parse_data(mut iter: ByteIter) -> Result<(ByteIter, (Bytes, ValueType)), RusdisError> {
     match iter.next() {
         Some(data_type) => match data_type {
             0x00 => {
@@ -237,6 +246,150 @@ fn parse_data(
 
                 Ok((iter, (key, ValueType::String(value))))
             }
+            // RDB_TYPE_LIST
+            0x01 => {
+                let (iter, key) = decode_string(iter)?;
+                let (mut iter, length) = decode_length(iter)?;
+
+                let mut list = VecDeque::with_capacity(length);
+                for _ in 0..length {
+                    let (p_iter, element) = decode_string(iter)?;
+                    list.push_back(element);
+                    iter = p_iter;
+                }
+
+                Ok((iter, (key, ValueType::List(list))))
+            }
+            // RDB_TYPE_LIST_QUICKLIST_2
+            0x12 => {
+                let (iter, key) = decode_string(iter)?;
+                let (mut iter, nodes) = decode_length(iter)?;
+
+                let mut list = VecDeque::new();
+                for _ in 0..nodes {
+                    let (p_iter, container) = decode_length(iter)?;
+                    let (p_iter, node) = decode_string(p_iter)?;
+                    match container {
+                        // QUICKLIST_NODE_CONTAINER_PLAIN
+                        1 => list.push_back(node),
+                        // QUICKLIST_NODE_CONTAINER_PACKED
+                        2 => list.extend(decode_listpack(&node)?),
+                        _ => {
+                            return Err(RusdisError::RDBFileParserError {
+                                msg: "Invalid quicklist node container".to_string(),
+                            })
+                        }
+                    }
+                    iter = p_iter;
+                }
+
+                Ok((iter, (key, ValueType::List(list))))
+            }
+            // RDB_TYPE_HASH
+            0x04 => {
+                let (iter, key) = decode_string(iter)?;
+                let (mut iter, length) = decode_length(iter)?;
+
+                let mut hash = HashMap::with_capacity(length);
+                for _ in 0..length {
+                    let (p_iter, field) = decode_string(iter)?;
+                    let (p_iter, value) = decode_string(p_iter)?;
+                    hash.insert(field, value);
+                    iter = p_iter;
+                }
+
+                Ok((iter, (key, ValueType::Hash(hash))))
+            }
+            // RDB_TYPE_HASH_LISTPACK
+            0x10 => {
+                let (iter, key) = decode_string(iter)?;
+                let (iter, listpack) = decode_string(iter)?;
+
+                let mut elements = decode_listpack(&listpack)?.into_iter();
+                let mut hash = HashMap::new();
+                while let (Some(field), Some(value)) = (elements.next(), elements.next()) {
+                    hash.insert(field, value);
+                }
+
+                Ok((iter, (key, ValueType::Hash(hash))))
+            }
+            // RDB_TYPE_SET
+            0x02 => {
+                let (iter, key) = decode_string(iter)?;
+                let (mut iter, length) = decode_length(iter)?;
+
+                let mut set = HashSet::with_capacity(length);
+                for _ in 0..length {
+                    let (p_iter, member) = decode_string(iter)?;
+                    set.insert(member);
+                    iter = p_iter;
+                }
+
+                Ok((iter, (key, ValueType::Set(set))))
+            }
+            // RDB_TYPE_SET_INTSET
+            0x0b => {
+                let (iter, key) = decode_string(iter)?;
+                let (iter, intset) = decode_string(iter)?;
+
+                let set = decode_intset(&intset)?.into_iter().collect();
+                Ok((iter, (key, ValueType::Set(set))))
+            }
+            // RDB_TYPE_SET_LISTPACK
+            0x14 => {
+                let (iter, key) = decode_string(iter)?;
+                let (iter, listpack) = decode_string(iter)?;
+
+                let set = decode_listpack(&listpack)?.into_iter().collect();
+                Ok((iter, (key, ValueType::Set(set))))
+            }
+            // RDB_TYPE_ZSET and RDB_TYPE_ZSET_2, scores as text or binary doubles
+            0x03 | 0x05 => {
+                let binary = data_type == 0x05;
+                let (iter, key) = decode_string(iter)?;
+                let (mut iter, length) = decode_length(iter)?;
+
+                let mut members = Vec::with_capacity(length);
+                for _ in 0..length {
+                    let (p_iter, member) = decode_string(iter)?;
+                    let (p_iter, score) = if binary {
+                        decode_binary_double(p_iter)?
+                    } else {
+                        decode_double(p_iter)?
+                    };
+                    members.push((member, score));
+                    iter = p_iter;
+                }
+
+                Ok((iter, (key, ValueType::ZSet(members))))
+            }
+            // RDB_TYPE_ZSET_LISTPACK
+            0x11 => {
+                let (iter, key) = decode_string(iter)?;
+                let (iter, listpack) = decode_string(iter)?;
+
+                let mut elements = decode_listpack(&listpack)?.into_iter();
+                let mut members = vec![];
+                while let (Some(member), Some(score)) = (elements.next(), elements.next()) {
+                    let score = std::str::from_utf8(&score)
+                        .ok()
+                        .and_then(|score| score.parse::<f64>().ok())
+                        .ok_or_else(|| RusdisError::RDBFileParserError {
+                            msg: "Invalid sorted set score".to_string(),
+                        })?;
+                    members.push((member, score));
+                }
+
+                Ok((iter, (key, ValueType::ZSet(members))))
+            }
+            // RDB_TYPE_STREAM_LISTPACKS, with the deletion metadata from _2 on and
+            // the consumer active times from _3 on
+            0x0f | 0x13 | 0x15 => {
+                let (iter, key) = decode_string(iter)?;
+                let (iter, stream) = decode_stream(iter, data_type)?;
+
+                Ok((iter, (key, ValueType::Stream(stream))))
+            }
             _ => Err(RusdisError::RDBFileParserError {
                 msg: "Not supported data type".to_string(),
             }),
@@ -247,9 +400,7 @@ fn parse_data(
     }
 }
 
-fn decode_string(
-    mut iter: Peekable<std::vec::IntoIter<u8>>,
-) -> Result<(Peekable<std::vec::IntoIter<u8>>, String), RusdisError> {
+fn decode_string(mut iter: ByteIter) -> Result<(ByteIter, Bytes), RusdisError> {
     let first_byte = iter.peek();
     if first_byte.is_none() {
         return Err(RusdisError::RDBFileParserError {
@@ -258,15 +409,35 @@ fn decode_string(
     }
 
     let first_byte = *first_byte.unwrap();
+    if first_byte == 0xc3 {
+        // skip the LZF flag
+        let _ = iter.next();
+        let (iter, compressed_length) = decode_length(iter)?;
+        let (mut iter, length) = decode_length(iter)?;
+
+        let mut compressed = Vec::with_capacity(compressed_length);
+        for _ in 0..compressed_length {
+            match iter.next() {
+                Some(byte) => compressed.push(byte),
+                None => {
+                    return Err(RusdisError::RDBFileParserError {
+                        msg: "Decode string failed".to_string(),
+                    })
+                }
+            }
+        }
+
+        return Ok((iter, Bytes::from(lzf_decompress(&compressed, length)?)));
+    }
+
     if first_byte & 0b1100_0000 == 0b1100_0000 {
         let (iter, res) = decode_length(iter)?;
-        let res = format!("{}", res);
-        return Ok((iter, res));
+        return Ok((iter, Bytes::from(res.to_string())));
     }
 
     let (mut iter, length) = decode_length(iter)?;
 
-    let mut res = String::new();
+    let mut res = Vec::with_capacity(length);
     for _ in 0..length {
         let cur_byte = iter.next();
         if cur_byte.is_none() {
@@ -275,16 +446,364 @@ fn decode_string(
             });
         }
 
-        let cur_byte = cur_byte.unwrap();
-        res.push(cur_byte as char);
+        res.push(cur_byte.unwrap());
+    }
+
+    Ok((iter, Bytes::from(res)))
+}
+
+fn lzf_decompress(input: &[u8], length: usize) -> Result<Vec<u8>, RusdisError> {
+    let err = || RusdisError::RDBFileParserError {
+        msg: "LZF decompression failed".to_string(),
+    };
+
+    let mut out = Vec::with_capacity(length);
+    let mut pos = 0;
+    while pos < input.len() {
+        let ctrl = input[pos] as usize;
+        pos += 1;
+
+        if ctrl < 32 {
+            // literal run of ctrl + 1 bytes
+            let literal = input.get(pos..pos + ctrl + 1).ok_or_else(err)?;
+            out.extend_from_slice(literal);
+            pos += ctrl + 1;
+        } else {
+            // back reference
+            let mut len = ctrl >> 5;
+            if len == 7 {
+                len += *input.get(pos).ok_or_else(err)? as usize;
+                pos += 1;
+            }
+            let offset = ((ctrl & 0x1f) << 8) + *input.get(pos).ok_or_else(err)? as usize + 1;
+            pos += 1;
+
+            let start = out.len().checked_sub(offset).ok_or_else(err)?;
+            for i in 0..len + 2 {
+                out.push(out[start + i]);
+            }
+        }
+    }
+
+    if out.len() != length {
+        return Err(err());
     }
 
-    Ok((iter, res))
+    Ok(out)
 }
 
-fn decode_length(
-    mut iter: Peekable<std::vec::IntoIter<u8>>,
-) -> Result<(Peekable<std::vec::IntoIter<u8>>, usize), RusdisError> {
+/// Decodes the elements of a listpack, integers rendered as decimal strings.
+fn decode_listpack(buf: &[u8]) -> Result<Vec<Bytes>, RusdisError> {
+    let err = || RusdisError::RDBFileParserError {
+        msg: "Invalid listpack".to_string(),
+    };
+    let slice = |from: usize, len: usize| buf.get(from..from + len).ok_or_else(err);
+    let int = |from: usize, len: usize| -> Result<i64, RusdisError> {
+        let bytes = slice(from, len)?;
+        let mut res = 0_u64;
+        for (i, byte) in bytes.iter().enumerate() {
+            res |= (*byte as u64) << (i * 8);
+        }
+        // sign extend
+        let shift = 64 - len * 8;
+        Ok(((res << shift) as i64) >> shift)
+    };
+
+    // skip total bytes and number of elements
+    let mut pos = 6;
+    let mut elements = vec![];
+    loop {
+        let byte = *buf.get(pos).ok_or_else(err)?;
+        let (element, entry_len) = match byte {
+            0xff => break,
+            b if b & 0x80 == 0 => (Bytes::from((b & 0x7f).to_string()), 1),
+            b if b & 0xc0 == 0x80 => {
+                let len = (b & 0x3f) as usize;
+                (Bytes::copy_from_slice(slice(pos + 1, len)?), 1 + len)
+            }
+            b if b & 0xe0 == 0xc0 => {
+                let mut num =
+                    (((b & 0x1f) as i64) << 8) + *slice(pos + 1, 1)?.first().unwrap() as i64;
+                if num >= 1 << 12 {
+                    num -= 1 << 13;
+                }
+                (Bytes::from(num.to_string()), 2)
+            }
+            b if b & 0xf0 == 0xe0 => {
+                let len =
+                    (((b & 0x0f) as usize) << 8) + *slice(pos + 1, 1)?.first().unwrap() as usize;
+                (Bytes::copy_from_slice(slice(pos + 2, len)?), 2 + len)
+            }
+            0xf0 => {
+                let len = int(pos + 1, 4)? as u32 as usize;
+                (Bytes::copy_from_slice(slice(pos + 5, len)?), 5 + len)
+            }
+            0xf1 => (Bytes::from(int(pos + 1, 2)?.to_string()), 3),
+            0xf2 => (Bytes::from(int(pos + 1, 3)?.to_string()), 4),
+            0xf3 => (Bytes::from(int(pos + 1, 4)?.to_string()), 5),
+            0xf4 => (Bytes::from(int(pos + 1, 8)?.to_string()), 9),
+            _ => return Err(err()),
+        };
+
+        let backlen_size = match entry_len {
+            0..=127 => 1,
+            128..=16383 => 2,
+            16384..=2097151 => 3,
+            2097152..=268435455 => 4,
+            _ => 5,
+        };
+        pos += entry_len + backlen_size;
+        elements.push(element);
+    }
+
+    Ok(elements)
+}
+
+/// Decodes a stream ID saved as two big-endian 64 bit integers.
+fn decode_raw_stream_id(bytes: &[u8]) -> Result<StreamId, RusdisError> {
+    let bytes: [u8; 16] = bytes
+        .try_into()
+        .map_err(|_| RusdisError::RDBFileParserError {
+            msg: "Invalid stream ID".to_string(),
+        })?;
+    let (ms, seq) = bytes.split_at(8);
+
+    Ok(StreamId::new(
+        u64::from_be_bytes(ms.try_into().unwrap()),
+        u64::from_be_bytes(seq.try_into().unwrap()),
+    ))
+}
+
+/// Decodes a stream ID saved as two lengths.
+fn decode_stream_id(iter: ByteIter) -> Result<(ByteIter, StreamId), RusdisError> {
+    let (iter, ms) = decode_length(iter)?;
+    let (iter, seq) = decode_length(iter)?;
+
+    Ok((iter, StreamId::new(ms as u64, seq as u64)))
+}
+
+/// Decodes a little-endian Unix time in milliseconds.
+fn decode_millis(mut iter: ByteIter) -> Result<(ByteIter, u64), RusdisError> {
+    let bytes: Vec<u8> = iter.by_ref().take(8).collect();
+    let bytes: [u8; 8] = bytes
+        .try_into()
+        .map_err(|_| RusdisError::RDBFileParserError {
+            msg: "Invalid Timestamp format".to_string(),
+        })?;
+
+    Ok((iter, u64::from_le_bytes(bytes)))
+}
+
+/// Decodes the entries of a stream listpack whose IDs are relative to
+/// `master`, skipping the deleted ones.
+fn decode_stream_listpack(
+    buf: &[u8],
+    master: StreamId,
+) -> Result<Vec<(StreamId, Fields)>, RusdisError> {
+    let err = || RusdisError::RDBFileParserError {
+        msg: "Invalid stream listpack".to_string(),
+    };
+    let mut elements = decode_listpack(buf)?.into_iter();
+    let mut next = || elements.next().ok_or_else(err);
+    let int = |element: Bytes| -> Result<u64, RusdisError> {
+        std::str::from_utf8(&element)
+            .ok()
+            .and_then(|num| num.parse::<i64>().ok())
+            .map(|num| num as u64)
+            .ok_or_else(err)
+    };
+
+    // master entry: count, deleted count, fields, 0
+    let count = int(next()?)? + int(next()?)?;
+    let master_fields = (0..int(next()?)?)
+        .map(|_| next())
+        .collect::<Result<Vec<_>, _>>()?;
+    next()?;
+
+    let mut entries = vec![];
+    for _ in 0..count {
+        let flags = int(next()?)?;
+        let id = StreamId::new(
+            master.ms.wrapping_add(int(next()?)?),
+            master.seq.wrapping_add(int(next()?)?),
+        );
+        let fields = if flags & 2 != 0 {
+            // same fields as the master entry, values only
+            master_fields
+                .iter()
+                .map(|field| Ok((field.clone(), next()?)))
+                .collect::<Result<Vec<_>, RusdisError>>()?
+        } else {
+            (0..int(next()?)?)
+                .map(|_| Ok((next()?, next()?)))
+                .collect::<Result<Vec<_>, RusdisError>>()?
+        };
+        // number of elements of the entry, for backward iteration
+        next()?;
+
+        if flags & 1 == 0 {
+            entries.push((id, fields));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Decodes a stream of RDB type `data_type` with its consumer groups.
+fn decode_stream(iter: ByteIter, data_type: u8) -> Result<(ByteIter, Stream), RusdisError> {
+    let mut stream = Stream::new();
+
+    let (mut iter, nodes) = decode_length(iter)?;
+    for _ in 0..nodes {
+        let (p_iter, master) = decode_string(iter)?;
+        let (p_iter, listpack) = decode_string(p_iter)?;
+        for (id, fields) in decode_stream_listpack(&listpack, decode_raw_stream_id(&master)?)? {
+            stream.append(id, fields);
+        }
+        iter = p_iter;
+    }
+
+    let (iter, length) = decode_length(iter)?;
+    let (mut iter, last_id) = decode_stream_id(iter)?;
+    let (mut max_deleted_id, mut entries_added) = (StreamId::MIN, length as u64);
+    if data_type >= 0x13 {
+        // the first ID is known from the entries
+        let (p_iter, _first_id) = decode_stream_id(iter)?;
+        let (p_iter, p_max_deleted_id) = decode_stream_id(p_iter)?;
+        let (p_iter, p_entries_added) = decode_length(p_iter)?;
+        (max_deleted_id, entries_added) = (p_max_deleted_id, p_entries_added as u64);
+        iter = p_iter;
+    }
+    stream.set_metadata(last_id, max_deleted_id, entries_added);
+
+    let (mut iter, groups) = decode_length(iter)?;
+    for _ in 0..groups {
+        let (p_iter, name) = decode_string(iter)?;
+        let (mut p_iter, last_id) = decode_stream_id(p_iter)?;
+        let mut entries_read = None;
+        if data_type >= 0x13 {
+            let (pp_iter, read) = decode_length(p_iter)?;
+            // -1 when unknown
+            entries_read = (read as u64 != u64::MAX).then_some(read as u64);
+            p_iter = pp_iter;
+        }
+        let mut group = ConsumerGroup::new(last_id, entries_read);
+
+        // delivery time and count of every pending entry
+        let (mut p_iter, pending_len) = decode_length(p_iter)?;
+        let mut pending = HashMap::with_capacity(pending_len);
+        for _ in 0..pending_len {
+            let raw: Vec<u8> = p_iter.by_ref().take(16).collect();
+            let (pp_iter, delivery_time) = decode_millis(p_iter)?;
+            let (pp_iter, delivery_count) = decode_length(pp_iter)?;
+            pending.insert(
+                decode_raw_stream_id(&raw)?,
+                (delivery_time, delivery_count as u64),
+            );
+            p_iter = pp_iter;
+        }
+
+        // consumers and the pending entries each one owns
+        let (mut p_iter, consumers) = decode_length(p_iter)?;
+        for _ in 0..consumers {
+            let (pp_iter, consumer) = decode_string(p_iter)?;
+            let (mut pp_iter, seen_time) = decode_millis(pp_iter)?;
+            let mut active_time = Some(seen_time);
+            if data_type >= 0x15 {
+                let (ppp_iter, time) = decode_millis(pp_iter)?;
+                // -1 when never active
+                active_time = (time != u64::MAX).then_some(time);
+                pp_iter = ppp_iter;
+            }
+            group.add_consumer(consumer.clone(), seen_time, active_time);
+
+            let (mut pp_iter, owned) = decode_length(pp_iter)?;
+            for _ in 0..owned {
+                let raw: Vec<u8> = pp_iter.by_ref().take(16).collect();
+                let id = decode_raw_stream_id(&raw)?;
+                let (delivery_time, delivery_count) =
+                    pending
+                        .remove(&id)
+                        .ok_or_else(|| RusdisError::RDBFileParserError {
+                            msg: "Consumer pending entry not in the group".to_string(),
+                        })?;
+                group.deliver(id, &consumer, delivery_time, delivery_count);
+            }
+            p_iter = pp_iter;
+        }
+
+        stream.insert_group(name, group);
+        iter = p_iter;
+    }
+
+    Ok((iter, stream))
+}
+
+/// Decodes a double saved as text behind a length byte, with 253, 254 and
+/// 255 standing for NaN, +inf and -inf.
+fn decode_double(mut iter: ByteIter) -> Result<(ByteIter, f64), RusdisError> {
+    let err = || RusdisError::RDBFileParserError {
+        msg: "Decode double failed".to_string(),
+    };
+
+    let num = match iter.next().ok_or_else(err)? {
+        253 => f64::NAN,
+        254 => f64::INFINITY,
+        255 => f64::NEG_INFINITY,
+        length => {
+            let text: Vec<u8> = iter.by_ref().take(length as usize).collect();
+            std::str::from_utf8(&text)
+                .ok()
+                .and_then(|text| text.parse::<f64>().ok())
+                .ok_or_else(err)?
+        }
+    };
+
+    Ok((iter, num))
+}
+
+/// Decodes a little-endian IEEE 754 double.
+fn decode_binary_double(mut iter: ByteIter) -> Result<(ByteIter, f64), RusdisError> {
+    let bytes: Vec<u8> = iter.by_ref().take(8).collect();
+    let bytes: [u8; 8] = bytes
+        .try_into()
+        .map_err(|_| RusdisError::RDBFileParserError {
+            msg: "Decode double failed".to_string(),
+        })?;
+
+    Ok((iter, f64::from_le_bytes(bytes)))
+}
+
+/// Decodes the integers of an intset, rendered as decimal strings.
+fn decode_intset(buf: &[u8]) -> Result<Vec<Bytes>, RusdisError> {
+    let err = || RusdisError::RDBFileParserError {
+        msg: "Invalid intset".to_string(),
+    };
+    let header = |from: usize| -> Result<usize, RusdisError> {
+        let bytes = buf.get(from..from + 4).ok_or_else(err)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    };
+
+    let encoding = header(0)?;
+    let length = header(4)?;
+    if ![2, 4, 8].contains(&encoding) || buf.len() != 8 + encoding * length {
+        return Err(err());
+    }
+
+    Ok(buf[8..]
+        .chunks(encoding)
+        .map(|chunk| {
+            let num = match encoding {
+                2 => i16::from_le_bytes(chunk.try_into().unwrap()) as i64,
+                4 => i32::from_le_bytes(chunk.try_into().unwrap()) as i64,
+                _ => i64::from_le_bytes(chunk.try_into().unwrap()),
+            };
+            Bytes::from(num.to_string())
+        })
+        .collect())
+}
+
+fn decode_length(mut iter: ByteIter) -> Result<(ByteIter, usize), RusdisError> {
     let first_byte = iter.next();
     if first_byte.is_none() {
         return Err(RusdisError::RDBFileParserError {
@@ -347,8 +866,10 @@ fn decode_length(
             }
         }
         0b1000_0000 => {
+            // 0x80 is followed by 32 bits, 0x81 by 64 bits
+            let size = if first_byte == 0x81 { 8 } else { 4 };
             let mut res = 0;
-            for _ in 0..4 {
+            for _ in 0..size {
                 let tmp = iter.next();
                 if tmp.is_none() {
                     return Err(RusdisError::RDBFileParserError {
@@ -442,12 +963,236 @@ mod test {
         let res = decode_string(vec.into_iter().peekable());
         assert!(res.is_ok());
         let (_, res) = res.unwrap();
-        assert_eq!(res.as_str(), "Hello, World!");
+        assert_eq!(res.as_ref(), b"Hello, World!");
 
         let vec = vec![0xc2, 0x87, 0xd6, 0x12, 0x00];
         let res = decode_string(vec.into_iter().peekable());
         assert!(res.is_ok());
         let (_, res) = res.unwrap();
-        assert_eq!(res.as_str(), "1234567");
+        assert_eq!(res.as_ref(), b"1234567");
+    }
+
+    #[test]
+    fn test_rdb_reader_decode_binary_string() {
+        let vec = vec![0x04, 0xff, 0x00, 0x80, 0xfe];
+        let res = decode_string(vec.into_iter().peekable());
+        assert!(res.is_ok());
+        let (_, res) = res.unwrap();
+        assert_eq!(res.as_ref(), &[0xff, 0x00, 0x80, 0xfe]);
+    }
+
+    #[test]
+    fn test_rdb_reader_decode_lzf_string() {
+        // literal "a" followed by a back reference of 5 bytes at distance 1
+        let vec = vec![0xc3, 0x04, 0x06, 0x00, b'a', 0x60, 0x00];
+        let res = decode_string(vec.into_iter().peekable());
+        assert!(res.is_ok());
+        let (_, res) = res.unwrap();
+        assert_eq!(res.as_ref(), b"aaaaaa");
+    }
+
+    #[test]
+    fn test_rdb_reader_parse_lists() {
+        // RDB_TYPE_LIST
+        let vec = vec![0x01, 0x01, b'l', 0x02, 0x01, b'a', 0xc0, 0x07];
+        let (_, (key, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        assert_eq!(key.as_ref(), b"l");
+        match value {
+            ValueType::List(list) => assert_eq!(list, ["a", "7"].map(Bytes::from)),
+            _ => panic!("expected a list"),
+        }
+
+        // RDB_TYPE_LIST_QUICKLIST_2 with one packed node holding "ab", 5, -1
+        let listpack = vec![
+            0x10, 0x00, 0x00, 0x00, 0x03, 0x00, 0x82, b'a', b'b', 0x03, 0x05, 0x01, 0xdf, 0xff,
+            0x02, 0xff,
+        ];
+        let mut vec = vec![0x12, 0x01, b'q', 0x01, 0x02, listpack.len() as u8];
+        vec.extend(listpack);
+        let (_, (key, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        assert_eq!(key.as_ref(), b"q");
+        match value {
+            ValueType::List(list) => assert_eq!(list, ["ab", "5", "-1"].map(Bytes::from)),
+            _ => panic!("expected a list"),
+        }
+    }
+
+    #[test]
+    fn test_rdb_reader_parse_hash_listpack() {
+        // RDB_TYPE_HASH_LISTPACK holding f => "v", n => 12
+        let listpack = vec![
+            0x12, 0x00, 0x00, 0x00, 0x04, 0x00, 0x81, b'f', 0x02, 0x81, b'v', 0x02, 0x81, b'n',
+            0x02, 0x0c, 0x01, 0xff,
+        ];
+        let mut vec = vec![0x10, 0x01, b'h', listpack.len() as u8];
+        vec.extend(listpack);
+        let (_, (key, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        assert_eq!(key.as_ref(), b"h");
+        match value {
+            ValueType::Hash(hash) => {
+                assert_eq!(hash.len(), 2);
+                assert_eq!(hash[&Bytes::from("f")], Bytes::from("v"));
+                assert_eq!(hash[&Bytes::from("n")], Bytes::from("12"));
+            }
+            _ => panic!("expected a hash"),
+        }
+    }
+
+    #[test]
+    fn test_rdb_reader_parse_sets() {
+        // RDB_TYPE_SET_INTSET holding 1 and -2 as 16 bit integers
+        let intset = vec![
+            0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xfe, 0xff, 0x01, 0x00,
+        ];
+        let mut vec = vec![0x0b, 0x01, b's', intset.len() as u8];
+        vec.extend(intset);
+        let (_, (key, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        assert_eq!(key.as_ref(), b"s");
+        match value {
+            ValueType::Set(set) => assert_eq!(set, HashSet::from(["1", "-2"].map(Bytes::from))),
+            _ => panic!("expected a set"),
+        }
+
+        // RDB_TYPE_SET_LISTPACK holding "a" and 7
+        let listpack = vec![
+            0x0e, 0x00, 0x00, 0x00, 0x02, 0x00, 0x81, b'a', 0x02, 0x07, 0x01, 0xff,
+        ];
+        let mut vec = vec![0x14, 0x01, b't', listpack.len() as u8];
+        vec.extend(listpack);
+        let (_, (_, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        match value {
+            ValueType::Set(set) => assert_eq!(set, HashSet::from(["a", "7"].map(Bytes::from))),
+            _ => panic!("expected a set"),
+        }
+    }
+
+    #[test]
+    fn test_rdb_reader_parse_sorted_sets() {
+        // RDB_TYPE_ZSET with a = 1.5 and b = +inf
+        let vec = vec![
+            0x03, 0x01, b'z', 0x02, 0x01, b'a', 0x03, b'1', b'.', b'5', 0x01, b'b', 254,
+        ];
+        let (_, (key, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        assert_eq!(key.as_ref(), b"z");
+        match value {
+            ValueType::ZSet(members) => assert_eq!(
+                members,
+                vec![(Bytes::from("a"), 1.5), (Bytes::from("b"), f64::INFINITY)]
+            ),
+            _ => panic!("expected a sorted set"),
+        }
+
+        // RDB_TYPE_ZSET_2 with a = -2
+        let mut vec = vec![0x05, 0x01, b'z', 0x01, 0x01, b'a'];
+        vec.extend((-2.0_f64).to_le_bytes());
+        let (_, (_, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        match value {
+            ValueType::ZSet(members) => assert_eq!(members, vec![(Bytes::from("a"), -2.0)]),
+            _ => panic!("expected a sorted set"),
+        }
+
+        // RDB_TYPE_ZSET_LISTPACK with a = 7
+        let listpack = vec![
+            0x0e, 0x00, 0x00, 0x00, 0x02, 0x00, 0x81, b'a', 0x02, 0x07, 0x01, 0xff,
+        ];
+        let mut vec = vec![0x11, 0x01, b'z', listpack.len() as u8];
+        vec.extend(listpack);
+        let (_, (_, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        match value {
+            ValueType::ZSet(members) => assert_eq!(members, vec![(Bytes::from("a"), 7.0)]),
+            _ => panic!("expected a sorted set"),
+        }
+    }
+
+    #[test]
+    fn test_rdb_reader_parse_streams() {
+        use crate::data::{Data, Keyspace, StreamData};
+        use crate::parser::Value;
+        use crate::stream::{execute_stream_command, StreamCommand};
+
+        // listpack of small integers and short strings
+        fn listpack(elements: &[&str]) -> Vec<u8> {
+            let mut buf = vec![0; 6];
+            for element in elements.iter() {
+                match element.parse::<u8>() {
+                    Ok(num) if num < 128 => buf.extend([num, 0x01]),
+                    _ => {
+                        buf.push(0x80 | element.len() as u8);
+                        buf.extend(element.as_bytes());
+                        buf.push(1 + element.len() as u8);
+                    }
+                }
+            }
+            buf.push(0xff);
+            buf
+        }
+        fn raw_id(ms: u64, seq: u64) -> Vec<u8> {
+            [ms.to_be_bytes(), seq.to_be_bytes()].concat()
+        }
+
+        // master 1-0 with field f: 1-0 f=a, 1-1 deleted, then 2-0 g=c
+        let listpack = listpack(&[
+            "2", "1", "1", "f", "0", "2", "0", "0", "a", "4", "3", "0", "1", "b", "4", "0", "1",
+            "0", "1", "g", "c", "7",
+        ]);
+        let mut vec = vec![0x15, 0x01, b'x', 0x01, 0x10];
+        vec.extend(raw_id(1, 0));
+        vec.extend([0x40, listpack.len() as u8]);
+        vec.extend(listpack);
+        // length, last ID, first ID, max deleted ID and entries added
+        vec.extend([0x02, 0x02, 0x00, 0x01, 0x00, 0x01, 0x01, 0x03]);
+        // group g at 2-0 with unknown entries read
+        vec.extend([0x01, 0x01, b'g', 0x02, 0x00, 0x81]);
+        vec.extend(u64::MAX.to_be_bytes());
+        // 2-0 pending, delivered twice, to consumer c never active
+        vec.push(0x01);
+        vec.extend(raw_id(2, 0));
+        vec.extend(1000_u64.to_le_bytes());
+        vec.extend([0x02, 0x01, 0x01, b'c']);
+        vec.extend(2000_u64.to_le_bytes());
+        vec.extend(u64::MAX.to_le_bytes());
+        vec.push(0x01);
+        vec.extend(raw_id(2, 0));
+
+        let (mut iter, (key, value)) = parse_data(vec.into_iter().peekable()).unwrap();
+        assert_eq!(iter.next(), None);
+        let stream = match value {
+            ValueType::Stream(stream) => stream,
+            _ => panic!("expected a stream"),
+        };
+        assert_eq!(stream.len(), 2);
+        assert_eq!(stream.last_id(), StreamId::new(2, 0));
+
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(key.clone(), Data::Stream(StreamData::new(stream)));
+        let bulk = |text: &str| Value::BulkString(Bytes::from(text.to_string()));
+        assert_eq!(
+            execute_stream_command(&mut keyspace, StreamCommand::InfoGroups(key.clone())).unwrap(),
+            Value::Array(vec![Value::Map(vec![
+                (bulk("name"), bulk("g")),
+                (bulk("consumers"), Value::Integer(1)),
+                (bulk("pending"), Value::Integer(1)),
+                (bulk("last-delivered-id"), bulk("2-0")),
+                (bulk("entries-read"), Value::Null),
+                (bulk("lag"), Value::Integer(0))
+            ])])
+        );
+        assert_eq!(
+            execute_stream_command(
+                &mut keyspace,
+                StreamCommand::Pending {
+                    key: key.clone(),
+                    group: Bytes::from("g"),
+                    range: None
+                }
+            )
+            .unwrap(),
+            Value::Array(vec![
+                Value::Integer(1),
+                bulk("2-0"),
+                bulk("2-0"),
+                Value::Array(vec![Value::Array(vec![bulk("c"), bulk("1")])])
+            ])
+        );
     }
 }
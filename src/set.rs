@@ -0,0 +1,747 @@
+use crate::command_parser::{parse_number, to_keyword, Command};
+use crate::data::{get_live, Data, Keyspace, SetData};
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetCommand {
+    Add {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    Rem {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    Members(Bytes),
+    IsMember {
+        key: Bytes,
+        member: Bytes,
+    },
+    MIsMember {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    Card(Bytes),
+    /// SPOP; `count` is set when given, even if it is 1
+    Pop {
+        key: Bytes,
+        count: Option<usize>,
+    },
+    /// SRANDMEMBER; a negative `count` allows the same member more than once
+    RandMember {
+        key: Bytes,
+        count: Option<i64>,
+    },
+    Move {
+        source: Bytes,
+        destination: Bytes,
+        member: Bytes,
+    },
+    /// SINTER, SUNION and SDIFF
+    Combine {
+        op: SetOp,
+        keys: Vec<Bytes>,
+    },
+    /// SINTERSTORE, SUNIONSTORE and SDIFFSTORE
+    Store {
+        op: SetOp,
+        destination: Bytes,
+        keys: Vec<Bytes>,
+    },
+    /// SINTERCARD; a `limit` of 0 means no limit
+    InterCard {
+        keys: Vec<Bytes>,
+        limit: usize,
+    },
+}
+
+/// How the sets of a multi-key set command are combined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetOp {
+    Inter,
+    Union,
+    /// Members of the first set missing from all the others
+    Diff,
+}
+
+pub(crate) fn parse_sadd_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Add {
+        key: argv[1].clone(),
+        members: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_srem_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Rem {
+        key: argv[1].clone(),
+        members: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_smembers_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Members(argv[1].clone())))
+}
+
+pub(crate) fn parse_sismember_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::IsMember {
+        key: argv[1].clone(),
+        member: argv[2].clone(),
+    }))
+}
+
+pub(crate) fn parse_smismember_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::MIsMember {
+        key: argv[1].clone(),
+        members: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_scard_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Card(argv[1].clone())))
+}
+
+pub(crate) fn parse_spop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    if argv.len() > 3 {
+        return Err(RusdisError::SyntaxError);
+    }
+
+    let count = match argv.get(2) {
+        Some(count) => {
+            let count = parse_number::<i64>(count)?;
+            if count < 0 {
+                return Err(RusdisError::Generic {
+                    msg: "value is out of range, must be positive".to_string(),
+                });
+            }
+            Some(count as usize)
+        }
+        None => None,
+    };
+
+    Ok(Command::SetType(SetCommand::Pop {
+        key: argv[1].clone(),
+        count,
+    }))
+}
+
+pub(crate) fn parse_srandmember_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    if argv.len() > 3 {
+        return Err(RusdisError::SyntaxError);
+    }
+
+    let count = match argv.get(2) {
+        Some(count) => Some(parse_number::<i64>(count)?),
+        None => None,
+    };
+    // as in Redis, so that the reply's length can't overflow
+    if count.is_some_and(|count| count.unsigned_abs() > (i64::MAX / 2) as u64) {
+        return Err(RusdisError::Generic {
+            msg: "value is out of range".to_string(),
+        });
+    }
+
+    Ok(Command::SetType(SetCommand::RandMember {
+        key: argv[1].clone(),
+        count,
+    }))
+}
+
+pub(crate) fn parse_smove_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Move {
+        source: argv[1].clone(),
+        destination: argv[2].clone(),
+        member: argv[3].clone(),
+    }))
+}
+
+pub(crate) fn parse_sinter_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Combine {
+        op: SetOp::Inter,
+        keys: argv[1..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_sunion_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Combine {
+        op: SetOp::Union,
+        keys: argv[1..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_sdiff_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Combine {
+        op: SetOp::Diff,
+        keys: argv[1..].to_vec(),
+    }))
+}
+
+fn parse_store(argv: &[Bytes], op: SetOp) -> Result<Command, RusdisError> {
+    Ok(Command::SetType(SetCommand::Store {
+        op,
+        destination: argv[1].clone(),
+        keys: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_sinterstore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_store(argv, SetOp::Inter)
+}
+
+pub(crate) fn parse_sunionstore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_store(argv, SetOp::Union)
+}
+
+pub(crate) fn parse_sdiffstore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_store(argv, SetOp::Diff)
+}
+
+/// Parses `numkeys key... [LIMIT limit]` of SINTERCARD and ZINTERCARD.
+pub(crate) fn parse_intercard_args(argv: &[Bytes]) -> Result<(Vec<Bytes>, usize), RusdisError> {
+    let numkeys = match parse_number::<i64>(&argv[1])? {
+        numkeys if numkeys > 0 => numkeys as usize,
+        _ => {
+            return Err(RusdisError::Generic {
+                msg: "numkeys should be greater than 0".to_string(),
+            })
+        }
+    };
+    if numkeys > argv.len() - 2 {
+        return Err(RusdisError::Generic {
+            msg: "Number of keys can't be greater than number of args".to_string(),
+        });
+    }
+    let keys = argv[2..2 + numkeys].to_vec();
+
+    let mut limit = 0;
+    let mut args = argv[2 + numkeys..].iter();
+    while let Some(option) = args.next() {
+        match (to_keyword(option).as_str(), args.next()) {
+            ("LIMIT", Some(value)) => {
+                limit = match parse_number::<i64>(value)? {
+                    limit if limit >= 0 => limit as usize,
+                    _ => {
+                        return Err(RusdisError::Generic {
+                            msg: "LIMIT can't be negative".to_string(),
+                        })
+                    }
+                }
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    Ok((keys, limit))
+}
+
+pub(crate) fn parse_sintercard_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (keys, limit) = parse_intercard_args(argv)?;
+
+    Ok(Command::SetType(SetCommand::InterCard { keys, limit }))
+}
+
+/// The set stored at `key`, if any.
+fn get_set<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<Option<&'a mut HashSet<Bytes>>, RusdisError> {
+    match get_live(keyspace, key) {
+        Some(Data::Set(data)) => Ok(Some(data.get_set_mut())),
+        Some(_) => Err(RusdisError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// The set stored at `key`, created empty if the key does not exist.
+fn get_or_create_set<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<&'a mut HashSet<Bytes>, RusdisError> {
+    get_live(keyspace, key);
+    match keyspace
+        .entry(key.clone())
+        .or_insert_with(|| Data::Set(SetData::new(HashSet::new())))
+    {
+        Data::Set(data) => Ok(data.get_set_mut()),
+        _ => Err(RusdisError::WrongType),
+    }
+}
+
+/// Deletes `key` if it holds an empty set, as Redis never keeps empty sets.
+fn remove_if_empty(keyspace: &mut Keyspace, key: &Bytes) {
+    if let Some(Data::Set(data)) = keyspace.get(key) {
+        if data.get_set().is_empty() {
+            keyspace.remove(key);
+        }
+    }
+}
+
+/// The sets stored at `keys`, `None` for missing keys. Fails if any key holds
+/// another type.
+fn get_sets<'a>(
+    keyspace: &'a mut Keyspace,
+    keys: &[Bytes],
+) -> Result<Vec<Option<&'a HashSet<Bytes>>>, RusdisError> {
+    for key in keys.iter() {
+        get_set(keyspace, key)?;
+    }
+
+    Ok(keys
+        .iter()
+        .map(|key| match keyspace.get(key) {
+            Some(Data::Set(data)) => Some(data.get_set()),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Combines the sets at `keys`, missing keys counting as empty sets.
+fn combine(
+    keyspace: &mut Keyspace,
+    op: SetOp,
+    keys: &[Bytes],
+) -> Result<HashSet<Bytes>, RusdisError> {
+    let sets = get_sets(keyspace, keys)?;
+    let first = match sets[0] {
+        Some(first) => first,
+        None if op == SetOp::Union => &HashSet::new(),
+        None => return Ok(HashSet::new()),
+    };
+
+    Ok(match op {
+        SetOp::Inter => first
+            .iter()
+            .filter(|member| {
+                sets[1..]
+                    .iter()
+                    .all(|set| set.is_some_and(|set| set.contains(*member)))
+            })
+            .cloned()
+            .collect(),
+        SetOp::Union => {
+            let mut union = first.clone();
+            for set in sets[1..].iter().flatten() {
+                union.extend(set.iter().cloned());
+            }
+            union
+        }
+        SetOp::Diff => first
+            .iter()
+            .filter(|member| {
+                !sets[1..]
+                    .iter()
+                    .any(|set| set.is_some_and(|set| set.contains(*member)))
+            })
+            .cloned()
+            .collect(),
+    })
+}
+
+fn bulk(bytes: &Bytes) -> Value {
+    Value::BulkString(bytes.clone())
+}
+
+pub fn execute_set_command(keyspace: &mut Keyspace, cmd: SetCommand) -> Result<Value, RusdisError> {
+    match cmd {
+        SetCommand::Add { key, members } => {
+            let set = get_or_create_set(keyspace, &key)?;
+            let added = members
+                .into_iter()
+                .filter(|member| set.insert(member.clone()))
+                .count();
+
+            Ok(Value::Integer(added as i64))
+        }
+        SetCommand::Rem { key, members } => {
+            let set = match get_set(keyspace, &key)? {
+                Some(set) => set,
+                None => return Ok(Value::Integer(0)),
+            };
+            let removed = members.iter().filter(|member| set.remove(*member)).count();
+            remove_if_empty(keyspace, &key);
+
+            Ok(Value::Integer(removed as i64))
+        }
+        SetCommand::Members(key) => Ok(Value::Set(
+            get_set(keyspace, &key)?
+                .map(|set| set.iter().map(bulk).collect())
+                .unwrap_or_default(),
+        )),
+        SetCommand::IsMember { key, member } => Ok(Value::Integer(
+            get_set(keyspace, &key)?.is_some_and(|set| set.contains(&member)) as i64,
+        )),
+        SetCommand::MIsMember { key, members } => {
+            let set = get_set(keyspace, &key)?;
+
+            Ok(Value::Array(
+                members
+                    .iter()
+                    .map(|member| {
+                        Value::Integer(set.as_ref().is_some_and(|set| set.contains(member)) as i64)
+                    })
+                    .collect(),
+            ))
+        }
+        SetCommand::Card(key) => Ok(Value::Integer(
+            get_set(keyspace, &key)?.map_or(0, |set| set.len()) as i64,
+        )),
+        SetCommand::Pop { key, count } => {
+            let set = match get_set(keyspace, &key)? {
+                Some(set) => set,
+                None if count.is_some() => return Ok(Value::Array(vec![])),
+                None => return Ok(Value::Null),
+            };
+            let picked = count.unwrap_or(1).min(set.len());
+            let popped: Vec<Bytes> = set
+                .iter()
+                .cloned()
+                .choose_multiple(&mut rand::thread_rng(), picked);
+            for member in popped.iter() {
+                set.remove(member);
+            }
+            remove_if_empty(keyspace, &key);
+
+            // the members are picked at random, so replicas remove the same ones
+            let mut srem = vec![Bytes::from("SREM"), key];
+            srem.extend(popped.iter().cloned());
+            keyspace.propagate_as(if popped.is_empty() {
+                vec![]
+            } else {
+                vec![srem]
+            });
+
+            Ok(match count {
+                Some(_) => Value::Array(popped.iter().map(bulk).collect()),
+                None => popped.first().map_or(Value::Null, bulk),
+            })
+        }
+        SetCommand::RandMember { key, count } => {
+            let set = get_set(keyspace, &key)?;
+            let count = match count {
+                Some(count) => count,
+                None => {
+                    return Ok(set
+                        .and_then(|set| set.iter().choose(&mut rand::thread_rng()))
+                        .map_or(Value::Null, bulk))
+                }
+            };
+            let set = match set {
+                Some(set) => set,
+                None => return Ok(Value::Array(vec![])),
+            };
+
+            let mut rng = rand::thread_rng();
+            let picked: Vec<&Bytes> = if count >= 0 {
+                let count = (count as usize).min(set.len());
+                set.iter().choose_multiple(&mut rng, count)
+            } else {
+                let members: Vec<&Bytes> = set.iter().collect();
+                (0..count.unsigned_abs())
+                    .map(|_| members[rng.gen_range(0..members.len())])
+                    .collect()
+            };
+
+            Ok(Value::Array(picked.into_iter().map(bulk).collect()))
+        }
+        SetCommand::Move {
+            source,
+            destination,
+            member,
+        } => {
+            // the destination must hold a set too, even if nothing moves
+            get_set(keyspace, &destination)?;
+            let set = match get_set(keyspace, &source)? {
+                Some(set) => set,
+                None => return Ok(Value::Integer(0)),
+            };
+            if source == destination {
+                return Ok(Value::Integer(set.contains(&member) as i64));
+            }
+            if !set.remove(&member) {
+                return Ok(Value::Integer(0));
+            }
+            remove_if_empty(keyspace, &source);
+            get_or_create_set(keyspace, &destination)?.insert(member);
+
+            Ok(Value::Integer(1))
+        }
+        SetCommand::Combine { op, keys } => Ok(Value::Set(
+            combine(keyspace, op, &keys)?.iter().map(bulk).collect(),
+        )),
+        SetCommand::Store {
+            op,
+            destination,
+            keys,
+        } => {
+            let set = combine(keyspace, op, &keys)?;
+            let len = set.len();
+            if set.is_empty() {
+                keyspace.remove(&destination);
+            } else {
+                keyspace.insert(destination, Data::Set(SetData::new(set)));
+            }
+
+            Ok(Value::Integer(len as i64))
+        }
+        SetCommand::InterCard { keys, limit } => {
+            let sets = get_sets(keyspace, &keys)?;
+            let mut sets: Vec<&HashSet<Bytes>> = match sets.into_iter().collect() {
+                Some(sets) => sets,
+                None => return Ok(Value::Integer(0)),
+            };
+            sets.sort_by_key(|set| set.len());
+
+            let members = sets[0]
+                .iter()
+                .filter(|member| sets[1..].iter().all(|set| set.contains(*member)));
+            let count = if limit > 0 {
+                members.take(limit).count()
+            } else {
+                members.count()
+            };
+
+            Ok(Value::Integer(count as i64))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::StringData;
+    use crate::test_util::{bulk, propagated, run};
+
+    fn sorted(reply: Value) -> Vec<Value> {
+        match reply {
+            Value::Array(mut members) | Value::Set(mut members) => {
+                members.sort_by_key(|member| format!("{:?}", member));
+                members
+            }
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+    }
+
+    #[test]
+    fn test_set_membership() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(
+            run(&mut keyspace, &["SADD", "s", "a", "b", "a"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SADD", "s", "b", "c"]),
+            Value::Integer(1)
+        );
+        assert_eq!(run(&mut keyspace, &["SCARD", "s"]), Value::Integer(3));
+        assert_eq!(
+            sorted(run(&mut keyspace, &["SMEMBERS", "s"])),
+            vec![bulk("a"), bulk("b"), bulk("c")]
+        );
+        assert_eq!(run(&mut keyspace, &["SMEMBERS", "x"]), Value::Set(vec![]));
+        assert_eq!(
+            run(&mut keyspace, &["SISMEMBER", "s", "a"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SMISMEMBER", "s", "a", "x", "c"]),
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(0),
+                Value::Integer(1)
+            ])
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["SMOVE", "s", "t", "a"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SMOVE", "s", "t", "a"]),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SMOVE", "s", "s", "b"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SREM", "s", "b", "c", "x"]),
+            Value::Integer(2)
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("s")));
+        assert_eq!(run(&mut keyspace, &["SCARD", "t"]), Value::Integer(1));
+    }
+
+    #[test]
+    fn test_set_pop_and_randmember() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(run(&mut keyspace, &["SPOP", "s"]), Value::Null);
+        assert_eq!(
+            run(&mut keyspace, &["SPOP", "s", "2"]),
+            Value::Array(vec![])
+        );
+        assert_eq!(run(&mut keyspace, &["SRANDMEMBER", "s"]), Value::Null);
+
+        run(&mut keyspace, &["SADD", "s", "a", "b", "c"]);
+        match run(&mut keyspace, &["SRANDMEMBER", "s", "-5"]) {
+            Value::Array(members) => assert_eq!(members.len(), 5),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        assert_eq!(
+            sorted(run(&mut keyspace, &["SRANDMEMBER", "s", "5"])).len(),
+            3
+        );
+        assert_eq!(
+            sorted(run(
+                &mut keyspace,
+                &["SRANDMEMBER", "s", "4611686018427387903"]
+            ))
+            .len(),
+            3
+        );
+        for count in ["4611686018427387904", "-9223372036854775808"] {
+            assert_eq!(
+                run(&mut keyspace, &["SRANDMEMBER", "s", count]),
+                Value::SimpleError("ERR value is out of range".to_string())
+            );
+        }
+        assert_eq!(run(&mut keyspace, &["SCARD", "s"]), Value::Integer(3));
+
+        assert_eq!(sorted(run(&mut keyspace, &["SPOP", "s", "2"])).len(), 2);
+        assert!(matches!(
+            run(&mut keyspace, &["SPOP", "s"]),
+            Value::BulkString(_)
+        ));
+        assert!(keyspace.is_empty());
+        assert_eq!(
+            run(&mut keyspace, &["SPOP", "s", "-1"]),
+            Value::SimpleError("ERR value is out of range, must be positive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_set_pop_propagates_srem() {
+        let mut keyspace = Keyspace::new();
+        run(&mut keyspace, &["SADD", "s", "a", "b", "c"]);
+
+        run(&mut keyspace, &["SPOP", "s", "0"]);
+        assert_eq!(propagated(&mut keyspace), Some(vec![]));
+
+        let popped = match run(&mut keyspace, &["SPOP", "s"]) {
+            Value::BulkString(member) => String::from_utf8_lossy(&member).to_string(),
+            reply => panic!("unexpected reply {:?}", reply),
+        };
+        assert_eq!(
+            propagated(&mut keyspace),
+            Some(vec![vec!["SREM".to_string(), "s".to_string(), popped]])
+        );
+
+        run(&mut keyspace, &["SPOP", "s", "9223372036854775807"]);
+        let mut srem = propagated(&mut keyspace).unwrap().remove(0);
+        srem[2..].sort();
+        assert_eq!(srem.len(), 4);
+        assert_eq!(&srem[..2], ["SREM", "s"]);
+    }
+
+    #[test]
+    fn test_set_algebra() {
+        let mut keyspace = Keyspace::new();
+        run(&mut keyspace, &["SADD", "a", "1", "2", "3", "4"]);
+        run(&mut keyspace, &["SADD", "b", "3", "4", "5"]);
+        run(&mut keyspace, &["SADD", "c", "4", "6"]);
+
+        assert_eq!(
+            sorted(run(&mut keyspace, &["SINTER", "a", "b", "c"])),
+            vec![bulk("4")]
+        );
+        assert_eq!(
+            sorted(run(&mut keyspace, &["SINTER", "a", "missing"])),
+            vec![]
+        );
+        assert_eq!(
+            sorted(run(&mut keyspace, &["SUNION", "missing", "b", "c"])),
+            ["3", "4", "5", "6"].map(bulk).to_vec()
+        );
+        assert_eq!(
+            sorted(run(&mut keyspace, &["SDIFF", "a", "b", "missing"])),
+            vec![bulk("1"), bulk("2")]
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["SINTERCARD", "2", "a", "b"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SINTERCARD", "2", "a", "b", "LIMIT", "1"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SINTERCARD", "2", "a", "missing"]),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SINTERCARD", "0", "a"]),
+            Value::SimpleError("ERR numkeys should be greater than 0".to_string())
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SINTERCARD", "3", "a", "b"]),
+            Value::SimpleError(
+                "ERR Number of keys can't be greater than number of args".to_string()
+            )
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SINTERCARD", "1", "a", "LIMIT", "-1"]),
+            Value::SimpleError("ERR LIMIT can't be negative".to_string())
+        );
+
+        // the destination is replaced whatever its type, and deleted when empty
+        keyspace.insert(
+            Bytes::from("dst"),
+            Data::String(StringData::new(Bytes::from("v"))),
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SUNIONSTORE", "dst", "b", "c"]),
+            Value::Integer(4)
+        );
+        assert_eq!(run(&mut keyspace, &["SCARD", "dst"]), Value::Integer(4));
+        assert_eq!(
+            run(&mut keyspace, &["SDIFFSTORE", "dst", "dst", "b", "c"]),
+            Value::Integer(0)
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("dst")));
+        assert_eq!(
+            run(&mut keyspace, &["SINTERSTORE", "a", "a", "b"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            sorted(run(&mut keyspace, &["SMEMBERS", "a"])),
+            vec![bulk("3"), bulk("4")]
+        );
+    }
+
+    #[test]
+    fn test_set_wrong_type() {
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(
+            Bytes::from("str"),
+            Data::String(StringData::new(Bytes::from("v"))),
+        );
+        run(&mut keyspace, &["SADD", "s", "a"]);
+
+        let wrong_type = RusdisError::WrongType.to_reply();
+        assert_eq!(run(&mut keyspace, &["SADD", "str", "a"]), wrong_type);
+        assert_eq!(run(&mut keyspace, &["SMEMBERS", "str"]), wrong_type);
+        assert_eq!(run(&mut keyspace, &["SMOVE", "s", "str", "a"]), wrong_type);
+        assert_eq!(
+            run(&mut keyspace, &["SINTER", "missing", "str"]),
+            wrong_type
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SUNIONSTORE", "dst", "s", "str"]),
+            wrong_type
+        );
+        assert_eq!(run(&mut keyspace, &["SCARD", "s"]), Value::Integer(1));
+    }
+}
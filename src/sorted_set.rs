@@ -0,0 +1,388 @@
+use bytes::Bytes;
+use rand::Rng;
+use std::collections::HashMap;
+
+const MAX_LEVEL: usize = 32;
+/// Chance for a node to get one more level
+const LEVEL_P: f64 = 0.25;
+/// Index of the header node, which holds no member
+const HEADER: usize = 0;
+
+#[derive(Clone, Debug)]
+struct Level {
+    forward: Option<usize>,
+    /// Number of nodes the forward link skips, counting the node it points to
+    span: usize,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    member: Bytes,
+    score: f64,
+    levels: Vec<Level>,
+    backward: Option<usize>,
+}
+
+impl Node {
+    /// Whether the node sorts before `(score, member)`.
+    fn precedes(&self, score: f64, member: &Bytes) -> bool {
+        self.score < score || (self.score == score && self.member < *member)
+    }
+}
+
+/// Skiplist ordered by score then member, with spans to find ranks in
+/// O(log N) as Redis does. Nodes live in an arena and link by index.
+#[derive(Clone, Debug)]
+struct SkipList {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    len: usize,
+    level: usize,
+}
+
+impl SkipList {
+    fn new() -> Self {
+        SkipList {
+            nodes: vec![Node {
+                member: Bytes::new(),
+                score: 0.0,
+                levels: vec![
+                    Level {
+                        forward: None,
+                        span: 0,
+                    };
+                    MAX_LEVEL
+                ],
+                backward: None,
+            }],
+            free: vec![],
+            len: 0,
+            level: 1,
+        }
+    }
+
+    fn random_level() -> usize {
+        let mut rng = rand::thread_rng();
+        let mut level = 1;
+        while level < MAX_LEVEL && rng.gen::<f64>() < LEVEL_P {
+            level += 1;
+        }
+
+        level
+    }
+
+    fn alloc(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(idx) => {
+                self.nodes[idx] = node;
+                idx
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// The last node before `(score, member)` on every level.
+    fn find_update(&self, score: f64, member: &Bytes) -> ([usize; MAX_LEVEL], [usize; MAX_LEVEL]) {
+        let mut update = [HEADER; MAX_LEVEL];
+        let mut rank = [0; MAX_LEVEL];
+        let mut x = HEADER;
+        for i in (0..self.level).rev() {
+            rank[i] = if i == self.level - 1 { 0 } else { rank[i + 1] };
+            while let Some(next) = self.nodes[x].levels[i].forward {
+                if !self.nodes[next].precedes(score, member) {
+                    break;
+                }
+                rank[i] += self.nodes[x].levels[i].span;
+                x = next;
+            }
+            update[i] = x;
+        }
+
+        (update, rank)
+    }
+
+    /// Inserts a member that is not in the list yet.
+    fn insert(&mut self, score: f64, member: Bytes) {
+        let (mut update, mut rank) = self.find_update(score, &member);
+
+        let level = Self::random_level();
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEADER;
+                self.nodes[HEADER].levels[i].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let new = self.alloc(Node {
+            member,
+            score,
+            levels: vec![
+                Level {
+                    forward: None,
+                    span: 0,
+                };
+                level
+            ],
+            backward: None,
+        });
+        for i in 0..level {
+            let prev = update[i];
+            let skipped = rank[0] - rank[i];
+            self.nodes[new].levels[i] = Level {
+                forward: self.nodes[prev].levels[i].forward,
+                span: self.nodes[prev].levels[i].span - skipped,
+            };
+            self.nodes[prev].levels[i] = Level {
+                forward: Some(new),
+                span: skipped + 1,
+            };
+        }
+        for (i, prev) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[*prev].levels[i].span += 1;
+        }
+
+        self.nodes[new].backward = (update[0] != HEADER).then_some(update[0]);
+        if let Some(next) = self.nodes[new].levels[0].forward {
+            self.nodes[next].backward = Some(new);
+        }
+        self.len += 1;
+    }
+
+    fn remove(&mut self, score: f64, member: &Bytes) -> bool {
+        let (update, _) = self.find_update(score, member);
+        let x = match self.nodes[update[0]].levels[0].forward {
+            Some(x) if self.nodes[x].score == score && self.nodes[x].member == *member => x,
+            _ => return false,
+        };
+
+        for (i, prev) in update.iter().enumerate().take(self.level) {
+            if self.nodes[*prev].levels[i].forward == Some(x) {
+                self.nodes[*prev].levels[i] = Level {
+                    forward: self.nodes[x].levels[i].forward,
+                    span: self.nodes[*prev].levels[i].span + self.nodes[x].levels[i].span - 1,
+                };
+            } else {
+                self.nodes[*prev].levels[i].span -= 1;
+            }
+        }
+        if let Some(next) = self.nodes[x].levels[0].forward {
+            self.nodes[next].backward = self.nodes[x].backward;
+        }
+        while self.level > 1 && self.nodes[HEADER].levels[self.level - 1].forward.is_none() {
+            self.level -= 1;
+        }
+
+        self.nodes[x].member = Bytes::new();
+        self.nodes[x].levels = vec![];
+        self.free.push(x);
+        self.len -= 1;
+
+        true
+    }
+
+    /// Number of leading nodes for which `pred` holds, `pred` holding for a
+    /// prefix of the list.
+    fn count_while(&self, pred: impl Fn(f64, &Bytes) -> bool) -> usize {
+        let mut x = HEADER;
+        let mut rank = 0;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.nodes[x].levels[i].forward {
+                if !pred(self.nodes[next].score, &self.nodes[next].member) {
+                    break;
+                }
+                rank += self.nodes[x].levels[i].span;
+                x = next;
+            }
+        }
+
+        rank
+    }
+
+    /// The node at the 0-based `rank`.
+    fn node_at(&self, rank: usize) -> Option<usize> {
+        let target = rank + 1;
+        let mut x = HEADER;
+        let mut traversed = 0;
+        for i in (0..self.level).rev() {
+            while let Some(next) = self.nodes[x].levels[i].forward {
+                if traversed + self.nodes[x].levels[i].span > target {
+                    break;
+                }
+                traversed += self.nodes[x].levels[i].span;
+                x = next;
+            }
+            if traversed == target {
+                return Some(x);
+            }
+        }
+
+        None
+    }
+}
+
+/// Members ordered by score, indexed both by member and by score: a hash
+/// gives the score of a member and a skiplist keeps the order.
+#[derive(Clone, Debug)]
+pub struct SortedSet {
+    scores: HashMap<Bytes, f64>,
+    list: SkipList,
+}
+
+impl Default for SortedSet {
+    fn default() -> Self {
+        SortedSet {
+            scores: HashMap::new(),
+            list: SkipList::new(),
+        }
+    }
+}
+
+impl SortedSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &Bytes) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Adds `member` or updates its score. Returns whether it is new.
+    pub fn insert(&mut self, member: Bytes, score: f64) -> bool {
+        match self.scores.insert(member.clone(), score) {
+            Some(old) if old == score => false,
+            Some(old) => {
+                self.list.remove(old, &member);
+                self.list.insert(score, member);
+                false
+            }
+            None => {
+                self.list.insert(score, member);
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, member: &Bytes) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => self.list.remove(score, member),
+            None => false,
+        }
+    }
+
+    /// 0-based position of `member` in ascending order.
+    pub fn rank(&self, member: &Bytes) -> Option<usize> {
+        let score = self.score(member)?;
+
+        Some(
+            self.list
+                .count_while(|s, m| s < score || (s == score && m < member)),
+        )
+    }
+
+    /// Number of leading members, in ascending order, for which `pred` holds.
+    /// `pred` must hold for a prefix of the set.
+    pub fn count_while(&self, pred: impl Fn(f64, &Bytes) -> bool) -> usize {
+        self.list.count_while(pred)
+    }
+
+    /// Iterates from the member at `rank`, towards higher scores or, if
+    /// `rev`, towards lower ones.
+    pub fn iter_from(&self, rank: usize, rev: bool) -> Iter<'_> {
+        Iter {
+            list: &self.list,
+            node: self.list.node_at(rank),
+            rev,
+        }
+    }
+
+    /// Iterates in ascending order.
+    pub fn iter(&self) -> Iter<'_> {
+        self.iter_from(0, false)
+    }
+}
+
+pub struct Iter<'a> {
+    list: &'a SkipList,
+    node: Option<usize>,
+    rev: bool,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a Bytes, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = &self.list.nodes[self.node?];
+        self.node = if self.rev {
+            node.backward
+        } else {
+            node.levels[0].forward
+        };
+
+        Some((&node.member, node.score))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn member(num: usize) -> Bytes {
+        Bytes::from(format!("m{:03}", num))
+    }
+
+    #[test]
+    fn test_sorted_set_matches_sorted_vec() {
+        let mut rng = rand::thread_rng();
+        let mut zset = SortedSet::new();
+        let mut expected: HashMap<Bytes, f64> = HashMap::new();
+
+        for _ in 0..2000 {
+            let m = member(rng.gen_range(0..200));
+            if rng.gen_bool(0.3) {
+                assert_eq!(zset.remove(&m), expected.remove(&m).is_some());
+            } else {
+                let score = rng.gen_range(0..50) as f64;
+                assert_eq!(
+                    zset.insert(m.clone(), score),
+                    expected.insert(m, score).is_none()
+                );
+            }
+        }
+
+        let mut sorted: Vec<(Bytes, f64)> = expected.into_iter().collect();
+        sorted.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+        assert_eq!(zset.len(), sorted.len());
+        let all: Vec<(Bytes, f64)> = zset.iter().map(|(m, s)| (m.clone(), s)).collect();
+        assert_eq!(all, sorted);
+        let reversed: Vec<(Bytes, f64)> = zset
+            .iter_from(sorted.len() - 1, true)
+            .map(|(m, s)| (m.clone(), s))
+            .collect();
+        assert_eq!(reversed, sorted.iter().rev().cloned().collect::<Vec<_>>());
+
+        for (rank, (m, score)) in sorted.iter().enumerate() {
+            assert_eq!(zset.rank(m), Some(rank));
+            assert_eq!(zset.score(m), Some(*score));
+            assert_eq!(zset.iter_from(rank, false).next().unwrap().0, m);
+        }
+        assert_eq!(
+            zset.count_while(|score, _| score < 25.0),
+            sorted.iter().filter(|(_, score)| *score < 25.0).count()
+        );
+        assert!(zset.iter_from(sorted.len(), false).next().is_none());
+    }
+}
@@ -0,0 +1,2600 @@
+use crate::blocking::BlockingOp;
+use crate::command_parser::{parse_number, to_keyword, Command};
+use crate::data::{get_live, Data, Keyspace, StreamData};
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Bound;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Entries per radix tree node in Redis; approximate trimming only removes
+/// whole nodes.
+const STREAM_NODE_MAX_ENTRIES: usize = 100;
+
+/// ID of a stream entry, `ms-seq`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        StreamId { ms, seq }
+    }
+
+    /// Parses `ms-seq`, or a lone `ms` with `missing_seq` as the sequence.
+    pub fn parse(bytes: &[u8], missing_seq: u64) -> Option<StreamId> {
+        let id = std::str::from_utf8(bytes).ok()?;
+        let (ms, seq) = match id.split_once('-') {
+            Some((ms, seq)) => (ms, seq.parse().ok()?),
+            None => (id, missing_seq),
+        };
+
+        Some(StreamId::new(ms.parse().ok()?, seq))
+    }
+
+    /// The smallest ID greater than this one.
+    pub fn next(&self) -> Option<StreamId> {
+        match self.seq.checked_add(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_add(1)?, 0)),
+        }
+    }
+
+    /// The greatest ID smaller than this one.
+    pub fn prev(&self) -> Option<StreamId> {
+        match self.seq.checked_sub(1) {
+            Some(seq) => Some(StreamId::new(self.ms, seq)),
+            None => Some(StreamId::new(self.ms.checked_sub(1)?, u64::MAX)),
+        }
+    }
+
+    pub fn to_bytes(self) -> Bytes {
+        Bytes::from(self.to_string())
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// Field-value pairs of an entry, in the order they were added.
+pub type Fields = Vec<(Bytes, Bytes)>;
+
+/// Unix time in milliseconds.
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// An entry delivered to a consumer of a group and not acknowledged yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingEntry {
+    consumer: Bytes,
+    /// Unix time of the last delivery, in milliseconds
+    delivery_time: u64,
+    delivery_count: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Consumer {
+    /// Unix time of the last attempted interaction, in milliseconds
+    seen_time: u64,
+    /// Unix time of the last successful interaction, if any
+    active_time: Option<u64>,
+    pending: BTreeSet<StreamId>,
+}
+
+/// Consumers sharing the entries of a stream, each entry delivered to one of
+/// them and pending until acknowledged.
+#[derive(Clone, Debug, Default)]
+pub struct ConsumerGroup {
+    last_id: StreamId,
+    /// Entries the group has read, `None` when it cannot be told
+    entries_read: Option<u64>,
+    pending: BTreeMap<StreamId, PendingEntry>,
+    consumers: BTreeMap<Bytes, Consumer>,
+}
+
+impl ConsumerGroup {
+    pub fn new(last_id: StreamId, entries_read: Option<u64>) -> Self {
+        ConsumerGroup {
+            last_id,
+            entries_read,
+            ..Default::default()
+        }
+    }
+
+    /// Adds a consumer. Returns false if it already exists.
+    pub fn add_consumer(&mut self, name: Bytes, seen_time: u64, active_time: Option<u64>) -> bool {
+        if self.consumers.contains_key(&name) {
+            return false;
+        }
+        self.consumers.insert(
+            name,
+            Consumer {
+                seen_time,
+                active_time,
+                pending: BTreeSet::new(),
+            },
+        );
+
+        true
+    }
+
+    /// The consumer called `name`, created if missing, marked as seen at `now`.
+    fn seen_consumer(&mut self, name: &Bytes, now: u64) -> &mut Consumer {
+        let consumer = self.consumers.entry(name.clone()).or_default();
+        consumer.seen_time = now;
+
+        consumer
+    }
+
+    /// Records `id` as delivered to `consumer`, taking it from the consumer
+    /// that had it pending, if any.
+    pub fn deliver(
+        &mut self,
+        id: StreamId,
+        consumer: &Bytes,
+        delivery_time: u64,
+        delivery_count: u64,
+    ) {
+        if let Some(previous) = self.pending.get(&id) {
+            if previous.consumer != *consumer {
+                if let Some(previous) = self.consumers.get_mut(&previous.consumer) {
+                    previous.pending.remove(&id);
+                }
+            }
+        }
+        self.pending.insert(
+            id,
+            PendingEntry {
+                consumer: consumer.clone(),
+                delivery_time,
+                delivery_count,
+            },
+        );
+        self.consumers
+            .entry(consumer.clone())
+            .or_default()
+            .pending
+            .insert(id);
+    }
+
+    /// Removes `id` from the pending entries. Returns whether it was pending.
+    fn ack(&mut self, id: &StreamId) -> bool {
+        let entry = match self.pending.remove(id) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        if let Some(consumer) = self.consumers.get_mut(&entry.consumer) {
+            consumer.pending.remove(id);
+        }
+
+        true
+    }
+
+    /// Removes a consumer and its pending entries, returning how many it had.
+    fn remove_consumer(&mut self, name: &Bytes) -> usize {
+        let consumer = match self.consumers.remove(name) {
+            Some(consumer) => consumer,
+            None => return 0,
+        };
+        for id in consumer.pending.iter() {
+            self.pending.remove(id);
+        }
+
+        consumer.pending.len()
+    }
+}
+
+/// Append-only log of entries ordered by ID.
+#[derive(Clone, Debug, Default)]
+pub struct Stream {
+    entries: BTreeMap<StreamId, Fields>,
+    /// Greatest ID ever added, kept when the entry is deleted
+    last_id: StreamId,
+    /// Greatest ID removed by XDEL
+    max_deleted_id: StreamId,
+    /// Entries ever added, counting the ones deleted since
+    entries_added: u64,
+    groups: BTreeMap<Bytes, ConsumerGroup>,
+}
+
+impl Stream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    /// ID of the first entry, 0-0 when there is none.
+    fn first_id(&self) -> StreamId {
+        self.entries
+            .first_key_value()
+            .map_or(StreamId::MIN, |(id, _)| *id)
+    }
+
+    /// Restores the metadata of a stream loaded from disk.
+    pub fn set_metadata(
+        &mut self,
+        last_id: StreamId,
+        max_deleted_id: StreamId,
+        entries_added: u64,
+    ) {
+        self.last_id = last_id;
+        self.max_deleted_id = max_deleted_id;
+        self.entries_added = entries_added;
+    }
+
+    /// Adds a consumer group. Returns false if one has that name already.
+    pub fn insert_group(&mut self, name: Bytes, group: ConsumerGroup) -> bool {
+        if self.groups.contains_key(&name) {
+            return false;
+        }
+        self.groups.insert(name, group);
+
+        true
+    }
+
+    /// Whether an entry still in the ID range of the stream was deleted at
+    /// `start` or after it.
+    fn has_tombstones(&self, start: StreamId) -> bool {
+        !self.is_empty()
+            && self.max_deleted_id != StreamId::MIN
+            && self.first_id() <= self.max_deleted_id
+            && start <= self.max_deleted_id
+    }
+
+    /// Number of entries ever added up to `id`, when no deletion in the way
+    /// makes it unknown.
+    fn entries_read_at(&self, id: StreamId) -> Option<u64> {
+        if self.entries_added == 0 || (self.is_empty() && id <= self.last_id) {
+            return Some(self.entries_added);
+        }
+        match id.cmp(&self.last_id) {
+            std::cmp::Ordering::Equal => return Some(self.entries_added),
+            std::cmp::Ordering::Greater => return None,
+            std::cmp::Ordering::Less => {}
+        }
+
+        let first_id = self.first_id();
+        if self.max_deleted_id == StreamId::MIN || self.max_deleted_id < first_id {
+            // nothing deleted past the first entry
+            let before_first = self.entries_added - self.len() as u64;
+            match id.cmp(&first_id) {
+                std::cmp::Ordering::Less => return Some(before_first),
+                std::cmp::Ordering::Equal => return Some(before_first + 1),
+                std::cmp::Ordering::Greater => {}
+            }
+        }
+
+        None
+    }
+
+    /// Entries the group has yet to read, when it can be told.
+    fn lag(&self, group: &ConsumerGroup) -> Option<u64> {
+        if self.entries_added == 0 {
+            return Some(0);
+        }
+        let entries_read = match group.entries_read {
+            Some(entries_read) if !self.has_tombstones(group.last_id) => entries_read,
+            _ => self.entries_read_at(group.last_id)?,
+        };
+
+        Some(self.entries_added.saturating_sub(entries_read))
+    }
+
+    pub fn last_entry(&self) -> Option<(&StreamId, &Fields)> {
+        self.entries.last_key_value()
+    }
+
+    /// The ID XADD gives a new entry, which must be greater than the last ID.
+    pub fn next_id(&self, id: NewId) -> Result<StreamId, RusdisError> {
+        let not_greater = || {
+            generic_error(
+                "The ID specified in XADD is equal or smaller than the target stream top item",
+            )
+        };
+
+        let id = match id {
+            NewId::Auto => {
+                let now = now_ms();
+                if now > self.last_id.ms {
+                    StreamId::new(now, 0)
+                } else {
+                    self.last_id.next().ok_or_else(|| {
+                        generic_error(
+                            "The stream has exhausted the last possible ID, unable to add more items",
+                        )
+                    })?
+                }
+            }
+            NewId::AutoSeq(ms) if ms == self.last_id.ms => {
+                StreamId::new(ms, self.last_id.seq.checked_add(1).ok_or_else(not_greater)?)
+            }
+            NewId::AutoSeq(ms) => StreamId::new(ms, 0),
+            NewId::Explicit(StreamId::MIN) => {
+                return Err(generic_error(
+                    "The ID specified in XADD must be greater than 0-0",
+                ))
+            }
+            NewId::Explicit(id) => id,
+        };
+
+        if id <= self.last_id {
+            return Err(not_greater());
+        }
+
+        Ok(id)
+    }
+
+    /// Appends an entry whose ID is greater than the last one.
+    pub fn append(&mut self, id: StreamId, fields: Fields) {
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        self.entries_added += 1;
+    }
+
+    pub fn remove(&mut self, id: &StreamId) -> bool {
+        if self.entries.remove(id).is_none() {
+            return false;
+        }
+        self.max_deleted_id = self.max_deleted_id.max(*id);
+
+        true
+    }
+
+    /// Entries with IDs from `start` to `end`, both inclusive, in ascending order
+    /// or descending if `rev`.
+    pub fn range(
+        &self,
+        start: StreamId,
+        end: StreamId,
+        rev: bool,
+    ) -> Box<dyn Iterator<Item = (&StreamId, &Fields)> + '_> {
+        if start > end {
+            return Box::new(std::iter::empty());
+        }
+
+        let range = self.entries.range(start..=end);
+        if rev {
+            Box::new(range.rev())
+        } else {
+            Box::new(range)
+        }
+    }
+
+    /// Entries with IDs greater than `id`, in ascending order.
+    pub fn after(&self, id: StreamId) -> impl Iterator<Item = (&StreamId, &Fields)> {
+        self.entries.range((Bound::Excluded(id), Bound::Unbounded))
+    }
+
+    /// Delivers up to `count` entries to `consumer` of `group`: entries never
+    /// delivered to the group, or the ones pending for the consumer after an
+    /// ID, whose delivery count grows. Entries deleted since are replied with
+    /// no fields. Returns `None` when there are no new entries.
+    fn read_group(
+        &mut self,
+        group: &Bytes,
+        consumer: &Bytes,
+        from: GroupReadFrom,
+        count: usize,
+        noack: bool,
+        now: u64,
+    ) -> Option<Vec<Value>> {
+        let after = match from {
+            GroupReadFrom::New => self.groups.get(group)?.last_id,
+            GroupReadFrom::Pending(id) => {
+                let group = self.groups.get_mut(group)?;
+                let ids: Vec<StreamId> = group
+                    .seen_consumer(consumer, now)
+                    .pending
+                    .range((Bound::Excluded(id), Bound::Unbounded))
+                    .take(count)
+                    .copied()
+                    .collect();
+
+                let mut replies = vec![];
+                for id in ids.iter() {
+                    replies.push(match self.entries.get(id) {
+                        Some(fields) => entry_reply((id, fields)),
+                        None => {
+                            Value::Array(vec![Value::BulkString(id.to_bytes()), Value::NullArray])
+                        }
+                    });
+                    if let Some(entry) = group.pending.get_mut(id) {
+                        entry.delivery_time = now;
+                        entry.delivery_count += 1;
+                    }
+                }
+                if !ids.is_empty() {
+                    group.seen_consumer(consumer, now).active_time = Some(now);
+                }
+                return Some(replies);
+            }
+        };
+
+        let entries: Vec<(StreamId, Value)> = self
+            .after(after)
+            .take(count)
+            .map(|entry| (*entry.0, entry_reply(entry)))
+            .collect();
+        // the read counter follows each delivery while it can be told
+        let mut entries_read = self.groups.get(group)?.entries_read;
+        for (id, _) in entries.iter() {
+            entries_read = match entries_read {
+                Some(read) if !self.has_tombstones(*id) => Some(read + 1),
+                _ if self.entries_added > 0 => self.entries_read_at(*id),
+                read => read,
+            };
+        }
+
+        let group = self.groups.get_mut(group)?;
+        group.seen_consumer(consumer, now);
+        let (last_id, _) = entries.last()?;
+        group.last_id = *last_id;
+        group.entries_read = entries_read;
+        group.seen_consumer(consumer, now).active_time = Some(now);
+        if !noack {
+            for (id, _) in entries.iter() {
+                group.deliver(*id, consumer, now, 1);
+            }
+        }
+
+        Some(entries.into_iter().map(|(_, reply)| reply).collect())
+    }
+
+    /// Removes the oldest entries as `options` asks. Returns how many were removed.
+    pub fn trim(&mut self, options: &TrimOptions) -> usize {
+        let excess = match options.strategy {
+            TrimStrategy::MaxLen(maxlen) => self.len().saturating_sub(maxlen),
+            TrimStrategy::MinId(minid) => self.entries.range(..minid).count(),
+        };
+        let count = if options.approx {
+            let mut nodes = excess / STREAM_NODE_MAX_ENTRIES;
+            if options.limit > 0 {
+                nodes = nodes.min(options.limit / STREAM_NODE_MAX_ENTRIES);
+            }
+            nodes * STREAM_NODE_MAX_ENTRIES
+        } else {
+            excess
+        };
+
+        for _ in 0..count {
+            self.entries.pop_first();
+        }
+
+        count
+    }
+}
+
+/// ID asked for by XADD.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NewId {
+    /// `*`
+    Auto,
+    /// `ms-*`
+    AutoSeq(u64),
+    Explicit(StreamId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrimStrategy {
+    MaxLen(usize),
+    /// Entries with smaller IDs are removed
+    MinId(StreamId),
+}
+
+/// MAXLEN or MINID trimming of XADD and XTRIM.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrimOptions {
+    pub strategy: TrimStrategy,
+    /// `~`, removing whole nodes only
+    pub approx: bool,
+    /// Most entries removed, 0 for no limit
+    pub limit: usize,
+}
+
+/// Where XREAD starts reading a stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadFrom {
+    /// Entries after the ID
+    After(StreamId),
+    /// `$`, entries added after the command runs
+    New,
+    /// `+`, the last entry
+    LastEntry,
+}
+
+/// Where XREADGROUP starts reading a stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupReadFrom {
+    /// `>`, entries never delivered to the group
+    New,
+    /// Entries pending for the consumer with IDs greater than this one
+    Pending(StreamId),
+}
+
+/// Delivery time XCLAIM sets on the entries it claims.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeliveryTime {
+    /// IDLE, milliseconds before now
+    Idle(i64),
+    /// TIME, Unix time in milliseconds
+    At(i64),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClaimOptions {
+    /// Now when not given
+    pub delivery_time: Option<DeliveryTime>,
+    /// RETRYCOUNT, otherwise the delivery count is incremented
+    pub retry_count: Option<u64>,
+    /// Claims entries of the stream pending for no consumer
+    pub force: bool,
+    /// Replies with IDs only, leaving delivery counts alone
+    pub justid: bool,
+    /// Moves the last ID of the group forward to it
+    pub last_id: Option<StreamId>,
+}
+
+/// The extended form of XPENDING.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingRange {
+    pub min_idle: u64,
+    pub start: StreamId,
+    pub end: StreamId,
+    pub count: usize,
+    pub consumer: Option<Bytes>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamCommand {
+    Add {
+        key: Bytes,
+        id: NewId,
+        no_mkstream: bool,
+        trim: Option<TrimOptions>,
+        fields: Fields,
+    },
+    /// XRANGE and, with `rev`, XREVRANGE
+    Range {
+        key: Bytes,
+        start: StreamId,
+        end: StreamId,
+        count: Option<usize>,
+        rev: bool,
+    },
+    Len(Bytes),
+    Del {
+        key: Bytes,
+        ids: Vec<StreamId>,
+    },
+    Trim {
+        key: Bytes,
+        options: TrimOptions,
+    },
+    /// XREAD without BLOCK; a `count` of 0 means no limit
+    Read {
+        streams: Vec<(Bytes, ReadFrom)>,
+        count: usize,
+    },
+    /// XGROUP CREATE; an `id` of `None` is `$`, the last ID of the stream
+    GroupCreate {
+        key: Bytes,
+        group: Bytes,
+        id: Option<StreamId>,
+        mkstream: bool,
+        entries_read: Option<u64>,
+    },
+    GroupSetId {
+        key: Bytes,
+        group: Bytes,
+        id: Option<StreamId>,
+        entries_read: Option<u64>,
+    },
+    GroupDestroy {
+        key: Bytes,
+        group: Bytes,
+    },
+    GroupCreateConsumer {
+        key: Bytes,
+        group: Bytes,
+        consumer: Bytes,
+    },
+    GroupDelConsumer {
+        key: Bytes,
+        group: Bytes,
+        consumer: Bytes,
+    },
+    /// XREADGROUP without BLOCK; a `count` of 0 means no limit
+    ReadGroup {
+        group: Bytes,
+        consumer: Bytes,
+        streams: Vec<(Bytes, GroupReadFrom)>,
+        count: usize,
+        noack: bool,
+    },
+    Ack {
+        key: Bytes,
+        group: Bytes,
+        ids: Vec<StreamId>,
+    },
+    /// XPENDING, summarized when `range` is `None`
+    Pending {
+        key: Bytes,
+        group: Bytes,
+        range: Option<PendingRange>,
+    },
+    Claim {
+        key: Bytes,
+        group: Bytes,
+        consumer: Bytes,
+        min_idle: u64,
+        ids: Vec<StreamId>,
+        options: ClaimOptions,
+    },
+    AutoClaim {
+        key: Bytes,
+        group: Bytes,
+        consumer: Bytes,
+        min_idle: u64,
+        start: StreamId,
+        count: usize,
+        justid: bool,
+    },
+    /// XINFO STREAM, with FULL and its COUNT in `full`, 0 for no limit
+    InfoStream {
+        key: Bytes,
+        full: Option<usize>,
+    },
+    InfoGroups(Bytes),
+    InfoConsumers {
+        key: Bytes,
+        group: Bytes,
+    },
+}
+
+fn generic_error(msg: &str) -> RusdisError {
+    RusdisError::Generic {
+        msg: msg.to_string(),
+    }
+}
+
+fn invalid_id() -> RusdisError {
+    generic_error("Invalid stream ID specified as stream command argument")
+}
+
+fn parse_id(bytes: &Bytes, missing_seq: u64) -> Result<StreamId, RusdisError> {
+    StreamId::parse(bytes, missing_seq).ok_or_else(invalid_id)
+}
+
+/// Parses the trimming options of XADD and XTRIM from `argv[idx]` on, along
+/// with NOMKSTREAM for XADD. XADD stops at the first other argument, its
+/// ID. Returns the options, whether NOMKSTREAM was given and the index of the
+/// next argument.
+fn parse_trim_options(
+    argv: &[Bytes],
+    mut idx: usize,
+    xadd: bool,
+) -> Result<(Option<TrimOptions>, bool, usize), RusdisError> {
+    let (mut strategy, mut approx, mut limit, mut no_mkstream) = (None, false, None, false);
+
+    while idx < argv.len() {
+        let keyword = to_keyword(&argv[idx]);
+        match keyword.as_str() {
+            "NOMKSTREAM" if xadd => {
+                no_mkstream = true;
+                idx += 1;
+            }
+            "MAXLEN" | "MINID" => {
+                idx += 1;
+                match argv.get(idx).map(|op| &op[..]) {
+                    Some(b"~") => {
+                        approx = true;
+                        idx += 1;
+                    }
+                    Some(b"=") => idx += 1,
+                    _ => {}
+                }
+                let threshold = argv.get(idx).ok_or(RusdisError::SyntaxError)?;
+                strategy = Some(if keyword == "MAXLEN" {
+                    let maxlen = parse_number::<i64>(threshold)?;
+                    if maxlen < 0 {
+                        return Err(generic_error("The MAXLEN argument must be >= 0."));
+                    }
+                    TrimStrategy::MaxLen(maxlen as usize)
+                } else {
+                    TrimStrategy::MinId(parse_id(threshold, 0)?)
+                });
+                idx += 1;
+            }
+            "LIMIT" => {
+                let value = argv.get(idx + 1).ok_or(RusdisError::SyntaxError)?;
+                let value = parse_number::<i64>(value)?;
+                if value < 0 {
+                    return Err(generic_error("The LIMIT argument must be >= 0."));
+                }
+                limit = Some(value as usize);
+                idx += 2;
+            }
+            _ if xadd => break,
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    let options = match strategy {
+        Some(_) if limit.is_some() && !approx => {
+            return Err(generic_error(
+                "syntax error, LIMIT cannot be used without the special ~ option",
+            ))
+        }
+        Some(strategy) => Some(TrimOptions {
+            strategy,
+            approx,
+            limit: limit.unwrap_or(if approx {
+                100 * STREAM_NODE_MAX_ENTRIES
+            } else {
+                0
+            }),
+        }),
+        None if limit.is_some() => {
+            return Err(generic_error(
+                "syntax error, LIMIT cannot be used without specifying a trimming strategy",
+            ))
+        }
+        None => None,
+    };
+
+    Ok((options, no_mkstream, idx))
+}
+
+pub(crate) fn parse_xadd_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (trim, no_mkstream, idx) = parse_trim_options(argv, 2, true)?;
+
+    let pairs = argv.get(idx + 1..).unwrap_or_default();
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return Err(RusdisError::WrongArity {
+            command: "xadd".to_string(),
+        });
+    }
+
+    let id = &argv[idx];
+    let id = if &id[..] == b"*" {
+        NewId::Auto
+    } else if let Some(ms) = id.strip_suffix(b"-*") {
+        NewId::AutoSeq(
+            std::str::from_utf8(ms)
+                .ok()
+                .and_then(|ms| ms.parse().ok())
+                .ok_or_else(invalid_id)?,
+        )
+    } else {
+        NewId::Explicit(parse_id(id, 0)?)
+    };
+
+    Ok(Command::Stream(StreamCommand::Add {
+        key: argv[1].clone(),
+        id,
+        no_mkstream,
+        trim,
+        fields: pairs
+            .chunks(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect(),
+    }))
+}
+
+/// Parses an XRANGE bound: `-`, `+`, an ID, or an ID after `(` to exclude it.
+/// A lone `ms` covers every sequence number of that millisecond.
+fn parse_range_bound(bytes: &Bytes, start: bool) -> Result<StreamId, RusdisError> {
+    match &bytes[..] {
+        b"-" => return Ok(StreamId::MIN),
+        b"+" => return Ok(StreamId::MAX),
+        _ => {}
+    }
+
+    let missing_seq = if start { 0 } else { u64::MAX };
+    match bytes.strip_prefix(b"(") {
+        Some(id) => {
+            let id = StreamId::parse(id, missing_seq).ok_or_else(invalid_id)?;
+            let id = if start { id.next() } else { id.prev() };
+            id.ok_or_else(|| {
+                generic_error(if start {
+                    "invalid start ID for the interval"
+                } else {
+                    "invalid end ID for the interval"
+                })
+            })
+        }
+        None => parse_id(bytes, missing_seq),
+    }
+}
+
+fn parse_range(argv: &[Bytes], rev: bool) -> Result<Command, RusdisError> {
+    let (start, end) = if rev {
+        (&argv[3], &argv[2])
+    } else {
+        (&argv[2], &argv[3])
+    };
+
+    let count = match &argv[4..] {
+        [] => None,
+        [option, count] if to_keyword(option) == "COUNT" => {
+            Some(parse_number::<i64>(count)?.max(0) as usize)
+        }
+        _ => return Err(RusdisError::SyntaxError),
+    };
+
+    Ok(Command::Stream(StreamCommand::Range {
+        key: argv[1].clone(),
+        start: parse_range_bound(start, true)?,
+        end: parse_range_bound(end, false)?,
+        count,
+        rev,
+    }))
+}
+
+pub(crate) fn parse_xrange_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_range(argv, false)
+}
+
+pub(crate) fn parse_xrevrange_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_range(argv, true)
+}
+
+pub(crate) fn parse_xlen_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Stream(StreamCommand::Len(argv[1].clone())))
+}
+
+pub(crate) fn parse_xdel_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Stream(StreamCommand::Del {
+        key: argv[1].clone(),
+        ids: argv[2..]
+            .iter()
+            .map(|id| parse_id(id, 0))
+            .collect::<Result<_, _>>()?,
+    }))
+}
+
+pub(crate) fn parse_xtrim_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    match parse_trim_options(argv, 2, false)? {
+        (Some(options), _, _) => Ok(Command::Stream(StreamCommand::Trim {
+            key: argv[1].clone(),
+            options,
+        })),
+        (None, _, _) => Err(RusdisError::SyntaxError),
+    }
+}
+
+/// Options of XREAD and XREADGROUP given before STREAMS.
+#[derive(Default)]
+struct ReadOptions {
+    /// GROUP name and consumer
+    group: Option<(Bytes, Bytes)>,
+    count: usize,
+    /// BLOCK, with `None` inside to block forever
+    block: Option<Option<Duration>>,
+    noack: bool,
+}
+
+/// Parses XREAD or XREADGROUP, returning the options, the stream keys and
+/// their IDs.
+fn parse_read(
+    argv: &[Bytes],
+    xreadgroup: bool,
+) -> Result<(ReadOptions, &[Bytes], &[Bytes]), RusdisError> {
+    let mut options = ReadOptions::default();
+    let mut streams_idx = None;
+
+    let mut idx = 1;
+    while idx < argv.len() {
+        match to_keyword(&argv[idx]).as_str() {
+            "COUNT" => {
+                let value = argv.get(idx + 1).ok_or(RusdisError::SyntaxError)?;
+                options.count = parse_number::<i64>(value)?.max(0) as usize;
+                idx += 2;
+            }
+            "BLOCK" => {
+                let value = argv.get(idx + 1).ok_or(RusdisError::SyntaxError)?;
+                let millis = parse_number::<i64>(value)
+                    .map_err(|_| generic_error("timeout is not an integer or out of range"))?;
+                if millis < 0 {
+                    return Err(generic_error("timeout is negative"));
+                }
+                options.block = Some((millis > 0).then(|| Duration::from_millis(millis as u64)));
+                idx += 2;
+            }
+            "GROUP" if idx + 2 < argv.len() => {
+                if !xreadgroup {
+                    return Err(generic_error(
+                        "The GROUP option is only supported by XREADGROUP. You called XREAD instead.",
+                    ));
+                }
+                options.group = Some((argv[idx + 1].clone(), argv[idx + 2].clone()));
+                idx += 3;
+            }
+            "NOACK" if xreadgroup => {
+                options.noack = true;
+                idx += 1;
+            }
+            "STREAMS" => {
+                streams_idx = Some(idx + 1);
+                break;
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    let args = &argv[streams_idx.ok_or(RusdisError::SyntaxError)?..];
+    if args.is_empty() || !args.len().is_multiple_of(2) {
+        return Err(generic_error(&format!(
+            "Unbalanced '{}' list of streams: for each stream key an ID or '{}' must be specified.",
+            if xreadgroup { "xreadgroup" } else { "xread" },
+            if xreadgroup { ">" } else { "$" },
+        )));
+    }
+    if xreadgroup && options.group.is_none() {
+        return Err(generic_error("Missing GROUP option for XREADGROUP"));
+    }
+    let (keys, ids) = args.split_at(args.len() / 2);
+
+    Ok((options, keys, ids))
+}
+
+pub(crate) fn parse_xread_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (options, keys, ids) = parse_read(argv, false)?;
+    let streams = keys
+        .iter()
+        .zip(ids.iter())
+        .map(|(key, id)| {
+            let from = match &id[..] {
+                b"$" => ReadFrom::New,
+                b"+" => ReadFrom::LastEntry,
+                _ => ReadFrom::After(parse_id(id, 0)?),
+            };
+            Ok((key.clone(), from))
+        })
+        .collect::<Result<Vec<_>, RusdisError>>()?;
+    let count = options.count;
+
+    Ok(match options.block {
+        Some(timeout) => Command::Block {
+            keys: keys.to_vec(),
+            op: BlockingOp::XRead { streams, count },
+            timeout,
+        },
+        None => Command::Stream(StreamCommand::Read { streams, count }),
+    })
+}
+
+pub(crate) fn parse_xreadgroup_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (options, keys, ids) = parse_read(argv, true)?;
+    let streams = keys
+        .iter()
+        .zip(ids.iter())
+        .map(|(key, id)| {
+            let from = match &id[..] {
+                b">" => GroupReadFrom::New,
+                b"$" => return Err(generic_error(
+                    "The $ ID is meaningless in the context of XREADGROUP: you want to read the history of this consumer by specifying a proper ID, or use the > ID to get new messages. The $ ID would just return an empty result set.",
+                )),
+                _ => GroupReadFrom::Pending(parse_id(id, 0)?),
+            };
+            Ok((key.clone(), from))
+        })
+        .collect::<Result<Vec<_>, RusdisError>>()?;
+    let (group, consumer) = options.group.unwrap_or_default();
+    let (count, noack) = (options.count, options.noack);
+
+    // reading the history of the consumer never blocks
+    let only_new = streams.iter().all(|(_, from)| *from == GroupReadFrom::New);
+    Ok(match options.block {
+        Some(timeout) if only_new => Command::Block {
+            keys: keys.to_vec(),
+            op: BlockingOp::XReadGroup {
+                group,
+                consumer,
+                streams,
+                count,
+                noack,
+            },
+            timeout,
+        },
+        _ => Command::Stream(StreamCommand::ReadGroup {
+            group,
+            consumer,
+            streams,
+            count,
+            noack,
+        }),
+    })
+}
+
+/// Parses the ID of XGROUP CREATE and SETID, `None` for `$`.
+fn parse_group_id(bytes: &Bytes) -> Result<Option<StreamId>, RusdisError> {
+    match &bytes[..] {
+        b"$" => Ok(None),
+        _ => parse_id(bytes, 0).map(Some),
+    }
+}
+
+/// Parses the ENTRIESREAD option of XGROUP CREATE and SETID, and for CREATE
+/// MKSTREAM too.
+fn parse_group_options(argv: &[Bytes], create: bool) -> Result<(bool, Option<u64>), RusdisError> {
+    let (mut mkstream, mut entries_read) = (false, None);
+
+    let mut idx = 5;
+    while idx < argv.len() {
+        match to_keyword(&argv[idx]).as_str() {
+            "MKSTREAM" if create => {
+                mkstream = true;
+                idx += 1;
+            }
+            "ENTRIESREAD" if idx + 1 < argv.len() => {
+                let value = parse_number::<i64>(&argv[idx + 1])?;
+                if value < -1 {
+                    return Err(generic_error(
+                        "value for ENTRIESREAD must be positive or -1",
+                    ));
+                }
+                entries_read = (value >= 0).then_some(value as u64);
+                idx += 2;
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    Ok((mkstream, entries_read))
+}
+
+pub(crate) fn parse_xgroup_create_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let id = parse_group_id(&argv[4])?;
+    let (mkstream, entries_read) = parse_group_options(argv, true)?;
+
+    Ok(Command::Stream(StreamCommand::GroupCreate {
+        key: argv[2].clone(),
+        group: argv[3].clone(),
+        id,
+        mkstream,
+        entries_read,
+    }))
+}
+
+pub(crate) fn parse_xgroup_setid_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let id = parse_group_id(&argv[4])?;
+    let (_, entries_read) = parse_group_options(argv, false)?;
+
+    Ok(Command::Stream(StreamCommand::GroupSetId {
+        key: argv[2].clone(),
+        group: argv[3].clone(),
+        id,
+        entries_read,
+    }))
+}
+
+pub(crate) fn parse_xgroup_destroy_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Stream(StreamCommand::GroupDestroy {
+        key: argv[2].clone(),
+        group: argv[3].clone(),
+    }))
+}
+
+pub(crate) fn parse_xgroup_createconsumer_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Stream(StreamCommand::GroupCreateConsumer {
+        key: argv[2].clone(),
+        group: argv[3].clone(),
+        consumer: argv[4].clone(),
+    }))
+}
+
+pub(crate) fn parse_xgroup_delconsumer_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Stream(StreamCommand::GroupDelConsumer {
+        key: argv[2].clone(),
+        group: argv[3].clone(),
+        consumer: argv[4].clone(),
+    }))
+}
+
+pub(crate) fn parse_xack_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Stream(StreamCommand::Ack {
+        key: argv[1].clone(),
+        group: argv[2].clone(),
+        ids: argv[3..]
+            .iter()
+            .map(|id| parse_id(id, 0))
+            .collect::<Result<_, _>>()?,
+    }))
+}
+
+pub(crate) fn parse_xpending_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let mut args = &argv[3..];
+    let range = if args.is_empty() {
+        None
+    } else {
+        let mut min_idle = 0;
+        if to_keyword(&args[0]) == "IDLE" {
+            let value = args.get(1).ok_or(RusdisError::SyntaxError)?;
+            min_idle = parse_number::<i64>(value)?.max(0) as u64;
+            args = &args[2..];
+        }
+        let (start, end, count, consumer) = match args {
+            [start, end, count] => (start, end, count, None),
+            [start, end, count, consumer] => (start, end, count, Some(consumer.clone())),
+            _ => return Err(RusdisError::SyntaxError),
+        };
+        Some(PendingRange {
+            min_idle,
+            start: parse_range_bound(start, true)?,
+            end: parse_range_bound(end, false)?,
+            count: parse_number::<i64>(count)?.max(0) as usize,
+            consumer,
+        })
+    };
+
+    Ok(Command::Stream(StreamCommand::Pending {
+        key: argv[1].clone(),
+        group: argv[2].clone(),
+        range,
+    }))
+}
+
+/// Parses the min-idle-time of XCLAIM and XAUTOCLAIM, negative values being 0.
+fn parse_min_idle(bytes: &Bytes, command: &str) -> Result<u64, RusdisError> {
+    let min_idle = parse_number::<i64>(bytes)
+        .map_err(|_| generic_error(&format!("Invalid min-idle-time argument for {}", command)))?;
+
+    Ok(min_idle.max(0) as u64)
+}
+
+pub(crate) fn parse_xclaim_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let min_idle = parse_min_idle(&argv[4], "XCLAIM")?;
+
+    // IDs come first, up to the first argument that is not one
+    let ids: Vec<StreamId> = argv[5..]
+        .iter()
+        .map_while(|id| StreamId::parse(id, 0))
+        .collect();
+    let mut options = ClaimOptions::default();
+
+    let mut idx = 5 + ids.len();
+    while idx < argv.len() {
+        let option = to_keyword(&argv[idx]);
+        let value = || {
+            let value = argv.get(idx + 1).ok_or(RusdisError::SyntaxError)?;
+            parse_number::<i64>(value).map_err(|_| {
+                generic_error(&format!("Invalid {} option argument for XCLAIM", option))
+            })
+        };
+        match option.as_str() {
+            "FORCE" => options.force = true,
+            "JUSTID" => options.justid = true,
+            "IDLE" => {
+                options.delivery_time = Some(DeliveryTime::Idle(value()?));
+                idx += 1;
+            }
+            "TIME" => {
+                options.delivery_time = Some(DeliveryTime::At(value()?));
+                idx += 1;
+            }
+            "RETRYCOUNT" => {
+                options.retry_count = Some(value()?.max(0) as u64);
+                idx += 1;
+            }
+            "LASTID" => {
+                let id = argv.get(idx + 1).ok_or(RusdisError::SyntaxError)?;
+                options.last_id = Some(parse_id(id, 0)?);
+                idx += 1;
+            }
+            _ => {
+                return Err(generic_error(&format!(
+                    "Unrecognized XCLAIM option '{}'",
+                    String::from_utf8_lossy(&argv[idx])
+                )))
+            }
+        }
+        idx += 1;
+    }
+
+    Ok(Command::Stream(StreamCommand::Claim {
+        key: argv[1].clone(),
+        group: argv[2].clone(),
+        consumer: argv[3].clone(),
+        min_idle,
+        ids,
+        options,
+    }))
+}
+
+pub(crate) fn parse_xautoclaim_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let min_idle = parse_min_idle(&argv[4], "XAUTOCLAIM")?;
+    let start = parse_range_bound(&argv[5], true)?;
+    let (mut count, mut justid) = (100, false);
+
+    let mut idx = 6;
+    while idx < argv.len() {
+        match to_keyword(&argv[idx]).as_str() {
+            "COUNT" if idx + 1 < argv.len() => {
+                let value = parse_number::<i64>(&argv[idx + 1])?;
+                if !(1..=i64::MAX / 10).contains(&value) {
+                    return Err(generic_error("COUNT must be > 0"));
+                }
+                count = value as usize;
+                idx += 2;
+            }
+            "JUSTID" => {
+                justid = true;
+                idx += 1;
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    Ok(Command::Stream(StreamCommand::AutoClaim {
+        key: argv[1].clone(),
+        group: argv[2].clone(),
+        consumer: argv[3].clone(),
+        min_idle,
+        start,
+        count,
+        justid,
+    }))
+}
+
+pub(crate) fn parse_xinfo_stream_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let full = match &argv[3..] {
+        [] => None,
+        [full] if to_keyword(full) == "FULL" => Some(10),
+        [full, option, count] if to_keyword(full) == "FULL" && to_keyword(option) == "COUNT" => {
+            Some(parse_number::<i64>(count)?.max(0) as usize)
+        }
+        _ => return Err(RusdisError::SyntaxError),
+    };
+
+    Ok(Command::Stream(StreamCommand::InfoStream {
+        key: argv[2].clone(),
+        full,
+    }))
+}
+
+pub(crate) fn parse_xinfo_groups_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Stream(StreamCommand::InfoGroups(argv[2].clone())))
+}
+
+pub(crate) fn parse_xinfo_consumers_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::Stream(StreamCommand::InfoConsumers {
+        key: argv[2].clone(),
+        group: argv[3].clone(),
+    }))
+}
+
+/// The stream stored at `key`, if any.
+fn get_stream<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<Option<&'a mut Stream>, RusdisError> {
+    match get_live(keyspace, key) {
+        Some(Data::Stream(data)) => Ok(Some(data.get_stream_mut())),
+        Some(_) => Err(RusdisError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// `[id, [field, value ...]]`
+fn entry_reply((id, fields): (&StreamId, &Fields)) -> Value {
+    Value::Array(vec![
+        Value::BulkString(id.to_bytes()),
+        Value::Array(
+            fields
+                .iter()
+                .flat_map(|(field, value)| {
+                    [
+                        Value::BulkString(field.clone()),
+                        Value::BulkString(value.clone()),
+                    ]
+                })
+                .collect(),
+        ),
+    ])
+}
+
+/// Pins `$` to the last ID each stream has now, so a blocked XREAD only sees
+/// entries added later.
+pub(crate) fn resolve_new(keyspace: &mut Keyspace, streams: &mut [(Bytes, ReadFrom)]) {
+    for (key, from) in streams.iter_mut() {
+        if *from == ReadFrom::New {
+            let last_id = match get_live(keyspace, key) {
+                Some(Data::Stream(data)) => data.get_stream().last_id(),
+                _ => StreamId::MIN,
+            };
+            *from = ReadFrom::After(last_id);
+        }
+    }
+}
+
+/// Whether XREAD reading from `from` gets entries of `stream`.
+pub(crate) fn has_entries(stream: &Stream, from: ReadFrom) -> bool {
+    match from {
+        ReadFrom::After(id) => stream.after(id).next().is_some(),
+        ReadFrom::New => false,
+        ReadFrom::LastEntry => !stream.is_empty(),
+    }
+}
+
+/// The XREAD reply: for each stream with entries to read, its key and up to
+/// `count` of them, or every one if `count` is 0. `None` if no stream has any.
+pub(crate) fn read(
+    keyspace: &mut Keyspace,
+    streams: &[(Bytes, ReadFrom)],
+    count: usize,
+) -> Result<Option<Value>, RusdisError> {
+    for (key, _) in streams.iter() {
+        get_stream(keyspace, key)?;
+    }
+
+    let count = if count == 0 { usize::MAX } else { count };
+    let mut replies = vec![];
+    for (key, from) in streams.iter() {
+        let stream = match keyspace.get(key) {
+            Some(Data::Stream(data)) => data.get_stream(),
+            _ => continue,
+        };
+        let entries: Vec<Value> = match from {
+            ReadFrom::After(id) => stream.after(*id).take(count).map(entry_reply).collect(),
+            ReadFrom::New => vec![],
+            ReadFrom::LastEntry => stream.last_entry().map(entry_reply).into_iter().collect(),
+        };
+        if !entries.is_empty() {
+            replies.push((Value::BulkString(key.clone()), Value::Array(entries)));
+        }
+    }
+
+    Ok((!replies.is_empty()).then_some(Value::ArrayMap(replies)))
+}
+
+fn no_group(key: &Bytes, group: &Bytes) -> RusdisError {
+    RusdisError::NoGroup {
+        msg: format!(
+            "No such key '{}' or consumer group '{}'",
+            String::from_utf8_lossy(key),
+            String::from_utf8_lossy(group)
+        ),
+    }
+}
+
+fn no_consumer_group(key: &Bytes, group: &Bytes) -> RusdisError {
+    RusdisError::NoGroup {
+        msg: format!(
+            "No such consumer group '{}' for key name '{}'",
+            String::from_utf8_lossy(group),
+            String::from_utf8_lossy(key)
+        ),
+    }
+}
+
+/// The stream XGROUP works on, which must exist.
+fn get_group_stream<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<&'a mut Stream, RusdisError> {
+    get_stream(keyspace, key)?.ok_or_else(|| {
+        generic_error(
+            "The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.",
+        )
+    })
+}
+
+/// The XREADGROUP reply: for each stream, its key and the entries delivered
+/// to `consumer` of `group`, up to `count` of them or every one if `count` is
+/// 0. `None` if no stream has new entries and no history was asked for.
+pub(crate) fn read_group(
+    keyspace: &mut Keyspace,
+    group: &Bytes,
+    consumer: &Bytes,
+    streams: &[(Bytes, GroupReadFrom)],
+    count: usize,
+    noack: bool,
+) -> Result<Option<Value>, RusdisError> {
+    for (key, _) in streams.iter() {
+        if !get_stream(keyspace, key)?.is_some_and(|stream| stream.groups.contains_key(group)) {
+            return Err(RusdisError::NoGroup {
+                msg: format!(
+                    "No such key '{}' or consumer group '{}' in XREADGROUP with GROUP option",
+                    String::from_utf8_lossy(key),
+                    String::from_utf8_lossy(group)
+                ),
+            });
+        }
+    }
+
+    let now = now_ms();
+    let count = if count == 0 { usize::MAX } else { count };
+    let mut replies = vec![];
+    for (key, from) in streams.iter() {
+        let stream = match get_stream(keyspace, key)? {
+            Some(stream) => stream,
+            None => continue,
+        };
+        if let Some(entries) = stream.read_group(group, consumer, *from, count, noack, now) {
+            replies.push((Value::BulkString(key.clone()), Value::Array(entries)));
+        }
+    }
+
+    Ok((!replies.is_empty()).then_some(Value::ArrayMap(replies)))
+}
+
+/// Whether XREADGROUP reading new entries for `group` gets some from `stream`.
+/// A missing group counts too, so blocked readers learn it is gone.
+pub(crate) fn has_group_entries(stream: &Stream, group: &Bytes) -> bool {
+    match stream.groups.get(group) {
+        Some(group) => stream.after(group.last_id).next().is_some(),
+        None => true,
+    }
+}
+
+/// The non-blocking XREADGROUP a served blocked one ran, for propagation.
+/// Trimming arguments of XADD and XTRIM for `options`.
+fn trim_argv(options: &TrimOptions) -> Vec<Bytes> {
+    let (strategy, threshold) = match options.strategy {
+        TrimStrategy::MaxLen(maxlen) => ("MAXLEN", Bytes::from(maxlen.to_string())),
+        TrimStrategy::MinId(minid) => ("MINID", minid.to_bytes()),
+    };
+    let mut argv = vec![
+        Bytes::from(strategy),
+        Bytes::from(if options.approx { "~" } else { "=" }),
+        threshold,
+    ];
+    if options.approx {
+        argv.extend([Bytes::from("LIMIT"), Bytes::from(options.limit.to_string())]);
+    }
+
+    argv
+}
+
+pub(crate) fn read_group_argv(
+    group: &Bytes,
+    consumer: &Bytes,
+    streams: &[(Bytes, GroupReadFrom)],
+    count: usize,
+    noack: bool,
+) -> Vec<Bytes> {
+    let mut argv = vec![
+        Bytes::from("XREADGROUP"),
+        Bytes::from("GROUP"),
+        group.clone(),
+        consumer.clone(),
+    ];
+    if count > 0 {
+        argv.extend([Bytes::from("COUNT"), Bytes::from(count.to_string())]);
+    }
+    if noack {
+        argv.push(Bytes::from("NOACK"));
+    }
+    argv.push(Bytes::from("STREAMS"));
+    argv.extend(streams.iter().map(|(key, _)| key.clone()));
+    argv.extend(streams.iter().map(|(_, from)| match from {
+        GroupReadFrom::New => Bytes::from(">"),
+        GroupReadFrom::Pending(id) => id.to_bytes(),
+    }));
+
+    argv
+}
+
+fn field(name: &str, value: Value) -> (Value, Value) {
+    (Value::BulkString(Bytes::from(name.to_string())), value)
+}
+
+fn id_reply(id: StreamId) -> Value {
+    Value::BulkString(id.to_bytes())
+}
+
+fn optional_integer(value: Option<u64>) -> Value {
+    value.map_or(Value::Null, |value| Value::Integer(value as i64))
+}
+
+/// The fields XINFO STREAM starts with, with or without FULL.
+fn stream_info(stream: &Stream) -> Vec<(Value, Value)> {
+    let nodes = stream.len().div_ceil(STREAM_NODE_MAX_ENTRIES) as i64;
+
+    vec![
+        field("length", Value::Integer(stream.len() as i64)),
+        field("radix-tree-keys", Value::Integer(nodes)),
+        field("radix-tree-nodes", Value::Integer(nodes)),
+        field("last-generated-id", id_reply(stream.last_id)),
+        field("max-deleted-entry-id", id_reply(stream.max_deleted_id)),
+        field("entries-added", Value::Integer(stream.entries_added as i64)),
+        field("recorded-first-entry-id", id_reply(stream.first_id())),
+    ]
+}
+
+/// The groups of XINFO STREAM FULL, with up to `count` pending entries each.
+fn full_groups_info(stream: &Stream, count: usize) -> Value {
+    Value::Array(
+        stream
+            .groups
+            .iter()
+            .map(|(name, group)| {
+                let pending = group
+                    .pending
+                    .iter()
+                    .take(count)
+                    .map(|(id, entry)| {
+                        Value::Array(vec![
+                            id_reply(*id),
+                            Value::BulkString(entry.consumer.clone()),
+                            Value::Integer(entry.delivery_time as i64),
+                            Value::Integer(entry.delivery_count as i64),
+                        ])
+                    })
+                    .collect();
+                let consumers = group
+                    .consumers
+                    .iter()
+                    .map(|(name, consumer)| {
+                        let pending = consumer
+                            .pending
+                            .iter()
+                            .take(count)
+                            .filter_map(|id| {
+                                let entry = group.pending.get(id)?;
+                                Some(Value::Array(vec![
+                                    id_reply(*id),
+                                    Value::Integer(entry.delivery_time as i64),
+                                    Value::Integer(entry.delivery_count as i64),
+                                ]))
+                            })
+                            .collect();
+                        Value::Map(vec![
+                            field("name", Value::BulkString(name.clone())),
+                            field("seen-time", Value::Integer(consumer.seen_time as i64)),
+                            field(
+                                "active-time",
+                                consumer
+                                    .active_time
+                                    .map_or(Value::Integer(-1), |time| Value::Integer(time as i64)),
+                            ),
+                            field("pel-count", Value::Integer(consumer.pending.len() as i64)),
+                            field("pending", Value::Array(pending)),
+                        ])
+                    })
+                    .collect();
+
+                Value::Map(vec![
+                    field("name", Value::BulkString(name.clone())),
+                    field("last-delivered-id", id_reply(group.last_id)),
+                    field("entries-read", optional_integer(group.entries_read)),
+                    field("lag", optional_integer(stream.lag(group))),
+                    field("pel-count", Value::Integer(group.pending.len() as i64)),
+                    field("pending", Value::Array(pending)),
+                    field("consumers", Value::Array(consumers)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+pub fn execute_stream_command(
+    keyspace: &mut Keyspace,
+    cmd: StreamCommand,
+) -> Result<Value, RusdisError> {
+    match cmd {
+        StreamCommand::Add {
+            key,
+            id,
+            no_mkstream,
+            trim,
+            fields,
+        } => {
+            if get_stream(keyspace, &key)?.is_none() {
+                if no_mkstream {
+                    return Ok(Value::Null);
+                }
+                // an ID a new stream rejects must not leave the stream behind
+                Stream::new().next_id(id)?;
+                keyspace.insert(key.clone(), Data::Stream(StreamData::new(Stream::new())));
+            }
+            let stream = match get_stream(keyspace, &key)? {
+                Some(stream) => stream,
+                None => return Ok(Value::Null),
+            };
+
+            let id = stream.next_id(id)?;
+            // replicas get the ID the entry was given, which may depend on the clock
+            let mut argv = vec![Bytes::from("XADD"), key];
+            argv.extend(trim.iter().flat_map(trim_argv));
+            argv.push(id.to_bytes());
+            argv.extend(fields.iter().flat_map(|(f, v)| [f.clone(), v.clone()]));
+
+            stream.append(id, fields);
+            if let Some(trim) = trim {
+                stream.trim(&trim);
+            }
+            keyspace.propagate_as(vec![argv]);
+
+            Ok(Value::BulkString(id.to_bytes()))
+        }
+        StreamCommand::Range {
+            key,
+            start,
+            end,
+            count,
+            rev,
+        } => {
+            let stream = match get_stream(keyspace, &key)? {
+                Some(stream) => stream,
+                None => return Ok(Value::Array(vec![])),
+            };
+
+            Ok(Value::Array(
+                stream
+                    .range(start, end, rev)
+                    .take(count.unwrap_or(usize::MAX))
+                    .map(entry_reply)
+                    .collect(),
+            ))
+        }
+        StreamCommand::Len(key) => Ok(Value::Integer(
+            get_stream(keyspace, &key)?.map_or(0, |stream| stream.len()) as i64,
+        )),
+        StreamCommand::Del { key, ids } => {
+            let stream = match get_stream(keyspace, &key)? {
+                Some(stream) => stream,
+                None => return Ok(Value::Integer(0)),
+            };
+
+            Ok(Value::Integer(
+                ids.iter().filter(|id| stream.remove(id)).count() as i64,
+            ))
+        }
+        StreamCommand::Trim { key, options } => Ok(Value::Integer(
+            get_stream(keyspace, &key)?.map_or(0, |stream| stream.trim(&options)) as i64,
+        )),
+        StreamCommand::Read { streams, count } => {
+            Ok(read(keyspace, &streams, count)?.unwrap_or(Value::NullArray))
+        }
+        StreamCommand::GroupCreate {
+            key,
+            group,
+            id,
+            mkstream,
+            entries_read,
+        } => {
+            if mkstream && get_stream(keyspace, &key)?.is_none() {
+                keyspace.insert(key.clone(), Data::Stream(StreamData::new(Stream::new())));
+            }
+            let stream = get_group_stream(keyspace, &key)?;
+
+            let id = id.unwrap_or(stream.last_id);
+            if !stream.insert_group(group, ConsumerGroup::new(id, entries_read)) {
+                return Err(RusdisError::BusyGroup);
+            }
+
+            Ok(Value::SimpleString("OK".to_string()))
+        }
+        StreamCommand::GroupSetId {
+            key,
+            group: name,
+            id,
+            entries_read,
+        } => {
+            let stream = get_group_stream(keyspace, &key)?;
+            let last_id = stream.last_id;
+            let group = stream
+                .groups
+                .get_mut(&name)
+                .ok_or_else(|| no_consumer_group(&key, &name))?;
+
+            group.last_id = id.unwrap_or(last_id);
+            group.entries_read = entries_read;
+
+            Ok(Value::SimpleString("OK".to_string()))
+        }
+        StreamCommand::GroupDestroy { key, group } => {
+            let stream = get_group_stream(keyspace, &key)?;
+
+            Ok(Value::Integer(stream.groups.remove(&group).is_some() as i64))
+        }
+        StreamCommand::GroupCreateConsumer {
+            key,
+            group: name,
+            consumer,
+        } => {
+            let group = get_group_stream(keyspace, &key)?
+                .groups
+                .get_mut(&name)
+                .ok_or_else(|| no_consumer_group(&key, &name))?;
+
+            Ok(Value::Integer(
+                group.add_consumer(consumer, now_ms(), None) as i64
+            ))
+        }
+        StreamCommand::GroupDelConsumer {
+            key,
+            group: name,
+            consumer,
+        } => {
+            let group = get_group_stream(keyspace, &key)?
+                .groups
+                .get_mut(&name)
+                .ok_or_else(|| no_consumer_group(&key, &name))?;
+
+            Ok(Value::Integer(group.remove_consumer(&consumer) as i64))
+        }
+        StreamCommand::ReadGroup {
+            group,
+            consumer,
+            streams,
+            count,
+            noack,
+        } => Ok(
+            read_group(keyspace, &group, &consumer, &streams, count, noack)?
+                .unwrap_or(Value::NullArray),
+        ),
+        StreamCommand::Ack { key, group, ids } => {
+            let group = match get_stream(keyspace, &key)?
+                .and_then(|stream| stream.groups.get_mut(&group))
+            {
+                Some(group) => group,
+                None => return Ok(Value::Integer(0)),
+            };
+
+            Ok(Value::Integer(
+                ids.iter().filter(|id| group.ack(id)).count() as i64,
+            ))
+        }
+        StreamCommand::Pending {
+            key,
+            group: name,
+            range,
+        } => {
+            let group = get_stream(keyspace, &key)?
+                .and_then(|stream| stream.groups.get_mut(&name))
+                .ok_or_else(|| no_group(&key, &name))?;
+
+            let range = match range {
+                Some(range) => range,
+                None => {
+                    let (first, last) = match (
+                        group.pending.first_key_value(),
+                        group.pending.last_key_value(),
+                    ) {
+                        (Some((first, _)), Some((last, _))) => (*first, *last),
+                        _ => {
+                            return Ok(Value::Array(vec![
+                                Value::Integer(0),
+                                Value::Null,
+                                Value::Null,
+                                Value::NullArray,
+                            ]))
+                        }
+                    };
+                    let consumers = group
+                        .consumers
+                        .iter()
+                        .filter(|(_, consumer)| !consumer.pending.is_empty())
+                        .map(|(name, consumer)| {
+                            Value::Array(vec![
+                                Value::BulkString(name.clone()),
+                                Value::BulkString(Bytes::from(consumer.pending.len().to_string())),
+                            ])
+                        })
+                        .collect();
+
+                    return Ok(Value::Array(vec![
+                        Value::Integer(group.pending.len() as i64),
+                        id_reply(first),
+                        id_reply(last),
+                        Value::Array(consumers),
+                    ]));
+                }
+            };
+
+            if range.start > range.end {
+                return Ok(Value::Array(vec![]));
+            }
+            let now = now_ms();
+            Ok(Value::Array(
+                group
+                    .pending
+                    .range(range.start..=range.end)
+                    .filter(|(_, entry)| {
+                        range
+                            .consumer
+                            .as_ref()
+                            .is_none_or(|consumer| entry.consumer == *consumer)
+                    })
+                    .filter(|(_, entry)| now.saturating_sub(entry.delivery_time) >= range.min_idle)
+                    .take(range.count)
+                    .map(|(id, entry)| {
+                        Value::Array(vec![
+                            id_reply(*id),
+                            Value::BulkString(entry.consumer.clone()),
+                            Value::Integer(now.saturating_sub(entry.delivery_time) as i64),
+                            Value::Integer(entry.delivery_count as i64),
+                        ])
+                    })
+                    .collect(),
+            ))
+        }
+        StreamCommand::Claim {
+            key,
+            group: name,
+            consumer,
+            min_idle,
+            ids,
+            options,
+        } => {
+            let Stream {
+                entries, groups, ..
+            } = get_stream(keyspace, &key)?.ok_or_else(|| no_group(&key, &name))?;
+            let group = groups.get_mut(&name).ok_or_else(|| no_group(&key, &name))?;
+
+            let now = now_ms();
+            let delivery_time = match options.delivery_time {
+                Some(DeliveryTime::Idle(idle)) => (now as i64).saturating_sub(idle),
+                Some(DeliveryTime::At(time)) => time,
+                None => now as i64,
+            };
+            let delivery_time = if (0..=now as i64).contains(&delivery_time) {
+                delivery_time as u64
+            } else {
+                now
+            };
+            if let Some(last_id) = options.last_id {
+                group.last_id = group.last_id.max(last_id);
+            }
+            group.seen_consumer(&consumer, now);
+
+            let mut claimed = vec![];
+            for id in ids.iter() {
+                let fields = entries.get(id);
+                let delivery_count = match (group.pending.get(id), fields) {
+                    // the entry was deleted since it was delivered
+                    (Some(_), None) => {
+                        group.ack(id);
+                        continue;
+                    }
+                    (Some(entry), Some(_)) => {
+                        if now.saturating_sub(entry.delivery_time) < min_idle {
+                            continue;
+                        }
+                        entry.delivery_count
+                    }
+                    (None, Some(_)) if options.force => 0,
+                    (None, _) => continue,
+                };
+                let delivery_count = match options.retry_count {
+                    Some(retry_count) => retry_count,
+                    None if options.justid => delivery_count,
+                    None => delivery_count + 1,
+                };
+
+                group.deliver(*id, &consumer, delivery_time, delivery_count);
+                claimed.push(match fields {
+                    Some(fields) if !options.justid => entry_reply((id, fields)),
+                    _ => id_reply(*id),
+                });
+            }
+            if !claimed.is_empty() {
+                group.seen_consumer(&consumer, now).active_time = Some(now);
+            }
+
+            Ok(Value::Array(claimed))
+        }
+        StreamCommand::AutoClaim {
+            key,
+            group: name,
+            consumer,
+            min_idle,
+            start,
+            count,
+            justid,
+        } => {
+            let Stream {
+                entries, groups, ..
+            } = get_stream(keyspace, &key)?.ok_or_else(|| no_group(&key, &name))?;
+            let group = groups.get_mut(&name).ok_or_else(|| no_group(&key, &name))?;
+
+            let now = now_ms();
+            group.seen_consumer(&consumer, now);
+
+            // at most ten pending entries are looked at per entry asked for
+            let candidates: Vec<(StreamId, u64, u64)> = group
+                .pending
+                .range(start..)
+                .take(count * 10)
+                .map(|(id, entry)| (*id, entry.delivery_time, entry.delivery_count))
+                .collect();
+            let (mut claimed, mut deleted, mut last) = (vec![], vec![], None);
+            for (id, delivery_time, delivery_count) in candidates {
+                if claimed.len() == count {
+                    break;
+                }
+                last = Some(id);
+
+                let fields = match entries.get(&id) {
+                    Some(fields) => fields,
+                    None => {
+                        group.ack(&id);
+                        deleted.push(id_reply(id));
+                        continue;
+                    }
+                };
+                if now.saturating_sub(delivery_time) < min_idle {
+                    continue;
+                }
+
+                let delivery_count = if justid {
+                    delivery_count
+                } else {
+                    delivery_count + 1
+                };
+                group.deliver(id, &consumer, now, delivery_count);
+                claimed.push(if justid {
+                    id_reply(id)
+                } else {
+                    entry_reply((&id, fields))
+                });
+            }
+            if !claimed.is_empty() {
+                group.seen_consumer(&consumer, now).active_time = Some(now);
+            }
+            let cursor = last
+                .and_then(|last| {
+                    group
+                        .pending
+                        .range((Bound::Excluded(last), Bound::Unbounded))
+                        .next()
+                })
+                .map_or(StreamId::MIN, |(id, _)| *id);
+
+            Ok(Value::Array(vec![
+                id_reply(cursor),
+                Value::Array(claimed),
+                Value::Array(deleted),
+            ]))
+        }
+        StreamCommand::InfoStream { key, full } => {
+            let stream = get_stream(keyspace, &key)?.ok_or_else(|| generic_error("no such key"))?;
+
+            let mut info = stream_info(stream);
+            match full {
+                Some(count) => {
+                    let count = if count == 0 { usize::MAX } else { count };
+                    info.push(field(
+                        "entries",
+                        Value::Array(
+                            stream
+                                .range(StreamId::MIN, StreamId::MAX, false)
+                                .take(count)
+                                .map(entry_reply)
+                                .collect(),
+                        ),
+                    ));
+                    info.push(field("groups", full_groups_info(stream, count)));
+                }
+                None => {
+                    info.push(field("groups", Value::Integer(stream.groups.len() as i64)));
+                    info.push(field(
+                        "first-entry",
+                        stream
+                            .range(StreamId::MIN, StreamId::MAX, false)
+                            .next()
+                            .map_or(Value::Null, entry_reply),
+                    ));
+                    info.push(field(
+                        "last-entry",
+                        stream.last_entry().map_or(Value::Null, entry_reply),
+                    ));
+                }
+            }
+
+            Ok(Value::Map(info))
+        }
+        StreamCommand::InfoGroups(key) => {
+            let stream = get_stream(keyspace, &key)?.ok_or_else(|| generic_error("no such key"))?;
+
+            Ok(Value::Array(
+                stream
+                    .groups
+                    .iter()
+                    .map(|(name, group)| {
+                        Value::Map(vec![
+                            field("name", Value::BulkString(name.clone())),
+                            field("consumers", Value::Integer(group.consumers.len() as i64)),
+                            field("pending", Value::Integer(group.pending.len() as i64)),
+                            field("last-delivered-id", id_reply(group.last_id)),
+                            field("entries-read", optional_integer(group.entries_read)),
+                            field("lag", optional_integer(stream.lag(group))),
+                        ])
+                    })
+                    .collect(),
+            ))
+        }
+        StreamCommand::InfoConsumers { key, group: name } => {
+            let stream = get_stream(keyspace, &key)?.ok_or_else(|| generic_error("no such key"))?;
+            let group = stream
+                .groups
+                .get(&name)
+                .ok_or_else(|| no_consumer_group(&key, &name))?;
+
+            let now = now_ms();
+            Ok(Value::Array(
+                group
+                    .consumers
+                    .iter()
+                    .map(|(name, consumer)| {
+                        let inactive = consumer
+                            .active_time
+                            .map_or(-1, |time| now.saturating_sub(time) as i64);
+                        Value::Map(vec![
+                            field("name", Value::BulkString(name.clone())),
+                            field("pending", Value::Integer(consumer.pending.len() as i64)),
+                            field(
+                                "idle",
+                                Value::Integer(now.saturating_sub(consumer.seen_time) as i64),
+                            ),
+                            field("inactive", Value::Integer(inactive)),
+                        ])
+                    })
+                    .collect(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{bulk, error, propagated, run};
+
+    fn entry(id: &str, fields: &[&str]) -> Value {
+        Value::Array(vec![
+            bulk(id),
+            Value::Array(fields.iter().map(|field| bulk(field)).collect()),
+        ])
+    }
+
+    #[test]
+    fn test_stream_id() {
+        assert_eq!(StreamId::parse(b"5-3", 0), Some(StreamId::new(5, 3)));
+        assert_eq!(
+            StreamId::parse(b"5", u64::MAX),
+            Some(StreamId::new(5, u64::MAX))
+        );
+        assert_eq!(StreamId::parse(b"5-", 0), None);
+        assert_eq!(StreamId::parse(b"a-1", 0), None);
+        assert_eq!(StreamId::new(1, u64::MAX).next(), Some(StreamId::new(2, 0)));
+        assert_eq!(StreamId::new(2, 0).prev(), Some(StreamId::new(1, u64::MAX)));
+        assert_eq!(StreamId::MAX.next(), None);
+        assert_eq!(StreamId::MIN.prev(), None);
+    }
+
+    #[test]
+    fn test_stream_add() {
+        let mut keyspace = Keyspace::new();
+
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "s", "1-1", "a", "1"]),
+            bulk("1-1")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "s", "1-*", "b", "2"]),
+            bulk("1-2")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "s", "3", "c", "3"]),
+            bulk("3-0")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "s", "2-5", "d", "4"]),
+            error(
+                "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+            )
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "e", "0-0", "a", "1"]),
+            error("ERR The ID specified in XADD must be greater than 0-0")
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("e")));
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "e", "0-*", "a", "1"]),
+            bulk("0-1")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "s", "x-1", "a", "1"]),
+            error("ERR Invalid stream ID specified as stream command argument")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "s", "*", "a"]),
+            error("ERR wrong number of arguments for 'xadd' command")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XADD", "none", "NOMKSTREAM", "*", "a", "1"]
+            ),
+            Value::Null
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("none")));
+
+        match run(&mut keyspace, &["XADD", "s", "*", "a", "1"]) {
+            Value::BulkString(id) => {
+                assert!(StreamId::parse(&id, 0).unwrap() > StreamId::new(3, 0))
+            }
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        assert_eq!(run(&mut keyspace, &["XLEN", "s"]), Value::Integer(4));
+        assert_eq!(run(&mut keyspace, &["XLEN", "none"]), Value::Integer(0));
+
+        assert_eq!(
+            run(&mut keyspace, &["XDEL", "s", "1-2", "1-2", "9-9"]),
+            Value::Integer(1)
+        );
+        // deleting the last entry keeps its ID as the floor
+        run(&mut keyspace, &["XDEL", "e", "0-1"]);
+        assert_eq!(run(&mut keyspace, &["XLEN", "e"]), Value::Integer(0));
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "e", "0-1", "a", "1"]),
+            error(
+                "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+            )
+        );
+    }
+
+    #[test]
+    fn test_stream_add_propagates_id() {
+        let mut keyspace = Keyspace::new();
+
+        let id = match run(&mut keyspace, &["XADD", "s", "MAXLEN", "5", "*", "a", "1"]) {
+            Value::BulkString(id) => String::from_utf8_lossy(&id).to_string(),
+            reply => panic!("unexpected reply {:?}", reply),
+        };
+        assert_eq!(
+            propagated(&mut keyspace),
+            Some(vec![["XADD", "s", "MAXLEN", "=", "5", &id, "a", "1"]
+                .map(String::from)
+                .to_vec()])
+        );
+
+        run(
+            &mut keyspace,
+            &["XADD", "t", "MINID", "~", "1", "5-*", "b", "2"],
+        );
+        assert_eq!(
+            propagated(&mut keyspace),
+            Some(vec![[
+                "XADD", "t", "MINID", "~", "1-0", "LIMIT", "10000", "5-0", "b", "2"
+            ]
+            .map(String::from)
+            .to_vec()])
+        );
+    }
+
+    #[test]
+    fn test_stream_range() {
+        let mut keyspace = Keyspace::new();
+        for id in ["1-0", "1-1", "2-0", "3-5"] {
+            run(&mut keyspace, &["XADD", "s", id, "id", id]);
+        }
+
+        assert_eq!(
+            run(&mut keyspace, &["XRANGE", "s", "-", "+", "COUNT", "2"]),
+            Value::Array(vec![
+                entry("1-0", &["id", "1-0"]),
+                entry("1-1", &["id", "1-1"])
+            ])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XRANGE", "s", "1", "2"]),
+            Value::Array(vec![
+                entry("1-0", &["id", "1-0"]),
+                entry("1-1", &["id", "1-1"]),
+                entry("2-0", &["id", "2-0"])
+            ])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XRANGE", "s", "(1-1", "+"]),
+            Value::Array(vec![
+                entry("2-0", &["id", "2-0"]),
+                entry("3-5", &["id", "3-5"])
+            ])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XREVRANGE", "s", "+", "-", "COUNT", "1"]),
+            Value::Array(vec![entry("3-5", &["id", "3-5"])])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XREVRANGE", "s", "(2-0", "1-1"]),
+            Value::Array(vec![entry("1-1", &["id", "1-1"])])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XRANGE", "s", "3", "1"]),
+            Value::Array(vec![])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XRANGE", "s", "-", "+", "COUNT", "0"]),
+            Value::Array(vec![])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XRANGE", "none", "-", "+"]),
+            Value::Array(vec![])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "XRANGE",
+                    "s",
+                    "(18446744073709551615-18446744073709551615",
+                    "+"
+                ]
+            ),
+            error("ERR invalid start ID for the interval")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XRANGE", "s", "-", "+", "LIMIT", "1"]),
+            error("ERR syntax error")
+        );
+    }
+
+    #[test]
+    fn test_stream_trim() {
+        let mut keyspace = Keyspace::new();
+        for seq in 1..=250 {
+            let id = format!("1-{}", seq);
+            run(&mut keyspace, &["XADD", "s", &id, "f", "v"]);
+        }
+
+        // approximate trimming removes whole nodes only
+        assert_eq!(
+            run(&mut keyspace, &["XTRIM", "s", "MAXLEN", "~", "60"]),
+            Value::Integer(100)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XTRIM", "s", "MAXLEN", "~", "60", "LIMIT", "99"]
+            ),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XTRIM", "s", "MINID", "1-151"]),
+            Value::Integer(50)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XADD", "s", "MAXLEN", "=", "10", "2-0", "f", "v"]
+            ),
+            bulk("2-0")
+        );
+        assert_eq!(run(&mut keyspace, &["XLEN", "s"]), Value::Integer(10));
+        assert_eq!(
+            run(&mut keyspace, &["XRANGE", "s", "-", "1-242"]),
+            Value::Array(vec![entry("1-242", &["f", "v"])])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XTRIM", "none", "MAXLEN", "0"]),
+            Value::Integer(0)
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["XTRIM", "s", "MAXLEN", "10", "LIMIT", "5"]),
+            error("ERR syntax error, LIMIT cannot be used without the special ~ option")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XTRIM", "s", "MAXLEN", "-1"]),
+            error("ERR The MAXLEN argument must be >= 0.")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XTRIM", "s", "LIMIT", "5"]),
+            error("ERR syntax error, LIMIT cannot be used without specifying a trimming strategy")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XTRIM", "s", "COUNT", "5"]),
+            error("ERR syntax error")
+        );
+    }
+
+    #[test]
+    fn test_stream_read() {
+        let mut keyspace = Keyspace::new();
+        run(&mut keyspace, &["XADD", "a", "1-0", "f", "1"]);
+        run(&mut keyspace, &["XADD", "a", "2-0", "f", "2"]);
+        run(&mut keyspace, &["XADD", "b", "5-0", "f", "5"]);
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XREAD", "COUNT", "1", "STREAMS", "a", "b", "c", "0", "5", "0"]
+            ),
+            Value::ArrayMap(vec![(
+                bulk("a"),
+                Value::Array(vec![entry("1-0", &["f", "1"])])
+            )])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XREAD", "STREAMS", "a", "b", "+", "$"]),
+            Value::ArrayMap(vec![(
+                bulk("a"),
+                Value::Array(vec![entry("2-0", &["f", "2"])])
+            )])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XREAD", "STREAMS", "a", "2"]),
+            Value::NullArray
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XREAD", "STREAMS", "a", "b", "0"]),
+            error("ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XREAD", "BLOCK", "-1", "STREAMS", "a", "0"]
+            ),
+            error("ERR timeout is negative")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XREAD", "COUNT", "1", "a", "0"]),
+            error("ERR syntax error")
+        );
+
+        keyspace.insert(
+            Bytes::from("list"),
+            Data::List(crate::data::ListData::new(Default::default())),
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XREAD", "STREAMS", "a", "list", "0", "0"]),
+            RusdisError::WrongType.to_reply()
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XADD", "list", "*", "f", "v"]),
+            RusdisError::WrongType.to_reply()
+        );
+    }
+
+    #[test]
+    fn test_stream_groups() {
+        let mut keyspace = Keyspace::new();
+        for id in ["1-0", "2-0", "3-0"] {
+            run(&mut keyspace, &["XADD", "s", id, "f", id]);
+        }
+
+        assert_eq!(
+            run(&mut keyspace, &["XGROUP", "CREATE", "s", "g", "0"]),
+            Value::SimpleString("OK".to_string())
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XGROUP", "CREATE", "s", "g", "$"]),
+            error("BUSYGROUP Consumer Group name already exists")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XGROUP", "CREATE", "none", "g", "$"]),
+            error("ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XGROUP", "CREATE", "new", "g", "$", "MKSTREAM"]
+            ),
+            Value::SimpleString("OK".to_string())
+        );
+        assert_eq!(run(&mut keyspace, &["XLEN", "new"]), Value::Integer(0));
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XGROUP", "SETID", "s", "g", "0", "ENTRIESREAD", "-2"]
+            ),
+            error("ERR value for ENTRIESREAD must be positive or -1")
+        );
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "XREADGROUP",
+                    "GROUP",
+                    "g",
+                    "alice",
+                    "COUNT",
+                    "2",
+                    "STREAMS",
+                    "s",
+                    ">"
+                ]
+            ),
+            Value::ArrayMap(vec![(
+                bulk("s"),
+                Value::Array(vec![
+                    entry("1-0", &["f", "1-0"]),
+                    entry("2-0", &["f", "2-0"])
+                ])
+            )])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XREADGROUP", "GROUP", "g", "bob", "STREAMS", "s", ">"]
+            ),
+            Value::ArrayMap(vec![(
+                bulk("s"),
+                Value::Array(vec![entry("3-0", &["f", "3-0"])])
+            )])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XREADGROUP", "GROUP", "g", "bob", "STREAMS", "s", ">"]
+            ),
+            Value::NullArray
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XREADGROUP", "GROUP", "nope", "bob", "STREAMS", "s", ">"]
+            ),
+            error(
+                "NOGROUP No such key 's' or consumer group 'nope' in XREADGROUP with GROUP option"
+            )
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XREADGROUP", "GROUP", "g", "bob", "STREAMS", "s", "$"]
+            ),
+            error("ERR The $ ID is meaningless in the context of XREADGROUP: you want to read the history of this consumer by specifying a proper ID, or use the > ID to get new messages. The $ ID would just return an empty result set.")
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["XPENDING", "s", "g"]),
+            Value::Array(vec![
+                Value::Integer(3),
+                bulk("1-0"),
+                bulk("3-0"),
+                Value::Array(vec![
+                    Value::Array(vec![bulk("alice"), bulk("2")]),
+                    Value::Array(vec![bulk("bob"), bulk("1")])
+                ])
+            ])
+        );
+        // reading the history delivers the pending entries again
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XREADGROUP", "GROUP", "g", "alice", "STREAMS", "s", "0"]
+            ),
+            Value::ArrayMap(vec![(
+                bulk("s"),
+                Value::Array(vec![
+                    entry("1-0", &["f", "1-0"]),
+                    entry("2-0", &["f", "2-0"])
+                ])
+            )])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XACK", "s", "g", "1-0", "9-0"]),
+            Value::Integer(1)
+        );
+        match run(
+            &mut keyspace,
+            &["XPENDING", "s", "g", "-", "+", "10", "alice"],
+        ) {
+            Value::Array(entries) => match &entries[..] {
+                [Value::Array(entry)] => {
+                    assert_eq!(entry[0], bulk("2-0"));
+                    assert_eq!(entry[1], bulk("alice"));
+                    assert_eq!(entry[3], Value::Integer(2));
+                }
+                entries => panic!("unexpected entries {:?}", entries),
+            },
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XCLAIM", "s", "g", "bob", "0", "2-0", "JUSTID"]
+            ),
+            Value::Array(vec![bulk("2-0")])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XCLAIM", "s", "g", "bob", "3600000", "3-0"]
+            ),
+            Value::Array(vec![])
+        );
+        run(&mut keyspace, &["XDEL", "s", "3-0"]);
+        assert_eq!(
+            run(&mut keyspace, &["XAUTOCLAIM", "s", "g", "alice", "0", "0"]),
+            Value::Array(vec![
+                bulk("0-0"),
+                Value::Array(vec![entry("2-0", &["f", "2-0"])]),
+                Value::Array(vec![bulk("3-0")])
+            ])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XPENDING", "none", "g"]),
+            error("NOGROUP No such key 'none' or consumer group 'g'")
+        );
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["XGROUP", "CREATECONSUMER", "s", "g", "carol"]
+            ),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XGROUP", "DELCONSUMER", "s", "g", "alice"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XINFO", "GROUPS", "s"]),
+            Value::Array(vec![Value::Map(vec![
+                (bulk("name"), bulk("g")),
+                (bulk("consumers"), Value::Integer(2)),
+                (bulk("pending"), Value::Integer(0)),
+                (bulk("last-delivered-id"), bulk("3-0")),
+                (bulk("entries-read"), Value::Integer(3)),
+                (bulk("lag"), Value::Integer(0))
+            ])])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XINFO", "STREAM", "s"]),
+            Value::Map(vec![
+                (bulk("length"), Value::Integer(2)),
+                (bulk("radix-tree-keys"), Value::Integer(1)),
+                (bulk("radix-tree-nodes"), Value::Integer(1)),
+                (bulk("last-generated-id"), bulk("3-0")),
+                (bulk("max-deleted-entry-id"), bulk("3-0")),
+                (bulk("entries-added"), Value::Integer(3)),
+                (bulk("recorded-first-entry-id"), bulk("1-0")),
+                (bulk("groups"), Value::Integer(1)),
+                (bulk("first-entry"), entry("1-0", &["f", "1-0"])),
+                (bulk("last-entry"), entry("2-0", &["f", "2-0"]))
+            ])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XINFO", "CONSUMERS", "s", "nope"]),
+            error("NOGROUP No such consumer group 'nope' for key name 's'")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XINFO", "STREAM", "none"]),
+            error("ERR no such key")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XGROUP", "DESTROY", "s", "g"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["XACK", "s", "g", "2-0"]),
+            Value::Integer(0)
+        );
+    }
+}
@@ -0,0 +1,823 @@
+use crate::command_parser::{parse_number, to_keyword, Command};
+use crate::data::{get_live, Data, Keyspace, StringData};
+use crate::hash::{FieldExpiry, TtlUpdate};
+use crate::utils::unix_millis;
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longest string a command can make, Redis' default `proto-max-bulk-len`.
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StringCommand {
+    Append {
+        key: Bytes,
+        value: Bytes,
+    },
+    StrLen(Bytes),
+    GetRange {
+        key: Bytes,
+        start: i64,
+        end: i64,
+    },
+    SetRange {
+        key: Bytes,
+        offset: usize,
+        value: Bytes,
+    },
+    MGet(Vec<Bytes>),
+    MSet(Vec<(Bytes, Bytes)>),
+    MSetNx(Vec<(Bytes, Bytes)>),
+    GetSet {
+        key: Bytes,
+        value: Bytes,
+    },
+    GetDel(Bytes),
+    /// GETEX, leaving the expiration as it is when `update` is `None`
+    GetEx {
+        key: Bytes,
+        update: Option<TtlUpdate>,
+    },
+    SetNx {
+        key: Bytes,
+        value: Bytes,
+    },
+    /// SETEX and PSETEX
+    SetEx {
+        key: Bytes,
+        expiry: FieldExpiry,
+        value: Bytes,
+    },
+    Lcs {
+        first: Bytes,
+        second: Bytes,
+        reply: LcsReply,
+        min_match_len: usize,
+        with_match_len: bool,
+    },
+}
+
+/// What LCS replies with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LcsReply {
+    /// The common subsequence itself
+    String,
+    /// LEN, its length
+    Len,
+    /// IDX, the ranges matching in both strings
+    Idx,
+}
+
+fn generic_error(msg: &str) -> RusdisError {
+    RusdisError::Generic {
+        msg: msg.to_string(),
+    }
+}
+
+fn too_long() -> RusdisError {
+    generic_error("string exceeds maximum allowed size (proto-max-bulk-len)")
+}
+
+fn invalid_expire_time(argv: &[Bytes]) -> RusdisError {
+    RusdisError::Generic {
+        msg: format!(
+            "invalid expire time in '{}' command",
+            String::from_utf8_lossy(&argv[0]).to_lowercase()
+        ),
+    }
+}
+
+/// Parses a positive expiration given in `unit` milliseconds, as relative
+/// time unless `absolute`.
+fn parse_expiry(
+    argv: &[Bytes],
+    bytes: &Bytes,
+    unit: i64,
+    absolute: bool,
+) -> Result<FieldExpiry, RusdisError> {
+    let time = parse_number::<i64>(bytes)?;
+    let millis = match time.checked_mul(unit) {
+        Some(millis) if time > 0 => millis,
+        _ => return Err(invalid_expire_time(argv)),
+    };
+
+    if absolute {
+        return Ok(FieldExpiry::At(millis as u64));
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+    if now.checked_add(millis).is_none() {
+        return Err(invalid_expire_time(argv));
+    }
+
+    Ok(FieldExpiry::In(millis as u64))
+}
+
+/// The key and value pairs of MSET and MSETNX.
+fn parse_pairs(argv: &[Bytes]) -> Result<Vec<(Bytes, Bytes)>, RusdisError> {
+    if argv.len().is_multiple_of(2) {
+        return Err(RusdisError::WrongArity {
+            command: String::from_utf8_lossy(&argv[0]).to_lowercase(),
+        });
+    }
+
+    Ok(argv[1..]
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect())
+}
+
+pub(crate) fn parse_append_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::Append {
+        key: argv[1].clone(),
+        value: argv[2].clone(),
+    }))
+}
+
+pub(crate) fn parse_strlen_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::StrLen(argv[1].clone())))
+}
+
+pub(crate) fn parse_getrange_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::GetRange {
+        key: argv[1].clone(),
+        start: parse_number(&argv[2])?,
+        end: parse_number(&argv[3])?,
+    }))
+}
+
+pub(crate) fn parse_setrange_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let offset = parse_number::<i64>(&argv[2])?;
+    if offset < 0 {
+        return Err(generic_error("offset is out of range"));
+    }
+
+    Ok(Command::String(StringCommand::SetRange {
+        key: argv[1].clone(),
+        offset: offset as usize,
+        value: argv[3].clone(),
+    }))
+}
+
+pub(crate) fn parse_mget_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::MGet(argv[1..].to_vec())))
+}
+
+pub(crate) fn parse_mset_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::MSet(parse_pairs(argv)?)))
+}
+
+pub(crate) fn parse_msetnx_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::MSetNx(parse_pairs(argv)?)))
+}
+
+pub(crate) fn parse_getset_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::GetSet {
+        key: argv[1].clone(),
+        value: argv[2].clone(),
+    }))
+}
+
+pub(crate) fn parse_getdel_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::GetDel(argv[1].clone())))
+}
+
+pub(crate) fn parse_getex_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let mut option: Option<(String, TtlUpdate)> = None;
+    let mut idx = 2;
+    while idx < argv.len() {
+        let keyword = to_keyword(&argv[idx]);
+        // only one of the options, though the same one may be repeated
+        if option.as_ref().is_some_and(|(given, _)| *given != keyword) {
+            return Err(RusdisError::SyntaxError);
+        }
+
+        let update = match keyword.as_str() {
+            "PERSIST" => TtlUpdate::Persist,
+            "EX" | "PX" | "EXAT" | "PXAT" => {
+                idx += 1;
+                let bytes = argv.get(idx).ok_or(RusdisError::SyntaxError)?;
+                let (unit, absolute) = match keyword.as_str() {
+                    "EX" => (1000, false),
+                    "PX" => (1, false),
+                    "EXAT" => (1000, true),
+                    _ => (1, true),
+                };
+                TtlUpdate::Expire(parse_expiry(argv, bytes, unit, absolute)?)
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        };
+        option = Some((keyword, update));
+        idx += 1;
+    }
+
+    Ok(Command::String(StringCommand::GetEx {
+        key: argv[1].clone(),
+        update: option.map(|(_, update)| update),
+    }))
+}
+
+pub(crate) fn parse_setnx_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::SetNx {
+        key: argv[1].clone(),
+        value: argv[2].clone(),
+    }))
+}
+
+fn parse_setex(argv: &[Bytes], unit: i64) -> Result<Command, RusdisError> {
+    Ok(Command::String(StringCommand::SetEx {
+        key: argv[1].clone(),
+        expiry: parse_expiry(argv, &argv[2], unit, false)?,
+        value: argv[3].clone(),
+    }))
+}
+
+pub(crate) fn parse_setex_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_setex(argv, 1000)
+}
+
+pub(crate) fn parse_psetex_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_setex(argv, 1)
+}
+
+pub(crate) fn parse_lcs_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (mut idx_given, mut len_given, mut with_match_len) = (false, false, false);
+    let mut min_match_len = 0;
+    let mut idx = 3;
+    while idx < argv.len() {
+        match to_keyword(&argv[idx]).as_str() {
+            "IDX" => idx_given = true,
+            "LEN" => len_given = true,
+            "WITHMATCHLEN" => with_match_len = true,
+            "MINMATCHLEN" if idx + 1 < argv.len() => {
+                min_match_len = parse_number::<i64>(&argv[idx + 1])?.max(0) as usize;
+                idx += 1;
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+        idx += 1;
+    }
+    if idx_given && len_given {
+        return Err(generic_error(
+            "If you want both the length and indexes, please just use IDX.",
+        ));
+    }
+
+    Ok(Command::String(StringCommand::Lcs {
+        first: argv[1].clone(),
+        second: argv[2].clone(),
+        reply: match (idx_given, len_given) {
+            (true, _) => LcsReply::Idx,
+            (_, true) => LcsReply::Len,
+            _ => LcsReply::String,
+        },
+        min_match_len,
+        with_match_len,
+    }))
+}
+
+/// The string stored at `key`, if any.
+pub(crate) fn get_string<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<Option<&'a mut StringData>, RusdisError> {
+    match get_live(keyspace, key) {
+        Some(Data::String(data)) => Ok(Some(data)),
+        Some(_) => Err(RusdisError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// Stores `value` at `key` with no expiration, replacing whatever is there.
+fn set_string(keyspace: &mut Keyspace, key: Bytes, value: Bytes) {
+    keyspace.insert(key, Data::String(StringData::new(value)));
+}
+
+fn bulk_or_null(data: Option<&mut StringData>) -> Value {
+    data.map_or(Value::Null, |data| {
+        Value::BulkString(data.get_data().clone())
+    })
+}
+
+/// The bytes `start` to `end` of a string of `len` bytes, both inclusive and
+/// counting from the end when negative. `None` if the range is empty.
+fn string_range(start: i64, end: i64, len: usize) -> Option<(usize, usize)> {
+    if (start < 0 && end < 0 && start > end) || len == 0 {
+        return None;
+    }
+    let len = len as i64;
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.clamp(0, len - 1);
+
+    (start <= end).then_some((start as usize, end as usize))
+}
+
+/// The longest common subsequence of `a` and `b`, with the ranges of the
+/// runs matching in both, from the last to the first. The ranges are
+/// `[a_start, a_end, b_start, b_end]`, ends inclusive.
+fn lcs(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<[usize; 4]>) {
+    let width = b.len() + 1;
+    // table[i * width + j] is the LCS length of a[..i] and b[..j]
+    let mut table = vec![0_u32; (a.len() + 1) * width];
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            table[i * width + j] = if a[i - 1] == b[j - 1] {
+                table[(i - 1) * width + j - 1] + 1
+            } else {
+                table[(i - 1) * width + j].max(table[i * width + j - 1])
+            };
+        }
+    }
+
+    let mut common = vec![0; table[a.len() * width + b.len()] as usize];
+    let mut matches = vec![];
+    let mut run: Option<[usize; 4]> = None;
+    let (mut i, mut j, mut left) = (a.len(), b.len(), common.len());
+    while i > 0 && j > 0 {
+        let mut emit = false;
+        if a[i - 1] == b[j - 1] {
+            common[left - 1] = a[i - 1];
+            run = match run {
+                None => Some([i - 1, i - 1, j - 1, j - 1]),
+                // extend the run backwards while it stays contiguous
+                Some([a_start, a_end, b_start, b_end]) if a_start == i && b_start == j => {
+                    Some([a_start - 1, a_end, b_start - 1, b_end])
+                }
+                Some(run) => {
+                    emit = true;
+                    Some(run)
+                }
+            };
+            // a run reaching the start of either string cannot grow
+            if run.is_some_and(|run| run[0] == 0 || run[2] == 0) {
+                emit = true;
+            }
+            left -= 1;
+            i -= 1;
+            j -= 1;
+        } else {
+            if table[(i - 1) * width + j] > table[i * width + j - 1] {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+            emit = run.is_some();
+        }
+
+        if emit {
+            matches.extend(run.take());
+        }
+    }
+
+    (common, matches)
+}
+
+pub fn execute_string_command(
+    keyspace: &mut Keyspace,
+    cmd: StringCommand,
+) -> Result<Value, RusdisError> {
+    match cmd {
+        StringCommand::Append { key, value } => match get_string(keyspace, &key)? {
+            Some(data) => {
+                let len = data.get_data().len() + value.len();
+                if len > MAX_STRING_LEN {
+                    return Err(too_long());
+                }
+                let mut buf = Vec::with_capacity(len);
+                buf.extend_from_slice(data.get_data());
+                buf.extend_from_slice(&value);
+                data.set_data(Bytes::from(buf));
+
+                Ok(Value::Integer(len as i64))
+            }
+            None => {
+                let len = value.len();
+                set_string(keyspace, key, value);
+
+                Ok(Value::Integer(len as i64))
+            }
+        },
+        StringCommand::StrLen(key) => Ok(Value::Integer(
+            get_string(keyspace, &key)?.map_or(0, |data| data.get_data().len()) as i64,
+        )),
+        StringCommand::GetRange { key, start, end } => {
+            let value = match get_string(keyspace, &key)? {
+                Some(data) => data.get_data().clone(),
+                None => Bytes::new(),
+            };
+
+            Ok(Value::BulkString(
+                match string_range(start, end, value.len()) {
+                    Some((start, end)) => value.slice(start..=end),
+                    None => Bytes::new(),
+                },
+            ))
+        }
+        StringCommand::SetRange { key, offset, value } => {
+            let data = get_string(keyspace, &key)?;
+            if value.is_empty() {
+                // nothing to write, and a missing key stays missing
+                return Ok(Value::Integer(
+                    data.map_or(0, |data| data.get_data().len()) as i64
+                ));
+            }
+            if offset + value.len() > MAX_STRING_LEN {
+                return Err(too_long());
+            }
+
+            let mut buf = data
+                .as_ref()
+                .map_or(vec![], |data| data.get_data().to_vec());
+            if buf.len() < offset + value.len() {
+                buf.resize(offset + value.len(), 0);
+            }
+            buf[offset..offset + value.len()].copy_from_slice(&value);
+            let len = buf.len();
+            match data {
+                Some(data) => data.set_data(Bytes::from(buf)),
+                None => set_string(keyspace, key, Bytes::from(buf)),
+            }
+
+            Ok(Value::Integer(len as i64))
+        }
+        StringCommand::MGet(keys) => Ok(Value::Array(
+            keys.iter()
+                .map(|key| match get_live(keyspace, key) {
+                    Some(Data::String(data)) => Value::BulkString(data.get_data().clone()),
+                    _ => Value::Null,
+                })
+                .collect(),
+        )),
+        StringCommand::MSet(pairs) => {
+            for (key, value) in pairs.into_iter() {
+                set_string(keyspace, key, value);
+            }
+
+            Ok(Value::SimpleString("OK".to_string()))
+        }
+        StringCommand::MSetNx(pairs) => {
+            for (key, _) in pairs.iter() {
+                if get_live(keyspace, key).is_some() {
+                    return Ok(Value::Integer(0));
+                }
+            }
+            for (key, value) in pairs.into_iter() {
+                set_string(keyspace, key, value);
+            }
+
+            Ok(Value::Integer(1))
+        }
+        StringCommand::GetSet { key, value } => {
+            let old = bulk_or_null(get_string(keyspace, &key)?);
+            set_string(keyspace, key, value);
+
+            Ok(old)
+        }
+        StringCommand::GetDel(key) => {
+            let value = bulk_or_null(get_string(keyspace, &key)?);
+            keyspace.remove(&key);
+
+            Ok(value)
+        }
+        StringCommand::GetEx { key, update } => {
+            let value = match get_string(keyspace, &key)? {
+                Some(data) => Value::BulkString(data.get_data().clone()),
+                None => return Ok(Value::Null),
+            };
+            match update {
+                Some(TtlUpdate::Expire(expiry)) => {
+                    let expiration = expiry.absolute().resolve();
+                    if expiration <= SystemTime::now() {
+                        keyspace.remove(&key);
+                    } else {
+                        keyspace.set_expiration(&key, Some(expiration));
+                    }
+                    keyspace.propagate_as(vec![vec![
+                        Bytes::from("GETEX"),
+                        key,
+                        Bytes::from("PXAT"),
+                        unix_millis(expiration),
+                    ]]);
+                }
+                Some(TtlUpdate::Persist) => keyspace.set_expiration(&key, None),
+                Some(TtlUpdate::KeepTtl) | None => {}
+            }
+
+            Ok(value)
+        }
+        StringCommand::SetNx { key, value } => {
+            if get_live(keyspace, &key).is_some() {
+                return Ok(Value::Integer(0));
+            }
+            set_string(keyspace, key, value);
+
+            Ok(Value::Integer(1))
+        }
+        StringCommand::SetEx { key, expiry, value } => {
+            let expiration = expiry.absolute().resolve();
+            set_string(keyspace, key.clone(), value.clone());
+            keyspace.set_expiration(&key, Some(expiration));
+            keyspace.propagate_as(vec![vec![
+                Bytes::from("SET"),
+                key,
+                value,
+                Bytes::from("PXAT"),
+                unix_millis(expiration),
+            ]]);
+
+            Ok(Value::SimpleString("OK".to_string()))
+        }
+        StringCommand::Lcs {
+            first,
+            second,
+            reply,
+            min_match_len,
+            with_match_len,
+        } => {
+            let mut values = vec![];
+            for key in [&first, &second] {
+                values.push(match get_live(keyspace, key) {
+                    Some(Data::String(data)) => data.get_data().clone(),
+                    Some(_) => {
+                        return Err(generic_error(
+                            "The specified keys must contain string values",
+                        ))
+                    }
+                    None => Bytes::new(),
+                });
+            }
+            let (a, b) = (&values[0], &values[1]);
+            if (a.len() + 1).saturating_mul(b.len() + 1).saturating_mul(4) > MAX_STRING_LEN {
+                return Err(generic_error(
+                    "Insufficient memory, transient memory for LCS exceeds proto-max-bulk-len",
+                ));
+            }
+
+            let (common, matches) = lcs(a, b);
+            match reply {
+                LcsReply::String => Ok(Value::BulkString(Bytes::from(common))),
+                LcsReply::Len => Ok(Value::Integer(common.len() as i64)),
+                LcsReply::Idx => {
+                    let range = |start: usize, end: usize| {
+                        Value::Array(vec![
+                            Value::Integer(start as i64),
+                            Value::Integer(end as i64),
+                        ])
+                    };
+                    let matches = matches
+                        .into_iter()
+                        .filter(|[a_start, a_end, ..]| a_end - a_start + 1 >= min_match_len)
+                        .map(|[a_start, a_end, b_start, b_end]| {
+                            let mut reply = vec![range(a_start, a_end), range(b_start, b_end)];
+                            if with_match_len {
+                                reply.push(Value::Integer((a_end - a_start + 1) as i64));
+                            }
+                            Value::Array(reply)
+                        })
+                        .collect();
+
+                    Ok(Value::Map(vec![
+                        (
+                            Value::BulkString(Bytes::from("matches")),
+                            Value::Array(matches),
+                        ),
+                        (
+                            Value::BulkString(Bytes::from("len")),
+                            Value::Integer(common.len() as i64),
+                        ),
+                    ]))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{bulk, error, now_millis, ok, propagated, run};
+
+    fn range(start: i64, end: i64) -> Value {
+        Value::Array(vec![Value::Integer(start), Value::Integer(end)])
+    }
+
+    #[test]
+    fn test_string_ranges() {
+        let mut keyspace = Keyspace::new();
+
+        assert_eq!(
+            run(&mut keyspace, &["APPEND", "s", "Hello"]),
+            Value::Integer(5)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["APPEND", "s", " World"]),
+            Value::Integer(11)
+        );
+        assert_eq!(run(&mut keyspace, &["STRLEN", "s"]), Value::Integer(11));
+        assert_eq!(run(&mut keyspace, &["STRLEN", "none"]), Value::Integer(0));
+
+        assert_eq!(
+            run(&mut keyspace, &["GETRANGE", "s", "0", "3"]),
+            bulk("Hell")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETRANGE", "s", "-3", "-1"]),
+            bulk("rld")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETRANGE", "s", "0", "-1"]),
+            bulk("Hello World")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETRANGE", "s", "10", "100"]),
+            bulk("d")
+        );
+        assert_eq!(run(&mut keyspace, &["GETRANGE", "s", "-1", "-5"]), bulk(""));
+        assert_eq!(run(&mut keyspace, &["GETRANGE", "s", "5", "3"]), bulk(""));
+        assert_eq!(
+            run(&mut keyspace, &["GETRANGE", "none", "0", "-1"]),
+            bulk("")
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["SETRANGE", "s", "6", "Redis"]),
+            Value::Integer(11)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETRANGE", "s", "0", "-1"]),
+            bulk("Hello Redis")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SETRANGE", "padded", "3", "ab"]),
+            Value::Integer(5)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETRANGE", "padded", "0", "-1"]),
+            Value::BulkString(Bytes::from_static(b"\0\0\0ab"))
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SETRANGE", "empty", "3", ""]),
+            Value::Integer(0)
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("empty")));
+        assert_eq!(
+            run(&mut keyspace, &["SETRANGE", "s", "-1", "x"]),
+            error("ERR offset is out of range")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["SETRANGE", "s", "536870911", "xy"]),
+            error("ERR string exceeds maximum allowed size (proto-max-bulk-len)")
+        );
+
+        keyspace.insert(
+            Bytes::from("list"),
+            Data::List(crate::data::ListData::new(Default::default())),
+        );
+        assert_eq!(
+            run(&mut keyspace, &["APPEND", "list", "x"]),
+            RusdisError::WrongType.to_reply()
+        );
+    }
+
+    #[test]
+    fn test_string_get_and_set() {
+        let mut keyspace = Keyspace::new();
+
+        assert_eq!(run(&mut keyspace, &["MSET", "a", "1", "b", "2"]), ok());
+        assert_eq!(
+            run(&mut keyspace, &["MSET", "a", "1", "b"]),
+            error("ERR wrong number of arguments for 'mset' command")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["MGET", "a", "none", "b"]),
+            Value::Array(vec![bulk("1"), Value::Null, bulk("2")])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["MSETNX", "a", "x", "c", "3"]),
+            Value::Integer(0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["MGET", "c"]),
+            Value::Array(vec![Value::Null])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["MSETNX", "c", "3", "d", "4"]),
+            Value::Integer(1)
+        );
+
+        assert_eq!(run(&mut keyspace, &["SETNX", "a", "x"]), Value::Integer(0));
+        assert_eq!(run(&mut keyspace, &["SETNX", "e", "5"]), Value::Integer(1));
+        assert_eq!(run(&mut keyspace, &["GETSET", "a", "10"]), bulk("1"));
+        assert_eq!(run(&mut keyspace, &["GETSET", "f", "6"]), Value::Null);
+        assert_eq!(run(&mut keyspace, &["GETDEL", "a"]), bulk("10"));
+        assert_eq!(run(&mut keyspace, &["GETDEL", "a"]), Value::Null);
+
+        // PERSIST keeps the key past its expiration, PXAT in the past deletes it
+        assert_eq!(run(&mut keyspace, &["PSETEX", "t", "20", "v"]), ok());
+        assert_eq!(run(&mut keyspace, &["GETEX", "t", "PERSIST"]), bulk("v"));
+        assert_eq!(run(&mut keyspace, &["SETEX", "p", "100", "v"]), ok());
+        assert_eq!(run(&mut keyspace, &["GETEX", "p", "PX", "1"]), bulk("v"));
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        assert_eq!(
+            run(&mut keyspace, &["MGET", "t", "p"]),
+            Value::Array(vec![bulk("v"), Value::Null])
+        );
+        assert_eq!(run(&mut keyspace, &["GETEX", "t", "PXAT", "1"]), bulk("v"));
+        assert_eq!(run(&mut keyspace, &["GETEX", "t"]), Value::Null);
+
+        assert_eq!(
+            run(&mut keyspace, &["SETEX", "t", "0", "v"]),
+            error("ERR invalid expire time in 'setex' command")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETEX", "t", "EX", "10", "PERSIST"]),
+            error("ERR syntax error")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["GETEX", "t", "EX", "-1"]),
+            error("ERR invalid expire time in 'getex' command")
+        );
+    }
+
+    #[test]
+    fn test_string_propagates_absolute_expirations() {
+        let mut keyspace = Keyspace::new();
+
+        let before = now_millis();
+        run(&mut keyspace, &["SETEX", "k", "100", "v"]);
+        let commands = propagated(&mut keyspace).unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(&commands[0][..4], ["SET", "k", "v", "PXAT"]);
+        let at: u64 = commands[0][4].parse().unwrap();
+        assert!((before + 100_000..=now_millis() + 100_000).contains(&at));
+
+        run(&mut keyspace, &["GETEX", "k", "PX", "5000"]);
+        let commands = propagated(&mut keyspace).unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(&commands[0][..3], ["GETEX", "k", "PXAT"]);
+
+        run(&mut keyspace, &["GETEX", "k", "PERSIST"]);
+        assert_eq!(propagated(&mut keyspace), None);
+    }
+
+    #[test]
+    fn test_string_lcs() {
+        let mut keyspace = Keyspace::new();
+        run(
+            &mut keyspace,
+            &["MSET", "key1", "ohmytext", "key2", "mynewtext"],
+        );
+
+        assert_eq!(run(&mut keyspace, &["LCS", "key1", "key2"]), bulk("mytext"));
+        assert_eq!(
+            run(&mut keyspace, &["LCS", "key1", "key2", "LEN"]),
+            Value::Integer(6)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["LCS", "key1", "key2", "IDX"]),
+            Value::Map(vec![
+                (
+                    bulk("matches"),
+                    Value::Array(vec![
+                        Value::Array(vec![range(4, 7), range(5, 8)]),
+                        Value::Array(vec![range(2, 3), range(0, 1)]),
+                    ])
+                ),
+                (bulk("len"), Value::Integer(6)),
+            ])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "LCS",
+                    "key1",
+                    "key2",
+                    "IDX",
+                    "MINMATCHLEN",
+                    "4",
+                    "WITHMATCHLEN"
+                ]
+            ),
+            Value::Map(vec![
+                (
+                    bulk("matches"),
+                    Value::Array(vec![Value::Array(vec![
+                        range(4, 7),
+                        range(5, 8),
+                        Value::Integer(4)
+                    ])])
+                ),
+                (bulk("len"), Value::Integer(6)),
+            ])
+        );
+        assert_eq!(run(&mut keyspace, &["LCS", "key1", "none"]), bulk(""));
+        assert_eq!(
+            run(&mut keyspace, &["LCS", "key1", "key2", "IDX", "LEN"]),
+            error("ERR If you want both the length and indexes, please just use IDX.")
+        );
+    }
+}
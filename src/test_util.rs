@@ -0,0 +1,89 @@
+use crate::bitmap::execute_bitmap_command;
+use crate::command_parser::Command;
+use crate::command_table::lookup_command;
+use crate::data::{Data, Keyspace};
+use crate::geo::execute_geo_command;
+use crate::hash::execute_hash_command;
+use crate::hyperloglog::execute_hll_command;
+use crate::list::execute_list_command;
+use crate::parser::Value;
+use crate::set::execute_set_command;
+use crate::stream::execute_stream_command;
+use crate::string::execute_string_command;
+use crate::zset::execute_zset_command;
+use bytes::Bytes;
+
+/// Parses `args` as a call and runs it against `keyspace`, returning the reply
+/// a client would get.
+pub fn run(keyspace: &mut Keyspace, args: &[&str]) -> Value {
+    keyspace.take_propagate_as();
+    let argv: Vec<Bytes> = args
+        .iter()
+        .map(|arg| Bytes::from(arg.to_string()))
+        .collect();
+    let cmd = match lookup_command(&argv).and_then(|spec| spec.parse(&argv)) {
+        Ok(cmd) => cmd,
+        Err(e) => return e.to_reply(),
+    };
+
+    let reply = match cmd {
+        Command::String(cmd) => execute_string_command(keyspace, cmd),
+        Command::List(cmd) => execute_list_command(keyspace, cmd),
+        Command::Hash(cmd) => execute_hash_command(keyspace, cmd),
+        Command::SetType(cmd) => execute_set_command(keyspace, cmd),
+        Command::ZSet(cmd) => execute_zset_command(keyspace, cmd),
+        Command::Stream(cmd) => execute_stream_command(keyspace, cmd),
+        Command::Bitmap(cmd) => execute_bitmap_command(keyspace, cmd),
+        Command::HyperLogLog(cmd) => execute_hll_command(keyspace, cmd),
+        Command::Geo(cmd) => execute_geo_command(keyspace, cmd),
+        cmd => panic!("unexpected command {:?}", cmd),
+    };
+
+    reply.unwrap_or_else(|e| e.to_reply())
+}
+
+/// What the last call run propagates instead of its arguments, if anything.
+pub fn propagated(keyspace: &mut Keyspace) -> Option<Vec<Vec<String>>> {
+    keyspace.take_propagate_as().map(|commands| {
+        commands
+            .iter()
+            .map(|argv| {
+                argv.iter()
+                    .map(|arg| String::from_utf8_lossy(arg).to_string())
+                    .collect()
+            })
+            .collect()
+    })
+}
+
+/// Current unix time in milliseconds.
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+pub fn bulk(s: &str) -> Value {
+    Value::BulkString(Bytes::from(s.to_string()))
+}
+
+pub fn bulks(elements: &[&str]) -> Value {
+    Value::Array(elements.iter().map(|e| bulk(e)).collect())
+}
+
+pub fn error(msg: &str) -> Value {
+    Value::SimpleError(msg.to_string())
+}
+
+pub fn ok() -> Value {
+    Value::SimpleString("OK".to_string())
+}
+
+/// Raw value of the string stored at `key`.
+pub fn string(keyspace: &Keyspace, key: &str) -> Bytes {
+    match keyspace.get(&Bytes::from(key.to_string())) {
+        Some(Data::String(data)) => data.get_data().clone(),
+        data => panic!("unexpected data {:?}", data),
+    }
+}
@@ -1,5 +1,9 @@
-use crate::command_parser::Command;
+use crate::encoder::encode_to_bytes;
+use crate::parser::Value;
+use bytes::Bytes;
 use rand::{distributions::Alphanumeric, Rng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 pub(crate) fn generate_random_string(length: usize) -> String {
     rand::thread_rng()
         .sample_iter(&Alphanumeric)
@@ -8,36 +12,28 @@ pub(crate) fn generate_random_string(length: usize) -> String {
         .collect::<String>()
 }
 
-pub(crate) fn generate_resp(cmd: Command) -> String {
-    match cmd {
-        Command::Set { key, value, px } => {
-            let mut string = format!(
-                "$3\r\nset\r\n${}\r\n{}\r\n${}\r\n{}\r\n",
-                key.len(),
-                key,
-                value.len(),
-                value
-            );
-
-            let mut length = 3;
+/// Builds the array of bulk strings a command is sent as.
+pub(crate) fn bulk_string_array(args: &[&[u8]]) -> Value {
+    Value::Array(
+        args.iter()
+            .map(|arg| Value::BulkString(Bytes::copy_from_slice(arg)))
+            .collect(),
+    )
+}
 
-            if let Some(millis) = px {
-                let millis_string = millis.to_string();
-                string += format!(
-                    "$2\r\npx\r\n${}\r\n{}\r\n",
-                    millis_string.len(),
-                    millis_string
-                )
-                .as_str();
-                length += 2;
-            }
+/// Encodes a command the way it is propagated to replicas.
+pub(crate) fn generate_resp(argv: &[Bytes]) -> Bytes {
+    let args: Vec<&[u8]> = argv.iter().map(|arg| arg.as_ref()).collect();
+    encode_to_bytes(&bulk_string_array(&args), 2)
+}
 
-            format!("*{}\r\n{}", length, string)
-        }
-        _ => {
-            format!("$0\r\n\r\n")
-        }
-    }
+/// Unix time of `time` in milliseconds, as a command argument.
+pub(crate) fn unix_millis(time: SystemTime) -> Bytes {
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    Bytes::from(millis.to_string())
 }
 
 #[cfg(test)]
@@ -46,14 +42,10 @@ mod test {
 
     #[test]
     fn test_generate_resp() {
-        let set_command = Command::Set {
-            key: "key1".to_string(),
-            value: "apple".to_string(),
-            px: Some(5000),
-        };
+        let set_command = ["set", "key1", "apple", "px", "5000"].map(Bytes::from);
         assert_eq!(
-            "*5\r\n$3\r\nset\r\n$4\r\nkey1\r\n$5\r\napple\r\n$2\r\npx\r\n$4\r\n5000\r\n",
-            generate_resp(set_command)
+            &b"*5\r\n$3\r\nset\r\n$4\r\nkey1\r\n$5\r\napple\r\n$2\r\npx\r\n$4\r\n5000\r\n"[..],
+            generate_resp(&set_command)
         );
     }
 }
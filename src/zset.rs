@@ -0,0 +1,1670 @@
+use crate::blocking::{parse_timeout, BlockingOp};
+use crate::command_parser::{parse_number, to_keyword, Command};
+use crate::data::{get_live, Data, Keyspace, ZSetData};
+use crate::hash::parse_float;
+use crate::list::{parse_bounded_count, resolve_range};
+use crate::set::{parse_intercard_args, SetOp};
+use crate::sorted_set::SortedSet;
+use crate::{RusdisError, Value};
+use bytes::Bytes;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZSetCommand {
+    Add {
+        key: Bytes,
+        members: Vec<(f64, Bytes)>,
+        options: AddOptions,
+    },
+    Rem {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    Score {
+        key: Bytes,
+        member: Bytes,
+    },
+    MScore {
+        key: Bytes,
+        members: Vec<Bytes>,
+    },
+    IncrBy {
+        key: Bytes,
+        increment: f64,
+        member: Bytes,
+    },
+    Card(Bytes),
+    Count {
+        key: Bytes,
+        min: ScoreBound,
+        max: ScoreBound,
+    },
+    /// ZRANK and ZREVRANK
+    Rank {
+        key: Bytes,
+        member: Bytes,
+        rev: bool,
+        with_score: bool,
+    },
+    Range {
+        key: Bytes,
+        range: RangeSpec,
+        with_scores: bool,
+    },
+    /// ZPOPMIN and ZPOPMAX; `count` is set when given, even if it is 1
+    Pop {
+        key: Bytes,
+        max: bool,
+        count: Option<usize>,
+    },
+    /// ZMPOP
+    MPop {
+        keys: Vec<Bytes>,
+        max: bool,
+        count: usize,
+    },
+    /// ZRANDMEMBER; a negative `count` allows the same member more than once
+    RandMember {
+        key: Bytes,
+        count: Option<i64>,
+        with_scores: bool,
+    },
+    /// ZUNION, ZINTER and ZDIFF
+    Combine {
+        op: SetOp,
+        keys: Vec<Bytes>,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+        with_scores: bool,
+    },
+    /// ZUNIONSTORE, ZINTERSTORE and ZDIFFSTORE
+    Store {
+        op: SetOp,
+        destination: Bytes,
+        keys: Vec<Bytes>,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+    },
+    /// ZINTERCARD; a `limit` of 0 means no limit
+    InterCard {
+        keys: Vec<Bytes>,
+        limit: usize,
+    },
+    RangeStore {
+        destination: Bytes,
+        source: Bytes,
+        range: RangeSpec,
+    },
+}
+
+/// How the scores of a member found in several inputs are combined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    fn apply(&self, a: f64, b: f64) -> f64 {
+        match self {
+            // inf + -inf counts as 0, as in Redis
+            Aggregate::Sum => zero_if_nan(a + b),
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+}
+
+fn zero_if_nan(num: f64) -> f64 {
+    if num.is_nan() {
+        0.0
+    } else {
+        num
+    }
+}
+
+/// Options of ZADD.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AddOptions {
+    /// NX
+    only_new: bool,
+    /// XX
+    only_existing: bool,
+    /// GT
+    only_greater: bool,
+    /// LT
+    only_less: bool,
+    /// CH, counting changed members along with added ones
+    changed: bool,
+    incr: bool,
+}
+
+impl AddOptions {
+    /// Options of GEOADD, which only takes NX, XX and CH.
+    pub(crate) fn geo(only_new: bool, only_existing: bool, changed: bool) -> Self {
+        AddOptions {
+            only_new,
+            only_existing,
+            changed,
+            ..Default::default()
+        }
+    }
+}
+
+/// A `min` or `max` score argument, such as `1.5`, `(1.5` or `-inf`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreBound {
+    pub value: f64,
+    pub exclusive: bool,
+}
+
+impl ScoreBound {
+    /// Whether `score` is below the range this bound is the minimum of.
+    fn is_below(&self, score: f64) -> bool {
+        score < self.value || (self.exclusive && score == self.value)
+    }
+
+    /// Whether `score` is above the range this bound is the maximum of.
+    fn is_above(&self, score: f64) -> bool {
+        score > self.value || (self.exclusive && score == self.value)
+    }
+}
+
+/// A `min` or `max` lexicographical argument: `-`, `+`, `[member` or `(member`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexBound {
+    Min,
+    Max,
+    Inclusive(Bytes),
+    Exclusive(Bytes),
+}
+
+impl LexBound {
+    fn is_below(&self, member: &Bytes) -> bool {
+        match self {
+            LexBound::Min => false,
+            LexBound::Max => true,
+            LexBound::Inclusive(bound) => member < bound,
+            LexBound::Exclusive(bound) => member <= bound,
+        }
+    }
+
+    fn is_above(&self, member: &Bytes) -> bool {
+        match self {
+            LexBound::Min => true,
+            LexBound::Max => false,
+            LexBound::Inclusive(bound) => member > bound,
+            LexBound::Exclusive(bound) => member >= bound,
+        }
+    }
+}
+
+/// What a range command selects, always given as `min` then `max`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RangeBy {
+    Rank { start: i64, stop: i64 },
+    Score { min: ScoreBound, max: ScoreBound },
+    Lex { min: LexBound, max: LexBound },
+}
+
+/// A range of a sorted set, as ZRANGE and ZRANGESTORE take it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeSpec {
+    pub by: RangeBy,
+    pub rev: bool,
+    /// LIMIT offset and count, a negative count meaning all the rest
+    pub limit: Option<(i64, i64)>,
+}
+
+fn generic_error(msg: &str) -> RusdisError {
+    RusdisError::Generic {
+        msg: msg.to_string(),
+    }
+}
+
+fn parse_score(bytes: &Bytes) -> Result<f64, RusdisError> {
+    parse_float(bytes).ok_or_else(|| generic_error("value is not a valid float"))
+}
+
+pub(crate) fn parse_score_bound(bytes: &Bytes) -> Result<ScoreBound, RusdisError> {
+    let (value, exclusive) = match bytes.strip_prefix(b"(") {
+        Some(value) => (Bytes::copy_from_slice(value), true),
+        None => (bytes.clone(), false),
+    };
+
+    Ok(ScoreBound {
+        value: parse_float(&value).ok_or_else(|| generic_error("min or max is not a float"))?,
+        exclusive,
+    })
+}
+
+pub(crate) fn parse_lex_bound(bytes: &Bytes) -> Result<LexBound, RusdisError> {
+    match bytes.first() {
+        Some(b'-') if bytes.len() == 1 => Ok(LexBound::Min),
+        Some(b'+') if bytes.len() == 1 => Ok(LexBound::Max),
+        Some(b'[') => Ok(LexBound::Inclusive(bytes.slice(1..))),
+        Some(b'(') => Ok(LexBound::Exclusive(bytes.slice(1..))),
+        _ => Err(generic_error("min or max not valid string range item")),
+    }
+}
+
+pub(crate) fn parse_zadd_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let mut options = AddOptions::default();
+    let mut idx = 2;
+    while let Some(option) = argv.get(idx) {
+        match to_keyword(option).as_str() {
+            "NX" => options.only_new = true,
+            "XX" => options.only_existing = true,
+            "GT" => options.only_greater = true,
+            "LT" => options.only_less = true,
+            "CH" => options.changed = true,
+            "INCR" => options.incr = true,
+            _ => break,
+        }
+        idx += 1;
+    }
+
+    let pairs = &argv[idx..];
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return Err(RusdisError::SyntaxError);
+    }
+    if options.only_new && options.only_existing {
+        return Err(generic_error(
+            "XX and NX options at the same time are not compatible",
+        ));
+    }
+    if (options.only_greater && options.only_less)
+        || (options.only_new && (options.only_greater || options.only_less))
+    {
+        return Err(generic_error(
+            "GT, LT, and/or NX options at the same time are not compatible",
+        ));
+    }
+    if options.incr && pairs.len() > 2 {
+        return Err(generic_error(
+            "INCR option supports a single increment-element pair",
+        ));
+    }
+
+    Ok(Command::ZSet(ZSetCommand::Add {
+        key: argv[1].clone(),
+        members: pairs
+            .chunks(2)
+            .map(|pair| Ok((parse_score(&pair[0])?, pair[1].clone())))
+            .collect::<Result<_, RusdisError>>()?,
+        options,
+    }))
+}
+
+pub(crate) fn parse_zrem_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::ZSet(ZSetCommand::Rem {
+        key: argv[1].clone(),
+        members: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_zscore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::ZSet(ZSetCommand::Score {
+        key: argv[1].clone(),
+        member: argv[2].clone(),
+    }))
+}
+
+pub(crate) fn parse_zmscore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::ZSet(ZSetCommand::MScore {
+        key: argv[1].clone(),
+        members: argv[2..].to_vec(),
+    }))
+}
+
+pub(crate) fn parse_zincrby_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::ZSet(ZSetCommand::IncrBy {
+        key: argv[1].clone(),
+        increment: parse_score(&argv[2])?,
+        member: argv[3].clone(),
+    }))
+}
+
+pub(crate) fn parse_zcard_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::ZSet(ZSetCommand::Card(argv[1].clone())))
+}
+
+pub(crate) fn parse_zcount_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    Ok(Command::ZSet(ZSetCommand::Count {
+        key: argv[1].clone(),
+        min: parse_score_bound(&argv[2])?,
+        max: parse_score_bound(&argv[3])?,
+    }))
+}
+
+fn parse_rank(argv: &[Bytes], rev: bool) -> Result<Command, RusdisError> {
+    let with_score = match argv.get(3) {
+        Some(option) if to_keyword(option) == "WITHSCORE" => true,
+        Some(_) => return Err(RusdisError::SyntaxError),
+        None => false,
+    };
+    if argv.len() > 4 {
+        return Err(RusdisError::SyntaxError);
+    }
+
+    Ok(Command::ZSet(ZSetCommand::Rank {
+        key: argv[1].clone(),
+        member: argv[2].clone(),
+        rev,
+        with_score,
+    }))
+}
+
+pub(crate) fn parse_zrank_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_rank(argv, false)
+}
+
+pub(crate) fn parse_zrevrank_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_rank(argv, true)
+}
+
+/// Parses `start stop [BYSCORE|BYLEX] [REV] [LIMIT offset count]` starting
+/// at `idx`, along with WITHSCORES when `with_scores_allowed`.
+pub(crate) fn parse_range_args(
+    argv: &[Bytes],
+    idx: usize,
+    with_scores_allowed: bool,
+) -> Result<(RangeSpec, bool), RusdisError> {
+    let (mut by_score, mut by_lex, mut rev, mut with_scores) = (false, false, false, false);
+    let mut limit = None;
+    let mut args = argv[idx + 2..].iter();
+    while let Some(option) = args.next() {
+        match to_keyword(option).as_str() {
+            "BYSCORE" => by_score = true,
+            "BYLEX" => by_lex = true,
+            "REV" => rev = true,
+            "WITHSCORES" if with_scores_allowed => with_scores = true,
+            "LIMIT" => match (args.next(), args.next()) {
+                (Some(offset), Some(count)) => {
+                    limit = Some((parse_number::<i64>(offset)?, parse_number::<i64>(count)?))
+                }
+                _ => return Err(RusdisError::SyntaxError),
+            },
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    if by_score && by_lex {
+        return Err(RusdisError::SyntaxError);
+    }
+    if limit.is_some() && !by_score && !by_lex {
+        return Err(generic_error(
+            "syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX",
+        ));
+    }
+    if with_scores && by_lex {
+        return Err(generic_error(
+            "syntax error, WITHSCORES not supported in combination with BYLEX",
+        ));
+    }
+
+    // with REV, score and lex ranges are given from max to min
+    let (min, max) = if rev && (by_score || by_lex) {
+        (&argv[idx + 1], &argv[idx])
+    } else {
+        (&argv[idx], &argv[idx + 1])
+    };
+    let by = if by_score {
+        RangeBy::Score {
+            min: parse_score_bound(min)?,
+            max: parse_score_bound(max)?,
+        }
+    } else if by_lex {
+        RangeBy::Lex {
+            min: parse_lex_bound(min)?,
+            max: parse_lex_bound(max)?,
+        }
+    } else {
+        RangeBy::Rank {
+            start: parse_number(min)?,
+            stop: parse_number(max)?,
+        }
+    };
+
+    Ok((RangeSpec { by, rev, limit }, with_scores))
+}
+
+pub(crate) fn parse_zrange_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (range, with_scores) = parse_range_args(argv, 2, true)?;
+
+    Ok(Command::ZSet(ZSetCommand::Range {
+        key: argv[1].clone(),
+        range,
+        with_scores,
+    }))
+}
+
+fn parse_pop(argv: &[Bytes], max: bool) -> Result<Command, RusdisError> {
+    if argv.len() > 3 {
+        return Err(RusdisError::SyntaxError);
+    }
+
+    let count = match argv.get(2) {
+        Some(count) => {
+            let count = parse_number::<i64>(count)?;
+            if count < 0 {
+                return Err(generic_error("value is out of range, must be positive"));
+            }
+            Some(count as usize)
+        }
+        None => None,
+    };
+
+    Ok(Command::ZSet(ZSetCommand::Pop {
+        key: argv[1].clone(),
+        max,
+        count,
+    }))
+}
+
+/// Parses the `numkeys key [key ...] MIN|MAX [COUNT count]` tail of ZMPOP,
+/// starting at `argv[idx]`.
+pub(crate) fn parse_zmpop_args(
+    argv: &[Bytes],
+    idx: usize,
+) -> Result<(Vec<Bytes>, bool, usize), RusdisError> {
+    let numkeys = parse_bounded_count(&argv[idx], 1, "numkeys should be greater than 0")?;
+    if numkeys > argv.len() - idx - 2 {
+        return Err(RusdisError::SyntaxError);
+    }
+    let keys = argv[idx + 1..idx + 1 + numkeys].to_vec();
+    let max = match to_keyword(&argv[idx + 1 + numkeys]).as_str() {
+        "MIN" => false,
+        "MAX" => true,
+        _ => return Err(RusdisError::SyntaxError),
+    };
+
+    let mut count = None;
+    let mut args = argv[idx + 2 + numkeys..].iter();
+    while let Some(option) = args.next() {
+        match (to_keyword(option).as_str(), args.next()) {
+            ("COUNT", Some(value)) if count.is_none() => {
+                count = Some(parse_bounded_count(
+                    value,
+                    1,
+                    "count should be greater than 0",
+                )?)
+            }
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    Ok((keys, max, count.unwrap_or(1)))
+}
+
+pub(crate) fn parse_zmpop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (keys, max, count) = parse_zmpop_args(argv, 1)?;
+
+    Ok(Command::ZSet(ZSetCommand::MPop { keys, max, count }))
+}
+
+fn parse_bzpop(argv: &[Bytes], max: bool) -> Result<Command, RusdisError> {
+    Ok(Command::Block {
+        keys: argv[1..argv.len() - 1].to_vec(),
+        op: BlockingOp::ZPop { max },
+        timeout: parse_timeout(&argv[argv.len() - 1])?,
+    })
+}
+
+pub(crate) fn parse_bzpopmin_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_bzpop(argv, false)
+}
+
+pub(crate) fn parse_bzpopmax_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_bzpop(argv, true)
+}
+
+pub(crate) fn parse_bzmpop_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let timeout = parse_timeout(&argv[1])?;
+    let (keys, max, count) = parse_zmpop_args(argv, 2)?;
+
+    Ok(Command::Block {
+        keys,
+        op: BlockingOp::ZMPop { max, count },
+        timeout,
+    })
+}
+
+pub(crate) fn parse_zpopmin_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_pop(argv, false)
+}
+
+pub(crate) fn parse_zpopmax_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_pop(argv, true)
+}
+
+pub(crate) fn parse_zrandmember_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let count = match argv.get(2) {
+        Some(count) => Some(parse_number::<i64>(count)?),
+        None => None,
+    };
+    // as in Redis, so that the reply's length can't overflow
+    if count.is_some_and(|count| count.unsigned_abs() > (i64::MAX / 2) as u64) {
+        return Err(generic_error("value is out of range"));
+    }
+    let with_scores = match argv.get(3) {
+        Some(option) if to_keyword(option) == "WITHSCORES" => true,
+        Some(_) => return Err(RusdisError::SyntaxError),
+        None => false,
+    };
+    if argv.len() > 4 {
+        return Err(RusdisError::SyntaxError);
+    }
+
+    Ok(Command::ZSet(ZSetCommand::RandMember {
+        key: argv[1].clone(),
+        count,
+        with_scores,
+    }))
+}
+
+/// Keys, weights, aggregate and WITHSCORES of a multi-key command.
+type CombineArgs = (Vec<Bytes>, Option<Vec<f64>>, Aggregate, bool);
+
+/// Parses `numkeys key... [WEIGHTS weight...] [AGGREGATE SUM|MIN|MAX]` starting
+/// at `idx`, along with WITHSCORES when `with_scores_allowed`. ZDIFF takes
+/// neither WEIGHTS nor AGGREGATE.
+fn parse_combine_args(
+    argv: &[Bytes],
+    idx: usize,
+    op: SetOp,
+    with_scores_allowed: bool,
+) -> Result<CombineArgs, RusdisError> {
+    let numkeys = parse_number::<i64>(&argv[idx])?;
+    if numkeys < 1 {
+        return Err(RusdisError::Generic {
+            msg: format!(
+                "at least 1 input key is needed for '{}' command",
+                String::from_utf8_lossy(&argv[0]).to_lowercase()
+            ),
+        });
+    }
+    let numkeys = numkeys as usize;
+    if numkeys > argv.len() - idx - 1 {
+        return Err(RusdisError::SyntaxError);
+    }
+    let keys = argv[idx + 1..idx + 1 + numkeys].to_vec();
+
+    let (mut weights, mut aggregate, mut with_scores) = (None, Aggregate::Sum, false);
+    let mut args = argv[idx + 1 + numkeys..].iter();
+    while let Some(option) = args.next() {
+        match to_keyword(option).as_str() {
+            "WEIGHTS" if op != SetOp::Diff => {
+                let values: Vec<&Bytes> = args.by_ref().take(numkeys).collect();
+                if values.len() < numkeys {
+                    return Err(RusdisError::SyntaxError);
+                }
+                weights = Some(
+                    values
+                        .into_iter()
+                        .map(|weight| {
+                            parse_float(weight)
+                                .ok_or_else(|| generic_error("weight value is not a float"))
+                        })
+                        .collect::<Result<_, _>>()?,
+                );
+            }
+            "AGGREGATE" if op != SetOp::Diff => {
+                aggregate = match args.next().map(to_keyword).as_deref() {
+                    Some("SUM") => Aggregate::Sum,
+                    Some("MIN") => Aggregate::Min,
+                    Some("MAX") => Aggregate::Max,
+                    _ => return Err(RusdisError::SyntaxError),
+                }
+            }
+            "WITHSCORES" if with_scores_allowed => with_scores = true,
+            _ => return Err(RusdisError::SyntaxError),
+        }
+    }
+
+    Ok((keys, weights, aggregate, with_scores))
+}
+
+fn parse_combine(argv: &[Bytes], op: SetOp) -> Result<Command, RusdisError> {
+    let (keys, weights, aggregate, with_scores) = parse_combine_args(argv, 1, op, true)?;
+
+    Ok(Command::ZSet(ZSetCommand::Combine {
+        op,
+        keys,
+        weights,
+        aggregate,
+        with_scores,
+    }))
+}
+
+fn parse_store(argv: &[Bytes], op: SetOp) -> Result<Command, RusdisError> {
+    let (keys, weights, aggregate, _) = parse_combine_args(argv, 2, op, false)?;
+
+    Ok(Command::ZSet(ZSetCommand::Store {
+        op,
+        destination: argv[1].clone(),
+        keys,
+        weights,
+        aggregate,
+    }))
+}
+
+pub(crate) fn parse_zunion_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_combine(argv, SetOp::Union)
+}
+
+pub(crate) fn parse_zinter_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_combine(argv, SetOp::Inter)
+}
+
+pub(crate) fn parse_zdiff_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_combine(argv, SetOp::Diff)
+}
+
+pub(crate) fn parse_zunionstore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_store(argv, SetOp::Union)
+}
+
+pub(crate) fn parse_zinterstore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_store(argv, SetOp::Inter)
+}
+
+pub(crate) fn parse_zdiffstore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    parse_store(argv, SetOp::Diff)
+}
+
+pub(crate) fn parse_zintercard_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (keys, limit) = parse_intercard_args(argv)?;
+
+    Ok(Command::ZSet(ZSetCommand::InterCard { keys, limit }))
+}
+
+pub(crate) fn parse_zrangestore_command(argv: &[Bytes]) -> Result<Command, RusdisError> {
+    let (range, _) = parse_range_args(argv, 3, false)?;
+
+    Ok(Command::ZSet(ZSetCommand::RangeStore {
+        destination: argv[1].clone(),
+        source: argv[2].clone(),
+        range,
+    }))
+}
+
+/// The sorted set stored at `key`, if any.
+pub(crate) fn get_zset<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<Option<&'a mut SortedSet>, RusdisError> {
+    match get_live(keyspace, key) {
+        Some(Data::ZSet(data)) => Ok(Some(data.get_zset_mut())),
+        Some(_) => Err(RusdisError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// The sorted set stored at `key`, created empty if the key does not exist.
+fn get_or_create_zset<'a>(
+    keyspace: &'a mut Keyspace,
+    key: &Bytes,
+) -> Result<&'a mut SortedSet, RusdisError> {
+    get_live(keyspace, key);
+    match keyspace
+        .entry(key.clone())
+        .or_insert_with(|| Data::ZSet(ZSetData::new(SortedSet::new())))
+    {
+        Data::ZSet(data) => Ok(data.get_zset_mut()),
+        _ => Err(RusdisError::WrongType),
+    }
+}
+
+/// Deletes `key` if it holds an empty sorted set, as Redis never keeps empty ones.
+fn remove_if_empty(keyspace: &mut Keyspace, key: &Bytes) {
+    if let Some(Data::ZSet(data)) = keyspace.get(key) {
+        if data.get_zset().is_empty() {
+            keyspace.remove(key);
+        }
+    }
+}
+
+/// A sorted set or a plain set, whose members all score 1, read by the
+/// multi-key commands.
+enum Input<'a> {
+    ZSet(&'a SortedSet),
+    Set(&'a HashSet<Bytes>),
+    Missing,
+}
+
+impl<'a> Input<'a> {
+    fn len(&self) -> usize {
+        match self {
+            Input::ZSet(zset) => zset.len(),
+            Input::Set(set) => set.len(),
+            Input::Missing => 0,
+        }
+    }
+
+    fn score(&self, member: &Bytes) -> Option<f64> {
+        match self {
+            Input::ZSet(zset) => zset.score(member),
+            Input::Set(set) => set.contains(member).then_some(1.0),
+            Input::Missing => None,
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (&'a Bytes, f64)> + 'a> {
+        match self {
+            Input::ZSet(zset) => Box::new(zset.iter()),
+            Input::Set(set) => Box::new(set.iter().map(|member| (member, 1.0))),
+            Input::Missing => Box::new(std::iter::empty()),
+        }
+    }
+}
+
+/// The sorted sets or sets stored at `keys`. Fails if any key holds another type.
+fn get_inputs<'a>(
+    keyspace: &'a mut Keyspace,
+    keys: &[Bytes],
+) -> Result<Vec<Input<'a>>, RusdisError> {
+    for key in keys.iter() {
+        match get_live(keyspace, key) {
+            Some(Data::ZSet(_)) | Some(Data::Set(_)) | None => {}
+            Some(_) => return Err(RusdisError::WrongType),
+        }
+    }
+
+    Ok(keys
+        .iter()
+        .map(|key| match keyspace.get(key) {
+            Some(Data::ZSet(data)) => Input::ZSet(data.get_zset()),
+            Some(Data::Set(data)) => Input::Set(data.get_set()),
+            _ => Input::Missing,
+        })
+        .collect())
+}
+
+/// Combines the inputs at `keys`, each score multiplied by the weight of its input.
+fn combine(
+    keyspace: &mut Keyspace,
+    op: SetOp,
+    keys: &[Bytes],
+    weights: Option<&[f64]>,
+    aggregate: Aggregate,
+) -> Result<SortedSet, RusdisError> {
+    let inputs = get_inputs(keyspace, keys)?;
+    let weight = |idx: usize| weights.map_or(1.0, |weights| weights[idx]);
+
+    let mut scores: HashMap<Bytes, f64> = HashMap::new();
+    match op {
+        SetOp::Union => {
+            for (idx, input) in inputs.iter().enumerate() {
+                for (member, score) in input.iter() {
+                    let score = zero_if_nan(score * weight(idx));
+                    scores
+                        .entry(member.clone())
+                        .and_modify(|total| *total = aggregate.apply(*total, score))
+                        .or_insert(score);
+                }
+            }
+        }
+        SetOp::Inter => {
+            // walk the smallest input, looking members up in the others
+            let smallest = (0..inputs.len())
+                .min_by_key(|idx| inputs[*idx].len())
+                .unwrap_or_default();
+            for (member, _) in inputs[smallest].iter() {
+                let mut total = None;
+                for (idx, input) in inputs.iter().enumerate() {
+                    let score = match input.score(member) {
+                        Some(score) => zero_if_nan(score * weight(idx)),
+                        None => {
+                            total = None;
+                            break;
+                        }
+                    };
+                    total = Some(total.map_or(score, |total| aggregate.apply(total, score)));
+                }
+                if let Some(total) = total {
+                    scores.insert(member.clone(), total);
+                }
+            }
+        }
+        SetOp::Diff => {
+            for (member, score) in inputs[0].iter() {
+                if !inputs[1..]
+                    .iter()
+                    .any(|input| input.score(member).is_some())
+                {
+                    scores.insert(member.clone(), score);
+                }
+            }
+        }
+    }
+
+    let mut zset = SortedSet::new();
+    for (member, score) in scores.into_iter() {
+        zset.insert(member, score);
+    }
+
+    Ok(zset)
+}
+
+/// Stores `zset` at `destination`, replacing what is there, or deletes the
+/// key if `zset` is empty. Returns the number of members stored.
+pub(crate) fn store(keyspace: &mut Keyspace, destination: Bytes, zset: SortedSet) -> usize {
+    let len = zset.len();
+    if zset.is_empty() {
+        keyspace.remove(&destination);
+    } else {
+        keyspace.insert(destination, Data::ZSet(ZSetData::new(zset)));
+    }
+
+    len
+}
+
+fn bulk(bytes: &Bytes) -> Value {
+    Value::BulkString(bytes.clone())
+}
+
+/// Members and scores, as pairs when `with_scores`.
+fn members_reply(members: Vec<(Bytes, f64)>, with_scores: bool) -> Value {
+    if with_scores {
+        Value::Pairs(
+            members
+                .into_iter()
+                .map(|(member, score)| (Value::BulkString(member), Value::Double(score)))
+                .collect(),
+        )
+    } else {
+        Value::Array(
+            members
+                .into_iter()
+                .map(|(member, _)| Value::BulkString(member))
+                .collect(),
+        )
+    }
+}
+
+/// The members `range` selects, in the order it asks for.
+pub(crate) fn range(zset: &SortedSet, range: &RangeSpec) -> Vec<(Bytes, f64)> {
+    let len = zset.len();
+    // ascending ranks [start, end)
+    let (start, end) = match &range.by {
+        RangeBy::Rank { start, stop } => match resolve_range(*start, *stop, len) {
+            Some((start, stop)) if range.rev => (len - 1 - stop, len - start),
+            Some((start, stop)) => (start, stop + 1),
+            None => (0, 0),
+        },
+        RangeBy::Score { min, max } => (
+            zset.count_while(|score, _| min.is_below(score)),
+            zset.count_while(|score, _| !max.is_above(score)),
+        ),
+        RangeBy::Lex { min, max } => (
+            zset.count_while(|_, member| min.is_below(member)),
+            zset.count_while(|_, member| !max.is_above(member)),
+        ),
+    };
+    if start >= end {
+        return vec![];
+    }
+
+    let (offset, count) = match range.limit {
+        Some((offset, _)) if offset < 0 => return vec![],
+        Some((offset, count)) => (
+            offset as usize,
+            if count < 0 {
+                usize::MAX
+            } else {
+                count as usize
+            },
+        ),
+        None => (0, usize::MAX),
+    };
+    let from = if range.rev { end - 1 } else { start };
+
+    zset.iter_from(from, range.rev)
+        .take(end - start)
+        .skip(offset)
+        .take(count)
+        .map(|(member, score)| (member.clone(), score))
+        .collect()
+}
+
+/// Pops up to `count` members from the low end, or the high end if `max`.
+pub(crate) fn pop(zset: &mut SortedSet, max: bool, count: usize) -> Vec<(Bytes, f64)> {
+    let from = if max { zset.len().saturating_sub(1) } else { 0 };
+    let popped: Vec<(Bytes, f64)> = zset
+        .iter_from(from, max)
+        .take(count)
+        .map(|(member, score)| (member.clone(), score))
+        .collect();
+    for (member, _) in popped.iter() {
+        zset.remove(member);
+    }
+
+    popped
+}
+
+/// Pops up to `count` members from the sorted set at `key`, deleting the key
+/// once it is empty. `None` if the key does not exist.
+pub(crate) fn pop_key(
+    keyspace: &mut Keyspace,
+    key: &Bytes,
+    max: bool,
+    count: usize,
+) -> Result<Option<Vec<(Bytes, f64)>>, RusdisError> {
+    let popped = match get_zset(keyspace, key)? {
+        Some(zset) => pop(zset, max, count),
+        None => return Ok(None),
+    };
+    remove_if_empty(keyspace, key);
+
+    Ok(Some(popped))
+}
+
+/// `[key, [[member, score] ...]]`, the reply of ZMPOP and BZMPOP.
+pub(crate) fn mpop_reply(key: &Bytes, popped: Vec<(Bytes, f64)>) -> Value {
+    Value::Array(vec![
+        bulk(key),
+        Value::Array(
+            popped
+                .into_iter()
+                .map(|(member, score)| {
+                    Value::Array(vec![Value::BulkString(member), Value::Double(score)])
+                })
+                .collect(),
+        ),
+    ])
+}
+
+pub fn execute_zset_command(
+    keyspace: &mut Keyspace,
+    cmd: ZSetCommand,
+) -> Result<Value, RusdisError> {
+    match cmd {
+        ZSetCommand::Add {
+            key,
+            members,
+            options,
+        } => {
+            let zset = if options.only_existing {
+                match get_zset(keyspace, &key)? {
+                    Some(zset) => zset,
+                    None if options.incr => return Ok(Value::Null),
+                    None => return Ok(Value::Integer(0)),
+                }
+            } else {
+                get_or_create_zset(keyspace, &key)?
+            };
+
+            let (mut added, mut changed) = (0, 0);
+            let mut incr_score = None;
+            for (score, member) in members.into_iter() {
+                let new_score = match zset.score(&member) {
+                    Some(current) => {
+                        if options.only_new {
+                            continue;
+                        }
+                        let new_score = if options.incr { current + score } else { score };
+                        if new_score.is_nan() {
+                            return Err(generic_error("resulting score is not a number (NaN)"));
+                        }
+                        if (options.only_greater && new_score <= current)
+                            || (options.only_less && new_score >= current)
+                        {
+                            continue;
+                        }
+                        if new_score != current {
+                            zset.insert(member, new_score);
+                            changed += 1;
+                        }
+                        new_score
+                    }
+                    None => {
+                        if options.only_existing {
+                            continue;
+                        }
+                        zset.insert(member, score);
+                        added += 1;
+                        score
+                    }
+                };
+                incr_score = Some(new_score);
+            }
+            remove_if_empty(keyspace, &key);
+
+            Ok(if options.incr {
+                incr_score.map_or(Value::Null, Value::Double)
+            } else if options.changed {
+                Value::Integer(added + changed)
+            } else {
+                Value::Integer(added)
+            })
+        }
+        ZSetCommand::Rem { key, members } => {
+            let zset = match get_zset(keyspace, &key)? {
+                Some(zset) => zset,
+                None => return Ok(Value::Integer(0)),
+            };
+            let removed = members.iter().filter(|member| zset.remove(member)).count();
+            remove_if_empty(keyspace, &key);
+
+            Ok(Value::Integer(removed as i64))
+        }
+        ZSetCommand::Score { key, member } => Ok(get_zset(keyspace, &key)?
+            .and_then(|zset| zset.score(&member))
+            .map_or(Value::Null, Value::Double)),
+        ZSetCommand::MScore { key, members } => {
+            let zset = get_zset(keyspace, &key)?;
+
+            Ok(Value::Array(
+                members
+                    .iter()
+                    .map(|member| {
+                        zset.as_ref()
+                            .and_then(|zset| zset.score(member))
+                            .map_or(Value::Null, Value::Double)
+                    })
+                    .collect(),
+            ))
+        }
+        ZSetCommand::IncrBy {
+            key,
+            increment,
+            member,
+        } => {
+            let zset = get_or_create_zset(keyspace, &key)?;
+            let score = zset.score(&member).unwrap_or(0.0) + increment;
+            if score.is_nan() {
+                remove_if_empty(keyspace, &key);
+                return Err(generic_error("resulting score is not a number (NaN)"));
+            }
+            zset.insert(member, score);
+
+            Ok(Value::Double(score))
+        }
+        ZSetCommand::Card(key) => Ok(Value::Integer(
+            get_zset(keyspace, &key)?.map_or(0, |zset| zset.len()) as i64,
+        )),
+        ZSetCommand::Count { key, min, max } => {
+            let zset = match get_zset(keyspace, &key)? {
+                Some(zset) => zset,
+                None => return Ok(Value::Integer(0)),
+            };
+            let start = zset.count_while(|score, _| min.is_below(score));
+            let end = zset.count_while(|score, _| !max.is_above(score));
+
+            Ok(Value::Integer(end.saturating_sub(start) as i64))
+        }
+        ZSetCommand::Rank {
+            key,
+            member,
+            rev,
+            with_score,
+        } => {
+            let zset = get_zset(keyspace, &key)?;
+            let (rank, score) = match zset.and_then(|zset| Some((zset.rank(&member)?, zset))) {
+                Some((rank, zset)) => {
+                    let rank = if rev { zset.len() - 1 - rank } else { rank };
+                    (rank, zset.score(&member).unwrap_or_default())
+                }
+                None if with_score => return Ok(Value::NullArray),
+                None => return Ok(Value::Null),
+            };
+
+            Ok(if with_score {
+                Value::Array(vec![Value::Integer(rank as i64), Value::Double(score)])
+            } else {
+                Value::Integer(rank as i64)
+            })
+        }
+        ZSetCommand::Range {
+            key,
+            range: spec,
+            with_scores,
+        } => Ok(members_reply(
+            get_zset(keyspace, &key)?
+                .map(|zset| range(zset, &spec))
+                .unwrap_or_default(),
+            with_scores,
+        )),
+        ZSetCommand::Pop { key, max, count } => {
+            let popped = pop_key(keyspace, &key, max, count.unwrap_or(1))?.unwrap_or_default();
+
+            Ok(match count {
+                Some(_) => members_reply(popped, true),
+                None => Value::Array(
+                    popped
+                        .into_iter()
+                        .flat_map(|(member, score)| {
+                            [Value::BulkString(member), Value::Double(score)]
+                        })
+                        .collect(),
+                ),
+            })
+        }
+        ZSetCommand::MPop { keys, max, count } => {
+            for key in keys.iter() {
+                if let Some(popped) = pop_key(keyspace, key, max, count)? {
+                    return Ok(mpop_reply(key, popped));
+                }
+            }
+
+            Ok(Value::NullArray)
+        }
+        ZSetCommand::RandMember {
+            key,
+            count,
+            with_scores,
+        } => {
+            let zset = get_zset(keyspace, &key)?;
+            let count = match count {
+                Some(count) => count,
+                None => {
+                    return Ok(zset
+                        .and_then(|zset| zset.iter().choose(&mut rand::thread_rng()))
+                        .map_or(Value::Null, |(member, _)| bulk(member)))
+                }
+            };
+            let zset = match zset {
+                Some(zset) => zset,
+                None => return Ok(Value::Array(vec![])),
+            };
+
+            let mut rng = rand::thread_rng();
+            let picked: Vec<(&Bytes, f64)> = if count >= 0 {
+                let count = (count as usize).min(zset.len());
+                zset.iter().choose_multiple(&mut rng, count)
+            } else {
+                (0..count.unsigned_abs())
+                    .filter_map(|_| zset.iter_from(rng.gen_range(0..zset.len()), false).next())
+                    .collect()
+            };
+
+            Ok(members_reply(
+                picked
+                    .into_iter()
+                    .map(|(member, score)| (member.clone(), score))
+                    .collect(),
+                with_scores,
+            ))
+        }
+        ZSetCommand::Combine {
+            op,
+            keys,
+            weights,
+            aggregate,
+            with_scores,
+        } => {
+            let zset = combine(keyspace, op, &keys, weights.as_deref(), aggregate)?;
+
+            Ok(members_reply(
+                zset.iter()
+                    .map(|(member, score)| (member.clone(), score))
+                    .collect(),
+                with_scores,
+            ))
+        }
+        ZSetCommand::Store {
+            op,
+            destination,
+            keys,
+            weights,
+            aggregate,
+        } => {
+            let zset = combine(keyspace, op, &keys, weights.as_deref(), aggregate)?;
+
+            Ok(Value::Integer(store(keyspace, destination, zset) as i64))
+        }
+        ZSetCommand::InterCard { keys, limit } => {
+            let inputs = get_inputs(keyspace, &keys)?;
+            let smallest = (0..inputs.len())
+                .min_by_key(|idx| inputs[*idx].len())
+                .unwrap_or_default();
+            let members = inputs[smallest]
+                .iter()
+                .filter(|(member, _)| inputs.iter().all(|input| input.score(member).is_some()));
+            let count = if limit > 0 {
+                members.take(limit).count()
+            } else {
+                members.count()
+            };
+
+            Ok(Value::Integer(count as i64))
+        }
+        ZSetCommand::RangeStore {
+            destination,
+            source,
+            range: spec,
+        } => {
+            let mut zset = SortedSet::new();
+            if let Some(source) = get_zset(keyspace, &source)? {
+                for (member, score) in range(source, &spec).into_iter() {
+                    zset.insert(member, score);
+                }
+            }
+
+            Ok(Value::Integer(store(keyspace, destination, zset) as i64))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::StringData;
+    use crate::test_util::{bulks, error, run};
+
+    #[test]
+    fn test_zset_add() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "XX", "1", "a"]),
+            Value::Integer(0)
+        );
+        assert!(keyspace.is_empty());
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "1", "a", "2", "b"]),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZADD", "z", "CH", "5", "a", "2", "b", "3", "c"]
+            ),
+            Value::Integer(2)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "NX", "9", "a", "4", "d"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZADD", "z", "GT", "CH", "1", "a", "3", "b"]
+            ),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZADD", "z", "LT", "CH", "1", "a", "9", "b"]
+            ),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "INCR", "2.5", "a"]),
+            Value::Double(3.5)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "INCR", "GT", "-1", "a"]),
+            Value::Null
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZINCRBY", "z", "-0.5", "a"]),
+            Value::Double(3.0)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZSCORE", "z", "a"]),
+            Value::Double(3.0)
+        );
+        assert_eq!(run(&mut keyspace, &["ZSCORE", "z", "x"]), Value::Null);
+        assert_eq!(
+            run(&mut keyspace, &["ZMSCORE", "z", "b", "x"]),
+            Value::Array(vec![Value::Double(3.0), Value::Null])
+        );
+        assert_eq!(run(&mut keyspace, &["ZCARD", "z"]), Value::Integer(4));
+
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "NX", "XX", "1", "a"]),
+            error("ERR XX and NX options at the same time are not compatible")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "NX", "GT", "1", "a"]),
+            error("ERR GT, LT, and/or NX options at the same time are not compatible")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "INCR", "1", "a", "2", "b"]),
+            error("ERR INCR option supports a single increment-element pair")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "x", "a"]),
+            error("ERR value is not a valid float")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZADD", "z", "1", "a", "2"]),
+            error("ERR syntax error")
+        );
+        run(&mut keyspace, &["ZADD", "z", "inf", "a"]);
+        assert_eq!(
+            run(&mut keyspace, &["ZINCRBY", "z", "-inf", "a"]),
+            error("ERR resulting score is not a number (NaN)")
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["ZREM", "z", "a", "b", "c", "x"]),
+            Value::Integer(3)
+        );
+        assert_eq!(run(&mut keyspace, &["ZREM", "z", "d"]), Value::Integer(1));
+        assert!(keyspace.is_empty());
+    }
+
+    #[test]
+    fn test_zset_ranges() {
+        let mut keyspace = Keyspace::new();
+        run(
+            &mut keyspace,
+            &[
+                "ZADD", "z", "1", "a", "2", "b", "2", "c", "3", "d", "4", "e",
+            ],
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "z", "1", "-2"]),
+            bulks(&["b", "c", "d"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "z", "0", "1", "REV"]),
+            bulks(&["e", "d"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "z", "0", "0", "WITHSCORES"]),
+            Value::Pairs(vec![(
+                Value::BulkString(Bytes::from("a")),
+                Value::Double(1.0)
+            )])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "z", "(1", "3", "BYSCORE"]),
+            bulks(&["b", "c", "d"])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZRANGE", "z", "+inf", "2", "BYSCORE", "REV", "LIMIT", "1", "2"]
+            ),
+            bulks(&["d", "c"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "z", "5", "9", "BYSCORE"]),
+            bulks(&[])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZCOUNT", "z", "2", "(4"]),
+            Value::Integer(3)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZCOUNT", "z", "3", "1"]),
+            Value::Integer(0)
+        );
+
+        run(
+            &mut keyspace,
+            &["ZADD", "l", "0", "a", "0", "b", "0", "c", "0", "d"],
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "l", "[b", "(d", "BYLEX"]),
+            bulks(&["b", "c"])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZRANGE", "l", "+", "-", "BYLEX", "REV", "LIMIT", "0", "2"]
+            ),
+            bulks(&["d", "c"])
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "z", "0", "1", "LIMIT", "0", "1"]),
+            error(
+                "ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"
+            )
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZRANGE", "l", "-", "+", "BYLEX", "WITHSCORES"]
+            ),
+            error("ERR syntax error, WITHSCORES not supported in combination with BYLEX")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "z", "a", "2", "BYSCORE"]),
+            error("ERR min or max is not a float")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "l", "a", "+", "BYLEX"]),
+            error("ERR min or max not valid string range item")
+        );
+
+        assert_eq!(run(&mut keyspace, &["ZRANK", "z", "c"]), Value::Integer(2));
+        assert_eq!(
+            run(&mut keyspace, &["ZREVRANK", "z", "c", "WITHSCORE"]),
+            Value::Array(vec![Value::Integer(2), Value::Double(2.0)])
+        );
+        assert_eq!(run(&mut keyspace, &["ZRANK", "z", "x"]), Value::Null);
+        assert_eq!(
+            run(&mut keyspace, &["ZRANK", "z", "x", "WITHSCORE"]),
+            Value::NullArray
+        );
+    }
+
+    #[test]
+    fn test_zset_pop_and_randmember() {
+        let mut keyspace = Keyspace::new();
+        assert_eq!(run(&mut keyspace, &["ZPOPMIN", "z"]), bulks(&[]));
+        run(&mut keyspace, &["ZADD", "z", "1", "a", "2", "b", "3", "c"]);
+
+        assert_eq!(
+            run(&mut keyspace, &["ZPOPMIN", "z"]),
+            Value::Array(vec![
+                Value::BulkString(Bytes::from("a")),
+                Value::Double(1.0)
+            ])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZPOPMAX", "z", "5"]),
+            Value::Pairs(vec![
+                (Value::BulkString(Bytes::from("c")), Value::Double(3.0)),
+                (Value::BulkString(Bytes::from("b")), Value::Double(2.0)),
+            ])
+        );
+        assert!(keyspace.is_empty());
+
+        assert_eq!(run(&mut keyspace, &["ZRANDMEMBER", "z"]), Value::Null);
+        run(&mut keyspace, &["ZADD", "z", "1", "a", "2", "b", "3", "c"]);
+        match run(&mut keyspace, &["ZRANDMEMBER", "z", "-5", "WITHSCORES"]) {
+            Value::Pairs(pairs) => assert_eq!(pairs.len(), 5),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        match run(&mut keyspace, &["ZRANDMEMBER", "z", "5"]) {
+            Value::Array(members) => assert_eq!(members.len(), 3),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        match run(&mut keyspace, &["ZRANDMEMBER", "z", "4611686018427387903"]) {
+            Value::Array(members) => assert_eq!(members.len(), 3),
+            reply => panic!("unexpected reply {:?}", reply),
+        }
+        for count in ["4611686018427387904", "-9223372036854775808"] {
+            assert_eq!(
+                run(&mut keyspace, &["ZRANDMEMBER", "z", count, "WITHSCORES"]),
+                Value::SimpleError("ERR value is out of range".to_string())
+            );
+        }
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZMPOP", "2", "missing", "z", "MAX", "COUNT", "2"]
+            ),
+            mpop_reply(
+                &Bytes::from("z"),
+                vec![(Bytes::from("c"), 3.0), (Bytes::from("b"), 2.0)]
+            )
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZMPOP", "1", "missing", "MIN"]),
+            Value::NullArray
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZMPOP", "0", "z", "MIN"]),
+            error("ERR numkeys should be greater than 0")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZMPOP", "1", "z", "MIN", "COUNT", "0"]),
+            error("ERR count should be greater than 0")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZMPOP", "1", "z", "LEFT"]),
+            error("ERR syntax error")
+        );
+    }
+
+    #[test]
+    fn test_zset_aggregation() {
+        let mut keyspace = Keyspace::new();
+        run(&mut keyspace, &["ZADD", "a", "1", "x", "2", "y", "3", "z"]);
+        run(
+            &mut keyspace,
+            &["ZADD", "b", "10", "y", "20", "z", "30", "w"],
+        );
+        keyspace.insert(
+            Bytes::from("s"),
+            Data::Set(crate::data::SetData::new(HashSet::from([
+                Bytes::from("z"),
+                Bytes::from("v"),
+            ]))),
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["ZUNION", "2", "a", "b", "WITHSCORES"]),
+            Value::Pairs(
+                [("x", 1.0), ("y", 12.0), ("z", 23.0), ("w", 30.0)]
+                    .iter()
+                    .map(|(m, s)| (Value::BulkString(Bytes::from(*m)), Value::Double(*s)))
+                    .collect()
+            )
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "ZINTER",
+                    "3",
+                    "a",
+                    "b",
+                    "s",
+                    "WEIGHTS",
+                    "2",
+                    "1",
+                    "5",
+                    "WITHSCORES"
+                ]
+            ),
+            Value::Pairs(vec![(
+                Value::BulkString(Bytes::from("z")),
+                Value::Double(31.0)
+            )])
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZINTER", "2", "a", "b", "AGGREGATE", "MAX"]
+            ),
+            bulks(&["y", "z"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZDIFF", "3", "a", "b", "missing"]),
+            bulks(&["x"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZINTERCARD", "2", "a", "b", "LIMIT", "1"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZINTERCARD", "2", "a", "missing"]),
+            Value::Integer(0)
+        );
+
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZUNIONSTORE", "dst", "2", "a", "s", "AGGREGATE", "MIN"]
+            ),
+            Value::Integer(4)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "dst", "0", "-1"]),
+            bulks(&["v", "x", "z", "y"])
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZINTERSTORE", "dst", "2", "a", "missing"]),
+            Value::Integer(0)
+        );
+        assert!(!keyspace.contains_key(&Bytes::from("dst")));
+        assert_eq!(
+            run(&mut keyspace, &["ZDIFFSTORE", "dst", "2", "b", "a"]),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &[
+                    "ZRANGESTORE",
+                    "dst",
+                    "b",
+                    "(30",
+                    "-inf",
+                    "BYSCORE",
+                    "REV",
+                    "LIMIT",
+                    "0",
+                    "1"
+                ]
+            ),
+            Value::Integer(1)
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGE", "dst", "0", "-1"]),
+            bulks(&["z"])
+        );
+
+        assert_eq!(
+            run(&mut keyspace, &["ZUNIONSTORE", "dst", "0", "a"]),
+            error("ERR at least 1 input key is needed for 'zunionstore' command")
+        );
+        assert_eq!(
+            run(
+                &mut keyspace,
+                &["ZUNION", "2", "a", "b", "WEIGHTS", "1", "x"]
+            ),
+            error("ERR weight value is not a float")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZUNION", "2", "a", "b", "WEIGHTS", "1"]),
+            error("ERR syntax error")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZDIFF", "2", "a", "b", "AGGREGATE", "MIN"]),
+            error("ERR syntax error")
+        );
+        assert_eq!(
+            run(&mut keyspace, &["ZRANGESTORE", "dst", "s", "0", "-1"]),
+            RusdisError::WrongType.to_reply()
+        );
+    }
+
+    #[test]
+    fn test_zset_wrong_type() {
+        let mut keyspace = Keyspace::new();
+        keyspace.insert(
+            Bytes::from("s"),
+            Data::String(StringData::new(Bytes::from("v"))),
+        );
+
+        let wrong_type = RusdisError::WrongType.to_reply();
+        assert_eq!(run(&mut keyspace, &["ZADD", "s", "1", "a"]), wrong_type);
+        assert_eq!(run(&mut keyspace, &["ZRANGE", "s", "0", "-1"]), wrong_type);
+        assert_eq!(run(&mut keyspace, &["ZSCORE", "s", "a"]), wrong_type);
+    }
+}